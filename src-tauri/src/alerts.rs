@@ -0,0 +1,849 @@
+// 日志告警模块
+// 后台订阅日志流广播（不依赖日志页面是否打开），按规则检查新记录：
+// - level：级别达到阈值（例如 ERROR 及以上）
+// - pattern：消息或续行匹配正则表达式
+// - error_rate：一分钟内的错误数达到阈值（可限定模块）
+// - silence：网关运行期间超过 N 分钟没有新日志
+// 规则触发后按配置的动作发送 alert-triggered 事件、弹出系统通知，并写入可确认的告警历史。
+
+use anyhow::{Context, Result};
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value as JsonValue};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{Emitter, Manager};
+use tauri_plugin_notification::NotificationExt;
+
+use crate::log_parser::{LevelGroup, LogRecord};
+use crate::log_stream::LogHub;
+
+/// 没有新日志时检查 silence 规则的间隔
+const SILENCE_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+/// 告警历史最多保留的条数
+const MAX_HISTORY: usize = 500;
+
+/// 告警中附带的日志内容最大长度
+const MAX_SAMPLE_CHARS: usize = 500;
+
+/// 告警条件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", rename_all_fields = "camelCase")]
+pub enum AlertCondition {
+    /// 级别达到阈值：DEBUG / INFO / WARNING / ERROR / CRITICAL
+    Level { min_level: String },
+    /// 消息或续行匹配正则表达式
+    Pattern {
+        pattern: String,
+        #[serde(default)]
+        case_sensitive: bool,
+    },
+    /// 一分钟内的错误（ERROR / CRITICAL）数达到阈值，规则设置了 module 时只统计该模块的错误
+    ErrorRate { per_minute: usize },
+    /// 网关运行期间超过指定分钟数没有新日志
+    Silence { minutes: u64 },
+}
+
+/// 规则触发后执行的动作
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AlertActions {
+    /// 向前端发送 alert-triggered 事件
+    pub event: bool,
+    /// 弹出系统通知
+    pub notification: bool,
+    /// 写入告警历史
+    pub history: bool,
+}
+
+impl Default for AlertActions {
+    fn default() -> Self {
+        Self {
+            event: true,
+            notification: true,
+            history: true,
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_cooldown() -> u64 {
+    300
+}
+
+/// 告警规则
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlertRule {
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    pub condition: AlertCondition,
+    /// 只检查指定模块的日志（子串匹配），对 silence 规则无效
+    #[serde(default)]
+    pub module: Option<String>,
+    #[serde(default)]
+    pub actions: AlertActions,
+    /// 同一规则两次触发之间的最短间隔（秒）
+    #[serde(default = "default_cooldown")]
+    pub cooldown_secs: u64,
+}
+
+/// 一条告警记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlertEntry {
+    pub id: String,
+    pub rule_id: String,
+    pub rule_name: String,
+    pub message: String,
+    /// 触发告警的日志内容
+    pub sample: Option<String>,
+    pub triggered_at: String,
+    #[serde(default)]
+    pub acknowledged: bool,
+    #[serde(default)]
+    pub acknowledged_at: Option<String>,
+}
+
+/// 默认规则：错误日志与错误激增开启，无日志告警默认关闭
+fn default_rules() -> Vec<AlertRule> {
+    vec![
+        AlertRule {
+            id: "errors".to_string(),
+            name: "错误日志".to_string(),
+            enabled: true,
+            condition: AlertCondition::Level { min_level: "ERROR".to_string() },
+            module: None,
+            actions: AlertActions::default(),
+            cooldown_secs: default_cooldown(),
+        },
+        AlertRule {
+            id: "error-burst".to_string(),
+            name: "错误激增".to_string(),
+            enabled: true,
+            condition: AlertCondition::ErrorRate { per_minute: 10 },
+            module: None,
+            actions: AlertActions::default(),
+            cooldown_secs: 600,
+        },
+        AlertRule {
+            id: "silence".to_string(),
+            name: "网关长时间无日志".to_string(),
+            enabled: false,
+            condition: AlertCondition::Silence { minutes: 30 },
+            module: None,
+            actions: AlertActions::default(),
+            cooldown_secs: 0,
+        },
+    ]
+}
+
+/// 获取告警规则文件路径
+fn get_rules_path() -> PathBuf {
+    let home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
+    home.join(".nanobot").join("alert_rules.json")
+}
+
+/// 获取告警历史文件路径
+fn get_history_path() -> PathBuf {
+    let home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
+    home.join(".nanobot").join("alert_history.json")
+}
+
+/// 加载告警规则，文件不存在时使用默认规则
+fn load_rules() -> Vec<AlertRule> {
+    let path = get_rules_path();
+    if !path.exists() {
+        return default_rules();
+    }
+    match fs::read_to_string(&path)
+        .map_err(anyhow::Error::from)
+        .and_then(|content| serde_json::from_str(&content).map_err(anyhow::Error::from))
+    {
+        Ok(rules) => rules,
+        Err(e) => {
+            log::warn!("读取告警规则失败，使用默认规则: {}", e);
+            default_rules()
+        }
+    }
+}
+
+fn load_history() -> Vec<AlertEntry> {
+    fs::read_to_string(get_history_path())
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// 写入 JSON 文件，必要时创建目录
+fn write_json<T: Serialize>(path: &PathBuf, value: &T) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).context("创建配置目录失败")?;
+    }
+    let content = serde_json::to_string_pretty(value).context("序列化失败")?;
+    fs::write(path, content).with_context(|| format!("写入文件失败: {}", path.display()))?;
+    Ok(())
+}
+
+/// 级别的严重程度，用于阈值比较
+fn level_rank(level: &str) -> u8 {
+    match level.to_uppercase().as_str() {
+        "TRACE" => 0,
+        "DEBUG" => 1,
+        "INFO" => 2,
+        "SUCCESS" => 3,
+        "WARNING" | "WARN" => 4,
+        "ERROR" => 5,
+        "CRITICAL" => 6,
+        _ => 0,
+    }
+}
+
+/// 检查规则是否有效，返回编译后的正则表达式
+fn compile_rule(rule: &AlertRule) -> Result<Option<Regex>, String> {
+    if rule.name.trim().is_empty() {
+        return Err("告警规则名称不能为空".to_string());
+    }
+    match &rule.condition {
+        AlertCondition::Level { min_level } if level_rank(min_level) == 0 && !min_level.eq_ignore_ascii_case("TRACE") => {
+            Err(format!("规则「{}」的级别无效: {}", rule.name, min_level))
+        }
+        AlertCondition::Pattern { pattern, case_sensitive } => RegexBuilder::new(pattern)
+            .case_insensitive(!case_sensitive)
+            .build()
+            .map(Some)
+            .map_err(|e| format!("规则「{}」的正则表达式无效: {}", rule.name, e)),
+        AlertCondition::ErrorRate { per_minute: 0 } => {
+            Err(format!("规则「{}」的错误数阈值必须大于 0", rule.name))
+        }
+        AlertCondition::Silence { minutes: 0 } => {
+            Err(format!("规则「{}」的时长必须大于 0", rule.name))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// 截取记录原文作为告警附带内容
+fn sample_of(record: &LogRecord) -> String {
+    let text = record.to_text();
+    if text.chars().count() > MAX_SAMPLE_CHARS {
+        let truncated: String = text.chars().take(MAX_SAMPLE_CHARS).collect();
+        format!("{}…", truncated)
+    } else {
+        text
+    }
+}
+
+struct CompiledRule {
+    rule: AlertRule,
+    pattern: Option<Regex>,
+}
+
+/// 告警引擎：规则、触发状态与告警历史
+struct AlertEngine {
+    rules: Vec<CompiledRule>,
+    history: Vec<AlertEntry>,
+    /// 各规则上次触发的时间，用于冷却
+    last_fired: HashMap<String, Instant>,
+    /// 各 error_rate 规则最近一分钟内统计到的错误记录的到达时间
+    error_times: HashMap<String, VecDeque<Instant>>,
+    /// 最近一次读取到新日志的时间
+    last_record_at: Instant,
+    /// 本次无日志期间已经触发过的 silence 规则
+    silence_fired: HashSet<String>,
+    next_id: u64,
+}
+
+impl AlertEngine {
+    fn new(rules: Vec<AlertRule>, history: Vec<AlertEntry>) -> Self {
+        let mut engine = AlertEngine {
+            rules: Vec::new(),
+            history,
+            last_fired: HashMap::new(),
+            error_times: HashMap::new(),
+            last_record_at: Instant::now(),
+            silence_fired: HashSet::new(),
+            next_id: 0,
+        };
+        engine.set_rules(rules);
+        engine
+    }
+
+    fn set_rules(&mut self, rules: Vec<AlertRule>) {
+        self.rules = rules
+            .into_iter()
+            .filter_map(|rule| match compile_rule(&rule) {
+                Ok(pattern) => Some(CompiledRule { rule, pattern }),
+                Err(e) => {
+                    log::warn!("忽略无效的告警规则: {}", e);
+                    None
+                }
+            })
+            .collect();
+        self.last_fired.clear();
+        self.error_times.clear();
+        self.silence_fired.clear();
+    }
+
+    /// 检查冷却时间，可以触发时记录触发时间
+    fn try_fire(&mut self, rule: &AlertRule, now: Instant) -> bool {
+        if let Some(last) = self.last_fired.get(&rule.id) {
+            if now.duration_since(*last) < Duration::from_secs(rule.cooldown_secs) {
+                return false;
+            }
+        }
+        self.last_fired.insert(rule.id.clone(), now);
+        true
+    }
+
+    fn make_entry(&mut self, rule: &AlertRule, message: String, sample: Option<String>) -> AlertEntry {
+        self.next_id += 1;
+        let now = chrono::Local::now();
+        AlertEntry {
+            id: format!("{}-{}", now.timestamp_millis(), self.next_id),
+            rule_id: rule.id.clone(),
+            rule_name: rule.name.clone(),
+            message,
+            sample,
+            triggered_at: now.to_rfc3339(),
+            acknowledged: false,
+            acknowledged_at: None,
+        }
+    }
+
+    /// 检查新读取的记录，返回触发的告警及对应动作
+    fn evaluate(&mut self, records: &[LogRecord], now: Instant) -> Vec<(AlertEntry, AlertActions)> {
+        let mut fired = Vec::new();
+        if records.is_empty() {
+            return fired;
+        }
+        self.last_record_at = now;
+        self.silence_fired.clear();
+
+        let rules: Vec<(AlertRule, Option<Regex>)> = self
+            .rules
+            .iter()
+            .filter(|c| c.rule.enabled)
+            .map(|c| (c.rule.clone(), c.pattern.clone()))
+            .collect();
+
+        for (rule, pattern) in rules {
+            let module_matches = |record: &&LogRecord| {
+                rule.module
+                    .as_deref()
+                    .filter(|m| !m.is_empty())
+                    .is_none_or(|m| record.module.as_deref().is_some_and(|rm| rm.contains(m)))
+            };
+
+            let (matched, message) = match &rule.condition {
+                AlertCondition::Level { min_level } => {
+                    let threshold = level_rank(min_level);
+                    let hits: Vec<&LogRecord> = records
+                        .iter()
+                        .filter(module_matches)
+                        .filter(|r| level_rank(&r.level) >= threshold)
+                        .collect();
+                    let message = hits.first().map(|r| match hits.len() {
+                        1 => format!("[{}] {}", r.level, r.message),
+                        n => format!("[{}] {}（另有 {} 条）", r.level, r.message, n - 1),
+                    });
+                    (hits.first().copied(), message)
+                }
+                AlertCondition::Pattern { .. } => {
+                    let pattern = pattern.as_ref().expect("pattern 规则已编译");
+                    let hit = records.iter().filter(module_matches).find(|r| {
+                        pattern.is_match(&r.message) || r.continuation.iter().any(|l| pattern.is_match(l))
+                    });
+                    (hit, hit.map(|r| format!("日志匹配 {}: {}", pattern.as_str(), r.message)))
+                }
+                AlertCondition::ErrorRate { per_minute } => {
+                    let errors: Vec<&LogRecord> = records
+                        .iter()
+                        .filter(module_matches)
+                        .filter(|r| r.level_group() == LevelGroup::Error)
+                        .collect();
+                    let times = self.error_times.entry(rule.id.clone()).or_default();
+                    times.extend(std::iter::repeat_n(now, errors.len()));
+                    while times
+                        .front()
+                        .is_some_and(|t| now.duration_since(*t) > Duration::from_secs(60))
+                    {
+                        times.pop_front();
+                    }
+                    let count = times.len();
+                    match errors.last().copied() {
+                        Some(record) if count >= *per_minute => {
+                            (Some(record), Some(format!("最近一分钟内出现 {} 条错误日志", count)))
+                        }
+                        _ => (None, None),
+                    }
+                }
+                AlertCondition::Silence { .. } => (None, None),
+            };
+
+            if let (Some(record), Some(message)) = (matched, message) {
+                if self.try_fire(&rule, now) {
+                    let entry = self.make_entry(&rule, message, Some(sample_of(record)));
+                    fired.push((entry, rule.actions.clone()));
+                }
+            }
+        }
+        fired
+    }
+
+    /// 是否有启用的规则，没有时不需要读取日志
+    fn has_enabled_rules(&self) -> bool {
+        self.rules.iter().any(|c| c.rule.enabled)
+    }
+
+    /// 已到期、尚未触发的 silence 规则
+    fn silence_due(&self, now: Instant) -> Vec<AlertRule> {
+        let idle = now.duration_since(self.last_record_at);
+        self.rules
+            .iter()
+            .filter(|c| c.rule.enabled && !self.silence_fired.contains(&c.rule.id))
+            .filter(|c| matches!(c.rule.condition, AlertCondition::Silence { minutes } if idle >= Duration::from_secs(minutes * 60)))
+            .map(|c| c.rule.clone())
+            .collect()
+    }
+
+    /// 检查长时间无日志的规则，gateway_running 只在有规则到期时调用
+    fn check_silence(&mut self, now: Instant, gateway_running: impl FnOnce() -> bool) -> Vec<(AlertEntry, AlertActions)> {
+        let idle = now.duration_since(self.last_record_at);
+        let due = self.silence_due(now);
+        if due.is_empty() || !gateway_running() {
+            return Vec::new();
+        }
+
+        let mut fired = Vec::new();
+        for rule in due {
+            self.silence_fired.insert(rule.id.clone());
+            if self.try_fire(&rule, now) {
+                let message = format!("网关正在运行，但已有 {} 分钟没有新日志", idle.as_secs() / 60);
+                let entry = self.make_entry(&rule, message, None);
+                fired.push((entry, rule.actions.clone()));
+            }
+        }
+        fired
+    }
+
+    /// 将告警写入历史，超出上限时丢弃最早的记录
+    fn record(&mut self, entries: impl IntoIterator<Item = AlertEntry>) -> bool {
+        let before = self.history.len();
+        self.history.extend(entries);
+        let added = self.history.len() > before;
+        if self.history.len() > MAX_HISTORY {
+            let excess = self.history.len() - MAX_HISTORY;
+            self.history.drain(..excess);
+        }
+        added
+    }
+
+    /// 确认告警，ids 为 None 时确认全部，返回新确认的数量
+    fn acknowledge(&mut self, ids: Option<&[String]>, at: &str) -> usize {
+        let mut count = 0;
+        for alert in self.history.iter_mut().filter(|a| !a.acknowledged) {
+            if ids.is_none_or(|ids| ids.contains(&alert.id)) {
+                alert.acknowledged = true;
+                alert.acknowledged_at = Some(at.to_string());
+                count += 1;
+            }
+        }
+        count
+    }
+
+    fn unacknowledged(&self) -> usize {
+        self.history.iter().filter(|a| !a.acknowledged).count()
+    }
+}
+
+/// 告警状态，由后台任务与命令共享
+pub struct AlertState {
+    engine: Mutex<AlertEngine>,
+    /// 规则保存后通知后台任务
+    rules_changed: tokio::sync::Notify,
+}
+
+impl AlertState {
+    pub fn new() -> Self {
+        Self {
+            engine: Mutex::new(AlertEngine::new(load_rules(), load_history())),
+            rules_changed: tokio::sync::Notify::new(),
+        }
+    }
+
+    fn save_history(engine: &AlertEngine) {
+        if let Err(e) = write_json(&get_history_path(), &engine.history) {
+            log::warn!("保存告警历史失败: {:#}", e);
+        }
+    }
+}
+
+/// 执行告警动作
+fn dispatch(app: &tauri::AppHandle, state: &AlertState, fired: Vec<(AlertEntry, AlertActions)>) {
+    if fired.is_empty() {
+        return;
+    }
+
+    for (entry, actions) in &fired {
+        log::info!("告警触发 [{}]: {}", entry.rule_name, entry.message);
+        if actions.event {
+            if let Err(e) = app.emit("alert-triggered", entry) {
+                log::warn!("发送告警事件失败: {:?}", e);
+            }
+        }
+        if actions.notification {
+            if let Err(e) = app
+                .notification()
+                .builder()
+                .title(format!("nanobot 告警：{}", entry.rule_name))
+                .body(&entry.message)
+                .show()
+            {
+                log::warn!("发送系统通知失败: {:?}", e);
+            }
+        }
+    }
+
+    let mut engine = state.engine.lock().unwrap();
+    let to_record = fired
+        .into_iter()
+        .filter(|(_, actions)| actions.history)
+        .map(|(entry, _)| entry);
+    if engine.record(to_record) {
+        AlertState::save_history(&engine);
+    }
+}
+
+/// 后台告警任务：有启用的规则时订阅日志流广播并检查规则
+/// 没有启用的规则时取消订阅，日志读取任务在没有其他订阅者时随之停止
+pub async fn run_alert_loop(app: tauri::AppHandle) {
+    let state = app.state::<Arc<AlertState>>().inner().clone();
+    let hub = app.state::<Arc<LogHub>>().inner().clone();
+
+    loop {
+        if !state.engine.lock().unwrap().has_enabled_rules() {
+            state.rules_changed.notified().await;
+            continue;
+        }
+
+        // 只检查订阅之后写入的日志，无日志时长也从订阅时开始计算
+        let mut receiver = match hub.subscribe_channel(&app) {
+            Ok(receiver) => receiver,
+            Err(e) => {
+                log::warn!("告警任务无法订阅日志: {}", e);
+                return;
+            }
+        };
+        state.engine.lock().unwrap().last_record_at = Instant::now();
+
+        loop {
+            let records = tokio::select! {
+                batch = receiver.recv() => match batch {
+                    Some(records) => records,
+                    None => return,
+                },
+                _ = state.rules_changed.notified() => {
+                    if state.engine.lock().unwrap().has_enabled_rules() {
+                        continue;
+                    }
+                    break;
+                }
+                _ = tokio::time::sleep(SILENCE_CHECK_INTERVAL) => Vec::new(),
+            };
+
+            let now = Instant::now();
+            let (mut fired, silence_due) = {
+                let mut engine = state.engine.lock().unwrap();
+                let fired = engine.evaluate(&records, now);
+                (fired, !engine.silence_due(now).is_empty())
+            };
+            // 进程检查较慢，放到阻塞线程中执行，且只在有 silence 规则到期时才检查
+            if silence_due {
+                let silence_state = state.clone();
+                let silence = tokio::task::spawn_blocking(move || {
+                    let mut engine = silence_state.engine.lock().unwrap();
+                    engine.check_silence(now, crate::process::check_nanobot_running)
+                })
+                .await
+                .unwrap_or_default();
+                fired.extend(silence);
+            }
+
+            dispatch(&app, &state, fired);
+        }
+
+        drop(receiver);
+        hub.release_closed_channels();
+    }
+}
+
+/// 获取告警规则
+#[tauri::command]
+pub async fn get_alert_rules(state: tauri::State<'_, Arc<AlertState>>) -> Result<JsonValue, String> {
+    let engine = state.engine.lock().unwrap();
+    let rules: Vec<&AlertRule> = engine.rules.iter().map(|c| &c.rule).collect();
+    Ok(json!({
+        "rules": rules
+    }))
+}
+
+/// 保存告警规则，立即生效
+#[tauri::command]
+pub async fn save_alert_rules(
+    rules: Vec<AlertRule>,
+    state: tauri::State<'_, Arc<AlertState>>,
+) -> Result<JsonValue, String> {
+    let mut rules = rules;
+    let mut ids = HashSet::new();
+    for (index, rule) in rules.iter_mut().enumerate() {
+        compile_rule(rule)?;
+        if rule.id.trim().is_empty() {
+            rule.id = format!("rule-{}-{}", chrono::Local::now().timestamp_millis(), index);
+        }
+        if !ids.insert(rule.id.clone()) {
+            return Err(format!("告警规则 ID 重复: {}", rule.id));
+        }
+    }
+
+    write_json(&get_rules_path(), &rules).map_err(|e| format!("保存告警规则失败: {:#}", e))?;
+    let count = rules.len();
+    state.engine.lock().unwrap().set_rules(rules);
+    state.rules_changed.notify_one();
+
+    Ok(json!({
+        "success": true,
+        "message": format!("已保存 {} 条告警规则", count)
+    }))
+}
+
+/// 查询告警历史（最新的在前）
+#[tauri::command]
+pub async fn get_alert_history(
+    unacknowledged_only: Option<bool>,
+    limit: Option<usize>,
+    state: tauri::State<'_, Arc<AlertState>>,
+) -> Result<JsonValue, String> {
+    let engine = state.engine.lock().unwrap();
+    let only_pending = unacknowledged_only.unwrap_or(false);
+    let alerts: Vec<&AlertEntry> = engine
+        .history
+        .iter()
+        .rev()
+        .filter(|a| !only_pending || !a.acknowledged)
+        .take(limit.unwrap_or(100))
+        .collect();
+
+    Ok(json!({
+        "alerts": alerts,
+        "total": engine.history.len(),
+        "unacknowledged": engine.unacknowledged()
+    }))
+}
+
+/// 确认告警，未指定 ID 时确认全部
+#[tauri::command]
+pub async fn acknowledge_alerts(
+    ids: Option<Vec<String>>,
+    state: tauri::State<'_, Arc<AlertState>>,
+) -> Result<JsonValue, String> {
+    let mut engine = state.engine.lock().unwrap();
+    let count = engine.acknowledge(ids.as_deref(), &chrono::Local::now().to_rfc3339());
+    if count > 0 {
+        AlertState::save_history(&engine);
+    }
+
+    Ok(json!({
+        "success": true,
+        "acknowledged": count,
+        "unacknowledged": engine.unacknowledged()
+    }))
+}
+
+/// 清空告警历史
+#[tauri::command]
+pub async fn clear_alert_history(state: tauri::State<'_, Arc<AlertState>>) -> Result<JsonValue, String> {
+    let mut engine = state.engine.lock().unwrap();
+    engine.history.clear();
+    AlertState::save_history(&engine);
+
+    Ok(json!({
+        "success": true,
+        "message": "告警历史已清空"
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::log_parser::parse_header;
+
+    fn record(level: &str, module: &str, message: &str) -> LogRecord {
+        parse_header(&format!("2026-02-10 12:00:00.000 | {:<8} | {}:run:1 - {}", level, module, message)).unwrap()
+    }
+
+    fn rule(id: &str, condition: AlertCondition, module: Option<&str>, cooldown_secs: u64) -> AlertRule {
+        AlertRule {
+            id: id.to_string(),
+            name: id.to_string(),
+            enabled: true,
+            condition,
+            module: module.map(|m| m.to_string()),
+            actions: AlertActions::default(),
+            cooldown_secs,
+        }
+    }
+
+    fn fired_ids(fired: &[(AlertEntry, AlertActions)]) -> Vec<&str> {
+        fired.iter().map(|(entry, _)| entry.rule_id.as_str()).collect()
+    }
+
+    #[test]
+    fn fires_level_and_pattern_rules() {
+        let mut engine = AlertEngine::new(
+            vec![
+                rule("errors", AlertCondition::Level { min_level: "ERROR".to_string() }, None, 0),
+                rule("telegram", AlertCondition::Level { min_level: "WARNING".to_string() }, Some("telegram"), 0),
+                rule(
+                    "timeout",
+                    AlertCondition::Pattern { pattern: "TimeoutError".to_string(), case_sensitive: false },
+                    None,
+                    0,
+                ),
+            ],
+            Vec::new(),
+        );
+        let now = Instant::now();
+
+        let fired = engine.evaluate(&[record("INFO", "nanobot.agent", "ok")], now);
+        assert!(fired.is_empty());
+
+        let mut error = record("ERROR", "nanobot.agent", "failed");
+        error.continuation.push("asyncio.timeouterror: read".to_string());
+        let fired = engine.evaluate(&[record("WARNING", "nanobot.channels.slack", "slow"), error.clone(), error], now);
+        assert_eq!(fired_ids(&fired), ["errors", "timeout"]);
+        assert_eq!(fired[0].0.message, "[ERROR] failed（另有 1 条）");
+        assert!(fired[0].0.sample.as_deref().unwrap().contains("timeouterror"));
+
+        // module 只匹配指定模块
+        let fired = engine.evaluate(&[record("WARNING", "nanobot.channels.telegram", "slow")], now);
+        assert_eq!(fired_ids(&fired), ["telegram"]);
+    }
+
+    #[test]
+    fn counts_error_rate_per_rule_module() {
+        let mut engine = AlertEngine::new(
+            vec![
+                rule("all", AlertCondition::ErrorRate { per_minute: 3 }, None, 0),
+                rule("telegram", AlertCondition::ErrorRate { per_minute: 3 }, Some("telegram"), 0),
+            ],
+            Vec::new(),
+        );
+        let now = Instant::now();
+        let other = record("ERROR", "nanobot.agent", "x");
+        let telegram = record("ERROR", "nanobot.channels.telegram", "y");
+
+        let fired = engine.evaluate(&[other.clone(), other.clone(), telegram.clone()], now);
+        assert_eq!(fired_ids(&fired), ["all"]);
+        assert!(fired[0].0.message.contains("3 条"));
+
+        // 超过一分钟的错误不再计入
+        let later = now + Duration::from_secs(61);
+        let fired = engine.evaluate(&[telegram.clone(), telegram.clone()], later);
+        assert!(fired.is_empty());
+        let fired = engine.evaluate(&[telegram], later + Duration::from_secs(1));
+        assert_eq!(fired_ids(&fired), ["all", "telegram"]);
+    }
+
+    #[test]
+    fn fires_silence_only_while_gateway_runs() {
+        let mut engine = AlertEngine::new(
+            vec![rule("silence", AlertCondition::Silence { minutes: 1 }, None, 0)],
+            Vec::new(),
+        );
+        let start = Instant::now();
+        engine.evaluate(&[record("INFO", "nanobot.agent", "ok")], start);
+
+        // 未到期时不检查进程
+        assert!(engine.silence_due(start + Duration::from_secs(30)).is_empty());
+        assert!(engine
+            .check_silence(start + Duration::from_secs(30), || panic!("不应检查进程"))
+            .is_empty());
+
+        let idle = start + Duration::from_secs(61);
+        assert!(engine.check_silence(idle, || false).is_empty());
+        let fired = engine.check_silence(idle, || true);
+        assert_eq!(fired_ids(&fired), ["silence"]);
+        assert!(fired[0].0.sample.is_none());
+        // 同一段无日志期间只触发一次，收到新日志后重新计时
+        assert!(engine.silence_due(idle + Duration::from_secs(60)).is_empty());
+        engine.evaluate(&[record("INFO", "nanobot.agent", "ok")], idle);
+        assert_eq!(engine.silence_due(idle + Duration::from_secs(61)).len(), 1);
+
+        // 没有 silence 规则时从不到期
+        let engine = AlertEngine::new(default_rules().into_iter().filter(|r| r.id != "silence").collect(), Vec::new());
+        assert!(engine.silence_due(start + Duration::from_secs(86400)).is_empty());
+    }
+
+    #[test]
+    fn respects_cooldown() {
+        let mut engine = AlertEngine::new(
+            vec![rule("errors", AlertCondition::Level { min_level: "ERROR".to_string() }, None, 300)],
+            Vec::new(),
+        );
+        let now = Instant::now();
+        let error = [record("ERROR", "nanobot.agent", "failed")];
+
+        assert_eq!(engine.evaluate(&error, now).len(), 1);
+        assert!(engine.evaluate(&error, now + Duration::from_secs(10)).is_empty());
+        assert_eq!(engine.evaluate(&error, now + Duration::from_secs(301)).len(), 1);
+
+        // 重新保存规则后冷却状态重置
+        engine.set_rules(vec![rule("errors", AlertCondition::Level { min_level: "ERROR".to_string() }, None, 300)]);
+        assert_eq!(engine.evaluate(&error, now + Duration::from_secs(302)).len(), 1);
+    }
+
+    #[test]
+    fn caps_history_and_acknowledges() {
+        let mut engine = AlertEngine::new(Vec::new(), Vec::new());
+        let source = rule("errors", AlertCondition::Level { min_level: "ERROR".to_string() }, None, 0);
+        let entries: Vec<AlertEntry> = (0..MAX_HISTORY + 10)
+            .map(|i| engine.make_entry(&source, format!("alert {}", i), None))
+            .collect();
+        let first_kept = entries[10].id.clone();
+
+        assert!(engine.record(entries));
+        assert!(!engine.record(Vec::new()));
+        assert_eq!(engine.history.len(), MAX_HISTORY);
+        assert_eq!(engine.history[0].id, first_kept);
+        assert_eq!(engine.unacknowledged(), MAX_HISTORY);
+
+        let ids = vec![first_kept.clone(), "missing".to_string()];
+        assert_eq!(engine.acknowledge(Some(&ids), "t1"), 1);
+        assert_eq!(engine.history[0].acknowledged_at.as_deref(), Some("t1"));
+        // 已确认的不会重复计数
+        assert_eq!(engine.acknowledge(Some(&ids), "t2"), 0);
+        assert_eq!(engine.acknowledge(None, "t3"), MAX_HISTORY - 1);
+        assert_eq!(engine.unacknowledged(), 0);
+        assert_eq!(engine.history[0].acknowledged_at.as_deref(), Some("t1"));
+    }
+
+    #[test]
+    fn rejects_invalid_rules() {
+        assert!(compile_rule(&rule("a", AlertCondition::Level { min_level: "LOUD".to_string() }, None, 0)).is_err());
+        assert!(compile_rule(&rule("a", AlertCondition::ErrorRate { per_minute: 0 }, None, 0)).is_err());
+        assert!(compile_rule(&rule("a", AlertCondition::Silence { minutes: 0 }, None, 0)).is_err());
+        let pattern = AlertCondition::Pattern { pattern: "(".to_string(), case_sensitive: true };
+        assert!(compile_rule(&rule("a", pattern, None, 0)).is_err());
+    }
+}
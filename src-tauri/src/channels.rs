@@ -0,0 +1,925 @@
+// 消息渠道凭据检测模块
+// 针对各渠道调用最轻量的鉴权接口（telegram getMe、slack auth.test 等），
+// 在启动 gateway 之前发现错误的 token。
+
+use base64::Engine;
+use serde::Serialize;
+use serde_json::{json, Value as JsonValue};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::{Duration, Instant};
+
+use crate::config_model::{ChannelConfig, NanobotConfig};
+use crate::interpolation::UnresolvedReference;
+
+/// 单次检测的超时时间
+const CHANNEL_CHECK_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// 各渠道的 API 地址，测试时可替换为本地服务
+#[derive(Debug, Clone)]
+pub struct ChannelCheckEndpoints {
+    pub telegram: String,
+    pub discord: String,
+    pub slack: String,
+    pub feishu: String,
+    pub dingtalk: String,
+    /// 未配置 homeserver 时使用的 Matrix 服务器
+    pub matrix: String,
+}
+
+impl Default for ChannelCheckEndpoints {
+    fn default() -> Self {
+        Self {
+            telegram: "https://api.telegram.org".to_string(),
+            discord: "https://discord.com/api/v10".to_string(),
+            slack: "https://slack.com/api".to_string(),
+            feishu: "https://open.feishu.cn".to_string(),
+            dingtalk: "https://api.dingtalk.com".to_string(),
+            matrix: "https://matrix.org".to_string(),
+        }
+    }
+}
+
+/// 渠道检测结果
+#[derive(Debug, Clone, Serialize)]
+pub struct ChannelCheckResult {
+    pub channel: String,
+    /// ok / auth_failed / unreachable / error / not_configured / unsupported / skipped
+    pub status: String,
+    pub message: String,
+    /// 鉴权成功时的身份信息，例如机器人用户名
+    pub identity: Option<String>,
+    pub latency_ms: Option<u64>,
+}
+
+impl ChannelCheckResult {
+    fn new(channel: &str, status: &str, message: impl Into<String>) -> Self {
+        Self {
+            channel: channel.to_string(),
+            status: status.to_string(),
+            message: message.into(),
+            identity: None,
+            latency_ms: None,
+        }
+    }
+
+    fn ok(channel: &str, identity: Option<String>) -> Self {
+        Self {
+            identity,
+            ..Self::new(channel, "ok", "凭据有效")
+        }
+    }
+}
+
+/// 渠道检测的中间错误
+enum CheckError {
+    /// 缺少必填字段
+    NotConfigured(String),
+    /// 凭据被拒绝
+    Auth(String),
+    /// 网络不可达
+    Unreachable(String),
+    /// 其他错误
+    Other(String),
+}
+
+impl From<reqwest::Error> for CheckError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_connect() || e.is_timeout() {
+            CheckError::Unreachable(format!("无法连接: {}", e))
+        } else {
+            CheckError::Other(format!("请求失败: {}", e))
+        }
+    }
+}
+
+type CheckOutcome = Result<Option<String>, CheckError>;
+
+fn require<'a>(channel: &'a ChannelConfig, key: &str) -> Result<&'a str, CheckError> {
+    channel
+        .field_str(key)
+        .ok_or_else(|| CheckError::NotConfigured(format!("缺少 {}", key)))
+}
+
+fn build_client(proxy: Option<&str>) -> Result<reqwest::Client, CheckError> {
+    let mut builder = reqwest::Client::builder()
+        .timeout(CHANNEL_CHECK_TIMEOUT)
+        .user_agent("nanoboard/1.0");
+    if let Some(proxy) = proxy {
+        let proxy = reqwest::Proxy::all(proxy)
+            .map_err(|e| CheckError::Other(format!("代理地址无效: {}", e)))?;
+        builder = builder.proxy(proxy);
+    }
+    builder
+        .build()
+        .map_err(|e| CheckError::Other(format!("创建 HTTP 客户端失败: {}", e)))
+}
+
+/// 读取 JSON 响应，401/403 视为鉴权失败
+async fn read_json(response: reqwest::Response) -> Result<(u16, JsonValue), CheckError> {
+    let status = response.status().as_u16();
+    let body: JsonValue = response.json().await.unwrap_or(JsonValue::Null);
+    if status == 401 || status == 403 {
+        let detail = body
+            .get("description")
+            .or_else(|| body.get("message"))
+            .or_else(|| body.get("error"))
+            .and_then(|v| v.as_str())
+            .unwrap_or("");
+        return Err(CheckError::Auth(format!("凭据被拒绝 ({}) {}", status, detail).trim_end().to_string()));
+    }
+    Ok((status, body))
+}
+
+fn str_field(body: &JsonValue, pointer: &str) -> Option<String> {
+    body.pointer(pointer).and_then(|v| v.as_str()).map(|s| s.to_string())
+}
+
+/// Telegram: GET /bot{token}/getMe
+async fn check_telegram(channel: &ChannelConfig, endpoints: &ChannelCheckEndpoints) -> CheckOutcome {
+    let token = require(channel, "token")?;
+    let client = build_client(channel.field_str("proxy"))?;
+    let response = client
+        .get(format!("{}/bot{}/getMe", endpoints.telegram.trim_end_matches('/'), token))
+        .send()
+        .await?;
+    let (status, body) = read_json(response).await?;
+
+    if body.get("ok").and_then(|v| v.as_bool()) == Some(true) {
+        Ok(str_field(&body, "/result/username").map(|u| format!("@{}", u)))
+    } else if status == 404 {
+        // Telegram 对格式错误的 token 返回 404
+        Err(CheckError::Auth("token 无效".to_string()))
+    } else {
+        Err(CheckError::Other(format!(
+            "Telegram 返回错误: {}",
+            str_field(&body, "/description").unwrap_or_else(|| status.to_string())
+        )))
+    }
+}
+
+/// Discord: GET /gateway/bot，需要有效的 Bot token
+async fn check_discord(channel: &ChannelConfig, endpoints: &ChannelCheckEndpoints) -> CheckOutcome {
+    let token = require(channel, "token")?;
+    let client = build_client(None)?;
+    let base = endpoints.discord.trim_end_matches('/');
+
+    let response = client
+        .get(format!("{}/gateway/bot", base))
+        .header("Authorization", format!("Bot {}", token))
+        .send()
+        .await?;
+    let (status, body) = read_json(response).await?;
+    if !(200..300).contains(&status) || body.get("url").is_none() {
+        return Err(CheckError::Other(format!("Discord 返回错误状态: {}", status)));
+    }
+
+    // 获取机器人用户名，失败不影响检测结果
+    let identity = match client
+        .get(format!("{}/users/@me", base))
+        .header("Authorization", format!("Bot {}", token))
+        .send()
+        .await
+    {
+        Ok(response) => response
+            .json::<JsonValue>()
+            .await
+            .ok()
+            .and_then(|b| str_field(&b, "/username")),
+        Err(_) => None,
+    };
+    Ok(identity)
+}
+
+/// Slack: auth.test 校验 botToken；配置了 appToken 时用 apps.connections.open 校验 Socket Mode
+async fn check_slack(channel: &ChannelConfig, endpoints: &ChannelCheckEndpoints) -> CheckOutcome {
+    let bot_token = require(channel, "botToken")?;
+    let client = build_client(None)?;
+    let base = endpoints.slack.trim_end_matches('/');
+
+    let response = client
+        .post(format!("{}/auth.test", base))
+        .bearer_auth(bot_token)
+        .send()
+        .await?;
+    let (_, body) = read_json(response).await?;
+    if body.get("ok").and_then(|v| v.as_bool()) != Some(true) {
+        let error = str_field(&body, "/error").unwrap_or_else(|| "unknown_error".to_string());
+        return Err(CheckError::Auth(format!("botToken 无效: {}", error)));
+    }
+    let identity = match (str_field(&body, "/user"), str_field(&body, "/team")) {
+        (Some(user), Some(team)) => Some(format!("{} @ {}", user, team)),
+        (user, _) => user,
+    };
+
+    let socket_mode = channel.field_str("mode").is_none_or(|m| m == "socket");
+    if let (true, Some(app_token)) = (socket_mode, channel.field_str("appToken")) {
+        let response = client
+            .post(format!("{}/apps.connections.open", base))
+            .bearer_auth(app_token)
+            .send()
+            .await?;
+        let (_, body) = read_json(response).await?;
+        if body.get("ok").and_then(|v| v.as_bool()) != Some(true) {
+            let error = str_field(&body, "/error").unwrap_or_else(|| "unknown_error".to_string());
+            return Err(CheckError::Auth(format!("appToken 无效: {}", error)));
+        }
+    }
+
+    Ok(identity)
+}
+
+/// 飞书: 获取 tenant_access_token
+async fn check_feishu(channel: &ChannelConfig, endpoints: &ChannelCheckEndpoints) -> CheckOutcome {
+    let app_id = require(channel, "appId")?;
+    let app_secret = require(channel, "appSecret")?;
+    let client = build_client(None)?;
+
+    let response = client
+        .post(format!(
+            "{}/open-apis/auth/v3/tenant_access_token/internal",
+            endpoints.feishu.trim_end_matches('/')
+        ))
+        .json(&json!({ "app_id": app_id, "app_secret": app_secret }))
+        .send()
+        .await?;
+    let (status, body) = read_json(response).await?;
+
+    match body.get("code").and_then(|c| c.as_i64()) {
+        Some(0) => Ok(Some(app_id.to_string())),
+        Some(code) => Err(CheckError::Auth(format!(
+            "飞书返回错误 {}: {}",
+            code,
+            str_field(&body, "/msg").unwrap_or_default()
+        ))),
+        None => Err(CheckError::Other(format!("飞书返回错误状态: {}", status))),
+    }
+}
+
+/// 钉钉: 获取企业内部应用 accessToken
+async fn check_dingtalk(channel: &ChannelConfig, endpoints: &ChannelCheckEndpoints) -> CheckOutcome {
+    let client_id = require(channel, "clientId")?;
+    let client_secret = require(channel, "clientSecret")?;
+    let client = build_client(None)?;
+
+    let response = client
+        .post(format!("{}/v1.0/oauth2/accessToken", endpoints.dingtalk.trim_end_matches('/')))
+        .json(&json!({ "appKey": client_id, "appSecret": client_secret }))
+        .send()
+        .await?;
+    let (status, body) = read_json(response).await?;
+
+    if body.get("accessToken").and_then(|t| t.as_str()).is_some() {
+        Ok(Some(client_id.to_string()))
+    } else if (400..500).contains(&status) {
+        Err(CheckError::Auth(format!(
+            "钉钉返回错误: {}",
+            str_field(&body, "/message").unwrap_or_else(|| status.to_string())
+        )))
+    } else {
+        Err(CheckError::Other(format!("钉钉返回错误状态: {}", status)))
+    }
+}
+
+/// Matrix: GET /_matrix/client/v3/account/whoami
+async fn check_matrix(channel: &ChannelConfig, endpoints: &ChannelCheckEndpoints) -> CheckOutcome {
+    let homeserver = channel.field_str("homeserver").unwrap_or(&endpoints.matrix);
+    let access_token = require(channel, "accessToken")?;
+    let client = build_client(None)?;
+
+    let response = client
+        .get(format!(
+            "{}/_matrix/client/v3/account/whoami",
+            homeserver.trim_end_matches('/')
+        ))
+        .bearer_auth(access_token)
+        .send()
+        .await?;
+    let (status, body) = read_json(response).await?;
+
+    match str_field(&body, "/user_id") {
+        Some(user_id) => {
+            if let Some(expected) = channel.field_str("userId") {
+                if expected != user_id {
+                    return Err(CheckError::Auth(format!(
+                        "accessToken 属于 {}，与配置的 userId {} 不一致",
+                        user_id, expected
+                    )));
+                }
+            }
+            Ok(Some(user_id))
+        }
+        None => Err(CheckError::Other(format!("Matrix 返回错误状态: {}", status))),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// 邮件渠道：IMAP / SMTP 登录检测（阻塞 IO，在 spawn_blocking 中执行）
+// ---------------------------------------------------------------------------
+
+trait MailStream: Read + Write {}
+impl<T: Read + Write> MailStream for T {}
+
+fn connect_tcp(host: &str, port: u16) -> Result<TcpStream, CheckError> {
+    let addr = (host, port)
+        .to_socket_addrs()
+        .map_err(|e| CheckError::Unreachable(format!("无法解析 {}: {}", host, e)))?
+        .next()
+        .ok_or_else(|| CheckError::Unreachable(format!("无法解析 {}", host)))?;
+    let stream = TcpStream::connect_timeout(&addr, CHANNEL_CHECK_TIMEOUT)
+        .map_err(|e| CheckError::Unreachable(format!("无法连接 {}:{}: {}", host, port, e)))?;
+    let _ = stream.set_read_timeout(Some(CHANNEL_CHECK_TIMEOUT));
+    let _ = stream.set_write_timeout(Some(CHANNEL_CHECK_TIMEOUT));
+    Ok(stream)
+}
+
+fn wrap_tls(host: &str, stream: TcpStream) -> Result<native_tls::TlsStream<TcpStream>, CheckError> {
+    let connector = native_tls::TlsConnector::new()
+        .map_err(|e| CheckError::Other(format!("初始化 TLS 失败: {}", e)))?;
+    connector
+        .connect(host, stream)
+        .map_err(|e| CheckError::Unreachable(format!("TLS 握手失败: {}", e)))
+}
+
+fn io_error(e: std::io::Error) -> CheckError {
+    CheckError::Unreachable(format!("连接中断: {}", e))
+}
+
+/// IMAP 字符串参数使用带引号的形式
+fn imap_quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn check_imap(channel: &ChannelConfig) -> Result<(), CheckError> {
+    let host = require(channel, "imapHost")?;
+    let username = require(channel, "imapUsername")?;
+    let password = require(channel, "imapPassword")?;
+    let use_ssl = channel.field_bool("imapUseSsl").unwrap_or(true);
+    let port = channel.field_port("imapPort").unwrap_or(if use_ssl { 993 } else { 143 });
+
+    let tcp = connect_tcp(host, port)?;
+    let stream: Box<dyn MailStream> = if use_ssl {
+        Box::new(wrap_tls(host, tcp)?)
+    } else {
+        Box::new(tcp)
+    };
+    let mut reader = BufReader::new(stream);
+
+    let mut line = String::new();
+    reader.read_line(&mut line).map_err(io_error)?;
+    if !line.starts_with("* OK") {
+        return Err(CheckError::Other(format!("IMAP 服务器响应异常: {}", line.trim())));
+    }
+
+    let command = format!("a1 LOGIN {} {}\r\n", imap_quote(username), imap_quote(password));
+    reader.get_mut().write_all(command.as_bytes()).map_err(io_error)?;
+
+    loop {
+        line.clear();
+        if reader.read_line(&mut line).map_err(io_error)? == 0 {
+            return Err(CheckError::Unreachable("IMAP 连接被关闭".to_string()));
+        }
+        if let Some(status) = line.strip_prefix("a1 ") {
+            let _ = reader.get_mut().write_all(b"a2 LOGOUT\r\n");
+            return if status.starts_with("OK") {
+                Ok(())
+            } else {
+                Err(CheckError::Auth(format!("IMAP 登录失败: {}", status.trim())))
+            };
+        }
+    }
+}
+
+/// 读取一条完整的 SMTP 响应（可能为多行），返回状态码和最后一行
+fn smtp_read(reader: &mut impl BufRead) -> Result<(u16, String), CheckError> {
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line).map_err(io_error)? == 0 {
+            return Err(CheckError::Unreachable("SMTP 连接被关闭".to_string()));
+        }
+        // 多行响应以 "250-" 形式续行，最后一行为 "250 "
+        if line.len() < 4 || line.as_bytes()[3] != b'-' {
+            break;
+        }
+    }
+    let code = line.get(..3).and_then(|c| c.parse().ok()).unwrap_or(0);
+    Ok((code, line.trim().to_string()))
+}
+
+fn smtp_command<S: Read + Write>(
+    reader: &mut BufReader<S>,
+    command: &str,
+    expected: u16,
+) -> Result<(u16, String), CheckError> {
+    reader
+        .get_mut()
+        .write_all(format!("{}\r\n", command).as_bytes())
+        .map_err(io_error)?;
+    let (code, line) = smtp_read(reader)?;
+    if code != expected {
+        return Err(CheckError::Other(format!("SMTP 服务器响应异常: {}", line)));
+    }
+    Ok((code, line))
+}
+
+/// EHLO 之后使用 AUTH PLAIN 登录
+fn smtp_login<S: Read + Write>(reader: &mut BufReader<S>, username: &str, password: &str) -> Result<(), CheckError> {
+    smtp_command(reader, "EHLO nanoboard", 250)?;
+    let credentials = base64::engine::general_purpose::STANDARD
+        .encode(format!("\0{}\0{}", username, password));
+    reader
+        .get_mut()
+        .write_all(format!("AUTH PLAIN {}\r\n", credentials).as_bytes())
+        .map_err(io_error)?;
+    let (code, line) = smtp_read(reader)?;
+    let _ = reader.get_mut().write_all(b"QUIT\r\n");
+    match code {
+        235 => Ok(()),
+        535 | 534 | 530 => Err(CheckError::Auth(format!("SMTP 登录失败: {}", line))),
+        _ => Err(CheckError::Other(format!("SMTP 服务器响应异常: {}", line))),
+    }
+}
+
+fn check_smtp(channel: &ChannelConfig) -> Result<(), CheckError> {
+    let host = require(channel, "smtpHost")?;
+    let username = require(channel, "smtpUsername")?;
+    let password = require(channel, "smtpPassword")?;
+    let use_ssl = channel.field_bool("smtpUseSsl").unwrap_or(false);
+    let use_tls = channel.field_bool("smtpUseTls").unwrap_or(true);
+    let port = channel.field_port("smtpPort").unwrap_or(if use_ssl { 465 } else { 587 });
+
+    let tcp = connect_tcp(host, port)?;
+    if use_ssl {
+        let mut reader = BufReader::new(wrap_tls(host, tcp)?);
+        smtp_read(&mut reader)?;
+        return smtp_login(&mut reader, username, password);
+    }
+
+    let mut reader = BufReader::new(tcp);
+    smtp_read(&mut reader)?;
+    if !use_tls {
+        return smtp_login(&mut reader, username, password);
+    }
+
+    smtp_command(&mut reader, "EHLO nanoboard", 250)?;
+    smtp_command(&mut reader, "STARTTLS", 220)?;
+    let mut reader = BufReader::new(wrap_tls(host, reader.into_inner())?);
+    smtp_login(&mut reader, username, password)
+}
+
+async fn check_email(channel: &ChannelConfig) -> CheckOutcome {
+    let channel = channel.clone();
+    tokio::task::spawn_blocking(move || {
+        check_imap(&channel)?;
+        check_smtp(&channel)?;
+        Ok(channel.field_str("imapUsername").map(|s| s.to_string()))
+    })
+    .await
+    .map_err(|e| CheckError::Other(format!("检测任务失败: {}", e)))?
+}
+
+/// 使用指定的 API 地址检测单个渠道
+pub async fn check_channel_with(
+    name: &str,
+    channel: &ChannelConfig,
+    endpoints: &ChannelCheckEndpoints,
+) -> ChannelCheckResult {
+    let started = Instant::now();
+    let outcome = match name {
+        "telegram" => check_telegram(channel, endpoints).await,
+        "discord" => check_discord(channel, endpoints).await,
+        "slack" => check_slack(channel, endpoints).await,
+        "feishu" => check_feishu(channel, endpoints).await,
+        "dingtalk" => check_dingtalk(channel, endpoints).await,
+        "email" => check_email(channel).await,
+        "matrix" => check_matrix(channel, endpoints).await,
+        _ => return ChannelCheckResult::new(name, "unsupported", "暂不支持检测该渠道"),
+    };
+    let latency_ms = Some(started.elapsed().as_millis() as u64);
+
+    let result = match outcome {
+        Ok(identity) => ChannelCheckResult::ok(name, identity),
+        Err(CheckError::NotConfigured(message)) => return ChannelCheckResult::new(name, "not_configured", message),
+        Err(CheckError::Auth(message)) => ChannelCheckResult::new(name, "auth_failed", message),
+        Err(CheckError::Unreachable(message)) => ChannelCheckResult::new(name, "unreachable", message),
+        Err(CheckError::Other(message)) => ChannelCheckResult::new(name, "error", message),
+    };
+    ChannelCheckResult { latency_ms, ..result }
+}
+
+/// 渠道配置中引用了未定义的环境变量时返回跳过结果，与提供商检测一致
+fn skipped_for_unresolved(name: &str, unresolved: &[UnresolvedReference]) -> Option<ChannelCheckResult> {
+    let prefix = format!("channels.{}.", name);
+    let variables: Vec<&str> = unresolved
+        .iter()
+        .filter(|r| r.path.starts_with(&prefix))
+        .map(|r| r.variable.as_str())
+        .collect();
+    if variables.is_empty() {
+        return None;
+    }
+    Some(ChannelCheckResult::new(
+        name,
+        "skipped",
+        format!("引用的环境变量未定义: {}", variables.join(", ")),
+    ))
+}
+
+/// 检测消息渠道凭据
+/// 指定 name 时只检测该渠道（无论是否启用），否则检测所有已启用的渠道
+#[tauri::command]
+pub async fn check_channels(name: Option<String>) -> Result<JsonValue, String> {
+    let config = crate::config::load_config_internal()?;
+    if config.get("error").and_then(|e| e.as_str()) == Some("config_not_found") {
+        return Ok(json!({
+            "success": false,
+            "message": "配置文件不存在",
+            "results": []
+        }));
+    }
+
+    // 使用展开环境变量后的配置，仅在内存中使用
+    let interpolated = crate::interpolation::interpolate_config(&config);
+    let channels = NanobotConfig::from_json(&interpolated.config)?
+        .channels
+        .unwrap_or_default()
+        .entries;
+
+    let endpoints = ChannelCheckEndpoints::default();
+    let mut skipped = Vec::new();
+    let mut handles = Vec::new();
+    for (channel_name, channel) in channels {
+        let selected = match &name {
+            Some(n) => *n == channel_name,
+            None => channel.is_enabled(),
+        };
+        if !selected {
+            continue;
+        }
+        if let Some(result) = skipped_for_unresolved(&channel_name, &interpolated.unresolved) {
+            skipped.push(result);
+            continue;
+        }
+        let endpoints = endpoints.clone();
+        handles.push(tokio::spawn(async move {
+            check_channel_with(&channel_name, &channel, &endpoints).await
+        }));
+    }
+
+    if handles.is_empty() && skipped.is_empty() {
+        return Ok(json!({
+            "success": false,
+            "message": match &name {
+                Some(n) => format!("渠道 {} 未配置", n),
+                None => "没有启用任何消息渠道".to_string(),
+            },
+            "results": []
+        }));
+    }
+
+    let mut results = skipped;
+    for handle in handles {
+        match handle.await {
+            Ok(result) => results.push(result),
+            Err(e) => log::warn!("渠道检测任务失败: {}", e),
+        }
+    }
+
+    let ok_count = results.iter().filter(|r| r.status == "ok").count();
+    Ok(json!({
+        "success": true,
+        "message": format!("{}/{} 个渠道凭据有效", ok_count, results.len()),
+        "results": results
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::stub_server;
+    use std::net::TcpListener;
+    use std::sync::{Arc, Mutex};
+
+    fn endpoints(base: &str) -> ChannelCheckEndpoints {
+        ChannelCheckEndpoints {
+            telegram: base.to_string(),
+            discord: base.to_string(),
+            slack: base.to_string(),
+            feishu: base.to_string(),
+            dingtalk: base.to_string(),
+            matrix: base.to_string(),
+        }
+    }
+
+    fn channel(value: JsonValue) -> ChannelConfig {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[tokio::test]
+    async fn checks_telegram_get_me() {
+        let base = stub_server(vec![
+            ("/bot123:good/getMe", 200, r#"{"ok":true,"result":{"username":"nano_bot"}}"#),
+            ("/bot123:revoked/getMe", 401, r#"{"ok":false,"description":"Unauthorized"}"#),
+        ])
+        .await;
+        let endpoints = endpoints(&base);
+
+        let result = check_channel_with("telegram", &channel(json!({ "token": "123:good" })), &endpoints).await;
+        assert_eq!(result.status, "ok", "{}", result.message);
+        assert_eq!(result.identity.as_deref(), Some("@nano_bot"));
+        assert!(result.latency_ms.is_some());
+
+        let result = check_channel_with("telegram", &channel(json!({ "token": "123:revoked" })), &endpoints).await;
+        assert_eq!(result.status, "auth_failed");
+        assert!(result.message.contains("Unauthorized"));
+
+        // 格式错误的 token，Telegram 返回 404
+        let result = check_channel_with("telegram", &channel(json!({ "token": "bad" })), &endpoints).await;
+        assert_eq!(result.status, "auth_failed");
+
+        let result = check_channel_with("telegram", &channel(json!({})), &endpoints).await;
+        assert_eq!(result.status, "not_configured");
+        assert_eq!(result.latency_ms, None);
+    }
+
+    #[tokio::test]
+    async fn checks_slack_auth_test() {
+        let ok = stub_server(vec![("/auth.test", 200, r#"{"ok":true,"user":"nanobot","team":"acme"}"#)]).await;
+        let result = check_channel_with("slack", &channel(json!({ "botToken": "xoxb-1" })), &endpoints(&ok)).await;
+        assert_eq!(result.status, "ok", "{}", result.message);
+        assert_eq!(result.identity.as_deref(), Some("nanobot @ acme"));
+
+        // Slack 鉴权失败时仍返回 200，由 ok 字段区分
+        let denied = stub_server(vec![("/auth.test", 200, r#"{"ok":false,"error":"invalid_auth"}"#)]).await;
+        let result = check_channel_with("slack", &channel(json!({ "botToken": "xoxb-1" })), &endpoints(&denied)).await;
+        assert_eq!(result.status, "auth_failed");
+        assert!(result.message.contains("invalid_auth"));
+
+        // Socket Mode 下继续校验 appToken
+        let app_denied = stub_server(vec![
+            ("/auth.test", 200, r#"{"ok":true,"user":"nanobot"}"#),
+            ("/apps.connections.open", 200, r#"{"ok":false,"error":"not_allowed_token_type"}"#),
+        ])
+        .await;
+        let config = channel(json!({ "botToken": "xoxb-1", "appToken": "xapp-1" }));
+        let result = check_channel_with("slack", &config, &endpoints(&app_denied)).await;
+        assert_eq!(result.status, "auth_failed");
+        assert!(result.message.starts_with("appToken"));
+    }
+
+    #[tokio::test]
+    async fn checks_matrix_whoami() {
+        let base = stub_server(vec![(
+            "/_matrix/client/v3/account/whoami",
+            200,
+            r#"{"user_id":"@bot:example.org"}"#,
+        )])
+        .await;
+
+        // 未配置 homeserver 时使用默认服务器
+        let result = check_channel_with("matrix", &channel(json!({ "accessToken": "syt" })), &endpoints(&base)).await;
+        assert_eq!(result.status, "ok", "{}", result.message);
+        assert_eq!(result.identity.as_deref(), Some("@bot:example.org"));
+
+        let config = channel(json!({ "homeserver": base, "accessToken": "syt", "userId": "@other:example.org" }));
+        let defaults = ChannelCheckEndpoints::default();
+        let result = check_channel_with("matrix", &config, &defaults).await;
+        assert_eq!(result.status, "auth_failed");
+
+        let denied = stub_server(vec![(
+            "/_matrix/client/v3/account/whoami",
+            401,
+            r#"{"errcode":"M_UNKNOWN_TOKEN","error":"Invalid access token"}"#,
+        )])
+        .await;
+        let result = check_channel_with("matrix", &channel(json!({ "accessToken": "syt" })), &endpoints(&denied)).await;
+        assert_eq!(result.status, "auth_failed");
+        assert!(result.message.contains("Invalid access token"));
+    }
+
+    #[tokio::test]
+    async fn checks_discord_gateway() {
+        let base = stub_server(vec![
+            ("/gateway/bot", 200, r#"{"url":"wss://gateway.discord.gg","shards":1}"#),
+            ("/users/@me", 200, r#"{"id":"1","username":"nanobot"}"#),
+        ])
+        .await;
+        let result = check_channel_with("discord", &channel(json!({ "token": "t" })), &endpoints(&base)).await;
+        assert_eq!(result.status, "ok", "{}", result.message);
+        assert_eq!(result.identity.as_deref(), Some("nanobot"));
+
+        // 获取用户名失败不影响检测结果
+        let base = stub_server(vec![("/gateway/bot", 200, r#"{"url":"wss://gateway.discord.gg"}"#)]).await;
+        let result = check_channel_with("discord", &channel(json!({ "token": "t" })), &endpoints(&base)).await;
+        assert_eq!(result.status, "ok", "{}", result.message);
+        assert_eq!(result.identity, None);
+
+        let denied = stub_server(vec![("/gateway/bot", 401, r#"{"message":"401: Unauthorized","code":0}"#)]).await;
+        let result = check_channel_with("discord", &channel(json!({ "token": "t" })), &endpoints(&denied)).await;
+        assert_eq!(result.status, "auth_failed");
+        assert!(result.message.contains("Unauthorized"));
+
+        let broken = stub_server(vec![("/gateway/bot", 200, r#"{}"#)]).await;
+        let result = check_channel_with("discord", &channel(json!({ "token": "t" })), &endpoints(&broken)).await;
+        assert_eq!(result.status, "error");
+    }
+
+    #[tokio::test]
+    async fn checks_feishu_tenant_token() {
+        const PATH: &str = "/open-apis/auth/v3/tenant_access_token/internal";
+        let config = channel(json!({ "appId": "cli_a1", "appSecret": "s" }));
+
+        let ok = stub_server(vec![(PATH, 200, r#"{"code":0,"msg":"ok","tenant_access_token":"t-1"}"#)]).await;
+        let result = check_channel_with("feishu", &config, &endpoints(&ok)).await;
+        assert_eq!(result.status, "ok", "{}", result.message);
+        assert_eq!(result.identity.as_deref(), Some("cli_a1"));
+
+        // 飞书鉴权失败时返回 200 与非零 code
+        let denied = stub_server(vec![(PATH, 200, r#"{"code":10014,"msg":"app secret invalid"}"#)]).await;
+        let result = check_channel_with("feishu", &config, &endpoints(&denied)).await;
+        assert_eq!(result.status, "auth_failed");
+        assert!(result.message.contains("10014") && result.message.contains("app secret invalid"));
+
+        let broken = stub_server(vec![(PATH, 502, "bad gateway")]).await;
+        let result = check_channel_with("feishu", &config, &endpoints(&broken)).await;
+        assert_eq!(result.status, "error");
+        assert!(result.message.contains("502"));
+
+        let result = check_channel_with("feishu", &channel(json!({ "appId": "cli_a1" })), &endpoints(&ok)).await;
+        assert_eq!(result.status, "not_configured");
+    }
+
+    #[tokio::test]
+    async fn checks_dingtalk_access_token() {
+        const PATH: &str = "/v1.0/oauth2/accessToken";
+        let config = channel(json!({ "clientId": "ding1", "clientSecret": "s" }));
+
+        let ok = stub_server(vec![(PATH, 200, r#"{"accessToken":"t-1","expireIn":7200}"#)]).await;
+        let result = check_channel_with("dingtalk", &config, &endpoints(&ok)).await;
+        assert_eq!(result.status, "ok", "{}", result.message);
+        assert_eq!(result.identity.as_deref(), Some("ding1"));
+
+        let denied = stub_server(vec![(PATH, 400, r#"{"code":"invalidClientSecret","message":"secret invalid"}"#)]).await;
+        let result = check_channel_with("dingtalk", &config, &endpoints(&denied)).await;
+        assert_eq!(result.status, "auth_failed");
+        assert!(result.message.contains("secret invalid"));
+
+        let broken = stub_server(vec![(PATH, 500, "{}")]).await;
+        let result = check_channel_with("dingtalk", &config, &endpoints(&broken)).await;
+        assert_eq!(result.status, "error");
+    }
+
+    /// 收到的命令行
+    type Received = Arc<Mutex<Vec<String>>>;
+
+    /// 按脚本应答的本地 TCP 服务（模拟 IMAP / SMTP）
+    /// 连接后先发送 greeting，之后每收到一行由 reply 生成响应，返回 None 时关闭连接
+    fn line_server(greeting: &'static str, reply: fn(&str) -> Option<String>) -> (u16, Received) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let received = Received::default();
+        let lines = received.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else {
+                    return;
+                };
+                let _ = stream.write_all(greeting.as_bytes());
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut line = String::new();
+                while matches!(reader.read_line(&mut line), Ok(n) if n > 0) {
+                    let command = line.trim_end().to_string();
+                    lines.lock().unwrap().push(command.clone());
+                    match reply(&command) {
+                        Some(response) => {
+                            let _ = stream.write_all(response.as_bytes());
+                        }
+                        None => break,
+                    }
+                    line.clear();
+                }
+            }
+        });
+        (port, received)
+    }
+
+    /// 客户端发送最后一条命令后不等待响应，服务线程可能稍后才读到
+    fn received_eventually(received: &Received, command: &str) -> bool {
+        for _ in 0..100 {
+            if received.lock().unwrap().iter().any(|c| c == command) {
+                return true;
+            }
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        false
+    }
+
+    fn imap_reply(command: &str) -> Option<String> {
+        if command.starts_with("a1 LOGIN \"bot@example.com\" \"pa\\\"ss\"") {
+            Some("* CAPABILITY IMAP4rev1\r\na1 OK LOGIN completed\r\n".to_string())
+        } else if command.starts_with("a1 LOGIN") {
+            Some("a1 NO [AUTHENTICATIONFAILED] Invalid credentials\r\n".to_string())
+        } else {
+            None
+        }
+    }
+
+    fn smtp_reply(command: &str) -> Option<String> {
+        let expected = base64::engine::general_purpose::STANDARD.encode("\0bot@example.com\0pa\"ss");
+        if command.starts_with("EHLO") {
+            Some("250-stub.example.com\r\n250-AUTH PLAIN LOGIN\r\n250 OK\r\n".to_string())
+        } else if command == format!("AUTH PLAIN {}", expected) {
+            Some("235 2.7.0 Authentication successful\r\n".to_string())
+        } else if command.starts_with("AUTH PLAIN") {
+            Some("535 5.7.8 Authentication credentials invalid\r\n".to_string())
+        } else {
+            None
+        }
+    }
+
+    fn email_channel(imap_port: u16, smtp_port: u16, password: &str) -> ChannelConfig {
+        channel(json!({
+            "imapHost": "127.0.0.1",
+            "imapPort": imap_port,
+            "imapUsername": "bot@example.com",
+            "imapPassword": password,
+            "imapUseSsl": false,
+            "smtpHost": "127.0.0.1",
+            "smtpPort": smtp_port,
+            "smtpUsername": "bot@example.com",
+            "smtpPassword": password,
+            "smtpUseSsl": false,
+            "smtpUseTls": false
+        }))
+    }
+
+    #[tokio::test]
+    async fn checks_email_imap_and_smtp_login() {
+        let (imap_port, imap_lines) = line_server("* OK IMAP4rev1 ready\r\n", imap_reply);
+        let (smtp_port, smtp_lines) = line_server("220 stub.example.com ESMTP\r\n", smtp_reply);
+        let defaults = ChannelCheckEndpoints::default();
+
+        // 密码中的引号在 IMAP 命令中被转义
+        let result = check_channel_with("email", &email_channel(imap_port, smtp_port, "pa\"ss"), &defaults).await;
+        assert_eq!(result.status, "ok", "{}", result.message);
+        assert_eq!(result.identity.as_deref(), Some("bot@example.com"));
+        assert!(received_eventually(&imap_lines, "a2 LOGOUT"));
+        assert!(received_eventually(&smtp_lines, "QUIT"));
+
+        // IMAP 登录被拒绝时不再检测 SMTP
+        let smtp_count = smtp_lines.lock().unwrap().len();
+        let result = check_channel_with("email", &email_channel(imap_port, smtp_port, "wrong"), &defaults).await;
+        assert_eq!(result.status, "auth_failed");
+        assert!(result.message.contains("AUTHENTICATIONFAILED"), "{}", result.message);
+        assert_eq!(smtp_lines.lock().unwrap().len(), smtp_count);
+
+        // IMAP 通过、SMTP 被拒绝
+        let (imap_ok, _) = line_server("* OK ready\r\n", |_| Some("a1 OK LOGIN completed\r\n".to_string()));
+        let result = check_channel_with("email", &email_channel(imap_ok, smtp_port, "wrong"), &defaults).await;
+        assert_eq!(result.status, "auth_failed");
+        assert!(result.message.starts_with("SMTP 登录失败"), "{}", result.message);
+    }
+
+    #[tokio::test]
+    async fn reports_unexpected_mail_server_responses() {
+        let defaults = ChannelCheckEndpoints::default();
+
+        // 问候语不是 IMAP 的 * OK
+        let (not_imap, _) = line_server("220 smtp.example.com ESMTP\r\n", |_| None);
+        let result = check_channel_with("email", &email_channel(not_imap, 1, "x"), &defaults).await;
+        assert_eq!(result.status, "error");
+        assert!(result.message.contains("IMAP 服务器响应异常"), "{}", result.message);
+
+        // 登录过程中连接被关闭
+        let (closing, _) = line_server("* OK ready\r\n", |_| None);
+        let result = check_channel_with("email", &email_channel(closing, 1, "x"), &defaults).await;
+        assert_eq!(result.status, "unreachable");
+
+        // SMTP 的 EHLO 没有返回 250
+        let (imap_ok, _) = line_server("* OK ready\r\n", |_| Some("a1 OK LOGIN completed\r\n".to_string()));
+        let (bad_smtp, _) = line_server("220 ready\r\n", |_| Some("502 5.5.1 Unrecognized command\r\n".to_string()));
+        let result = check_channel_with("email", &email_channel(imap_ok, bad_smtp, "x"), &defaults).await;
+        assert_eq!(result.status, "error");
+        assert!(result.message.contains("502"), "{}", result.message);
+    }
+
+    #[test]
+    fn skips_channels_with_unresolved_references() {
+        let config = json!({
+            "channels": {
+                "telegram": { "enabled": true, "token": "${TG_TOKEN_NOT_SET}" },
+                "slack": { "enabled": true, "botToken": "xoxb-1" }
+            }
+        });
+        let lookup = |_: &str| None;
+        let unresolved = crate::interpolation::interpolate_config_with(&config, &lookup).unresolved;
+
+        let result = skipped_for_unresolved("telegram", &unresolved).unwrap();
+        assert_eq!(result.status, "skipped");
+        assert!(result.message.contains("TG_TOKEN_NOT_SET"));
+        assert!(skipped_for_unresolved("slack", &unresolved).is_none());
+        // 名称前缀相同的渠道互不影响
+        assert!(skipped_for_unresolved("tele", &unresolved).is_none());
+    }
+}
@@ -0,0 +1,576 @@
+// 应用内对话控制台
+// 通过 `nanobot agent -m` 子进程向本地 agent 发送消息（gateway 目前没有可用的本地对话接口）：
+// - 每次发送是一轮对话，立即返回 turn_id，过程通过 chat-console 事件推送到调用的窗口；
+// - stdout 按行推送（"↳" 开头的为进度/工具提示），stderr 的 loguru 日志解析后推送，工具调用单独标记；
+// - 结束后从会话文件读取本轮新增的消息（含工具调用及结果）一并推送；
+// - 对话由 nanobot 自己写入会话文件，因此会出现在 list_chat_sessions 中；
+// - 网关运行时只允许 nanoboard: 开头的会话键：其他渠道的会话由网关在内存中持有并整体写回，
+//   同时由 agent 子进程写入会导致其中一方的消息丢失。
+
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+use tokio::io::{AsyncBufReadExt, BufReader};
+
+/// 未指定会话时使用的会话键，channel 为 nanoboard
+const DEFAULT_SESSION_KEY: &str = "nanoboard:console";
+
+/// 控制台自己的会话键前缀，网关不会读写这些会话
+const CONSOLE_KEY_PREFIX: &str = "nanoboard:";
+
+/// 默认单轮超时
+const DEFAULT_TIMEOUT_SECS: u64 = 300;
+
+/// 进行中的一轮对话
+struct ChatTurn {
+    session_key: String,
+    /// 发送后终止子进程
+    cancel: Option<tokio::sync::oneshot::Sender<()>>,
+}
+
+/// 对话控制台状态
+#[derive(Default)]
+pub struct ChatConsoleState {
+    turns: Mutex<HashMap<u64, ChatTurn>>,
+    next_id: Mutex<u64>,
+}
+
+impl ChatConsoleState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 登记新一轮对话，同一会话同时只允许一轮
+    fn begin(
+        &self,
+        session_key: &str,
+    ) -> Result<(u64, tokio::sync::oneshot::Receiver<()>), String> {
+        let mut turns = self.turns.lock().unwrap();
+        if turns.values().any(|t| t.session_key == session_key) {
+            return Err(format!("会话 {} 正在等待回复", session_key));
+        }
+        let id = {
+            let mut next_id = self.next_id.lock().unwrap();
+            *next_id += 1;
+            *next_id
+        };
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        turns.insert(
+            id,
+            ChatTurn {
+                session_key: session_key.to_string(),
+                cancel: Some(tx),
+            },
+        );
+        Ok((id, rx))
+    }
+
+    fn finish(&self, id: u64) {
+        self.turns.lock().unwrap().remove(&id);
+    }
+
+    /// 取消一轮对话，返回是否存在
+    fn cancel(&self, id: u64) -> bool {
+        let mut turns = self.turns.lock().unwrap();
+        match turns.get_mut(&id).and_then(|t| t.cancel.take()) {
+            Some(tx) => {
+                let _ = tx.send(());
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// 发送请求
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChatSendRequest {
+    pub message: String,
+    /// 会话键（如 nanoboard:console），与 session_id 二选一
+    #[serde(default)]
+    pub session_key: Option<String>,
+    /// 已有会话的文件名，从其元数据中读取会话键
+    #[serde(default)]
+    pub session_id: Option<String>,
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+}
+
+/// 与 nanobot 相同的会话文件名规则：冒号及文件名非法字符替换为下划线
+fn session_file_name(key: &str) -> String {
+    let safe: String = key
+        .chars()
+        .map(|c| match c {
+            ':' | '<' | '>' | '"' | '/' | '\\' | '|' | '?' | '*' => '_',
+            c => c,
+        })
+        .collect();
+    format!("{}.jsonl", safe.trim())
+}
+
+/// 解析请求对应的会话键
+fn resolve_session_key(request: &ChatSendRequest) -> Result<String, String> {
+    if let Some(session_id) = request.session_id.as_deref().filter(|s| !s.is_empty()) {
+        if session_id.contains('/') || session_id.contains('\\') || !session_id.ends_with(".jsonl")
+        {
+            return Err("无效的会话 ID 格式".to_string());
+        }
+        return crate::session::read_chat_session_key(session_id)
+            .ok_or_else(|| format!("无法读取会话 {} 的会话键", session_id));
+    }
+
+    let key = request
+        .session_key
+        .as_deref()
+        .map(str::trim)
+        .filter(|k| !k.is_empty())
+        .unwrap_or(DEFAULT_SESSION_KEY);
+    if key.len() > 128 || key.chars().any(|c| c.is_control()) {
+        return Err("无效的会话键".to_string());
+    }
+    Ok(key.to_string())
+}
+
+/// 网关运行时拒绝由网关管理的会话
+fn check_session_owner(session_key: &str, gateway_running: bool) -> Result<(), String> {
+    if gateway_running && !session_key.starts_with(CONSOLE_KEY_PREFIX) {
+        return Err(format!(
+            "会话 {} 由 nanobot 网关管理，网关运行时只能使用 {} 开头的会话，请先停止网关",
+            session_key, CONSOLE_KEY_PREFIX
+        ));
+    }
+    Ok(())
+}
+
+/// 会话当前的消息数，文件不存在时为 0
+fn message_count(path: &Path) -> usize {
+    if !path.exists() {
+        return 0;
+    }
+    crate::session_index::read_messages(path, usize::MAX, Some(0))
+        .map(|page| page.total)
+        .unwrap_or(0)
+}
+
+/// 推送一条控制台事件
+fn emit(app: &tauri::AppHandle, target: &str, turn_id: u64, kind: &str, data: serde_json::Value) {
+    let mut payload = json!({ "turn_id": turn_id, "kind": kind });
+    if let (Some(payload), serde_json::Value::Object(data)) = (payload.as_object_mut(), data) {
+        payload.extend(data);
+    }
+    if let Err(e) = app.emit_to(target, "chat-console", payload) {
+        log::warn!("发送对话事件到窗口 {} 失败: {:?}", target, e);
+    }
+}
+
+/// 执行一轮对话
+#[allow(clippy::too_many_arguments)]
+async fn run_turn(
+    app: tauri::AppHandle,
+    target: String,
+    turn_id: u64,
+    session_key: String,
+    session_path: PathBuf,
+    message: String,
+    timeout: Duration,
+    cancel: tokio::sync::oneshot::Receiver<()>,
+) {
+    let before = {
+        let path = session_path.clone();
+        tokio::task::spawn_blocking(move || message_count(&path))
+            .await
+            .unwrap_or(0)
+    };
+
+    match run_agent(
+        &app,
+        &target,
+        turn_id,
+        &session_key,
+        &message,
+        timeout,
+        cancel,
+    )
+    .await
+    {
+        Ok(exit_code) => {
+            // 读取本轮写入会话文件的消息
+            let path = session_path.clone();
+            let page = tokio::task::spawn_blocking(move || {
+                crate::session_index::read_messages(&path, before, None)
+            })
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|r| r.map_err(|e| e.to_string()));
+            let messages = match page {
+                Ok(page) => page.messages,
+                Err(e) => {
+                    log::warn!("读取会话 {} 的新消息失败: {}", session_key, e);
+                    Vec::new()
+                }
+            };
+            let reply = messages
+                .iter()
+                .rev()
+                .find(|m| m.role == "assistant" && !m.content.is_empty())
+                .map(|m| m.content.clone());
+            emit(
+                &app,
+                &target,
+                turn_id,
+                "done",
+                json!({
+                    "session_key": session_key,
+                    "session_id": session_path.file_name().map(|n| n.to_string_lossy().to_string()),
+                    "exit_code": exit_code,
+                    "success": exit_code == Some(0),
+                    "reply": reply,
+                    "messages": messages
+                }),
+            );
+        }
+        Err(e) => {
+            emit(
+                &app,
+                &target,
+                turn_id,
+                "error",
+                json!({ "session_key": session_key, "message": e }),
+            );
+        }
+    }
+
+    app.state::<Arc<ChatConsoleState>>().finish(turn_id);
+}
+
+/// 启动 nanobot agent 子进程并转发输出，返回退出码
+async fn run_agent(
+    app: &tauri::AppHandle,
+    target: &str,
+    turn_id: u64,
+    session_key: &str,
+    message: &str,
+    timeout: Duration,
+    cancel: tokio::sync::oneshot::Receiver<()>,
+) -> Result<Option<i32>, String> {
+    let (nanobot_cmd, _, module_args) = crate::process::find_nanobot_command()
+        .ok_or("未找到 nanobot 命令，请先安装 nanobot-ai 或配置正确的 Python 路径")?;
+    let launch = crate::interpolation::launch_config(&nanobot_cmd, &module_args, "agent")
+        .await
+        .map_err(|e| format!("无法展开配置中的环境变量: {}", e))?;
+    // 运行时配置在本轮对话结束（子进程退出）后才释放
+    let _resolved = launch.resolved;
+
+    let mut args = module_args;
+    args.extend([
+        "agent".to_string(),
+        "-m".to_string(),
+        message.to_string(),
+        "-s".to_string(),
+        session_key.to_string(),
+        "--no-markdown".to_string(),
+        "--logs".to_string(),
+    ]);
+    args.extend(launch.args);
+
+    let command = crate::process::apply_hidden_window(std::process::Command::new(&nanobot_cmd));
+    let mut command = tokio::process::Command::from(command);
+    command
+        .args(&args)
+        .envs(launch.envs)
+        .env("PYTHONUTF8", "1")
+        .env("PYTHONIOENCODING", "utf-8");
+    log::info!("对话控制台正在启动 nanobot agent (会话: {})", session_key);
+
+    let sink: EventSink = {
+        let app = app.clone();
+        let target = target.to_string();
+        Arc::new(move |kind, data| emit(&app, &target, turn_id, kind, data))
+    };
+    drive_agent(command, timeout, cancel, sink).await
+}
+
+/// 子进程输出事件的接收者，参数为事件类型与数据
+type EventSink = Arc<dyn Fn(&str, serde_json::Value) + Send + Sync>;
+
+/// 子进程结束后等待输出读完的最长时间
+/// 脱离进程组的孙进程可能一直持有输出管道，超时后不再等待
+const OUTPUT_DRAIN_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// 终止子进程
+/// Unix 下终止整个进程组，nanobot agent 启动的 MCP stdio 服务器会一并结束，不会继续持有输出管道
+async fn kill_agent(child: &mut tokio::process::Child) {
+    #[cfg(unix)]
+    if let Some(pid) = child.id() {
+        // 子进程以自身 PID 作为进程组 ID 启动，负数 PID 表示整个进程组
+        unsafe {
+            libc::kill(-(pid as libc::pid_t), libc::SIGKILL);
+        }
+    }
+    let _ = child.kill().await;
+}
+
+/// 运行子进程并把输出转发给 sink，超时或取消时终止子进程，返回退出码
+async fn drive_agent(
+    mut command: tokio::process::Command,
+    timeout: Duration,
+    mut cancel: tokio::sync::oneshot::Receiver<()>,
+    sink: EventSink,
+) -> Result<Option<i32>, String> {
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    #[cfg(unix)]
+    command.process_group(0);
+    let mut child = command
+        .spawn()
+        .map_err(|e| format!("启动 nanobot agent 失败: {}", e))?;
+    log::info!("nanobot agent 已启动 (PID: {:?})", child.id());
+
+    let stdout = child.stdout.take().ok_or("无法读取 nanobot agent 输出")?;
+    let stderr = child.stderr.take().ok_or("无法读取 nanobot agent 输出")?;
+
+    let stdout_task = {
+        let sink = sink.clone();
+        tokio::spawn(async move {
+            let mut lines = BufReader::new(stdout).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                let trimmed = line.trim();
+                if trimmed.is_empty() {
+                    continue;
+                }
+                let kind = if trimmed.starts_with('↳') {
+                    "progress"
+                } else {
+                    "output"
+                };
+                sink(kind, json!({ "text": line }));
+            }
+        })
+    };
+    let stderr_task = tokio::spawn(async move {
+        let mut lines = BufReader::new(stderr).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            match crate::log_parser::parse_header(&line) {
+                Some(record) if record.message.starts_with("Tool call:") => {
+                    let call = record
+                        .message
+                        .trim_start_matches("Tool call:")
+                        .trim()
+                        .to_string();
+                    sink("tool", json!({ "text": call, "record": record }));
+                }
+                Some(record) => sink("log", json!({ "text": line, "record": record })),
+                None if !line.trim().is_empty() => sink("log", json!({ "text": line })),
+                None => {}
+            }
+        }
+    });
+
+    let result = tokio::select! {
+        status = child.wait() => status
+            .map(|s| s.code())
+            .map_err(|e| format!("等待 nanobot agent 失败: {}", e)),
+        _ = tokio::time::sleep(timeout) => {
+            kill_agent(&mut child).await;
+            Err(format!("等待回复超时（{} 秒）", timeout.as_secs()))
+        }
+        _ = &mut cancel => {
+            kill_agent(&mut child).await;
+            Err("已取消".to_string())
+        }
+    };
+
+    // 等输出读完再推送结束事件，保证事件顺序
+    for mut task in [stdout_task, stderr_task] {
+        if tokio::time::timeout(OUTPUT_DRAIN_TIMEOUT, &mut task).await.is_err() {
+            task.abort();
+        }
+    }
+    result
+}
+
+/// 向本地 agent 发送一条消息，回复通过 chat-console 事件推送
+#[tauri::command]
+pub async fn send_chat_message(
+    window: tauri::Window,
+    request: ChatSendRequest,
+    state: tauri::State<'_, Arc<ChatConsoleState>>,
+) -> Result<serde_json::Value, String> {
+    let message = request.message.trim().to_string();
+    if message.is_empty() {
+        return Err("消息不能为空".to_string());
+    }
+    let session_key = resolve_session_key(&request)?;
+    check_session_owner(&session_key, crate::process::check_nanobot_running())?;
+    let session_path = crate::session::get_chat_sessions_path()
+        .map_err(|e| e.to_string())?
+        .join(session_file_name(&session_key));
+    let timeout = Duration::from_secs(request.timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS).max(1));
+
+    let (turn_id, cancel) = state.begin(&session_key)?;
+    let session_id = session_path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string());
+    tokio::spawn(run_turn(
+        window.app_handle().clone(),
+        window.label().to_string(),
+        turn_id,
+        session_key.clone(),
+        session_path,
+        message,
+        timeout,
+        cancel,
+    ));
+
+    Ok(json!({
+        "success": true,
+        "turn_id": turn_id,
+        "session_key": session_key,
+        "session_id": session_id
+    }))
+}
+
+/// 取消进行中的一轮对话
+#[tauri::command]
+pub async fn cancel_chat_message(
+    turn_id: u64,
+    state: tauri::State<'_, Arc<ChatConsoleState>>,
+) -> Result<serde_json::Value, String> {
+    if !state.cancel(turn_id) {
+        return Err("对话不存在或已结束".to_string());
+    }
+    Ok(json!({ "success": true }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limits_gateway_sessions_while_running() {
+        assert!(check_session_owner(DEFAULT_SESSION_KEY, true).is_ok());
+        assert!(check_session_owner("nanoboard:debug", true).is_ok());
+        assert!(check_session_owner("telegram:42", true).is_err());
+        assert!(check_session_owner("telegram:42", false).is_ok());
+    }
+
+    #[test]
+    fn maps_keys_to_session_files() {
+        assert_eq!(session_file_name("nanoboard:console"), "nanoboard_console.jsonl");
+        assert_eq!(session_file_name("a/b:c?"), "a_b_c_.jsonl");
+
+        let request = |key: &str| ChatSendRequest {
+            message: "hi".to_string(),
+            session_key: Some(key.to_string()),
+            session_id: None,
+            timeout_secs: None,
+        };
+        assert_eq!(resolve_session_key(&request("  ")).unwrap(), DEFAULT_SESSION_KEY);
+        assert!(resolve_session_key(&request("bad\nkey")).is_err());
+        let mut by_id = request("");
+        by_id.session_id = Some("../x.jsonl".to_string());
+        assert!(resolve_session_key(&by_id).is_err());
+    }
+
+    /// sink 收到的事件
+    type Events = Arc<Mutex<Vec<(String, serde_json::Value)>>>;
+
+    fn collecting_sink() -> (EventSink, Events) {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink: EventSink = {
+            let events = events.clone();
+            Arc::new(move |kind, data| events.lock().unwrap().push((kind.to_string(), data)))
+        };
+        (sink, events)
+    }
+
+    #[cfg(unix)]
+    fn script(script: &str) -> tokio::process::Command {
+        let mut command = tokio::process::Command::new("sh");
+        command.args(["-c", script]);
+        command
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn forwards_output_until_exit() {
+        let (sink, events) = collecting_sink();
+        let (_tx, cancel) = tokio::sync::oneshot::channel();
+        let command = script(
+            "echo '↳ web_search(\"rust\")'; \
+             echo '2026-01-01 10:00:00.000 | INFO     | nanobot.agent.loop:_run:10 - Tool call: web_search' >&2; \
+             echo 'plain log' >&2; echo 'Hello'; exit 3",
+        );
+
+        let result = drive_agent(command, Duration::from_secs(10), cancel, sink).await;
+        assert_eq!(result, Ok(Some(3)));
+
+        let events = events.lock().unwrap();
+        let kinds = |kind: &str| -> Vec<String> {
+            events
+                .iter()
+                .filter(|(k, _)| k == kind)
+                .map(|(_, data)| data["text"].as_str().unwrap().to_string())
+                .collect()
+        };
+        assert_eq!(kinds("progress"), ["↳ web_search(\"rust\")"]);
+        assert_eq!(kinds("output"), ["Hello"]);
+        assert_eq!(kinds("tool"), ["web_search"]);
+        assert_eq!(kinds("log"), ["plain log"]);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn kills_process_group_on_timeout() {
+        let (sink, events) = collecting_sink();
+        let (_tx, cancel) = tokio::sync::oneshot::channel();
+        // 后台的 sleep 模拟 MCP 服务器，继承了 stdout/stderr
+        let command = script("sleep 30 & echo started; sleep 30");
+
+        let started = std::time::Instant::now();
+        let result = drive_agent(command, Duration::from_millis(500), cancel, sink).await;
+        assert!(result.unwrap_err().contains("超时"));
+        assert!(started.elapsed() < OUTPUT_DRAIN_TIMEOUT, "{:?}", started.elapsed());
+        assert_eq!(events.lock().unwrap()[0].1["text"], "started");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn kills_process_group_on_cancel() {
+        let (sink, _) = collecting_sink();
+        let (tx, cancel) = tokio::sync::oneshot::channel();
+        let command = script("sleep 30 & sleep 30");
+
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            let _ = tx.send(());
+        });
+        let started = std::time::Instant::now();
+        let result = drive_agent(command, Duration::from_secs(30), cancel, sink).await;
+        assert_eq!(result, Err("已取消".to_string()));
+        assert!(started.elapsed() < OUTPUT_DRAIN_TIMEOUT, "{:?}", started.elapsed());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn stops_waiting_for_output_held_by_detached_children() {
+        let (sink, _) = collecting_sink();
+        let (_tx, cancel) = tokio::sync::oneshot::channel();
+        // 脱离进程组的孙进程继续持有 stderr，不应阻塞本轮结束
+        let command = script("setsid sleep 10 & exit 0");
+
+        let started = std::time::Instant::now();
+        let result = drive_agent(command, Duration::from_secs(30), cancel, sink).await;
+        assert_eq!(result, Ok(Some(0)));
+        assert!(started.elapsed() < OUTPUT_DRAIN_TIMEOUT * 2 + Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,409 @@
+// 聊天会话的类型化模型
+// nanobot 的会话文件为 JSONL：第一行通常是元数据（_type = metadata，含会话键与创建时间），
+// 其余每行一条消息，遵循 OpenAI 消息格式：
+// - assistant 消息可能带 tool_calls（arguments 为 JSON 字符串）；
+// - role = tool 的消息是工具结果，通过 tool_call_id 对应到调用；
+// - content 可能是字符串，也可能是包含文本/图片/文件的数组（多模态）。
+// 解析时保留这些信息，并把工具结果与耗时关联回对应的调用，便于审计 agent 的行为。
+
+use serde::Serialize;
+use serde_json::{Map, Value as JsonValue};
+use std::collections::HashMap;
+
+/// 会话元数据（第一行）
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SessionHeader {
+    /// 会话键，例如 telegram:123456
+    pub key: Option<String>,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+    /// 已被记忆整理（consolidation）处理的消息数
+    pub last_consolidated: Option<u64>,
+    pub metadata: Option<JsonValue>,
+    /// 其他未知字段
+    #[serde(flatten)]
+    pub extra: Map<String, JsonValue>,
+}
+
+/// 消息内容片段
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ContentPart {
+    Text { text: String },
+    /// 图片，url 可能是 data: URL 或本地路径
+    Image { url: String, detail: Option<String> },
+    /// 文件或其他媒体
+    File { name: Option<String>, path: Option<String>, mime_type: Option<String> },
+    /// 无法识别的片段，原样保留
+    Other { raw: JsonValue },
+}
+
+/// 一次工具调用
+#[derive(Debug, Clone, Serialize)]
+pub struct ToolCall {
+    pub id: Option<String>,
+    pub name: String,
+    /// 解析后的参数；arguments 不是合法 JSON 时为原始字符串
+    pub arguments: JsonValue,
+    /// 工具返回的内容，尚无结果时为 None
+    pub result: Option<String>,
+    pub result_timestamp: Option<String>,
+    /// 从发起调用到收到结果的耗时（毫秒），需要两条消息都有时间戳；
+    /// 时间戳是保存时补上的（见 SAME_SAVE_MAX_GAP_MS）而无法得知真实耗时时为 None
+    pub duration_ms: Option<i64>,
+    /// 结果是否看起来是错误
+    pub is_error: bool,
+}
+
+/// 一条会话消息
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionMessage {
+    /// 在会话中的序号（不含元数据行，从 0 开始）
+    pub index: usize,
+    /// 所在行号（从 1 开始）
+    pub line: usize,
+    pub role: String,
+    /// 文本内容（多模态内容中的文本片段合并）
+    pub content: String,
+    /// 内容包含非文本片段时的全部片段
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub parts: Vec<ContentPart>,
+    pub timestamp: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tool_calls: Vec<ToolCall>,
+    /// tool 消息对应的调用 ID
+    pub tool_call_id: Option<String>,
+    /// tool 消息的工具名
+    pub name: Option<String>,
+    /// 其他未知字段（如 tools_used、usage）
+    #[serde(flatten)]
+    pub extra: Map<String, JsonValue>,
+}
+
+/// 消息中已识别的字段，其余字段放入 extra
+const KNOWN_MESSAGE_FIELDS: &[&str] = &["role", "content", "timestamp", "tool_calls", "tool_call_id", "name"];
+
+fn parse_content(value: Option<&JsonValue>) -> (String, Vec<ContentPart>) {
+    let items = match value {
+        Some(JsonValue::String(s)) => return (s.clone(), Vec::new()),
+        Some(JsonValue::Array(items)) => items,
+        None | Some(JsonValue::Null) => return (String::new(), Vec::new()),
+        Some(other) => return (other.to_string(), Vec::new()),
+    };
+
+    let parts: Vec<ContentPart> = items.iter().map(parse_part).collect();
+    let text = parts
+        .iter()
+        .filter_map(|p| match p {
+            ContentPart::Text { text } => Some(text.as_str()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    (text, parts)
+}
+
+fn parse_part(item: &JsonValue) -> ContentPart {
+    let str_field = |v: &JsonValue, key: &str| v.get(key).and_then(|s| s.as_str()).map(|s| s.to_string());
+    match (item, item.get("type").and_then(|t| t.as_str())) {
+        (JsonValue::String(s), _) => ContentPart::Text { text: s.clone() },
+        (_, Some("text")) => ContentPart::Text {
+            text: str_field(item, "text").unwrap_or_default(),
+        },
+        (_, Some("image_url")) => {
+            // OpenAI 格式：{"type":"image_url","image_url":{"url":"..."}}，也兼容 image_url 直接为字符串
+            let image = item.get("image_url");
+            let url = image
+                .and_then(|i| i.as_str().map(|s| s.to_string()).or_else(|| str_field(i, "url")))
+                .unwrap_or_default();
+            ContentPart::Image {
+                url,
+                detail: image.and_then(|i| str_field(i, "detail")),
+            }
+        }
+        (_, Some("image")) => ContentPart::Image {
+            url: str_field(item, "url").or_else(|| str_field(item, "path")).unwrap_or_default(),
+            detail: None,
+        },
+        (_, Some("file" | "audio" | "video" | "document")) => {
+            let file = item.get("file").unwrap_or(item);
+            ContentPart::File {
+                name: str_field(file, "filename").or_else(|| str_field(file, "name")),
+                path: str_field(file, "path").or_else(|| str_field(file, "url")),
+                mime_type: str_field(file, "mime_type").or_else(|| str_field(file, "mimeType")),
+            }
+        }
+        _ => ContentPart::Other { raw: item.clone() },
+    }
+}
+
+fn parse_tool_call(value: &JsonValue) -> ToolCall {
+    // 兼容 {"function":{"name","arguments"}} 与扁平的 {"name","arguments"}
+    let function = value.get("function").unwrap_or(value);
+    let arguments = match function.get("arguments") {
+        Some(JsonValue::String(s)) => serde_json::from_str(s).unwrap_or_else(|_| JsonValue::String(s.clone())),
+        Some(other) => other.clone(),
+        None => JsonValue::Null,
+    };
+    ToolCall {
+        id: value.get("id").and_then(|v| v.as_str()).map(|s| s.to_string()),
+        name: function
+            .get("name")
+            .and_then(|v| v.as_str())
+            .unwrap_or("unknown")
+            .to_string(),
+        arguments,
+        result: None,
+        result_timestamp: None,
+        duration_ms: None,
+        is_error: false,
+    }
+}
+
+fn parse_timestamp(value: &str) -> Option<chrono::NaiveDateTime> {
+    chrono::DateTime::parse_from_rfc3339(value)
+        .map(|t| t.naive_utc())
+        .ok()
+        .or_else(|| chrono::NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f").ok())
+        .or_else(|| chrono::NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S%.f").ok())
+}
+
+/// 同一次保存的时间戳间隔上限（毫秒）
+/// nanobot 在一轮对话结束保存时才为消息补上时间戳（_save_turn 中 setdefault 当前时间），
+/// 同一次保存的消息时间戳只相差几微秒；调用与结果的间隔不超过该值时不作为耗时
+const SAME_SAVE_MAX_GAP_MS: i64 = 10;
+
+/// 由调用与结果消息的时间戳计算工具耗时，无法得知真实耗时时返回 None
+fn tool_duration_ms(started: Option<&str>, finished: Option<&str>) -> Option<i64> {
+    let start = parse_timestamp(started?)?;
+    let end = parse_timestamp(finished?)?;
+    Some((end - start).num_milliseconds()).filter(|ms| *ms > SAME_SAVE_MAX_GAP_MS)
+}
+
+/// nanobot 的工具执行错误以 "Error" 开头
+pub(crate) fn looks_like_error(result: &str) -> bool {
+    let trimmed = result.trim_start();
+    trimmed.starts_with("Error") || trimmed.starts_with("error:")
+}
+
+/// 逐行解析会话，可用于一次性解析或跟随文件追加的增量解析
+#[derive(Default)]
+pub struct SessionParser {
+    line: usize,
+    next_index: usize,
+    /// 尚未收到结果的工具调用：调用 ID -> (消息序号, 调用下标, 发起时间)
+    pending_calls: HashMap<String, (usize, usize, Option<String>)>,
+}
+
+/// 单行的解析结果
+pub enum ParsedLine {
+    Header(SessionHeader),
+    Message(SessionMessage),
+    Empty,
+    Invalid,
+}
+
+impl SessionParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 从文件中间开始解析：下一行是第 line + 1 行，下一条消息的序号为 index
+    pub fn starting_at(index: usize, line: usize) -> Self {
+        Self {
+            line,
+            next_index: index,
+            ..Self::default()
+        }
+    }
+
+    /// 已解析的行数
+    pub fn current_line(&self) -> usize {
+        self.line
+    }
+
+    /// 是否还有等待结果的工具调用
+    pub fn has_pending_calls(&self) -> bool {
+        !self.pending_calls.is_empty()
+    }
+
+    /// 解析一行
+    pub fn parse_line(&mut self, line: &str) -> ParsedLine {
+        self.line += 1;
+        if line.trim().is_empty() {
+            return ParsedLine::Empty;
+        }
+        let Ok(JsonValue::Object(mut map)) = serde_json::from_str::<JsonValue>(line) else {
+            return ParsedLine::Invalid;
+        };
+
+        if map.get("_type").and_then(|t| t.as_str()) == Some("metadata") {
+            map.remove("_type");
+            let mut take_str = |key: &str| map.remove(key).and_then(|v| v.as_str().map(|s| s.to_string()));
+            let key = take_str("key");
+            let created_at = take_str("created_at");
+            let updated_at = take_str("updated_at");
+            return ParsedLine::Header(SessionHeader {
+                key,
+                created_at,
+                updated_at,
+                last_consolidated: map.remove("last_consolidated").and_then(|v| v.as_u64()),
+                metadata: map.remove("metadata"),
+                extra: map,
+            });
+        }
+
+        let Some(role) = map.get("role").and_then(|r| r.as_str()).map(|s| s.to_string()) else {
+            return ParsedLine::Invalid;
+        };
+        let (content, parts) = parse_content(map.get("content"));
+        let str_field = |key: &str| map.get(key).and_then(|v| v.as_str()).map(|s| s.to_string());
+        let timestamp = str_field("timestamp");
+        let tool_call_id = str_field("tool_call_id");
+        let name = str_field("name");
+        let tool_calls: Vec<ToolCall> = map
+            .get("tool_calls")
+            .and_then(|v| v.as_array())
+            .map(|calls| calls.iter().map(parse_tool_call).collect())
+            .unwrap_or_default();
+        let extra = map
+            .into_iter()
+            .filter(|(key, _)| !KNOWN_MESSAGE_FIELDS.contains(&key.as_str()))
+            .collect();
+
+        let index = self.next_index;
+        self.next_index += 1;
+        for (call_index, call) in tool_calls.iter().enumerate() {
+            if let Some(id) = &call.id {
+                self.pending_calls
+                    .insert(id.clone(), (index, call_index, timestamp.clone()));
+            }
+        }
+
+        ParsedLine::Message(SessionMessage {
+            index,
+            line: self.line,
+            role,
+            content,
+            parts,
+            timestamp,
+            tool_calls,
+            tool_call_id,
+            name,
+            extra,
+        })
+    }
+
+    /// 若消息是工具结果，返回其对应调用的位置（消息序号, 调用下标）以及补全后的调用信息
+    pub fn resolve_tool_result(&mut self, message: &SessionMessage) -> Option<(usize, usize, ToolResult)> {
+        if message.role != "tool" {
+            return None;
+        }
+        let (index, call_index, started) = self.pending_calls.remove(message.tool_call_id.as_deref()?)?;
+        Some((index, call_index, ToolResult {
+            result: message.content.clone(),
+            timestamp: message.timestamp.clone(),
+            duration_ms: tool_duration_ms(started.as_deref(), message.timestamp.as_deref()),
+            is_error: looks_like_error(&message.content),
+        }))
+    }
+}
+
+/// 工具调用的结果
+pub struct ToolResult {
+    pub result: String,
+    pub timestamp: Option<String>,
+    pub duration_ms: Option<i64>,
+    pub is_error: bool,
+}
+
+impl ToolCall {
+    pub fn apply_result(&mut self, result: ToolResult) {
+        self.result = Some(result.result);
+        self.result_timestamp = result.timestamp;
+        self.duration_ms = result.duration_ms;
+        self.is_error = result.is_error;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(parsed: ParsedLine) -> SessionMessage {
+        match parsed {
+            ParsedLine::Message(message) => message,
+            _ => panic!("不是消息行"),
+        }
+    }
+
+    #[test]
+    fn parses_header_and_multimodal_content() {
+        let mut parser = SessionParser::new();
+        let header = r#"{"_type":"metadata","key":"telegram:42","created_at":"2026-02-10T12:00:00","last_consolidated":3,"custom":1}"#;
+        match parser.parse_line(header) {
+            ParsedLine::Header(header) => {
+                assert_eq!(header.key.as_deref(), Some("telegram:42"));
+                assert_eq!(header.last_consolidated, Some(3));
+                assert_eq!(header.extra["custom"], 1);
+            }
+            _ => panic!("应为元数据行"),
+        }
+        assert!(matches!(parser.parse_line("  "), ParsedLine::Empty));
+        assert!(matches!(parser.parse_line("not json"), ParsedLine::Invalid));
+        assert!(matches!(parser.parse_line(r#"{"content":"no role"}"#), ParsedLine::Invalid));
+
+        let user = message(parser.parse_line(
+            r#"{"role":"user","content":[{"type":"text","text":"看图"},{"type":"image_url","image_url":{"url":"/tmp/a.png"}},{"type":"file","file":{"filename":"a.pdf","path":"/tmp/a.pdf"}}],"usage":{"total":5}}"#,
+        ));
+        assert_eq!(user.index, 0);
+        assert_eq!(user.line, 5);
+        assert_eq!(user.content, "看图");
+        assert_eq!(user.parts.len(), 3);
+        assert!(matches!(&user.parts[1], ContentPart::Image { url, .. } if url == "/tmp/a.png"));
+        assert!(matches!(&user.parts[2], ContentPart::File { path: Some(p), .. } if p == "/tmp/a.pdf"));
+        assert_eq!(user.extra["usage"]["total"], 5);
+    }
+
+    #[test]
+    fn links_tool_results_to_calls() {
+        // 从第 10 行、第 4 条消息开始的增量解析
+        let mut parser = SessionParser::starting_at(4, 10);
+        let assistant = message(parser.parse_line(
+            r#"{"role":"assistant","content":"","timestamp":"2026-02-10T12:00:00.000","tool_calls":[{"id":"c1","function":{"name":"read_file","arguments":"{\"path\":\"a.md\"}"}},{"id":"c2","function":{"name":"exec","arguments":"ls -"}}]}"#,
+        ));
+        assert_eq!((assistant.index, assistant.line), (4, 11));
+        assert_eq!(assistant.tool_calls[0].arguments["path"], "a.md");
+        // 参数不是合法 JSON 时保留原文
+        assert_eq!(assistant.tool_calls[1].arguments, "ls -");
+        assert!(parser.has_pending_calls());
+
+        let result = message(parser.parse_line(
+            r#"{"role":"tool","tool_call_id":"c2","name":"exec","content":"Error: denied","timestamp":"2026-02-10T12:00:01.500"}"#,
+        ));
+        let (index, call_index, tool_result) = parser.resolve_tool_result(&result).unwrap();
+        assert_eq!((index, call_index), (4, 1));
+        assert_eq!(tool_result.duration_ms, Some(1500));
+        assert!(tool_result.is_error);
+
+        // 同一结果不会重复关联，未知的调用 ID 被忽略
+        assert!(parser.resolve_tool_result(&result).is_none());
+        let unknown = message(parser.parse_line(r#"{"role":"tool","tool_call_id":"zz","content":"ok"}"#));
+        assert!(parser.resolve_tool_result(&unknown).is_none());
+        assert!(parser.has_pending_calls());
+
+        // 同一次保存补上的时间戳不作为耗时
+        let same_save = message(parser.parse_line(
+            r#"{"role":"tool","tool_call_id":"c1","name":"read_file","content":"ok","timestamp":"2026-02-10T12:00:00.000350"}"#,
+        ));
+        let (_, _, same_save_result) = parser.resolve_tool_result(&same_save).unwrap();
+        assert_eq!(same_save_result.duration_ms, None);
+        assert_eq!(tool_duration_ms(Some("2026-02-10T12:00:01"), Some("2026-02-10T12:00:00")), None);
+        assert_eq!(tool_duration_ms(None, Some("2026-02-10T12:00:00")), None);
+
+        let mut call = assistant.tool_calls[1].clone();
+        call.apply_result(tool_result);
+        assert_eq!(call.result.as_deref(), Some("Error: denied"));
+        assert!(call.is_error);
+    }
+}
@@ -8,6 +8,7 @@ use tauri::State;
 use chrono::Utc;
 
 use crate::AppState;
+use crate::config_model::NanobotConfig;
 
 /// 获取nanobot配置文件路径
 pub fn get_config_path_internal() -> Result<PathBuf> {
@@ -31,8 +32,8 @@ pub struct ConfigHistoryVersion {
     pub size: u64,
 }
 
-/// 创建历史记录备份
-fn create_history_backup() -> Result<()> {
+/// 创建历史记录备份，返回备份文件名（配置文件不存在时返回 None）
+fn create_history_backup() -> Result<Option<String>> {
     let config_path = get_config_path_internal()?;
     let history_dir = get_config_history_dir()?;
 
@@ -42,7 +43,7 @@ fn create_history_backup() -> Result<()> {
 
     // 如果配置文件不存在，跳过
     if !config_path.exists() {
-        return Ok(());
+        return Ok(None);
     }
 
     // 读取当前配置
@@ -61,7 +62,7 @@ fn create_history_backup() -> Result<()> {
     // 清理旧备份（保留最近10个）
     cleanup_old_backups(10)?;
 
-    Ok(())
+    Ok(Some(backup_filename))
 }
 
 /// 清理旧备份文件，保留指定数量
@@ -128,17 +129,18 @@ pub async fn load_config(state: State<'_, AppState>) -> Result<JsonValue, String
     Ok(config)
 }
 
-/// 保存配置文件
-#[tauri::command]
-pub async fn save_config(config: JsonValue) -> Result<(), String> {
+/// 写入配置文件（内部函数），写入前创建历史备份，返回备份文件名
+/// 如果备份失败，阻止保存以保护用户配置
+pub fn write_config_internal(config: &JsonValue) -> Result<Option<String>, String> {
     let config_path = get_config_path_internal().map_err(|e| e.to_string())?;
 
     // 在保存前创建历史备份
-    // 如果备份失败，阻止保存以保护用户配置
-    if config_path.exists() {
+    let backup = if config_path.exists() {
         create_history_backup()
-            .map_err(|e| format!("创建配置备份失败，保存已取消: {}", e))?;
-    }
+            .map_err(|e| format!("创建配置备份失败，保存已取消: {}", e))?
+    } else {
+        None
+    };
 
     // 确保目录存在
     if let Some(parent) = config_path.parent() {
@@ -147,13 +149,19 @@ pub async fn save_config(config: JsonValue) -> Result<(), String> {
     }
 
     // 格式化JSON并保存
-    let content = serde_json::to_string_pretty(&config)
+    let content = serde_json::to_string_pretty(config)
         .map_err(|e| format!("序列化配置失败: {}", e))?;
 
     fs::write(&config_path, content)
         .map_err(|e| format!("写入配置文件失败: {}", e))?;
 
-    Ok(())
+    Ok(backup)
+}
+
+/// 保存配置文件
+#[tauri::command]
+pub async fn save_config(config: JsonValue) -> Result<(), String> {
+    write_config_internal(&config).map(|_| ())
 }
 
 /// 获取配置文件路径
@@ -169,38 +177,61 @@ pub async fn validate_config(config: JsonValue) -> Result<JsonValue, String> {
     let mut errors = Vec::new();
     let mut warnings = Vec::new();
 
+    // 展开环境变量引用，未定义的变量视为错误，后续检查基于展开后的配置
+    let interpolated = crate::interpolation::interpolate_config(&config);
+    errors.extend(crate::interpolation::describe_unresolved(&interpolated.unresolved));
+    let config = interpolated.config;
+
+    let config = match NanobotConfig::from_json(&config) {
+        Ok(typed) => typed,
+        Err(e) => {
+            errors.push(e);
+            return Ok(serde_json::json!({
+                "valid": false,
+                "errors": errors,
+                "warnings": warnings
+            }));
+        }
+    };
+
     // 检查providers配置
-    if let Some(providers) = config.get("providers") {
-        if let Some(obj) = providers.as_object() {
-            if obj.is_empty() {
-                warnings.push("未配置任何LLM提供商".to_string());
-            }
+    match &config.providers {
+        Some(providers) if providers.is_empty() => {
+            warnings.push("未配置任何LLM提供商".to_string());
         }
-    } else {
-        errors.push("缺少providers配置".to_string());
+        Some(_) => {}
+        None => errors.push("缺少providers配置".to_string()),
     }
 
     // 检查agents配置
-    if let Some(agents) = config.get("agents") {
-        if let Some(defaults) = agents.get("defaults") {
-            if let Some(model) = defaults.get("model") {
-                if model.as_str().map(|s| s.is_empty()).unwrap_or(true) {
-                    errors.push("默认model不能为空".to_string());
-                }
+    if let Some(model) = config.agent_defaults().and_then(|d| d.model.as_deref()) {
+        if model.is_empty() {
+            errors.push("默认model不能为空".to_string());
+        } else {
+            // 与所属提供商缓存的模型目录比对（不发起网络请求）
+            let configured = config
+                .providers
+                .iter()
+                .flatten()
+                .filter(|(_, p)| p.has_api_key() || p.api_base.as_deref().is_some_and(|b| !b.is_empty()))
+                .map(|(name, p)| (name.clone(), p.clone()))
+                .collect();
+            if let Some(warning) = crate::providers::check_default_model(
+                model,
+                config.agent_defaults().and_then(|d| d.provider.as_deref()),
+                &configured,
+            ) {
+                warnings.push(warning);
             }
         }
     }
 
     // 检查channels配置
-    if let Some(channels) = config.get("channels") {
-        if let Some(obj) = channels.as_object() {
-            let enabled_count = obj.values()
-                .filter(|v| v.get("enabled").and_then(|e| e.as_bool()).unwrap_or(false))
-                .count();
-
-            if enabled_count == 0 {
-                warnings.push("没有启用任何消息渠道".to_string());
-            }
+    if let Some(channels) = &config.channels {
+        let enabled_count = channels.entries.values().filter(|c| c.is_enabled()).count();
+
+        if enabled_count == 0 {
+            warnings.push("没有启用任何消息渠道".to_string());
         }
     }
 
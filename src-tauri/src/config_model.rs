@@ -0,0 +1,524 @@
+// nanobot 配置的类型化模型
+// 所有结构体都通过 `extra` 字段保留未知键，保证读取后再写回不会丢失
+// nanobot 新版本引入的配置项。
+
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::{json, Map, Value as JsonValue};
+use std::collections::BTreeMap;
+
+use crate::config::{load_config_internal, write_config_internal};
+
+/// 配置根对象
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NanobotConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agents: Option<AgentsConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub providers: Option<BTreeMap<String, ProviderConfig>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub channels: Option<ChannelsConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tools: Option<ToolsConfig>,
+    #[serde(flatten)]
+    pub extra: Map<String, JsonValue>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AgentsConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub defaults: Option<AgentDefaults>,
+    #[serde(flatten)]
+    pub extra: Map<String, JsonValue>,
+}
+
+/// agents.defaults
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AgentDefaults {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none", deserialize_with = "lenient_u64")]
+    pub max_tokens: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none", deserialize_with = "lenient_f64")]
+    pub temperature: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none", deserialize_with = "lenient_u64")]
+    pub max_tool_iterations: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none", deserialize_with = "lenient_u64")]
+    pub memory_window: Option<u64>,
+    #[serde(flatten)]
+    pub extra: Map<String, JsonValue>,
+}
+
+/// providers.<name>
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_base: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extra_headers: Option<BTreeMap<String, String>>,
+    #[serde(flatten)]
+    pub extra: Map<String, JsonValue>,
+}
+
+impl ProviderConfig {
+    /// 是否配置了非空的 API key
+    pub fn has_api_key(&self) -> bool {
+        self.api_key.as_deref().is_some_and(|k| !k.is_empty())
+    }
+}
+
+/// channels 节点
+/// 除了各渠道的对象外还包含 sendProgress、sendToolHints 等全局开关，
+/// 因此只把对象值解析为渠道，其余非对象值保留在 extra 中
+#[derive(Debug, Clone, Default)]
+pub struct ChannelsConfig {
+    pub send_progress: Option<bool>,
+    pub send_tool_hints: Option<bool>,
+    pub entries: BTreeMap<String, ChannelConfig>,
+    pub extra: Map<String, JsonValue>,
+}
+
+impl<'de> Deserialize<'de> for ChannelsConfig {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::{Error, IntoDeserializer};
+
+        let map = Map::<String, JsonValue>::deserialize(deserializer)?;
+        let mut channels = ChannelsConfig::default();
+        for (key, value) in map {
+            match key.as_str() {
+                "sendProgress" => {
+                    channels.send_progress = lenient_bool(value.into_deserializer()).map_err(D::Error::custom)?;
+                }
+                "sendToolHints" => {
+                    channels.send_tool_hints = lenient_bool(value.into_deserializer()).map_err(D::Error::custom)?;
+                }
+                _ if value.is_object() => {
+                    let channel = serde_json::from_value(value)
+                        .map_err(|e| D::Error::custom(format!("渠道 {}: {}", key, e)))?;
+                    channels.entries.insert(key, channel);
+                }
+                _ => {
+                    channels.extra.insert(key, value);
+                }
+            }
+        }
+        Ok(channels)
+    }
+}
+
+impl Serialize for ChannelsConfig {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeMap;
+
+        let mut map = serializer.serialize_map(None)?;
+        if let Some(send_progress) = self.send_progress {
+            map.serialize_entry("sendProgress", &send_progress)?;
+        }
+        if let Some(send_tool_hints) = self.send_tool_hints {
+            map.serialize_entry("sendToolHints", &send_tool_hints)?;
+        }
+        for (name, channel) in &self.entries {
+            map.serialize_entry(name, channel)?;
+        }
+        for (key, value) in &self.extra {
+            map.serialize_entry(key, value)?;
+        }
+        map.end()
+    }
+}
+
+/// channels.<name>
+/// 各渠道的凭据字段差异很大（token、appId、imapHost 等），统一保存在 extra 中
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChannelConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none", deserialize_with = "lenient_bool")]
+    pub enabled: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allow_from: Option<Vec<String>>,
+    #[serde(flatten)]
+    pub extra: Map<String, JsonValue>,
+}
+
+impl ChannelConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.unwrap_or(false)
+    }
+
+    /// 读取非空的字符串字段
+    pub fn field_str(&self, key: &str) -> Option<&str> {
+        self.extra
+            .get(key)
+            .and_then(|v| v.as_str())
+            .filter(|s| !s.is_empty())
+    }
+
+    /// 读取布尔字段，兼容插值后的字符串形式
+    pub fn field_bool(&self, key: &str) -> Option<bool> {
+        match self.extra.get(key)? {
+            JsonValue::Bool(b) => Some(*b),
+            JsonValue::String(s) => match s.trim().to_ascii_lowercase().as_str() {
+                "true" | "1" | "yes" | "on" => Some(true),
+                "false" | "0" | "no" | "off" => Some(false),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// 读取端口字段，兼容插值后的字符串形式
+    pub fn field_port(&self, key: &str) -> Option<u16> {
+        match self.extra.get(key)? {
+            JsonValue::Number(n) => n.as_u64().and_then(|n| u16::try_from(n).ok()),
+            JsonValue::String(s) => s.trim().parse().ok(),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolsConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none", deserialize_with = "lenient_bool")]
+    pub restrict_to_workspace: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mcp_servers: Option<BTreeMap<String, McpServerConfig>>,
+    #[serde(flatten)]
+    pub extra: Map<String, JsonValue>,
+}
+
+/// tools.mcpServers.<name>
+/// stdio 服务器使用 command/args/env，HTTP 服务器使用 url/headers
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpServerConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub args: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub env: Option<BTreeMap<String, String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub headers: Option<BTreeMap<String, String>>,
+    #[serde(flatten)]
+    pub extra: Map<String, JsonValue>,
+}
+
+// 环境变量插值后的值都是字符串（例如 "${MAX_TOKENS:-4096}" -> "4096"），
+// nanobot 端由 pydantic 自动转换类型，这里对数值和布尔字段做同样宽松的解析
+
+fn lenient_u64<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<u64>, D::Error> {
+    match Option::<JsonValue>::deserialize(deserializer)? {
+        None | Some(JsonValue::Null) => Ok(None),
+        Some(JsonValue::Number(n)) => n
+            .as_u64()
+            .map(Some)
+            .ok_or_else(|| serde::de::Error::custom(format!("期望非负整数，实际为 {}", n))),
+        Some(JsonValue::String(s)) => s
+            .trim()
+            .parse()
+            .map(Some)
+            .map_err(|_| serde::de::Error::custom(format!("期望整数，实际为 \"{}\"", s))),
+        Some(other) => Err(serde::de::Error::custom(format!("期望整数，实际为 {}", other))),
+    }
+}
+
+fn lenient_f64<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<f64>, D::Error> {
+    match Option::<JsonValue>::deserialize(deserializer)? {
+        None | Some(JsonValue::Null) => Ok(None),
+        Some(JsonValue::Number(n)) => Ok(n.as_f64()),
+        Some(JsonValue::String(s)) => s
+            .trim()
+            .parse()
+            .map(Some)
+            .map_err(|_| serde::de::Error::custom(format!("期望数字，实际为 \"{}\"", s))),
+        Some(other) => Err(serde::de::Error::custom(format!("期望数字，实际为 {}", other))),
+    }
+}
+
+fn lenient_bool<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<bool>, D::Error> {
+    match Option::<JsonValue>::deserialize(deserializer)? {
+        None | Some(JsonValue::Null) => Ok(None),
+        Some(JsonValue::Bool(b)) => Ok(Some(b)),
+        Some(JsonValue::String(s)) => match s.trim().to_ascii_lowercase().as_str() {
+            "true" | "1" | "yes" | "on" => Ok(Some(true)),
+            "false" | "0" | "no" | "off" | "" => Ok(Some(false)),
+            _ => Err(serde::de::Error::custom(format!("期望布尔值，实际为 \"{}\"", s))),
+        },
+        Some(other) => Err(serde::de::Error::custom(format!("期望布尔值，实际为 {}", other))),
+    }
+}
+
+impl NanobotConfig {
+    /// 从 JSON 解析配置，字段类型不匹配时返回错误信息
+    pub fn from_json(value: &JsonValue) -> Result<Self, String> {
+        serde_json::from_value(value.clone()).map_err(|e| format!("配置结构无效: {}", e))
+    }
+
+    pub fn agent_defaults(&self) -> Option<&AgentDefaults> {
+        self.agents.as_ref().and_then(|a| a.defaults.as_ref())
+    }
+
+    pub fn channel(&self, name: &str) -> Option<&ChannelConfig> {
+        self.channels.as_ref().and_then(|c| c.entries.get(name))
+    }
+}
+
+/// 将 patch 中的字段深度合并到 target 中
+/// 值为 null 的字段表示删除该键
+pub fn merge_json(target: &mut JsonValue, patch: &JsonValue) {
+    match (target, patch) {
+        (JsonValue::Object(target_map), JsonValue::Object(patch_map)) => {
+            for (key, value) in patch_map {
+                if value.is_null() {
+                    target_map.remove(key);
+                } else {
+                    merge_json(target_map.entry(key.clone()).or_insert(JsonValue::Null), value);
+                }
+            }
+        }
+        (target, patch) => *target = patch.clone(),
+    }
+}
+
+/// 读取磁盘上的原始配置，配置文件不存在时返回空对象
+fn load_raw_config() -> Result<JsonValue, String> {
+    let config = load_config_internal()?;
+    if config.get("error").and_then(|e| e.as_str()) == Some("config_not_found") {
+        return Ok(json!({}));
+    }
+    Ok(config)
+}
+
+/// 将字段合并到指定路径的配置节点并保存
+/// 合并在原始 JSON 上进行，未涉及的键（包括显式的 null）保持原样；
+/// 保存前用类型化模型校验合并结果
+fn update_section(path: &[&str], fields: &JsonValue) -> Result<JsonValue, String> {
+    if !fields.is_object() {
+        return Err("更新内容必须是对象".to_string());
+    }
+
+    let mut config = load_raw_config()?;
+    let mut node = &mut config;
+    for key in path {
+        let map = node
+            .as_object_mut()
+            .ok_or_else(|| format!("配置项 {} 不是对象", key))?;
+        node = map
+            .entry(key.to_string())
+            .or_insert_with(|| JsonValue::Object(Map::new()));
+    }
+    if !node.is_object() {
+        return Err(format!("配置项 {} 不是对象", path.join(".")));
+    }
+    merge_json(node, fields);
+
+    // 校验基于环境变量展开后的配置，写入的仍是原始配置
+    parse_interpolated(&config)?;
+    write_config_internal(&config)?;
+
+    Ok(node_at(&config, path))
+}
+
+fn node_at(config: &JsonValue, path: &[&str]) -> JsonValue {
+    let mut node = config;
+    for key in path {
+        match node.get(key) {
+            Some(child) => node = child,
+            None => return JsonValue::Null,
+        }
+    }
+    node.clone()
+}
+
+/// 按环境变量展开后的配置校验类型，与 validate_config 的结果保持一致
+fn parse_interpolated(config: &JsonValue) -> Result<NanobotConfig, String> {
+    NanobotConfig::from_json(&crate::interpolation::interpolate_config(config).config)
+}
+
+/// 类型化配置的 JSON 视图
+/// 数字、布尔的宽松写法按类型规范化，channels 中的全局开关与渠道分开解析，未知键原样保留；
+/// 含 `${VAR}` 引用的配置项仍返回原始引用，不会把展开后的取值交给前端
+fn typed_view(config: &JsonValue) -> Result<JsonValue, String> {
+    let result = crate::interpolation::interpolate_config(config);
+    let typed = NanobotConfig::from_json(&result.config)?;
+    let mut view = serde_json::to_value(&typed).map_err(|e| format!("序列化配置失败: {}", e))?;
+    for path in &result.interpolated_paths {
+        let pointer: String = path
+            .iter()
+            .map(|key| format!("/{}", key.replace('~', "~0").replace('/', "~1")))
+            .collect();
+        if let (Some(raw), Some(slot)) = (config.pointer(&pointer), view.pointer_mut(&pointer)) {
+            *slot = raw.clone();
+        }
+    }
+    Ok(view)
+}
+
+/// 获取类型化的配置
+/// 类型校验基于展开后的配置；`${VAR}` 引用不会被替换成实际取值
+#[tauri::command]
+pub async fn get_typed_config() -> Result<JsonValue, String> {
+    let config = load_raw_config()?;
+    match typed_view(&config) {
+        Ok(view) => Ok(json!({
+            "success": true,
+            "config": view
+        })),
+        Err(e) => Ok(json!({
+            "success": false,
+            "message": e
+        })),
+    }
+}
+
+/// 获取单个渠道的配置
+#[tauri::command]
+pub async fn get_channel_config(name: String) -> Result<JsonValue, String> {
+    let config = load_raw_config()?;
+    match parse_interpolated(&config)?.channel(&name) {
+        Some(_) => Ok(json!({
+            "success": true,
+            "channel": node_at(&typed_view(&config)?, &["channels", &name])
+        })),
+        None => Ok(json!({
+            "success": false,
+            "message": format!("渠道 {} 未配置", name)
+        })),
+    }
+}
+
+/// 更新单个渠道的字段（部分更新，字段值为 null 表示删除）
+#[tauri::command]
+pub async fn update_channel_config(name: String, fields: JsonValue) -> Result<JsonValue, String> {
+    let channel = update_section(&["channels", &name], &fields)?;
+    Ok(json!({
+        "success": true,
+        "message": format!("渠道 {} 配置已更新", name),
+        "channel": channel
+    }))
+}
+
+/// 更新单个 provider 的字段（部分更新，字段值为 null 表示删除）
+#[tauri::command]
+pub async fn update_provider_config(name: String, fields: JsonValue) -> Result<JsonValue, String> {
+    let provider = update_section(&["providers", &name], &fields)?;
+    Ok(json!({
+        "success": true,
+        "message": format!("提供商 {} 配置已更新", name),
+        "provider": provider
+    }))
+}
+
+/// 更新 agents.defaults 的字段（部分更新，字段值为 null 表示删除）
+#[tauri::command]
+pub async fn update_agent_defaults(fields: JsonValue) -> Result<JsonValue, String> {
+    let defaults = update_section(&["agents", "defaults"], &fields)?;
+    Ok(json!({
+        "success": true,
+        "message": "默认 Agent 配置已更新",
+        "defaults": defaults
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_channel_flags_next_to_channels() {
+        let raw = json!({
+            "channels": {
+                "sendProgress": true,
+                "sendToolHints": "false",
+                "futureFlag": 3,
+                "telegram": { "enabled": true, "token": "t", "allowFrom": ["1"] }
+            }
+        });
+        let config = NanobotConfig::from_json(&raw).unwrap();
+        let channels = config.channels.as_ref().unwrap();
+
+        assert_eq!(channels.send_progress, Some(true));
+        assert_eq!(channels.send_tool_hints, Some(false));
+        assert_eq!(channels.extra.get("futureFlag"), Some(&json!(3)));
+        assert_eq!(channels.entries.len(), 1);
+        assert!(config.channel("telegram").unwrap().is_enabled());
+        assert_eq!(config.channel("telegram").unwrap().field_str("token"), Some("t"));
+    }
+
+    #[test]
+    fn round_trips_unknown_keys() {
+        let raw = json!({
+            "agents": { "defaults": { "model": "m", "maxTokens": 10, "newOption": "x" } },
+            "channels": { "sendProgress": false, "slack": { "enabled": false, "mode": "socket" } },
+            "gateway": { "port": 18790 }
+        });
+        let config = NanobotConfig::from_json(&raw).unwrap();
+        assert_eq!(serde_json::to_value(&config).unwrap(), raw);
+    }
+
+    #[test]
+    fn rejects_invalid_channel_and_accepts_interpolated_strings() {
+        let err = NanobotConfig::from_json(&json!({ "channels": { "telegram": { "enabled": "maybe" } } }))
+            .unwrap_err();
+        assert!(err.contains("telegram"), "{}", err);
+
+        let config = NanobotConfig::from_json(&json!({
+            "agents": { "defaults": { "maxTokens": " 4096 ", "temperature": "0.5" } }
+        }))
+        .unwrap();
+        let defaults = config.agent_defaults().unwrap();
+        assert_eq!(defaults.max_tokens, Some(4096));
+        assert_eq!(defaults.temperature, Some(0.5));
+
+        assert!(NanobotConfig::from_json(&json!({
+            "agents": { "defaults": { "maxTokens": "${MAX_TOKENS}" } }
+        }))
+        .is_err());
+    }
+
+    #[test]
+    fn typed_view_normalizes_values_and_keeps_references() {
+        let raw = json!({
+            "agents": { "defaults": { "maxTokens": " 4096 ", "temperature": "0.5", "newOption": "x" } },
+            "channels": {
+                "sendProgress": "true",
+                "telegram": { "enabled": "yes", "token": "${NANOBOARD_TEST_UNSET_TOKEN:-fallback}" }
+            },
+            "providers": { "openrouter": { "apiKey": "${NANOBOARD_TEST_UNSET_KEY:-sk-secret}" } }
+        });
+        let view = typed_view(&raw).unwrap();
+
+        assert_eq!(view["agents"]["defaults"]["maxTokens"], json!(4096));
+        assert_eq!(view["agents"]["defaults"]["temperature"], json!(0.5));
+        assert_eq!(view["agents"]["defaults"]["newOption"], json!("x"));
+        assert_eq!(view["channels"]["sendProgress"], json!(true));
+        assert_eq!(view["channels"]["telegram"]["enabled"], json!(true));
+        assert_eq!(
+            view["channels"]["telegram"]["token"],
+            json!("${NANOBOARD_TEST_UNSET_TOKEN:-fallback}")
+        );
+        assert_eq!(
+            view["providers"]["openrouter"]["apiKey"],
+            json!("${NANOBOARD_TEST_UNSET_KEY:-sk-secret}")
+        );
+    }
+
+    #[test]
+    fn merges_patches() {
+        let mut target = json!({ "a": { "b": 1, "c": 2 }, "d": [1] });
+        merge_json(&mut target, &json!({ "a": { "b": null, "e": 3 }, "d": [2] }));
+        assert_eq!(target, json!({ "a": { "c": 2, "e": 3 }, "d": [2] }));
+    }
+}
@@ -0,0 +1,575 @@
+// 日志关联索引
+// 将日志记录关联到聊天会话（channel:chat_id）与定时任务运行：
+// - 会话轮次：nanobot 的 agent 循环逐条处理消息，"Processing message from K" 与 "Response to K"
+//   之间的记录（工具调用、LLM 错误等）都属于这一轮对话；
+// - 定时任务运行："Cron: executing job 'N' (ID)" 与 "Cron: job 'N' completed/failed" 之间的记录；
+// - 其他提到会话键（如 telegram:123）的记录单独作为一条关联。
+// 索引只解析头部行，按文件标识缓存；历史文件只索引一次，当前文件增量索引新增内容。
+// 注意：nanobot 日志中记录的是 channel:sender_id，私聊时与会话键 channel:chat_id 相同。
+
+use regex::Regex;
+use serde::Serialize;
+use serde_json::{json, Value as JsonValue};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+
+use crate::log_files::{discover_log_files, LogFile};
+use crate::log_parser::{for_each_record_at, parse_header, LogRecord};
+use crate::time_range::{in_range, normalize_time};
+
+/// 单个轮次最多返回的记录数，防止缺少结束标记时读取过多
+const MAX_SPAN_RECORDS: usize = 500;
+
+/// 默认返回的轮次/运行次数
+const DEFAULT_SPAN_LIMIT: usize = 20;
+
+/// 关联类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SpanKind {
+    /// 一轮对话（从收到消息到回复）
+    Turn,
+    /// 单条提到会话键的记录
+    Mention,
+    /// 一次定时任务运行
+    CronRun,
+}
+
+/// 一段关联的日志
+#[derive(Debug, Clone, Serialize)]
+pub struct LogSpan {
+    /// 会话键（channel:chat_id）或定时任务 ID
+    pub key: String,
+    pub kind: SpanKind,
+    #[serde(skip)]
+    pub file_id: String,
+    #[serde(skip)]
+    pub start_offset: u64,
+    /// 最后一条记录的偏移，未结束时为 None
+    #[serde(skip)]
+    pub end_offset: Option<u64>,
+    pub started_at: Option<String>,
+    pub finished_at: Option<String>,
+    /// completed / failed / running / interrupted，Mention 为 None
+    pub status: Option<String>,
+    /// 定时任务名称
+    pub name: Option<String>,
+}
+
+struct Patterns {
+    processing: Regex,
+    response: Regex,
+    mention: Regex,
+    cron_start: Regex,
+    cron_end: Regex,
+}
+
+fn patterns() -> &'static Patterns {
+    static PATTERNS: OnceLock<Patterns> = OnceLock::new();
+    PATTERNS.get_or_init(|| Patterns {
+        processing: Regex::new(r"Processing (?:system )?message from ([\w-]+:[^\s:]+)").unwrap(),
+        response: Regex::new(r"Response to ([\w-]+:[^\s:]+)").unwrap(),
+        mention: Regex::new(
+            r#"\b((?:telegram|discord|slack|feishu|dingtalk|whatsapp|email|qq|matrix|mochat|cli|cron):[^\s:,;'")\]]+)"#,
+        )
+        .unwrap(),
+        cron_start: Regex::new(r"Cron: executing job '(.*)' \(([\w-]+)\)").unwrap(),
+        cron_end: Regex::new(r"Cron: job '(.*)' (completed|failed)").unwrap(),
+    })
+}
+
+/// 单个文件的索引
+#[derive(Default)]
+struct FileIndex {
+    /// 已索引到的位置（总在完整行之后）
+    offset: u64,
+    /// 开始索引时文件的内容指纹，见 LogFile::head_fingerprint
+    head: Vec<u8>,
+    spans: Vec<LogSpan>,
+    /// 尚未结束的会话轮次（span 下标）
+    open_turn: Option<usize>,
+    /// 尚未结束的定时任务运行：任务名 -> span 下标
+    open_cron: HashMap<String, usize>,
+}
+
+impl FileIndex {
+    fn close(&mut self, index: usize, offset: u64, timestamp: &Option<String>, status: &str) {
+        let span = &mut self.spans[index];
+        span.end_offset = Some(offset);
+        span.finished_at = timestamp.clone();
+        span.status = Some(status.to_string());
+    }
+
+    /// 处理一条记录的头部
+    fn add(&mut self, file_id: &str, offset: u64, record: &LogRecord) {
+        let p = patterns();
+        let message = record.message.as_str();
+        let new_span = |key: String, kind: SpanKind, status: Option<&str>, name: Option<String>| LogSpan {
+            key,
+            kind,
+            file_id: file_id.to_string(),
+            start_offset: offset,
+            end_offset: (kind == SpanKind::Mention).then_some(offset),
+            started_at: record.timestamp.clone(),
+            finished_at: None,
+            status: status.map(|s| s.to_string()),
+            name,
+        };
+
+        if let Some(caps) = p.cron_start.captures(message) {
+            let span = new_span(caps[2].to_string(), SpanKind::CronRun, Some("running"), Some(caps[1].to_string()));
+            self.spans.push(span);
+            let index = self.spans.len() - 1;
+            if let Some(previous) = self.open_cron.insert(caps[1].to_string(), index) {
+                self.close(previous, offset - 1, &record.timestamp, "interrupted");
+            }
+            return;
+        }
+        if let Some(caps) = p.cron_end.captures(message) {
+            if let Some(index) = self.open_cron.remove(&caps[1]) {
+                self.close(index, offset, &record.timestamp, &caps[2]);
+            }
+            return;
+        }
+        if let Some(caps) = p.processing.captures(message) {
+            // agent 逐条处理消息，新的一轮开始意味着上一轮没有正常结束
+            if let Some(previous) = self.open_turn.take() {
+                self.close(previous, offset - 1, &record.timestamp, "interrupted");
+            }
+            self.spans.push(new_span(caps[1].to_string(), SpanKind::Turn, Some("running"), None));
+            self.open_turn = Some(self.spans.len() - 1);
+            return;
+        }
+        if let Some(caps) = p.response.captures(message) {
+            match self.open_turn {
+                Some(index) if self.spans[index].key == caps[1] => {
+                    self.open_turn = None;
+                    self.close(index, offset, &record.timestamp, "completed");
+                }
+                _ => self.spans.push(new_span(caps[1].to_string(), SpanKind::Mention, None, None)),
+            }
+            return;
+        }
+
+        // 轮次之外提到会话键的记录
+        let open_key = self.open_turn.map(|i| self.spans[i].key.clone());
+        for caps in p.mention.captures_iter(message) {
+            let key = caps[1].trim_end_matches(['.', '!', '?']).to_string();
+            if open_key.as_deref() != Some(key.as_str()) {
+                self.spans.push(new_span(key, SpanKind::Mention, None, None));
+            }
+        }
+    }
+
+    /// 从已索引位置继续索引，只处理完整的行
+    fn update(&mut self, file: &LogFile) -> std::io::Result<()> {
+        if file.compressed {
+            // 压缩文件只会完整索引一次
+            if self.offset > 0 {
+                return Ok(());
+            }
+            let mut reader = file.open_reader()?;
+            let mut offset = 0u64;
+            let mut buffer = Vec::new();
+            loop {
+                buffer.clear();
+                let read = reader.read_until(b'\n', &mut buffer)?;
+                if read == 0 {
+                    break;
+                }
+                self.index_line(&file.id, offset, &buffer);
+                offset += read as u64;
+            }
+            self.offset = offset.max(1);
+            return Ok(());
+        }
+
+        let mut handle = File::open(&file.path)?;
+        handle.seek(SeekFrom::Start(self.offset))?;
+        let mut reader = BufReader::new(handle);
+        let mut buffer = Vec::new();
+        loop {
+            buffer.clear();
+            let read = reader.read_until(b'\n', &mut buffer)?;
+            // 行尾没有换行符说明这一行还没写完，留到下次索引
+            if read == 0 || buffer.last() != Some(&b'\n') {
+                break;
+            }
+            self.index_line(&file.id, self.offset, &buffer);
+            self.offset += read as u64;
+        }
+        Ok(())
+    }
+
+    fn index_line(&mut self, file_id: &str, offset: u64, bytes: &[u8]) {
+        let line = String::from_utf8_lossy(bytes);
+        if let Some(record) = parse_header(line.trim_end_matches(['\n', '\r'])) {
+            self.add(file_id, offset, &record);
+        }
+    }
+}
+
+/// 所有日志文件的索引，按文件标识缓存
+#[derive(Default)]
+struct CorrelationIndex {
+    files: HashMap<String, FileIndex>,
+}
+
+impl CorrelationIndex {
+    /// 更新索引并返回满足条件的关联（按时间从旧到新）
+    fn find(&mut self, files: &[LogFile], predicate: impl Fn(&LogSpan) -> bool) -> std::io::Result<Vec<LogSpan>> {
+        // 已删除的文件不再保留索引
+        self.files.retain(|id, _| files.iter().any(|f| &f.id == id));
+
+        let mut found = Vec::new();
+        for file in files {
+            let entry = self.files.entry(file.id.clone()).or_default();
+            // 同一文件变小或开头内容变化说明被截断重写（复制后截断的轮转保留文件标识），
+            // 旧的关联已随内容复制到轮转文件中，丢弃后重新索引
+            if !file.compressed {
+                let head = file.head_fingerprint()?;
+                if file.size < entry.offset || (entry.offset > 0 && head != entry.head) {
+                    *entry = FileIndex::default();
+                }
+                if entry.offset == 0 {
+                    entry.head = head;
+                }
+            }
+            entry.update(file)?;
+            found.extend(entry.spans.iter().filter(|s| predicate(s)).cloned());
+        }
+        Ok(found)
+    }
+}
+
+static CORRELATION_INDEX: Mutex<Option<CorrelationIndex>> = Mutex::new(None);
+
+/// 更新全局索引并返回满足条件的关联（按时间从旧到新）
+fn find_spans(files: &[LogFile], predicate: impl Fn(&LogSpan) -> bool) -> std::io::Result<Vec<LogSpan>> {
+    let mut guard = CORRELATION_INDEX.lock().unwrap();
+    guard.get_or_insert_with(CorrelationIndex::default).find(files, predicate)
+}
+
+/// 读取一段关联的日志记录
+fn read_span(files: &[LogFile], span: &LogSpan) -> std::io::Result<Vec<LogRecord>> {
+    let Some(file) = files.iter().find(|f| f.id == span.file_id) else {
+        return Ok(Vec::new());
+    };
+    let (reader, start): (Box<dyn BufRead>, u64) = if file.compressed {
+        (file.open_reader()?, 0)
+    } else {
+        let mut handle = File::open(&file.path)?;
+        handle.seek(SeekFrom::Start(span.start_offset))?;
+        (Box::new(BufReader::new(handle)), span.start_offset)
+    };
+
+    let mut records = Vec::new();
+    for_each_record_at(reader, start, |offset, record| {
+        if offset < span.start_offset {
+            return true;
+        }
+        if span.end_offset.is_some_and(|end| offset > end) {
+            return false;
+        }
+        records.push(record);
+        records.len() < MAX_SPAN_RECORDS
+    });
+    Ok(records)
+}
+
+/// 将会话文件名（telegram_123.jsonl）转换为会话键（telegram:123）
+/// 优先读取会话文件第一行元数据中的 key
+fn resolve_session_key(session: &str) -> String {
+    if !session.ends_with(".jsonl") {
+        return session.to_string();
+    }
+    crate::session::read_chat_session_key(session)
+        .unwrap_or_else(|| session.trim_end_matches(".jsonl").replacen('_', ":", 1))
+}
+
+/// 查询关联日志并转换为响应
+fn spans_to_json(
+    log_path: &Path,
+    predicate: impl Fn(&LogSpan) -> bool,
+    since: Option<String>,
+    until: Option<String>,
+    limit: usize,
+) -> Result<JsonValue, String> {
+    let files = discover_log_files(log_path).map_err(|e| format!("读取日志目录失败: {}", e))?;
+    let (since, until) = (normalize_time(since.as_deref()), normalize_time(until.as_deref()));
+    // 按关联的开始时间过滤
+    let spans = find_spans(&files, |s| {
+        predicate(s) && in_range(s.started_at.as_deref(), since.as_deref(), until.as_deref())
+    })
+        .map_err(|e| format!("建立日志索引失败: {}", e))?;
+
+    let total = spans.len();
+    // 最新的在前
+    let mut results = Vec::new();
+    for span in spans.into_iter().rev().take(limit) {
+        let records = read_span(&files, &span).map_err(|e| format!("读取日志文件失败: {}", e))?;
+        let logs: Vec<String> = records.iter().map(|r| r.to_text()).collect();
+        let source = files.iter().find(|f| f.id == span.file_id).map(|f| f.name.clone());
+        results.push(json!({
+            "span": span,
+            "source": source,
+            "records": records,
+            "logs": logs
+        }));
+    }
+
+    Ok(json!({
+        "spans": results,
+        "total": total,
+        "showing": results.len()
+    }))
+}
+
+/// 获取会话相关的日志
+/// session 可以是会话键（telegram:123）或会话文件名（telegram_123.jsonl）
+#[tauri::command]
+pub async fn get_session_logs(
+    session: String,
+    since: Option<String>,
+    until: Option<String>,
+    limit: Option<usize>,
+) -> Result<JsonValue, String> {
+    let log_path = crate::logger::get_log_path().map_err(|e| e.to_string())?;
+    let key = resolve_session_key(&session);
+    let limit = limit.unwrap_or(DEFAULT_SPAN_LIMIT);
+
+    let mut result = tokio::task::spawn_blocking({
+        let key = key.clone();
+        move || {
+            spans_to_json(
+                &log_path,
+                |s| s.kind != SpanKind::CronRun && s.key == key,
+                since,
+                until,
+                limit,
+            )
+        }
+    })
+    .await
+    .map_err(|e| format!("日志关联任务失败: {}", e))??;
+    result["session_key"] = json!(key);
+    Ok(result)
+}
+
+/// 获取定时任务运行的日志（最近的运行在前）
+#[tauri::command]
+pub async fn get_cron_run_logs(
+    job_id: String,
+    since: Option<String>,
+    until: Option<String>,
+    limit: Option<usize>,
+) -> Result<JsonValue, String> {
+    let log_path = crate::logger::get_log_path().map_err(|e| e.to_string())?;
+    let limit = limit.unwrap_or(DEFAULT_SPAN_LIMIT);
+
+    let mut result = tokio::task::spawn_blocking({
+        let job_id = job_id.clone();
+        move || {
+            // 定时任务触发的对话使用 cron:{job_id} 作为会话键
+            let session_key = format!("cron:{}", job_id);
+            spans_to_json(
+                &log_path,
+                |s| (s.kind == SpanKind::CronRun && s.key == job_id) || (s.kind == SpanKind::Mention && s.key == session_key),
+                since,
+                until,
+                limit,
+            )
+        }
+    })
+    .await
+    .map_err(|e| format!("日志关联任务失败: {}", e))??;
+    result["job_id"] = json!(job_id);
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(second: u32, message: &str) -> String {
+        format!("2026-02-10 12:00:{:02}.000 | INFO     | nanobot.agent.loop:run:1 - {}\n", second, message)
+    }
+
+    /// 按顺序索引若干行，返回索引与每行的起始偏移
+    fn index_lines(lines: &[String]) -> (FileIndex, Vec<u64>) {
+        let mut index = FileIndex::default();
+        let mut offsets = Vec::new();
+        let mut offset = 0u64;
+        for line in lines {
+            offsets.push(offset);
+            index.index_line("f", offset, line.as_bytes());
+            offset += line.len() as u64;
+        }
+        (index, offsets)
+    }
+
+    #[test]
+    fn tracks_turn_status() {
+        let (index, offsets) = index_lines(&[
+            line(0, "Processing message from telegram:123: hi"),
+            line(1, "Tool call: web_search for telegram:123"),
+            line(2, "Response to telegram:123: hello"),
+            line(3, "Processing message from telegram:456: one"),
+            line(4, "Processing system message from cli:direct: two"),
+            line(5, "Response to discord:9: stray"),
+        ]);
+        let spans = &index.spans;
+        assert_eq!(spans.len(), 4);
+
+        // 正常结束的一轮，轮次内提到自身会话键的记录不单独关联
+        assert_eq!((spans[0].key.as_str(), spans[0].kind), ("telegram:123", SpanKind::Turn));
+        assert_eq!(spans[0].status.as_deref(), Some("completed"));
+        assert_eq!(spans[0].end_offset, Some(offsets[2]));
+        assert_eq!(spans[0].finished_at.as_deref(), Some("2026-02-10 12:00:02.000"));
+
+        // 下一轮开始时上一轮没有回复，标记为中断
+        assert_eq!(spans[1].status.as_deref(), Some("interrupted"));
+        assert_eq!(spans[1].end_offset, Some(offsets[4] - 1));
+
+        // 回复的会话与当前轮次不一致时只作为提及，当前轮次保持进行中
+        assert_eq!(spans[2].key, "cli:direct");
+        assert_eq!(spans[2].status.as_deref(), Some("running"));
+        assert_eq!(spans[2].end_offset, None);
+        assert_eq!((spans[3].key.as_str(), spans[3].kind), ("discord:9", SpanKind::Mention));
+        assert_eq!(index.open_turn, Some(2));
+    }
+
+    #[test]
+    fn tracks_cron_runs() {
+        let (index, offsets) = index_lines(&[
+            line(0, "Cron: executing job 'daily report' (abc123)"),
+            line(1, "Cron: job 'daily report' completed"),
+            line(2, "Cron: executing job 'sync' (s-1)"),
+            line(3, "Cron: executing job 'sync' (s-1)"),
+            line(4, "Cron: job 'sync' failed"),
+            line(5, "Cron: job 'unknown' completed"),
+        ]);
+        let spans = &index.spans;
+        assert_eq!(spans.len(), 3);
+        assert!(spans.iter().all(|s| s.kind == SpanKind::CronRun));
+
+        assert_eq!(spans[0].key, "abc123");
+        assert_eq!(spans[0].name.as_deref(), Some("daily report"));
+        assert_eq!(spans[0].status.as_deref(), Some("completed"));
+        assert_eq!(spans[0].end_offset, Some(offsets[1]));
+
+        // 同名任务再次开始，上一次运行视为中断
+        assert_eq!(spans[1].status.as_deref(), Some("interrupted"));
+        assert_eq!(spans[1].end_offset, Some(offsets[3] - 1));
+        assert_eq!(spans[2].status.as_deref(), Some("failed"));
+        assert!(index.open_cron.is_empty());
+    }
+
+    #[test]
+    fn extracts_mentions_without_trailing_punctuation() {
+        let (index, _) = index_lines(&[
+            line(0, "Sent reply to telegram:123."),
+            line(1, "Retrying discord:55! then slack:C01?"),
+            line(2, "Allowed (feishu:ou_1), ignoring 'email:a@b.c'"),
+            line(3, "Plain message without keys: http://example.com"),
+        ]);
+        let keys: Vec<&str> = index.spans.iter().map(|s| s.key.as_str()).collect();
+        assert_eq!(keys, ["telegram:123", "discord:55", "slack:C01", "feishu:ou_1", "email:a@b.c"]);
+        assert!(index.spans.iter().all(|s| s.kind == SpanKind::Mention && s.end_offset == Some(s.start_offset)));
+    }
+
+    #[test]
+    fn update_skips_half_written_line() {
+        let dir = std::env::temp_dir().join(format!("nanoboard-correlation-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("nanobot.log");
+
+        let first = line(0, "Processing message from telegram:1: hi");
+        let partial = line(1, "Response to telegram:1: hello");
+        let (written, rest) = partial.split_at(40);
+        std::fs::write(&path, format!("{}{}", first, written)).unwrap();
+
+        let file = |path: &Path| LogFile {
+            path: path.to_path_buf(),
+            name: "nanobot.log".to_string(),
+            id: "f".to_string(),
+            size: std::fs::metadata(path).unwrap().len(),
+            modified: None,
+            compressed: false,
+            current: true,
+        };
+
+        let mut index = FileIndex::default();
+        index.update(&file(&path)).unwrap();
+        assert_eq!(index.offset, first.len() as u64);
+        assert_eq!(index.spans.len(), 1);
+        assert_eq!(index.spans[0].status.as_deref(), Some("running"));
+
+        // 写完这一行后继续从上次位置索引
+        let mut content = std::fs::read_to_string(&path).unwrap();
+        content.push_str(rest);
+        std::fs::write(&path, content).unwrap();
+        index.update(&file(&path)).unwrap();
+        assert_eq!(index.offset, (first.len() + partial.len()) as u64);
+        assert_eq!(index.spans.len(), 1);
+        assert_eq!(index.spans[0].status.as_deref(), Some("completed"));
+        assert_eq!(index.spans[0].end_offset, Some(first.len() as u64));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn reindexes_current_file_after_copy_truncate() {
+        let dir = std::env::temp_dir().join(format!("nanoboard-correlation-rotate-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("nanobot.log");
+
+        let before = [
+            line(0, "Processing message from telegram:1: before"),
+            line(1, "Response to telegram:1: ok"),
+        ]
+        .concat();
+        std::fs::write(&path, &before).unwrap();
+
+        let mut index = CorrelationIndex::default();
+        let is_turn = |s: &LogSpan| s.kind == SpanKind::Turn;
+        let files = discover_log_files(&path).unwrap();
+        assert_eq!(index.find(&files, is_turn).unwrap().len(), 1);
+
+        // 复制后截断，并在下次查询前写入比原位置更多的内容
+        let settings = crate::log_rotation::LogRotationSettings { compress: false, ..Default::default() };
+        crate::log_rotation::rotate_log_file(&path, &settings).unwrap().unwrap();
+        let after = [
+            line(10, "Processing message from telegram:2: after, with a much longer first message"),
+            line(11, "Tool call: web_search for telegram:2"),
+            line(12, "Response to telegram:2: done"),
+        ]
+        .concat();
+        assert!(after.len() > before.len());
+        std::fs::write(&path, &after).unwrap();
+
+        let files = discover_log_files(&path).unwrap();
+        let spans = index.find(&files, is_turn).unwrap();
+        let keys: Vec<&str> = spans.iter().map(|s| s.key.as_str()).collect();
+        assert_eq!(keys.iter().filter(|k| **k == "telegram:1").count(), 1, "{:?}", keys);
+        assert_eq!(keys.iter().filter(|k| **k == "telegram:2").count(), 1, "{:?}", keys);
+
+        // 每个轮次都从各自所在的文件读取
+        for span in &spans {
+            let records = read_span(&files, span).unwrap();
+            assert!(records[0].message.contains(&span.key), "{:?}", records);
+            assert!(records.last().unwrap().message.starts_with("Response to"));
+        }
+        let current = files.iter().find(|f| f.current).unwrap();
+        let current_span = spans.iter().find(|s| s.key == "telegram:2").unwrap();
+        assert_eq!(current_span.file_id, current.id);
+        assert_eq!(current_span.start_offset, 0);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
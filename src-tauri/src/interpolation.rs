@@ -0,0 +1,619 @@
+// 配置值环境变量插值模块
+// 支持在配置中使用 `${VAR}` 与 `${VAR:-default}` 引用环境变量，
+// 变量来源为进程环境变量以及可选的 ~/.nanobot/.env 文件。
+// 插值结果永远不会写回 config.json；启动 nanobot 时通过每次启动独立的运行时配置路径（--config）传入，
+// Unix 上该路径是命名管道，展开后的取值不落盘；其他平台为仅当前用户可访问的临时文件，子进程退出后删除。
+
+use anyhow::{Context, Result};
+use dirs::home_dir;
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// 未能解析的环境变量引用
+#[derive(Debug, Clone, Serialize)]
+pub struct UnresolvedReference {
+    /// 配置项路径，例如 providers.openrouter.apiKey
+    pub path: String,
+    /// 引用的变量名
+    pub variable: String,
+}
+
+/// 插值结果
+#[derive(Debug, Clone)]
+pub struct InterpolationResult {
+    /// 解析后的配置（仅用于内存中使用，不得写入磁盘）
+    pub config: JsonValue,
+    /// 未解析的引用
+    pub unresolved: Vec<UnresolvedReference>,
+    /// 含有插值表达式的配置项路径（数组元素以下标作为路径段）
+    pub interpolated_paths: Vec<Vec<String>>,
+}
+
+/// 获取 .env 文件路径
+pub fn get_dotenv_path() -> Result<PathBuf> {
+    let home = home_dir().context("无法找到用户主目录")?;
+    Ok(home.join(".nanobot").join(".env"))
+}
+
+/// 解析 .env 文件内容
+/// 支持 `KEY=VALUE`、`export KEY=VALUE`、单双引号以及 `#` 注释
+fn parse_dotenv(content: &str) -> HashMap<String, String> {
+    let mut vars = HashMap::new();
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let Some(eq_pos) = line.find('=') else {
+            continue;
+        };
+
+        let key = line[..eq_pos].trim();
+        if !is_valid_var_name(key) {
+            continue;
+        }
+
+        let raw_value = line[eq_pos + 1..].trim();
+        let value = if let Some(inner) = raw_value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+            // 双引号支持常见转义
+            inner.replace("\\n", "\n").replace("\\\"", "\"").replace("\\\\", "\\")
+        } else if let Some(inner) = raw_value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')) {
+            inner.to_string()
+        } else {
+            // 未加引号的值允许行尾注释
+            match raw_value.find(" #") {
+                Some(pos) => raw_value[..pos].trim_end().to_string(),
+                None => raw_value.to_string(),
+            }
+        };
+
+        vars.insert(key.to_string(), value);
+    }
+
+    vars
+}
+
+/// 加载 .env 文件中的变量，文件不存在时返回空表
+pub fn load_dotenv() -> HashMap<String, String> {
+    let path = match get_dotenv_path() {
+        Ok(path) => path,
+        Err(_) => return HashMap::new(),
+    };
+
+    if !path.exists() {
+        return HashMap::new();
+    }
+
+    match fs::read_to_string(&path) {
+        Ok(content) => parse_dotenv(&content),
+        Err(e) => {
+            log::warn!("读取 .env 文件失败: {}", e);
+            HashMap::new()
+        }
+    }
+}
+
+fn is_valid_var_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {}
+        _ => return false,
+    }
+    chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// 判断字符串中是否包含插值表达式
+pub fn contains_reference(value: &str) -> bool {
+    value.contains("${")
+}
+
+/// 展开单个字符串中的 `${VAR}` / `${VAR:-default}`
+/// `$${` 表示字面量 `${`。返回展开后的字符串以及未解析的变量名
+fn interpolate_str(input: &str, lookup: &dyn Fn(&str) -> Option<String>) -> (String, Vec<String>) {
+    let mut output = String::with_capacity(input.len());
+    let mut missing = Vec::new();
+    let mut rest = input;
+
+    while let Some(pos) = rest.find('$') {
+        output.push_str(&rest[..pos]);
+        let after = &rest[pos..];
+
+        if let Some(stripped) = after.strip_prefix("$${") {
+            output.push_str("${");
+            rest = stripped;
+            continue;
+        }
+
+        if !after.starts_with("${") {
+            output.push('$');
+            rest = &after[1..];
+            continue;
+        }
+
+        let Some(end) = find_closing_brace(after) else {
+            // 没有闭合括号，按字面量处理
+            output.push_str(after);
+            rest = "";
+            break;
+        };
+
+        let expr = &after[2..end];
+        let (name, default) = match expr.find(":-") {
+            Some(idx) => (&expr[..idx], Some(&expr[idx + 2..])),
+            None => (expr, None),
+        };
+
+        if !is_valid_var_name(name) {
+            // 非法变量名，原样保留
+            output.push_str(&after[..=end]);
+        } else {
+            // 与 shell 一致：`:-` 在变量未定义或为空时使用默认值，已定义的空值不算未解析
+            match (lookup(name), default) {
+                (Some(value), Some(default)) if value.is_empty() => output.push_str(default),
+                (Some(value), _) => output.push_str(&value),
+                (None, Some(default)) => output.push_str(default),
+                (None, None) => {
+                    missing.push(name.to_string());
+                    output.push_str(&after[..=end]);
+                }
+            }
+        }
+
+        rest = &after[end + 1..];
+    }
+
+    output.push_str(rest);
+    (output, missing)
+}
+
+/// 查找 `${` 对应的闭合括号位置，默认值中可以包含成对的 `{}`
+fn find_closing_brace(expr: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    for (idx, c) in expr.char_indices().skip(1) {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(idx);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+fn interpolate_value(
+    value: &JsonValue,
+    path: &mut Vec<String>,
+    lookup: &dyn Fn(&str) -> Option<String>,
+    result: &mut InterpolationResult,
+) -> JsonValue {
+    match value {
+        JsonValue::String(s) if contains_reference(s) => {
+            let (expanded, missing) = interpolate_str(s, lookup);
+            for variable in missing {
+                result.unresolved.push(UnresolvedReference {
+                    path: display_path(path),
+                    variable,
+                });
+            }
+            result.interpolated_paths.push(path.clone());
+            JsonValue::String(expanded)
+        }
+        JsonValue::Object(map) => {
+            let mut out = serde_json::Map::with_capacity(map.len());
+            for (key, child) in map {
+                path.push(key.clone());
+                out.insert(key.clone(), interpolate_value(child, path, lookup, result));
+                path.pop();
+            }
+            JsonValue::Object(out)
+        }
+        JsonValue::Array(items) => {
+            let out = items
+                .iter()
+                .enumerate()
+                .map(|(idx, child)| {
+                    path.push(idx.to_string());
+                    let v = interpolate_value(child, path, lookup, result);
+                    path.pop();
+                    v
+                })
+                .collect();
+            JsonValue::Array(out)
+        }
+        _ => value.clone(),
+    }
+}
+
+/// 配置项路径的显示形式，数组下标显示为 key[0]
+fn display_path(path: &[String]) -> String {
+    let mut out = String::new();
+    for segment in path {
+        if !out.is_empty() && segment.parse::<usize>().is_ok() {
+            out.push_str(&format!("[{}]", segment));
+        } else {
+            if !out.is_empty() {
+                out.push('.');
+            }
+            out.push_str(segment);
+        }
+    }
+    out
+}
+
+/// 使用自定义查找函数展开配置
+pub fn interpolate_config_with(config: &JsonValue, lookup: &dyn Fn(&str) -> Option<String>) -> InterpolationResult {
+    let mut result = InterpolationResult {
+        config: JsonValue::Null,
+        unresolved: Vec::new(),
+        interpolated_paths: Vec::new(),
+    };
+    let mut path = Vec::new();
+    result.config = interpolate_value(config, &mut path, lookup, &mut result);
+    result
+}
+
+/// 展开配置中的环境变量引用
+/// 进程环境变量优先于 .env 文件中的同名变量
+pub fn interpolate_config(config: &JsonValue) -> InterpolationResult {
+    let dotenv = load_dotenv();
+    let lookup = |name: &str| std::env::var(name).ok().or_else(|| dotenv.get(name).cloned());
+    interpolate_config_with(config, &lookup)
+}
+
+/// 格式化未解析引用，用于错误提示
+pub fn describe_unresolved(unresolved: &[UnresolvedReference]) -> Vec<String> {
+    unresolved
+        .iter()
+        .map(|r| format!("配置项 {} 引用的环境变量 {} 未定义", r.path, r.variable))
+        .collect()
+}
+
+/// 启动 nanobot 子进程时需要附加的参数与环境变量
+#[derive(Debug, Default)]
+pub struct LaunchConfig {
+    pub args: Vec<String>,
+    pub envs: Vec<(String, String)>,
+    /// 运行时配置，需保持存活直到子进程退出（见 ResolvedConfigFile::release_on_exit）
+    pub resolved: Option<ResolvedConfigFile>,
+}
+
+/// 运行时配置文件名前缀，文件名中带有 nanoboard 的进程号与时间戳，每次启动互不相同
+const RESOLVED_PREFIX: &str = ".config.resolved.";
+
+/// 插值后的运行时配置
+/// 与 config.json 放在同一目录，nanobot 按配置文件所在目录推导的数据路径保持不变。
+/// Unix 上该路径是命名管道，由后台线程在每次被打开时写入完整配置，展开后的密钥只经过内核管道缓冲、
+/// 不落盘，nanobot 可以多次读取；进程异常退出时只留下不含内容的管道节点。
+/// 其他平台没有可用的命名管道，写入仅当前用户可访问的临时文件。释放本对象时停止后台线程并删除路径。
+#[derive(Debug)]
+pub struct ResolvedConfigFile {
+    path: PathBuf,
+    stop: Arc<AtomicBool>,
+    worker: Option<std::thread::JoinHandle<()>>,
+}
+
+impl ResolvedConfigFile {
+    /// 在指定目录下创建本次启动专用的运行时配置
+    pub fn create_in(dir: &Path, config: &JsonValue) -> Result<Self> {
+        let content = serde_json::to_string_pretty(config).context("序列化运行时配置失败")?;
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or(0);
+        let path = dir.join(format!("{}{}-{}.json", RESOLVED_PREFIX, std::process::id(), nanos));
+        let stop = Arc::new(AtomicBool::new(false));
+
+        #[cfg(unix)]
+        let worker = {
+            make_fifo(&path)?;
+            let worker_path = path.clone();
+            let worker_stop = stop.clone();
+            Some(std::thread::spawn(move || {
+                serve_fifo(&worker_path, content.as_bytes(), &worker_stop);
+            }))
+        };
+
+        #[cfg(not(unix))]
+        let worker = {
+            fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path)
+                .and_then(|mut file| std::io::Write::write_all(&mut file, content.as_bytes()))
+                .context("写入运行时配置文件失败")?;
+            None
+        };
+
+        Ok(Self { path, stop, worker })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 子进程退出后再释放（停止提供配置并删除路径）
+    pub fn release_on_exit(self, pid: u32) {
+        std::thread::spawn(move || {
+            let pid = sysinfo::Pid::from_u32(pid);
+            let mut system = sysinfo::System::new();
+            while system.refresh_process(pid) {
+                std::thread::sleep(Duration::from_secs(2));
+            }
+            drop(self);
+        });
+    }
+}
+
+impl Drop for ResolvedConfigFile {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// 创建仅当前用户可读写的命名管道
+#[cfg(unix)]
+fn make_fifo(path: &Path) -> Result<()> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = std::ffi::CString::new(path.as_os_str().as_bytes()).context("运行时配置路径无效")?;
+    // SAFETY: c_path 是以 NUL 结尾的有效路径字符串
+    if unsafe { libc::mkfifo(c_path.as_ptr(), 0o600) } != 0 {
+        return Err(std::io::Error::last_os_error()).context("创建运行时配置管道失败");
+    }
+    Ok(())
+}
+
+/// 每当有读端打开命名管道时写入完整配置，直到收到停止信号
+/// 读端打开后立即换上新的管道节点：之后的打开连接到新管道，当前读端在写完后能读到 EOF，
+/// 不会因为下一次写入而读到重复的内容
+#[cfg(unix)]
+fn serve_fifo(path: &Path, content: &[u8], stop: &AtomicBool) {
+    use std::io::Write;
+    use std::os::unix::fs::OpenOptionsExt;
+
+    let next = path.with_extension("next");
+    while !stop.load(Ordering::SeqCst) {
+        // 非阻塞打开写端：没有读端时返回 ENXIO，便于响应停止信号
+        let mut file = match fs::OpenOptions::new()
+            .write(true)
+            .custom_flags(libc::O_NONBLOCK)
+            .open(path)
+        {
+            Ok(file) => file,
+            Err(e) if e.raw_os_error() == Some(libc::ENXIO) => {
+                std::thread::sleep(Duration::from_millis(20));
+                continue;
+            }
+            Err(e) => {
+                log::warn!("打开运行时配置管道失败: {}", e);
+                break;
+            }
+        };
+
+        let _ = fs::remove_file(&next);
+        if let Err(e) = make_fifo(&next).and_then(|_| fs::rename(&next, path).context("替换运行时配置管道失败")) {
+            log::warn!("{:#}", e);
+            let _ = fs::remove_file(&next);
+            break;
+        }
+
+        let mut written = 0;
+        while written < content.len() && !stop.load(Ordering::SeqCst) {
+            match file.write(&content[written..]) {
+                Ok(n) => written += n,
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                    std::thread::sleep(Duration::from_millis(5));
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                // 读端提前关闭
+                Err(_) => break,
+            }
+        }
+    }
+}
+
+/// 已确认支持 --config 的命令（命令、前置参数、子命令）
+/// 只缓存支持的结果：不支持时启动会失败，用户升级 nanobot 后下次启动会重新检测
+static CONFIG_OPTION_SUPPORT: Mutex<Vec<(String, Vec<String>, String)>> = Mutex::new(Vec::new());
+
+/// 子命令是否支持 --config 参数
+async fn supports_config_option(nanobot_cmd: &str, base_args: &[String], subcommand: &str) -> bool {
+    let key = (nanobot_cmd.to_string(), base_args.to_vec(), subcommand.to_string());
+    if CONFIG_OPTION_SUPPORT.lock().map(|cache| cache.contains(&key)).unwrap_or(false) {
+        return true;
+    }
+    let command = crate::process::apply_hidden_window(std::process::Command::new(nanobot_cmd));
+    let supported = tokio::process::Command::from(command)
+        .args(base_args)
+        .args([subcommand, "--help"])
+        .env("PYTHONUTF8", "1")
+        .env("PYTHONIOENCODING", "utf-8")
+        .output()
+        .await
+        .map(|output| String::from_utf8_lossy(&output.stdout).contains("--config"))
+        .unwrap_or(false);
+    if supported {
+        if let Ok(mut cache) = CONFIG_OPTION_SUPPORT.lock() {
+            cache.push(key);
+        }
+    }
+    supported
+}
+
+/// 计算启动 nanobot 子命令（gateway / agent）时的参数与环境变量
+/// nanobot 读取配置时文件中的值优先于 NANOBOT_* 环境变量，因此不能只靠环境变量覆盖 `${VAR}`：
+/// 配置含有插值表达式时，将展开后的完整配置通过本次启动专用的运行时配置（`--config`）传入；
+/// 已安装的 nanobot 不支持 `--config` 时拒绝启动，避免以未展开的占位符运行。
+/// .env 文件中的变量也会一并注入，使 nanobot 内部代码可以直接读取。
+pub async fn launch_config(nanobot_cmd: &str, base_args: &[String], subcommand: &str) -> Result<LaunchConfig, String> {
+    let mut launch = LaunchConfig {
+        envs: load_dotenv()
+            .into_iter()
+            .filter(|(key, _)| std::env::var(key).is_err())
+            .collect(),
+        ..Default::default()
+    };
+
+    let config = crate::config::load_config_internal()?;
+    if config.get("error").and_then(|e| e.as_str()) == Some("config_not_found") {
+        return Ok(launch);
+    }
+
+    let result = interpolate_config(&config);
+    if !result.unresolved.is_empty() {
+        return Err(describe_unresolved(&result.unresolved).join("; "));
+    }
+    if result.interpolated_paths.is_empty() {
+        return Ok(launch);
+    }
+
+    if !supports_config_option(nanobot_cmd, base_args, subcommand).await {
+        return Err(format!(
+            "当前 nanobot 的 {} 命令不支持 --config 参数，无法传入展开后的 ${{VAR}} 配置，请升级 nanobot 或在配置中直接填写取值",
+            subcommand
+        ));
+    }
+
+    let dir = home_dir().ok_or("无法找到用户主目录")?.join(".nanobot");
+    let resolved = ResolvedConfigFile::create_in(&dir, &result.config).map_err(|e| format!("{:#}", e))?;
+    launch.args = vec!["--config".to_string(), resolved.path().to_string_lossy().to_string()];
+    launch.resolved = Some(resolved);
+    Ok(launch)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn lookup(name: &str) -> Option<String> {
+        match name {
+            "KEY" => Some("secret".to_string()),
+            "EMPTY" => Some(String::new()),
+            _ => None,
+        }
+    }
+
+    fn expand(input: &str) -> (String, Vec<String>) {
+        interpolate_str(input, &lookup)
+    }
+
+    #[test]
+    fn expands_variables_and_defaults() {
+        assert_eq!(expand("Bearer ${KEY}"), ("Bearer secret".to_string(), vec![]));
+        assert_eq!(expand("${MISSING:-fallback}"), ("fallback".to_string(), vec![]));
+        assert_eq!(expand("$${KEY} $5"), ("${KEY} $5".to_string(), vec![]));
+        assert_eq!(
+            expand("${MISSING}"),
+            ("${MISSING}".to_string(), vec!["MISSING".to_string()])
+        );
+    }
+
+    #[test]
+    fn default_may_contain_braces() {
+        assert_eq!(
+            expand(r#"${MISSING:-{"a":{"b":1}}}!"#),
+            (r#"{"a":{"b":1}}!"#.to_string(), vec![])
+        );
+        // 没有闭合括号时按字面量处理
+        assert_eq!(expand("${KEY:-{"), ("${KEY:-{".to_string(), vec![]));
+    }
+
+    #[test]
+    fn empty_value_is_resolved() {
+        assert_eq!(expand("[${EMPTY}]"), ("[]".to_string(), vec![]));
+        assert_eq!(expand("${EMPTY:-default}"), ("default".to_string(), vec![]));
+    }
+
+    #[test]
+    fn expands_array_elements_with_pointer_paths() {
+        let config = json!({
+            "mcpServers": { "fs": { "args": ["--token", "${KEY}", "${NOPE}"] } },
+            "plain": "value"
+        });
+        let result = interpolate_config_with(&config, &lookup);
+
+        assert_eq!(result.config["mcpServers"]["fs"]["args"][1], "secret");
+        assert_eq!(result.unresolved.len(), 1);
+        assert_eq!(result.unresolved[0].path, "mcpServers.fs.args[2]");
+        assert_eq!(result.unresolved[0].variable, "NOPE");
+
+        for path in &result.interpolated_paths {
+            let pointer: String = path.iter().map(|k| format!("/{}", k)).collect();
+            assert!(result.config.pointer(&pointer).is_some(), "{}", pointer);
+        }
+        assert_eq!(result.interpolated_paths.len(), 2);
+    }
+
+    #[test]
+    fn parses_dotenv() {
+        let vars = parse_dotenv(
+            "# comment\nexport A=1\nB=\"x\\ny\"\nC='raw $x'\nD=plain # note\n1BAD=no\n",
+        );
+        assert_eq!(vars.get("A").map(String::as_str), Some("1"));
+        assert_eq!(vars.get("B").map(String::as_str), Some("x\ny"));
+        assert_eq!(vars.get("C").map(String::as_str), Some("raw $x"));
+        assert_eq!(vars.get("D").map(String::as_str), Some("plain"));
+        assert!(!vars.contains_key("1BAD"));
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("nanoboard-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn resolved_config_is_private_and_removed_on_drop() {
+        let dir = temp_dir("resolved");
+        let config = json!({ "providers": { "openrouter": { "apiKey": "secret" } } });
+
+        let resolved = ResolvedConfigFile::create_in(&dir, &config).unwrap();
+        let path = resolved.path().to_path_buf();
+        assert!(path.file_name().unwrap().to_string_lossy().starts_with(RESOLVED_PREFIX));
+        // nanobot 可能多次打开配置文件
+        for _ in 0..3 {
+            let read: JsonValue = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+            assert_eq!(read, config);
+        }
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+            // 目录中只有不含内容的命名管道，展开后的密钥不落盘
+            for entry in fs::read_dir(&dir).unwrap() {
+                let meta = entry.unwrap().metadata().unwrap();
+                assert!(meta.file_type().is_fifo());
+                assert_eq!(meta.permissions().mode() & 0o777, 0o600);
+            }
+        }
+
+        // 每次启动使用不同的路径
+        let other = ResolvedConfigFile::create_in(&dir, &config).unwrap();
+        assert_ne!(other.path(), path);
+
+        drop(resolved);
+        drop(other);
+        assert!(!path.exists());
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
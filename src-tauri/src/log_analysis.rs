@@ -0,0 +1,325 @@
+// 日志错误聚类
+// 重复出现的错误通常只在 ID、时间、路径等细节上不同。将错误消息规范化
+// （数字、UUID、十六进制串、路径替换为占位符）后作为签名分组，统计次数、首次/最后出现时间，
+// 并保留一条带异常堆栈的样例，用于仪表盘展示"本周最常见的错误"。
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value as JsonValue};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use crate::log_files::discover_log_files;
+use crate::log_parser::{for_each_record_at, LogRecord};
+use crate::log_query::{LogQuery, RecordFilter};
+
+/// 默认返回的错误签名数量
+const DEFAULT_TOP: usize = 10;
+
+/// 样例堆栈最多保留的行数
+const MAX_SAMPLE_LINES: usize = 60;
+
+/// 错误分析条件
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ErrorAnalysisQuery {
+    /// 起始时间（含），格式同日志查询
+    pub since: Option<String>,
+    pub until: Option<String>,
+    /// 最近 N 天，未指定 since 时生效
+    pub days: Option<u32>,
+    /// 是否同时统计 WARNING
+    pub include_warnings: Option<bool>,
+    /// 返回的签名数量
+    pub limit: Option<usize>,
+}
+
+/// 一类错误
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorCluster {
+    /// 模块、规范化消息与异常类型的 FNV-1a 哈希，不随版本或运行变化，可作为稳定的 ID
+    pub signature: String,
+    /// 规范化后的消息
+    pub pattern: String,
+    pub module: Option<String>,
+    /// 出现过的最高级别
+    pub level: String,
+    /// 异常类型，例如 httpx.ConnectTimeout
+    pub exception: Option<String>,
+    pub count: usize,
+    pub first_seen: Option<String>,
+    pub last_seen: Option<String>,
+    /// 最近一次出现的原始消息
+    pub sample_message: String,
+    /// 最近一次带堆栈的出现的完整内容
+    pub sample_traceback: Vec<String>,
+}
+
+struct Normalizer {
+    uuid: Regex,
+    hex: Regex,
+    path: Regex,
+    number: Regex,
+    spaces: Regex,
+    exception: Regex,
+}
+
+fn normalizer() -> &'static Normalizer {
+    static NORMALIZER: OnceLock<Normalizer> = OnceLock::new();
+    NORMALIZER.get_or_init(|| Normalizer {
+        uuid: Regex::new(r"(?i)\b[0-9a-f]{8}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{12}\b").unwrap(),
+        hex: Regex::new(r"(?i)\b(?:0x[0-9a-f]+|[0-9a-f]*\d[0-9a-f]*[a-f][0-9a-f]*|[0-9a-f]*[a-f][0-9a-f]*\d[0-9a-f]*)\b").unwrap(),
+        path: Regex::new(r#"(?:[A-Za-z]:\\|~/|/)[^\s:'",)\]]*[/\\][^\s:'",)\]]*"#).unwrap(),
+        number: Regex::new(r"\d+(?:\.\d+)?").unwrap(),
+        spaces: Regex::new(r"\s+").unwrap(),
+        exception: Regex::new(r"^([A-Za-z_][\w.]*(?:Error|Exception|Exit|Interrupt|Timeout|Warning))\b").unwrap(),
+    })
+}
+
+/// 规范化错误消息：去掉易变的细节，只保留消息的结构
+pub fn normalize_message(message: &str) -> String {
+    let n = normalizer();
+    let text = n.uuid.replace_all(message, "<uuid>");
+    let text = n.path.replace_all(&text, "<path>");
+    // 十六进制串只替换长度足够的（如请求 ID、哈希），避免误伤普通单词
+    let text = n.hex.replace_all(&text, |caps: &regex::Captures| {
+        let m = &caps[0];
+        if m.len() >= 8 || m.starts_with("0x") || m.starts_with("0X") {
+            "<hex>".to_string()
+        } else {
+            m.to_string()
+        }
+    });
+    let text = n.number.replace_all(&text, "<n>");
+    n.spaces.replace_all(text.trim(), " ").to_string()
+}
+
+/// 从堆栈的最后几行中提取异常类型
+fn exception_type(record: &LogRecord) -> Option<String> {
+    record
+        .continuation
+        .iter()
+        .rev()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .take(5)
+        .find_map(|line| normalizer().exception.captures(line).map(|c| c[1].to_string()))
+}
+
+fn level_rank(level: &str) -> u8 {
+    match level {
+        "WARNING" => 1,
+        "ERROR" => 2,
+        "CRITICAL" => 3,
+        _ => 0,
+    }
+}
+
+/// 错误聚类器，逐条输入记录
+#[derive(Default)]
+pub struct ErrorClusterer {
+    clusters: HashMap<String, ErrorCluster>,
+    pub total: usize,
+}
+
+/// 计算错误签名：module|pattern|exception 的 64 位 FNV-1a 哈希
+/// 使用固定算法而不是 DefaultHasher，保存下来的签名在升级后仍然有效
+fn signature_of(module: Option<&str>, pattern: &str, exception: Option<&str>) -> String {
+    let text = format!("{}\x1f{}\x1f{}", module.unwrap_or(""), pattern, exception.unwrap_or(""));
+    fnv1a_hex(text.as_bytes())
+}
+
+/// 64 位 FNV-1a 哈希的十六进制表示
+pub fn fnv1a_hex(bytes: &[u8]) -> String {
+    const OFFSET: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+
+    let hash = bytes
+        .iter()
+        .fold(OFFSET, |hash, &byte| (hash ^ byte as u64).wrapping_mul(PRIME));
+    format!("{:016x}", hash)
+}
+
+impl ErrorClusterer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, record: &LogRecord) {
+        self.total += 1;
+        let pattern = normalize_message(&record.message);
+        let exception = exception_type(record);
+
+        let signature = signature_of(record.module.as_deref(), &pattern, exception.as_deref());
+
+        let cluster = self.clusters.entry(signature.clone()).or_insert_with(|| ErrorCluster {
+            signature,
+            pattern,
+            module: record.module.clone(),
+            level: record.level.clone(),
+            exception,
+            count: 0,
+            first_seen: record.timestamp.clone(),
+            last_seen: None,
+            sample_message: String::new(),
+            sample_traceback: Vec::new(),
+        });
+
+        cluster.count += 1;
+        if record.timestamp.is_some() {
+            cluster.last_seen = record.timestamp.clone();
+        }
+        if level_rank(&record.level) > level_rank(&cluster.level) {
+            cluster.level = record.level.clone();
+        }
+        cluster.sample_message = record.message.clone();
+        if !record.continuation.is_empty() || cluster.sample_traceback.is_empty() {
+            cluster.sample_traceback = record
+                .to_text()
+                .lines()
+                .take(MAX_SAMPLE_LINES)
+                .map(|l| l.to_string())
+                .collect();
+        }
+    }
+
+    /// 按出现次数从多到少返回前 limit 类错误
+    pub fn top(self, limit: usize) -> (Vec<ErrorCluster>, usize) {
+        let distinct = self.clusters.len();
+        let mut clusters: Vec<ErrorCluster> = self.clusters.into_values().collect();
+        clusters.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| b.last_seen.cmp(&a.last_seen)));
+        clusters.truncate(limit);
+        (clusters, distinct)
+    }
+}
+
+/// 扫描当前与历史日志文件，统计时间范围内的错误
+pub fn analyze_log_errors(log_path: &Path, query: &ErrorAnalysisQuery) -> Result<JsonValue, String> {
+    let since = query.since.clone().filter(|s| !s.is_empty()).or_else(|| {
+        query.days.map(|days| {
+            (chrono::Local::now() - chrono::Duration::days(days as i64))
+                .format("%Y-%m-%d %H:%M:%S")
+                .to_string()
+        })
+    });
+    let mut levels = vec!["ERROR".to_string()];
+    if query.include_warnings.unwrap_or(false) {
+        levels.push("WARN".to_string());
+    }
+    let filter = RecordFilter::new(&LogQuery {
+        levels: Some(levels),
+        since: since.clone(),
+        until: query.until.clone(),
+        ..Default::default()
+    })?;
+
+    let files = discover_log_files(log_path).map_err(|e| format!("读取日志目录失败: {}", e))?;
+    // 最后修改时间早于起始时间的历史文件不可能包含范围内的记录
+    let since_time = since
+        .as_deref()
+        .and_then(|s| chrono::NaiveDateTime::parse_from_str(s.get(..19)?, "%Y-%m-%d %H:%M:%S").ok());
+
+    let mut clusterer = ErrorClusterer::new();
+    let mut files_scanned = 0;
+    for file in &files {
+        let modified = file
+            .modified
+            .as_deref()
+            .and_then(|m| chrono::DateTime::parse_from_rfc3339(m).ok())
+            .map(|m| m.with_timezone(&chrono::Local).naive_local());
+        if let (Some(since), Some(modified)) = (since_time, modified) {
+            if !file.current && modified < since {
+                continue;
+            }
+        }
+
+        let reader = file
+            .open_reader()
+            .map_err(|e| format!("读取日志文件 {} 失败: {}", file.name, e))?;
+        files_scanned += 1;
+        for_each_record_at(reader, 0, |_, record| {
+            if filter.matches(&record) {
+                clusterer.add(&record);
+            }
+            true
+        });
+    }
+
+    let total = clusterer.total;
+    let (clusters, distinct) = clusterer.top(query.limit.unwrap_or(DEFAULT_TOP).max(1));
+    Ok(json!({
+        "clusters": clusters,
+        "total_errors": total,
+        "distinct": distinct,
+        "since": since,
+        "until": query.until,
+        "files_scanned": files_scanned
+    }))
+}
+
+/// 错误聚类分析
+#[tauri::command]
+pub async fn analyze_errors(query: Option<ErrorAnalysisQuery>) -> Result<JsonValue, String> {
+    let log_path = crate::logger::get_log_path().map_err(|e| e.to_string())?;
+    let query = query.unwrap_or_default();
+
+    tokio::task::spawn_blocking(move || analyze_log_errors(&log_path, &query))
+        .await
+        .map_err(|e| format!("错误分析任务失败: {}", e))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::log_parser::parse_lines;
+
+    #[test]
+    fn normalizes_volatile_details() {
+        assert_eq!(
+            normalize_message("Request 3f2a9c1e-0b4d-4e8a-9f00-1234567890ab failed after 30.5s"),
+            "Request <uuid> failed after <n>s"
+        );
+        assert_eq!(
+            normalize_message("Cannot read /home/alice/.nanobot/workspace/a.md:  trace deadbeef99 at 0x7f3a"),
+            "Cannot read <path>: trace <hex> at <hex>"
+        );
+        // 普通单词与短十六进制串保持不变
+        assert_eq!(normalize_message("bad face cafe"), "bad face cafe");
+        assert_eq!(
+            normalize_message("Timeout on chat 1001"),
+            normalize_message("Timeout on chat 2002")
+        );
+    }
+
+    #[test]
+    fn clusters_by_pattern_and_exception() {
+        let records = parse_lines([
+            "2026-02-10 12:00:00.000 | ERROR    | nanobot.agent:run:1 - Call 17 failed",
+            "Traceback (most recent call last):",
+            "httpx.ConnectTimeout: timed out",
+            "2026-02-10 12:05:00.000 | CRITICAL | nanobot.agent:run:1 - Call 42 failed",
+            "Traceback (most recent call last):",
+            "httpx.ConnectTimeout: timed out",
+            "2026-02-10 12:06:00.000 | ERROR    | nanobot.agent:run:1 - Call 43 failed",
+        ]);
+        let mut clusterer = ErrorClusterer::new();
+        for record in &records {
+            clusterer.add(record);
+        }
+        assert_eq!(clusterer.total, 3);
+
+        let (top, distinct) = clusterer.top(10);
+        assert_eq!(distinct, 2);
+        assert_eq!(top[0].count, 2);
+        assert_eq!(top[0].pattern, "Call <n> failed");
+        assert_eq!(top[0].exception.as_deref(), Some("httpx.ConnectTimeout"));
+        assert_eq!(top[0].level, "CRITICAL");
+        assert_eq!(top[0].first_seen.as_deref(), Some("2026-02-10 12:00:00.000"));
+        assert_eq!(top[0].last_seen.as_deref(), Some("2026-02-10 12:05:00.000"));
+        assert_eq!(top[1].exception, None);
+        // 签名使用固定算法，跨版本保持不变
+        assert_eq!(top[0].signature, "9dea476c468cadd1");
+        assert_ne!(top[0].signature, top[1].signature);
+    }
+}
@@ -0,0 +1,380 @@
+// 日志导出
+// 将过滤后的日志（含轮转的历史文件）导出为：
+// - text：日志原文
+// - jsonl：每行一条解析后的记录
+// - bundle：zip 支持包，包含日志、脱敏后的配置、诊断结果与版本信息，便于提交给 nanobot 维护者
+// 导出内容默认按配置中的密钥脱敏。
+
+use serde::Deserialize;
+use serde_json::{json, Value as JsonValue};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::log_files::discover_log_files;
+use crate::log_parser::for_each_record_at;
+use crate::log_query::{LogQuery, RecordFilter};
+use crate::redaction::Redactor;
+
+/// 导出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Text,
+    Jsonl,
+    Bundle,
+}
+
+impl ExportFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Text => "log",
+            ExportFormat::Jsonl => "jsonl",
+            ExportFormat::Bundle => "zip",
+        }
+    }
+}
+
+/// 日志导出请求
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogExportRequest {
+    pub format: ExportFormat,
+    /// 过滤条件，与日志查询相同（分页相关字段忽略）
+    pub filter: Option<LogQuery>,
+    /// 导出文件路径，未指定时保存到下载目录
+    pub path: Option<String>,
+    /// 是否包含轮转的历史日志文件，默认包含
+    pub include_rotated: Option<bool>,
+    /// 是否脱敏，默认开启
+    pub redact: Option<bool>,
+}
+
+/// 按时间顺序写出过滤后的日志，返回写出的记录数
+fn write_logs(
+    log_path: &Path,
+    filter: &RecordFilter,
+    include_rotated: bool,
+    redactor: Option<&Redactor>,
+    jsonl: bool,
+    out: &mut dyn Write,
+) -> Result<usize, String> {
+    let files = discover_log_files(log_path).map_err(|e| format!("读取日志目录失败: {}", e))?;
+    let mut count = 0;
+    let mut write_error = None;
+
+    for file in files.iter().filter(|f| include_rotated || f.current) {
+        let reader = file
+            .open_reader()
+            .map_err(|e| format!("读取日志文件 {} 失败: {}", file.name, e))?;
+        for_each_record_at(reader, 0, |_, record| {
+            if !filter.matches(&record) {
+                return true;
+            }
+            let record = match redactor {
+                Some(redactor) => redactor.redact_record(&record),
+                None => record,
+            };
+            let line = if jsonl {
+                serde_json::to_string(&record).unwrap_or_default()
+            } else {
+                record.to_text()
+            };
+            if let Err(e) = writeln!(out, "{}", line) {
+                write_error = Some(e);
+                return false;
+            }
+            count += 1;
+            true
+        });
+        if let Some(e) = write_error.take() {
+            return Err(format!("写入导出文件失败: {}", e));
+        }
+    }
+    Ok(count)
+}
+
+/// 版本与环境信息
+fn collect_versions() -> JsonValue {
+    json!({
+        "nanoboard": env!("CARGO_PKG_VERSION"),
+        "nanobot": crate::process::get_nanobot_version_internal().ok().flatten(),
+        "config_version": crate::migration::get_recorded_config_version(),
+        "os": std::env::consts::OS,
+        "arch": std::env::consts::ARCH,
+        "generated_at": chrono::Local::now().to_rfc3339()
+    })
+}
+
+/// 写出 zip 支持包
+fn write_bundle(
+    target: &Path,
+    log_path: &Path,
+    filter: &RecordFilter,
+    include_rotated: bool,
+    redactor: Option<&Redactor>,
+    config: Option<JsonValue>,
+    diagnostics: JsonValue,
+) -> Result<usize, String> {
+    let file = File::create(target).map_err(|e| format!("创建导出文件失败: {}", e))?;
+    let mut zip = ZipWriter::new(BufWriter::new(file));
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    let zip_err = |e: zip::result::ZipError| format!("写入支持包失败: {}", e);
+
+    zip.start_file("logs/nanobot.log", options).map_err(zip_err)?;
+    let count = write_logs(log_path, filter, include_rotated, redactor, false, &mut zip)?;
+
+    let redact_json = |value: &JsonValue| match redactor {
+        Some(redactor) => redactor.redact_json(value),
+        None => value.clone(),
+    };
+
+    if let Some(config) = config {
+        zip.start_file("config.json", options).map_err(zip_err)?;
+        let content = serde_json::to_string_pretty(&redact_json(&config)).unwrap_or_default();
+        zip.write_all(content.as_bytes()).map_err(|e| format!("写入支持包失败: {}", e))?;
+    }
+
+    zip.start_file("diagnostics.json", options).map_err(zip_err)?;
+    let content = serde_json::to_string_pretty(&redact_json(&diagnostics)).unwrap_or_default();
+    zip.write_all(content.as_bytes()).map_err(|e| format!("写入支持包失败: {}", e))?;
+
+    zip.start_file("versions.json", options).map_err(zip_err)?;
+    let content = serde_json::to_string_pretty(&collect_versions()).unwrap_or_default();
+    zip.write_all(content.as_bytes()).map_err(|e| format!("写入支持包失败: {}", e))?;
+
+    zip.finish()
+        .map_err(zip_err)?
+        .flush()
+        .map_err(|e| format!("写入支持包失败: {}", e))?;
+    Ok(count)
+}
+
+/// 默认导出路径：下载目录（不存在时为主目录）下以 prefix 开头、带时间戳的文件
+pub(crate) fn default_export_path(prefix: &str, extension: &str) -> PathBuf {
+    let dir = dirs::download_dir()
+        .or_else(dirs::home_dir)
+        .unwrap_or_else(|| PathBuf::from("."));
+    let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+    dir.join(format!("{}-{}.{}", prefix, stamp, extension))
+}
+
+/// 导出日志
+#[tauri::command]
+pub async fn export_logs(request: LogExportRequest) -> Result<JsonValue, String> {
+    let log_path = crate::logger::get_log_path().map_err(|e| e.to_string())?;
+    let filter = RecordFilter::new(&request.filter.clone().unwrap_or_default())?;
+    let target = request
+        .path
+        .as_deref()
+        .filter(|p| !p.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| {
+            let prefix = match request.format {
+                ExportFormat::Bundle => "nanobot-support",
+                _ => "nanobot-logs",
+            };
+            default_export_path(prefix, request.format.extension())
+        });
+    if let Some(parent) = target.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent).map_err(|e| format!("创建导出目录失败: {}", e))?;
+    }
+
+    let include_rotated = request.include_rotated.unwrap_or(true);
+    let redact = request.redact.unwrap_or(true);
+
+    // 诊断需要启动外部命令，在打包前异步完成
+    let diagnostics = if request.format == ExportFormat::Bundle {
+        crate::process::diagnose_nanobot()
+            .await
+            .unwrap_or_else(|e| json!({ "error": e }))
+    } else {
+        JsonValue::Null
+    };
+
+    // 配置使用原始内容（不插值），避免把环境变量中的密钥写入支持包
+    let config = match request.format {
+        ExportFormat::Bundle => crate::config::load_config_internal().ok(),
+        _ => None,
+    };
+
+    let format = request.format;
+    let export_target = target.clone();
+    let count = tokio::task::spawn_blocking(move || {
+        let redactor = redact.then(Redactor::from_current_config);
+        match format {
+            ExportFormat::Bundle => write_bundle(
+                &export_target,
+                &log_path,
+                &filter,
+                include_rotated,
+                redactor.as_ref(),
+                config,
+                diagnostics,
+            ),
+            _ => {
+                let file = File::create(&export_target).map_err(|e| format!("创建导出文件失败: {}", e))?;
+                let mut out = BufWriter::new(file);
+                let count = write_logs(
+                    &log_path,
+                    &filter,
+                    include_rotated,
+                    redactor.as_ref(),
+                    format == ExportFormat::Jsonl,
+                    &mut out,
+                )?;
+                out.flush().map_err(|e| format!("写入导出文件失败: {}", e))?;
+                Ok(count)
+            }
+        }
+    })
+    .await
+    .map_err(|e| format!("日志导出任务失败: {}", e))??;
+
+    let size = std::fs::metadata(&target).map(|m| m.len()).unwrap_or(0);
+    Ok(json!({
+        "success": true,
+        "message": format!("已导出 {} 条日志", count),
+        "path": target.to_string_lossy(),
+        "records": count,
+        "size": size,
+        "redacted": redact
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    const CURRENT: &str = "\
+2026-02-10 12:00:03.000 | INFO     | nanobot.agent.loop:run:1 - current info
+2026-02-10 12:00:04.000 | ERROR    | nanobot.channels.telegram:send:9 - send failed token sk-or-v1-abcdefghijklmnop123456
+Traceback (most recent call last)
+";
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("nanoboard-export-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// 写入一个轮转文件与当前日志，返回当前日志路径
+    fn log_dir(name: &str) -> (PathBuf, PathBuf) {
+        let dir = temp_dir(name);
+        let rotated = dir.join("nanobot.log.1");
+        std::fs::write(
+            &rotated,
+            "2026-02-10 12:00:01.000 | INFO     | nanobot.agent.loop:run:1 - rotated info\n\
+             2026-02-10 12:00:02.000 | ERROR    | nanobot.agent.loop:run:2 - rotated error\n",
+        )
+        .unwrap();
+        let old = std::time::SystemTime::now() - std::time::Duration::from_secs(60);
+        File::options().write(true).open(&rotated).unwrap().set_modified(old).unwrap();
+        let log_path = dir.join("nanobot.log");
+        std::fs::write(&log_path, CURRENT).unwrap();
+        (dir, log_path)
+    }
+
+    fn filter(levels: &[&str]) -> RecordFilter {
+        RecordFilter::new(&LogQuery {
+            levels: (!levels.is_empty()).then(|| levels.iter().map(|l| l.to_string()).collect()),
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    fn export(
+        log_path: &Path,
+        filter: &RecordFilter,
+        include_rotated: bool,
+        redactor: Option<&Redactor>,
+        jsonl: bool,
+    ) -> (usize, String) {
+        let mut out = Vec::new();
+        let count = write_logs(log_path, filter, include_rotated, redactor, jsonl, &mut out).unwrap();
+        (count, String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn writes_filtered_text_across_rotated_files() {
+        let (dir, log_path) = log_dir("text");
+
+        let (count, text) = export(&log_path, &filter(&[]), true, None, false);
+        assert_eq!(count, 4);
+        let messages: Vec<&str> = text.lines().filter_map(|l| l.split(" - ").nth(1)).collect();
+        assert_eq!(messages[..3], ["rotated info", "rotated error", "current info"]);
+        // 续行随记录一起导出
+        assert!(text.ends_with("Traceback (most recent call last)\n"));
+
+        let (count, text) = export(&log_path, &filter(&["error"]), true, None, false);
+        assert_eq!(count, 2);
+        assert!(text.contains("rotated error") && !text.contains("info"));
+
+        let (count, text) = export(&log_path, &filter(&["error"]), false, None, false);
+        assert_eq!(count, 1);
+        assert!(!text.contains("rotated"));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn writes_redacted_jsonl_records() {
+        let (dir, log_path) = log_dir("jsonl");
+        let redactor = Redactor::new(Vec::new());
+
+        let (count, text) = export(&log_path, &filter(&["error"]), false, Some(&redactor), true);
+        assert_eq!(count, 1);
+        let record: JsonValue = serde_json::from_str(text.trim_end()).unwrap();
+        assert_eq!(record["level"], "ERROR");
+        assert_eq!(record["module"], "nanobot.channels.telegram");
+        assert_eq!(record["message"], "send failed token [REDACTED]");
+
+        // 未脱敏时保留原文
+        let (_, text) = export(&log_path, &filter(&["error"]), false, None, true);
+        assert!(text.contains("sk-or-v1-abcdefghijklmnop123456"));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn bundles_logs_config_and_diagnostics() {
+        let (dir, log_path) = log_dir("bundle");
+        let target = dir.join("support.zip");
+        let config = json!({
+            "providers": { "openrouter": { "apiKey": "sk-or-v1-abcdefghijklmnop123456" } },
+            "agents": { "defaults": { "model": "anthropic/claude-opus-4-5" } }
+        });
+        let redactor = Redactor::new(crate::redaction::secrets_from_config(&config));
+        let diagnostics = json!({ "checks": [{ "name": "config", "detail": "key sk-or-v1-abcdefghijklmnop123456" }] });
+
+        let count =
+            write_bundle(&target, &log_path, &filter(&[]), true, Some(&redactor), Some(config), diagnostics).unwrap();
+        assert_eq!(count, 4);
+
+        let mut archive = zip::ZipArchive::new(File::open(&target).unwrap()).unwrap();
+        let mut read = |name: &str| {
+            let mut content = String::new();
+            archive.by_name(name).unwrap().read_to_string(&mut content).unwrap();
+            content
+        };
+
+        let logs = read("logs/nanobot.log");
+        assert!(logs.contains("rotated info") && logs.contains("send failed token [REDACTED]"));
+
+        let config: JsonValue = serde_json::from_str(&read("config.json")).unwrap();
+        assert_eq!(config["providers"]["openrouter"]["apiKey"], crate::redaction::REDACTED);
+        assert_eq!(config["agents"]["defaults"]["model"], "anthropic/claude-opus-4-5");
+
+        let diagnostics = read("diagnostics.json");
+        assert!(diagnostics.contains("key [REDACTED]") && !diagnostics.contains("abcdefghijklmnop"));
+
+        let versions: JsonValue = serde_json::from_str(&read("versions.json")).unwrap();
+        assert_eq!(versions["nanoboard"], env!("CARGO_PKG_VERSION"));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
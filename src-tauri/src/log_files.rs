@@ -0,0 +1,270 @@
+// 日志文件发现与标识
+// - 轮转后的历史文件与当前日志位于同一目录，支持以下命名：
+//     nanobot.log.1 / nanobot.log.1.gz          （按序号轮转）
+//     nanobot.log.2026-02-10 / nanobot.log.2026-02-10_12-00-00.gz（按日期轮转）
+//     nanobot.2026-02-10_12-00-00_000000.log[.gz]（loguru 的 rotation 参数）
+// - 文件标识：Unix 上为 (设备号, inode)，文件被重命名后标识不变，
+//   因此可以在轮转后继续读完旧文件，分页游标也随文件一起移动。
+
+use flate2::read::MultiGzDecoder;
+use serde::Serialize;
+use serde_json::{json, Value as JsonValue};
+use std::fs::{File, Metadata};
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// 当前日志文件名
+pub const LOG_FILE_NAME: &str = "nanobot.log";
+
+/// 文件标识，用于识别重命名后的同一文件
+#[cfg(unix)]
+pub fn file_identity(meta: &Metadata) -> String {
+    use std::os::unix::fs::MetadataExt;
+    format!("{:x}-{:x}", meta.dev(), meta.ino())
+}
+
+/// 文件标识，用于识别重命名后的同一文件
+/// 非 Unix 平台没有 inode，使用创建时间近似（重命名不改变创建时间）
+#[cfg(not(unix))]
+pub fn file_identity(meta: &Metadata) -> String {
+    let created = meta
+        .created()
+        .ok()
+        .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    format!("c{:x}", created)
+}
+
+/// 日志目录中的一个日志文件
+#[derive(Debug, Clone, Serialize)]
+pub struct LogFile {
+    #[serde(skip)]
+    pub path: PathBuf,
+    pub name: String,
+    /// 文件标识，见 file_identity
+    pub id: String,
+    pub size: u64,
+    /// 最后修改时间（RFC 3339）
+    pub modified: Option<String>,
+    pub compressed: bool,
+    /// 是否为 nanobot 正在写入的 nanobot.log
+    pub current: bool,
+}
+
+/// 内容指纹的最大长度
+const HEAD_FINGERPRINT_LEN: u64 = 256;
+
+impl LogFile {
+    /// 文件第一行（最多 256 字节）作为内容指纹
+    /// 复制后截断的轮转保留文件标识，截断后写入的内容超过原位置时文件也不会变小，
+    /// 只能通过开头内容的变化识别文件已被重写
+    pub fn head_fingerprint(&self) -> std::io::Result<Vec<u8>> {
+        let mut head = Vec::new();
+        File::open(&self.path)?
+            .take(HEAD_FINGERPRINT_LEN)
+            .read_to_end(&mut head)?;
+        if let Some(end) = head.iter().position(|&b| b == b'\n') {
+            head.truncate(end + 1);
+        }
+        Ok(head)
+    }
+
+    /// 打开文件，压缩文件自动解压
+    /// 压缩文件中的偏移为解压后内容的偏移
+    pub fn open_reader(&self) -> std::io::Result<Box<dyn BufRead>> {
+        let file = File::open(&self.path)?;
+        if self.compressed {
+            Ok(Box::new(BufReader::new(MultiGzDecoder::new(file))))
+        } else {
+            Ok(Box::new(BufReader::new(file)))
+        }
+    }
+}
+
+/// 判断是否为轮转后的历史日志文件
+pub fn is_rotated_log(name: &str) -> bool {
+    if name == LOG_FILE_NAME {
+        return false;
+    }
+    let base = name.strip_suffix(".gz").unwrap_or(name);
+    base.starts_with("nanobot.log.") || (base.starts_with("nanobot.") && base.ends_with(".log"))
+}
+
+/// nanobot.log.N 形式的轮转序号，序号越大越旧
+fn rotation_index(name: &str) -> Option<u32> {
+    let base = name.strip_suffix(".gz").unwrap_or(name);
+    base.strip_prefix("nanobot.log.")?.parse().ok()
+}
+
+fn describe(path: PathBuf, meta: &Metadata, current: bool) -> LogFile {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    LogFile {
+        compressed: name.ends_with(".gz"),
+        id: file_identity(meta),
+        size: meta.len(),
+        modified: meta
+            .modified()
+            .ok()
+            .map(|t| chrono::DateTime::<chrono::Local>::from(t).to_rfc3339()),
+        name,
+        path,
+        current,
+    }
+}
+
+/// 列出日志目录中的日志文件，按时间从旧到新排列，当前日志文件（如存在）位于最后
+pub fn discover_log_files(log_path: &Path) -> std::io::Result<Vec<LogFile>> {
+    let mut rotated: Vec<(SystemTime, Option<u32>, LogFile)> = Vec::new();
+
+    if let Some(dir) = log_path.parent().filter(|d| d.is_dir()) {
+        for entry in std::fs::read_dir(dir)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !is_rotated_log(&name) {
+                continue;
+            }
+            let Ok(meta) = entry.metadata() else {
+                continue;
+            };
+            if !meta.is_file() {
+                continue;
+            }
+            let modified = meta.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            rotated.push((modified, rotation_index(&name), describe(entry.path(), &meta, false)));
+        }
+    }
+
+    // 修改时间相同时（例如同一秒内连续轮转），序号大的更旧，其余按文件名排序
+    rotated.sort_by(|(ta, ia, a), (tb, ib, b)| {
+        ta.cmp(tb)
+            .then_with(|| ib.cmp(ia))
+            .then_with(|| a.name.cmp(&b.name))
+    });
+
+    let mut files: Vec<LogFile> = rotated.into_iter().map(|(_, _, f)| f).collect();
+    if let Ok(meta) = std::fs::metadata(log_path) {
+        files.push(describe(log_path.to_path_buf(), &meta, true));
+    }
+    Ok(files)
+}
+
+/// 列出当前与历史日志文件
+#[tauri::command]
+pub async fn list_log_files() -> Result<JsonValue, String> {
+    let log_path = crate::logger::get_log_path().map_err(|e| e.to_string())?;
+    let files = discover_log_files(&log_path)
+        .map_err(|e| format!("读取日志目录失败: {}", e))?;
+    let total_size: u64 = files.iter().map(|f| f.size).sum();
+
+    Ok(json!({
+        "files": files,
+        "total_size": total_size
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+    use std::time::Duration;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("nanoboard-logfiles-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write_with_mtime(path: &Path, content: &[u8], modified: SystemTime) {
+        std::fs::write(path, content).unwrap();
+        File::options().write(true).open(path).unwrap().set_modified(modified).unwrap();
+    }
+
+    #[test]
+    fn recognizes_rotated_logs() {
+        for name in [
+            "nanobot.log.1",
+            "nanobot.log.2.gz",
+            "nanobot.log.2026-02-10",
+            "nanobot.log.2026-02-10_12-00-00.gz",
+            "nanobot.2026-02-10_12-00-00_000000.log",
+            "nanobot.2026-02-10_12-00-00_000000.log.gz",
+        ] {
+            assert!(is_rotated_log(name), "{}", name);
+        }
+        for name in ["nanobot.log", "gateway.log", "nanobot.txt", "other.log.1"] {
+            assert!(!is_rotated_log(name), "{}", name);
+        }
+        assert_eq!(rotation_index("nanobot.log.2.gz"), Some(2));
+        assert_eq!(rotation_index("nanobot.log.2026-02-10"), None);
+    }
+
+    #[test]
+    fn orders_files_from_oldest_to_current() {
+        let dir = temp_dir("order");
+        let now = SystemTime::now();
+        // 同一时刻轮转的 .1 与 .2.gz 按序号排列，序号大的更旧
+        write_with_mtime(&dir.join("nanobot.log.1"), b"1", now - Duration::from_secs(100));
+        write_with_mtime(&dir.join("nanobot.log.2.gz"), b"2", now - Duration::from_secs(100));
+        write_with_mtime(&dir.join("nanobot.log.2026-02-10"), b"d", now - Duration::from_secs(300));
+        write_with_mtime(
+            &dir.join("nanobot.2026-02-11_12-00-00_000000.log.gz"),
+            b"l",
+            now - Duration::from_secs(200),
+        );
+        std::fs::write(dir.join("nanobot.log"), b"current").unwrap();
+        std::fs::write(dir.join("notes.txt"), b"x").unwrap();
+        std::fs::create_dir(dir.join("nanobot.log.3")).unwrap();
+
+        let files = discover_log_files(&dir.join("nanobot.log")).unwrap();
+        let names: Vec<&str> = files.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "nanobot.log.2026-02-10",
+                "nanobot.2026-02-11_12-00-00_000000.log.gz",
+                "nanobot.log.2.gz",
+                "nanobot.log.1",
+                "nanobot.log",
+            ]
+        );
+        assert!(files.iter().all(|f| f.current == (f.name == "nanobot.log")));
+        assert!(files[1].compressed && files[2].compressed && !files[3].compressed);
+
+        // 当前日志不存在时只返回历史文件
+        std::fs::remove_file(dir.join("nanobot.log")).unwrap();
+        let files = discover_log_files(&dir.join("nanobot.log")).unwrap();
+        assert_eq!(files.len(), 4);
+        assert!(files.iter().all(|f| !f.current));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn reads_compressed_logs() {
+        let dir = temp_dir("gzip");
+        let path = dir.join("nanobot.log.1.gz");
+        // 两个 gzip 成员拼接（例如追加压缩）也要完整读出
+        let mut content = Vec::new();
+        for part in ["first\nsecond\n", "third\n"] {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(part.as_bytes()).unwrap();
+            content.extend(encoder.finish().unwrap());
+        }
+        std::fs::write(&path, content).unwrap();
+
+        let file = discover_log_files(&dir.join("nanobot.log")).unwrap().remove(0);
+        assert!(file.compressed);
+        let lines: Vec<String> = file.open_reader().unwrap().lines().map(|l| l.unwrap()).collect();
+        assert_eq!(lines, ["first", "second", "third"]);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,279 @@
+// nanobot 日志解析模块
+// nanobot 使用 loguru 输出日志，格式为：
+//   2026-02-10 12:34:56.789 | INFO     | nanobot.agent.loop:run:42 - message
+// 不以该格式开头的行（如异常堆栈）属于上一条记录的续行。
+
+use serde::Serialize;
+use std::io::BufRead;
+
+/// 一条日志记录
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct LogRecord {
+    /// 时间戳原文，例如 2026-02-10 12:34:56.789；孤立续行为 None
+    pub timestamp: Option<String>,
+    /// 日志级别：TRACE / DEBUG / INFO / SUCCESS / WARNING / ERROR / CRITICAL，无法识别时为 UNKNOWN
+    pub level: String,
+    pub module: Option<String>,
+    pub function: Option<String>,
+    pub line: Option<u32>,
+    pub message: String,
+    /// 附加的续行（异常堆栈等）
+    pub continuation: Vec<String>,
+}
+
+/// 统计使用的级别分组
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LevelGroup {
+    Debug,
+    Info,
+    Warn,
+    Error,
+    Other,
+}
+
+const KNOWN_LEVELS: &[&str] = &["TRACE", "DEBUG", "INFO", "SUCCESS", "WARNING", "ERROR", "CRITICAL"];
+
+impl LogRecord {
+    /// 将不属于任何记录的行包装为记录
+    pub(crate) fn orphan(line: &str) -> Self {
+        Self {
+            timestamp: None,
+            level: "UNKNOWN".to_string(),
+            module: None,
+            function: None,
+            line: None,
+            message: line.to_string(),
+            continuation: Vec::new(),
+        }
+    }
+
+    pub fn level_group(&self) -> LevelGroup {
+        match self.level.as_str() {
+            "TRACE" | "DEBUG" => LevelGroup::Debug,
+            "INFO" | "SUCCESS" => LevelGroup::Info,
+            "WARNING" => LevelGroup::Warn,
+            "ERROR" | "CRITICAL" => LevelGroup::Error,
+            _ => LevelGroup::Other,
+        }
+    }
+
+    /// 还原为日志原文（头部与续行以换行连接）
+    pub fn to_text(&self) -> String {
+        let mut text = match &self.timestamp {
+            Some(ts) => {
+                let location = match (&self.module, &self.function, self.line) {
+                    (Some(m), Some(f), Some(l)) => format!("{}:{}:{}", m, f, l),
+                    (Some(m), Some(f), None) => format!("{}:{}", m, f),
+                    (Some(m), None, _) => m.clone(),
+                    _ => String::new(),
+                };
+                format!("{} | {:<8} | {} - {}", ts, self.level, location, self.message)
+            }
+            None => self.message.clone(),
+        };
+        for line in &self.continuation {
+            text.push('\n');
+            text.push_str(line);
+        }
+        text
+    }
+}
+
+/// 判断是否为 loguru 时间戳：YYYY-MM-DD HH:MM:SS[.fff]
+fn is_timestamp(s: &str) -> bool {
+    let bytes = s.as_bytes();
+    if bytes.len() < 19 {
+        return false;
+    }
+    let pattern = b"dddd-dd-dd dd:dd:dd";
+    pattern.iter().zip(bytes).all(|(p, b)| match p {
+        b'd' => b.is_ascii_digit(),
+        _ => p == b,
+    })
+}
+
+/// 解析日志头部行，不是头部时返回 None
+pub fn parse_header(line: &str) -> Option<LogRecord> {
+    let mut parts = line.splitn(3, '|');
+    let timestamp = parts.next()?.trim();
+    let level = parts.next()?.trim();
+    let rest = parts.next()?;
+
+    if !is_timestamp(timestamp) || !KNOWN_LEVELS.contains(&level) {
+        return None;
+    }
+
+    let rest = rest.strip_prefix(' ').unwrap_or(rest);
+    let (location, message) = match rest.split_once(" - ") {
+        Some((location, message)) => (location.trim(), message),
+        None => ("", rest),
+    };
+
+    // module:function:line，模块名本身不含冒号
+    let mut loc_parts = location.splitn(3, ':');
+    let module = loc_parts.next().filter(|s| !s.is_empty()).map(|s| s.to_string());
+    let function = loc_parts.next().map(|s| s.to_string());
+    let line_no = loc_parts.next().and_then(|s| s.parse().ok());
+
+    Some(LogRecord {
+        timestamp: Some(timestamp.to_string()),
+        level: level.to_string(),
+        module,
+        function,
+        line: line_no,
+        message: message.to_string(),
+        continuation: Vec::new(),
+    })
+}
+
+/// 增量日志解析器
+/// 逐行输入，遇到下一条记录的头部时输出上一条完整记录及其在文件中的字节偏移
+#[derive(Debug, Default)]
+pub struct RecordParser {
+    pending: Option<(u64, LogRecord)>,
+}
+
+impl RecordParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 输入一行（offset 为该行起始的字节偏移），返回因此完成的记录
+    pub fn push_line(&mut self, offset: u64, line: &str) -> Option<(u64, LogRecord)> {
+        match parse_header(line) {
+            Some(record) => self.pending.replace((offset, record)),
+            None => {
+                match &mut self.pending {
+                    Some((_, pending)) => pending.continuation.push(line.to_string()),
+                    None => self.pending = Some((offset, LogRecord::orphan(line))),
+                }
+                None
+            }
+        }
+    }
+
+    /// 是否有尚未输出的记录（可能还会有续行）
+    pub fn has_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// 结束输入，返回尚未输出的记录
+    pub fn finish(&mut self) -> Option<(u64, LogRecord)> {
+        self.pending.take()
+    }
+}
+
+/// 解析一组行（测试用，实际读取通过 RecordParser 逐行输入）
+#[cfg(test)]
+pub(crate) fn parse_lines<'a>(lines: impl IntoIterator<Item = &'a str>) -> Vec<LogRecord> {
+    let mut parser = RecordParser::new();
+    let mut records: Vec<LogRecord> = lines
+        .into_iter()
+        .filter_map(|line| parser.push_line(0, line).map(|(_, r)| r))
+        .collect();
+    records.extend(parser.finish().map(|(_, r)| r));
+    records
+}
+
+/// 逐行读取，返回每行起始的字节偏移（从 start_offset 开始计数）
+/// 非 UTF-8 内容按有损方式转换，不会中断读取
+pub fn for_each_line(mut reader: impl BufRead, start_offset: u64, mut f: impl FnMut(u64, &str) -> bool) {
+    let mut offset = start_offset;
+    let mut buffer = Vec::new();
+    loop {
+        buffer.clear();
+        let read = match reader.read_until(b'\n', &mut buffer) {
+            Ok(0) | Err(_) => break,
+            Ok(n) => n,
+        };
+        let line = String::from_utf8_lossy(&buffer);
+        let line = line.trim_end_matches(['\n', '\r']);
+        if !f(offset, line) {
+            break;
+        }
+        offset += read as u64;
+    }
+}
+
+/// 逐条读取记录并交给回调处理，避免整个文件读入内存
+/// 回调返回 false 时停止读取
+pub fn for_each_record_at(reader: impl BufRead, start_offset: u64, mut f: impl FnMut(u64, LogRecord) -> bool) {
+    let mut parser = RecordParser::new();
+    let mut stopped = false;
+    for_each_line(reader, start_offset, |offset, line| {
+        if let Some((record_offset, record)) = parser.push_line(offset, line) {
+            if !f(record_offset, record) {
+                stopped = true;
+                return false;
+            }
+        }
+        true
+    });
+    if !stopped {
+        if let Some((offset, record)) = parser.finish() {
+            f(offset, record);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &str = "2026-02-10 12:34:56.789 | INFO     | nanobot.agent.loop:run:42 - started - ok";
+
+    #[test]
+    fn parses_header_fields() {
+        let record = parse_header(HEADER).unwrap();
+        assert_eq!(record.timestamp.as_deref(), Some("2026-02-10 12:34:56.789"));
+        assert_eq!(record.level, "INFO");
+        assert_eq!(record.module.as_deref(), Some("nanobot.agent.loop"));
+        assert_eq!(record.function.as_deref(), Some("run"));
+        assert_eq!(record.line, Some(42));
+        // 消息本身可以包含分隔符
+        assert_eq!(record.message, "started - ok");
+        assert_eq!(record.level_group(), LevelGroup::Info);
+        assert_eq!(record.to_text(), HEADER);
+
+        assert!(parse_header("Traceback (most recent call last):").is_none());
+        assert!(parse_header("2026-02-10 12:34:56 | VERBOSE | x:y:1 - m").is_none());
+        assert!(parse_header("2026/02/10 12:34:56 | INFO | x:y:1 - m").is_none());
+    }
+
+    #[test]
+    fn attaches_continuation_lines() {
+        let records = parse_lines([
+            "orphan line",
+            HEADER,
+            "2026-02-10 12:35:00.000 | ERROR    | nanobot.channels.telegram:poll:7 - failed",
+            "Traceback (most recent call last):",
+            "  File \"x.py\", line 1",
+        ]);
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].level, "UNKNOWN");
+        assert_eq!(records[0].timestamp, None);
+        assert!(records[1].continuation.is_empty());
+        assert_eq!(records[2].level_group(), LevelGroup::Error);
+        assert_eq!(records[2].continuation.len(), 2);
+        assert!(records[2].to_text().ends_with("\n  File \"x.py\", line 1"));
+    }
+
+    #[test]
+    fn reports_record_offsets() {
+        let text = format!("{}\r\ncontinued\n{}\n", HEADER, HEADER);
+        let mut offsets = Vec::new();
+        for_each_record_at(text.as_bytes(), 100, |offset, record| {
+            offsets.push((offset, record.continuation.len()));
+            true
+        });
+        assert_eq!(offsets, vec![(100, 1), (100 + HEADER.len() as u64 + 12, 0)]);
+
+        // 回调返回 false 后不再输出剩余记录
+        let mut count = 0;
+        for_each_record_at(text.as_bytes(), 0, |_, _| {
+            count += 1;
+            false
+        });
+        assert_eq!(count, 1);
+    }
+}
@@ -0,0 +1,675 @@
+// 日志查询模块
+// 在解析后的日志记录上进行过滤、搜索与分页，逐条流式读取，不将整个文件读入内存；
+// 向更早方向翻页时从游标位置反向读取，查看最新日志只需读取文件尾部。
+// 查询覆盖当前日志与轮转后的历史文件（含 .gz），按时间从新到旧依次读取。
+// 分页游标由文件标识与记录的字节偏移组成，文件被重命名轮转后游标仍然有效；
+// 复制后截断的轮转保留文件标识，游标中附带的开头内容指纹用于识别文件已被重写。
+
+use regex::{Regex, RegexBuilder};
+use serde::Deserialize;
+use serde_json::{json, Value as JsonValue};
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufReader, Seek, SeekFrom};
+
+use crate::log_analysis::fnv1a_hex;
+use crate::log_files::{discover_log_files, LogFile};
+use crate::log_parser::{for_each_record_at, LevelGroup, LogRecord};
+use crate::log_tail::for_each_record_rev;
+use crate::time_range::{in_range, normalize_time};
+
+/// 默认每页记录数
+const DEFAULT_PAGE_SIZE: usize = 100;
+
+/// 单页最大记录数
+const MAX_PAGE_SIZE: usize = 1000;
+
+/// 日志查询条件
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogQuery {
+    /// 级别过滤，支持 debug / info / warn / error 分组或具体级别（如 SUCCESS）
+    pub levels: Option<Vec<String>>,
+    /// 起始时间（含），格式 YYYY-MM-DD HH:MM:SS，也接受 ISO 8601 的 T 分隔
+    pub since: Option<String>,
+    /// 结束时间（含）
+    pub until: Option<String>,
+    /// 模块过滤（子串匹配，例如 nanobot.channels）
+    pub module: Option<String>,
+    /// 搜索关键字，匹配消息与续行
+    pub search: Option<String>,
+    /// search 是否为正则表达式
+    pub regex: Option<bool>,
+    pub case_sensitive: Option<bool>,
+    /// 分页游标，由上一次查询返回
+    pub cursor: Option<String>,
+    /// 翻页方向：older（默认，向更早的记录）/ newer
+    pub direction: Option<String>,
+    pub limit: Option<usize>,
+}
+
+/// 编译后的过滤条件
+pub struct RecordFilter {
+    levels: Option<Vec<String>>,
+    since: Option<String>,
+    until: Option<String>,
+    module: Option<String>,
+    pattern: Option<Regex>,
+    substring: Option<String>,
+    case_sensitive: bool,
+}
+
+impl RecordFilter {
+    pub fn new(query: &LogQuery) -> Result<Self, String> {
+        let case_sensitive = query.case_sensitive.unwrap_or(false);
+        let search = query.search.as_deref().filter(|s| !s.is_empty());
+
+        let (pattern, substring) = match search {
+            Some(search) if query.regex.unwrap_or(false) => {
+                let regex = RegexBuilder::new(search)
+                    .case_insensitive(!case_sensitive)
+                    .build()
+                    .map_err(|e| format!("正则表达式无效: {}", e))?;
+                (Some(regex), None)
+            }
+            Some(search) if case_sensitive => (None, Some(search.to_string())),
+            Some(search) => (None, Some(search.to_lowercase())),
+            None => (None, None),
+        };
+
+        Ok(Self {
+            levels: query
+                .levels
+                .as_ref()
+                .filter(|l| !l.is_empty())
+                .map(|l| l.iter().map(|s| s.to_uppercase()).collect()),
+            since: normalize_time(query.since.as_deref()),
+            until: normalize_time(query.until.as_deref()),
+            module: query.module.clone().filter(|m| !m.is_empty()),
+            pattern,
+            substring,
+            case_sensitive,
+        })
+    }
+
+    fn level_matches(&self, record: &LogRecord) -> bool {
+        let Some(levels) = &self.levels else {
+            return true;
+        };
+        let group = match record.level_group() {
+            LevelGroup::Debug => "DEBUG",
+            LevelGroup::Info => "INFO",
+            LevelGroup::Warn => "WARN",
+            LevelGroup::Error => "ERROR",
+            LevelGroup::Other => "UNKNOWN",
+        };
+        levels.iter().any(|l| *l == record.level || *l == group)
+    }
+
+    /// 没有时间戳的孤立行无法判断时间，设置时间范围时排除
+    fn time_matches(&self, record: &LogRecord) -> bool {
+        in_range(record.timestamp.as_deref(), self.since.as_deref(), self.until.as_deref())
+    }
+
+    fn text_matches(&self, record: &LogRecord) -> bool {
+        if let Some(pattern) = &self.pattern {
+            return pattern.is_match(&record.message)
+                || record.continuation.iter().any(|l| pattern.is_match(l));
+        }
+        let Some(needle) = &self.substring else {
+            return true;
+        };
+        let contains = |text: &str| {
+            if self.case_sensitive {
+                text.contains(needle.as_str())
+            } else {
+                text.to_lowercase().contains(needle.as_str())
+            }
+        };
+        contains(&record.message) || record.continuation.iter().any(|l| contains(l))
+    }
+
+    /// 记录早于 since，按时间倒序读取时之后的记录都不会再匹配
+    fn before_since(&self, record: &LogRecord) -> bool {
+        match (self.since.as_deref(), record.timestamp.as_deref()) {
+            (Some(since), Some(timestamp)) => !in_range(Some(timestamp), Some(since), None),
+            _ => false,
+        }
+    }
+
+    pub fn matches(&self, record: &LogRecord) -> bool {
+        self.level_matches(record)
+            && self
+                .module
+                .as_deref()
+                .is_none_or(|m| record.module.as_deref().is_some_and(|rm| rm.contains(m)))
+            && self.time_matches(record)
+            && self.text_matches(record)
+    }
+}
+
+/// 记录位置：所在文件的标识与记录在文件中的字节偏移
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordPos {
+    pub file_id: String,
+    pub offset: u64,
+}
+
+/// 未压缩文件开头内容的指纹，压缩文件不会被重写，返回 None
+fn head_hash(file: &LogFile) -> Option<String> {
+    if file.compressed {
+        return None;
+    }
+    file.head_fingerprint().ok().map(|head| fnv1a_hex(&head))
+}
+
+/// 分页游标格式为 {文件标识}:o{偏移}[:h{开头内容指纹}]
+fn encode_cursor(pos: &RecordPos, head: Option<&str>) -> String {
+    match head {
+        Some(head) => format!("{}:o{}:h{}", pos.file_id, pos.offset, head),
+        None => format!("{}:o{}", pos.file_id, pos.offset),
+    }
+}
+
+/// 解析分页游标，返回文件标识、偏移与开头内容指纹
+/// 兼容旧版只含偏移的游标（o{偏移}，指向当前日志文件）与不含指纹的游标
+fn decode_cursor(cursor: &str) -> Result<(Option<String>, u64, Option<String>), String> {
+    let (cursor, head) = match cursor.rsplit_once(':') {
+        Some((rest, head)) if head.starts_with('h') => (rest, Some(head[1..].to_string())),
+        _ => (cursor, None),
+    };
+    let (file_id, offset) = match cursor.rsplit_once(':') {
+        Some((id, offset)) if !id.is_empty() => (Some(id.to_string()), offset),
+        Some(_) => return Err("无效的分页游标".to_string()),
+        None => (None, cursor),
+    };
+    offset
+        .strip_prefix('o')
+        .and_then(|s| s.parse().ok())
+        .map(|offset| (file_id, offset, head))
+        .ok_or_else(|| "无效的分页游标".to_string())
+}
+
+/// 在文件列表中定位游标所在的文件
+fn resolve_cursor(files: &[LogFile], file_id: Option<&str>) -> Result<usize, String> {
+    let index = match file_id {
+        Some(id) => files.iter().position(|f| f.id == id),
+        None => files.iter().position(|f| f.current),
+    };
+    index.ok_or_else(|| "分页游标所在的日志文件已不存在，请重新查询".to_string())
+}
+
+/// 检查游标在文件中是否仍然有效
+/// 复制后截断的轮转保留文件标识，指向当前文件的旧游标偏移已经失效：
+/// 偏移超出文件大小，或开头内容与生成游标时不同时拒绝
+fn check_cursor(file: &LogFile, offset: u64, head: Option<&str>) -> Result<(), String> {
+    if file.compressed {
+        return Ok(());
+    }
+    if offset > file.size || head.is_some_and(|head| head_hash(file).as_deref() != Some(head)) {
+        return Err("日志文件已被轮转或截断，分页游标已失效，请重新查询".to_string());
+    }
+    Ok(())
+}
+
+/// 查询结果的一页
+pub struct LogPage {
+    /// 记录（按时间正序）及其位置
+    pub records: Vec<(RecordPos, LogRecord)>,
+    /// 每条记录所在的文件名
+    pub sources: Vec<String>,
+    pub has_more_older: bool,
+    pub has_more_newer: bool,
+    pub scanned: usize,
+}
+
+impl LogPage {
+    pub fn empty() -> Self {
+        Self {
+            records: Vec::new(),
+            sources: Vec::new(),
+            has_more_older: false,
+            has_more_newer: false,
+            scanned: 0,
+        }
+    }
+}
+
+/// 在单个文件中收集 end（不含）之前最后 want 条匹配记录，按由新到旧返回
+/// 同时返回是否已读到早于 since 的记录，此时更早的文件无需再读
+fn collect_older(
+    file: &LogFile,
+    end: Option<u64>,
+    filter: &RecordFilter,
+    want: usize,
+    scanned: &mut usize,
+) -> std::io::Result<(Vec<(u64, LogRecord)>, bool)> {
+    let mut found = Vec::new();
+    let mut passed_since = false;
+    if want == 0 {
+        return Ok((found, passed_since));
+    }
+
+    if !file.compressed {
+        for_each_record_rev(&file.path, end.unwrap_or(file.size), |offset, record| {
+            *scanned += 1;
+            if filter.before_since(&record) {
+                passed_since = true;
+                return false;
+            }
+            if filter.matches(&record) {
+                found.push((offset, record));
+            }
+            found.len() < want
+        })?;
+        return Ok((found, passed_since));
+    }
+
+    // 压缩文件无法反向读取，正向解压并只保留最后 want 条匹配记录
+    let mut window: VecDeque<(u64, LogRecord)> = VecDeque::with_capacity(want + 1);
+    for_each_record_at(file.open_reader()?, 0, |offset, record| {
+        if end.is_some_and(|end| offset >= end) {
+            return false;
+        }
+        *scanned += 1;
+        if filter.before_since(&record) {
+            passed_since = true;
+            return true;
+        }
+        if filter.matches(&record) {
+            window.push_back((offset, record));
+            if window.len() > want {
+                window.pop_front();
+            }
+        }
+        true
+    });
+    found.extend(window.into_iter().rev());
+    Ok((found, passed_since))
+}
+
+/// 向更早的方向查询：从游标（不含）或最新日志末尾反向读取，当前文件读完后继续读取轮转的历史文件，
+/// 找到 limit 条匹配记录后再多找一条用于判断是否还有更早的记录；读到早于 since 的记录后停止
+pub fn query_older(
+    files: &[LogFile],
+    filter: &RecordFilter,
+    cursor: Option<(usize, u64)>,
+    limit: usize,
+) -> std::io::Result<LogPage> {
+    let mut page = LogPage::empty();
+    let Some(last) = files.len().checked_sub(1) else {
+        return Ok(page);
+    };
+    let (start, mut end) = match cursor {
+        Some((index, offset)) => (index, Some(offset)),
+        None => (last, None),
+    };
+
+    // 由新到旧收集
+    let mut collected: Vec<(usize, u64, LogRecord)> = Vec::with_capacity(limit + 1);
+    for index in (0..=start).rev() {
+        let want = limit + 1 - collected.len();
+        let (found, passed_since) = collect_older(&files[index], end.take(), filter, want, &mut page.scanned)?;
+        collected.extend(found.into_iter().map(|(offset, record)| (index, offset, record)));
+        if collected.len() > limit {
+            collected.truncate(limit);
+            page.has_more_older = true;
+            break;
+        }
+        if passed_since {
+            break;
+        }
+    }
+    collected.reverse();
+
+    for (index, offset, record) in collected {
+        page.sources.push(files[index].name.clone());
+        page.records.push((RecordPos { file_id: files[index].id.clone(), offset }, record));
+    }
+    // 游标本身来自上一页的匹配记录，因此有游标时一定存在更新的记录
+    page.has_more_newer = cursor.is_some();
+    Ok(page)
+}
+
+/// 向更新的方向查询：从游标（不含）之后正向读取最早的 limit 条匹配记录，
+/// 游标所在文件读完后继续读取更新的文件
+pub fn query_newer(
+    files: &[LogFile],
+    filter: &RecordFilter,
+    cursor: (usize, u64),
+    limit: usize,
+) -> std::io::Result<LogPage> {
+    let mut page = LogPage::empty();
+    page.has_more_older = true;
+    let (start, cursor_offset) = cursor;
+
+    'files: for (index, file) in files.iter().enumerate().skip(start) {
+        let skip_until = (index == start).then_some(cursor_offset);
+        let (reader, start_offset): (Box<dyn std::io::BufRead>, u64) = match skip_until {
+            // 未压缩文件直接定位到游标所在的记录头部
+            Some(offset) if !file.compressed => {
+                let mut handle = File::open(&file.path)?;
+                handle.seek(SeekFrom::Start(offset))?;
+                (Box::new(BufReader::new(handle)), offset)
+            }
+            _ => (file.open_reader()?, 0),
+        };
+
+        let mut full = false;
+        for_each_record_at(reader, start_offset, |offset, record| {
+            if skip_until.is_some_and(|cursor| offset <= cursor) {
+                return true;
+            }
+            page.scanned += 1;
+            if !filter.matches(&record) {
+                return true;
+            }
+            if page.records.len() == limit {
+                full = true;
+                return false;
+            }
+            page.sources.push(file.name.clone());
+            page.records.push((RecordPos { file_id: file.id.clone(), offset }, record));
+            true
+        });
+        if full {
+            page.has_more_newer = true;
+            break 'files;
+        }
+    }
+    Ok(page)
+}
+
+/// 将一页查询结果转换为前端响应
+/// 结果为空时沿用请求的游标，便于继续轮询更新的记录
+pub fn page_to_json(page: LogPage, request_cursor: Option<&str>, files: &[LogFile]) -> JsonValue {
+    let cursor_of = |pos: &RecordPos| {
+        let head = files.iter().find(|f| f.id == pos.file_id).and_then(head_hash);
+        encode_cursor(pos, head.as_deref())
+    };
+    let older_cursor = page
+        .records
+        .first()
+        .map(|(pos, _)| cursor_of(pos))
+        .or(request_cursor.map(|c| c.to_string()));
+    let newer_cursor = page
+        .records
+        .last()
+        .map(|(pos, _)| cursor_of(pos))
+        .or(request_cursor.map(|c| c.to_string()));
+    let records: Vec<&LogRecord> = page.records.iter().map(|(_, r)| r).collect();
+    let logs: Vec<String> = records.iter().map(|r| r.to_text()).collect();
+
+    json!({
+        "records": records,
+        "logs": logs,
+        "sources": page.sources,
+        "showing": records.len(),
+        "has_more_older": page.has_more_older,
+        "has_more_newer": page.has_more_newer,
+        "older_cursor": older_cursor,
+        "newer_cursor": newer_cursor,
+        "scanned": page.scanned
+    })
+}
+
+/// 查询日志（包含轮转后的历史日志文件）
+#[tauri::command]
+pub async fn query_logs(query: LogQuery) -> Result<JsonValue, String> {
+    let log_path = crate::logger::get_log_path().map_err(|e| e.to_string())?;
+    let filter = RecordFilter::new(&query)?;
+    let cursor = query.cursor.as_deref().map(decode_cursor).transpose()?;
+    let newer = match query.direction.as_deref() {
+        None | Some("older") => false,
+        Some("newer") => true,
+        Some(other) => return Err(format!("无效的翻页方向: {}", other)),
+    };
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+
+    tokio::task::spawn_blocking(move || {
+        let files = discover_log_files(&log_path)
+            .map_err(|e| format!("读取日志目录失败: {}", e))?;
+        if files.is_empty() {
+            return Ok(page_to_json(LogPage::empty(), query.cursor.as_deref(), &files));
+        }
+        let cursor = match cursor {
+            Some((file_id, offset, head)) => {
+                let index = resolve_cursor(&files, file_id.as_deref())?;
+                check_cursor(&files[index], offset, head.as_deref())?;
+                Some((index, offset))
+            }
+            None => None,
+        };
+
+        let page = match (newer, cursor) {
+            (true, Some(cursor)) => query_newer(&files, &filter, cursor, limit),
+            // 没有游标时向更新方向查询没有意义，等同于获取最新的记录
+            _ => query_older(&files, &filter, cursor, limit),
+        }
+        .map_err(|e| format!("读取日志文件失败: {}", e))?;
+        Ok(page_to_json(page, query.cursor.as_deref(), &files))
+    })
+    .await
+    .map_err(|e| format!("日志查询任务失败: {}", e))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::log_parser::parse_header;
+
+    fn log_file(id: &str, current: bool) -> LogFile {
+        LogFile {
+            path: Default::default(),
+            name: format!("{}.log", id),
+            id: id.to_string(),
+            size: 0,
+            modified: None,
+            compressed: false,
+            current,
+        }
+    }
+
+    #[test]
+    fn cursor_round_trip() {
+        let pos = RecordPos { file_id: "ino:42:1700000000".to_string(), offset: 1234 };
+        let cursor = encode_cursor(&pos, None);
+        // 文件标识本身可以包含冒号
+        assert_eq!(decode_cursor(&cursor).unwrap(), (Some(pos.file_id.clone()), 1234, None));
+        let cursor = encode_cursor(&pos, Some("00ff"));
+        assert_eq!(
+            decode_cursor(&cursor).unwrap(),
+            (Some(pos.file_id.clone()), 1234, Some("00ff".to_string()))
+        );
+
+        // 旧版游标只含偏移，指向当前日志文件
+        assert_eq!(decode_cursor("o99").unwrap(), (None, 99, None));
+        assert!(decode_cursor(":o1").is_err());
+        assert!(decode_cursor("abc:x1").is_err());
+        assert!(decode_cursor("abc:o-1").is_err());
+
+        let files = vec![log_file("a", true), log_file("b", false)];
+        assert_eq!(resolve_cursor(&files, None).unwrap(), 0);
+        assert_eq!(resolve_cursor(&files, Some("b")).unwrap(), 1);
+        assert!(resolve_cursor(&files, Some("gone")).is_err());
+    }
+
+    #[test]
+    fn filters_records() {
+        let info = parse_header("2026-02-10 12:34:56.789 | SUCCESS  | nanobot.channels.slack:run:1 - Connected").unwrap();
+        let error = parse_header("2026-02-11 08:00:00.000 | ERROR    | nanobot.agent:loop:2 - Tool failed").unwrap();
+        let filter = |query: LogQuery| RecordFilter::new(&query).unwrap();
+
+        let by_level = filter(LogQuery { levels: Some(vec!["info".to_string()]), ..Default::default() });
+        assert!(by_level.matches(&info));
+        assert!(!by_level.matches(&error));
+
+        let by_module = filter(LogQuery { module: Some("channels".to_string()), ..Default::default() });
+        assert!(by_module.matches(&info) && !by_module.matches(&error));
+
+        // until 只比较给定的精度，日期当天的记录都包含在内
+        let by_time = filter(LogQuery {
+            since: Some("2026-02-10T00:00:00Z".to_string()),
+            until: Some("2026-02-10".to_string()),
+            ..Default::default()
+        });
+        assert!(by_time.matches(&info) && !by_time.matches(&error));
+
+        let by_text = filter(LogQuery { search: Some("tool".to_string()), ..Default::default() });
+        assert!(!by_text.matches(&info) && by_text.matches(&error));
+        let by_regex = filter(LogQuery {
+            search: Some("^Conn".to_string()),
+            regex: Some(true),
+            case_sensitive: Some(true),
+            ..Default::default()
+        });
+        assert!(by_regex.matches(&info) && !by_regex.matches(&error));
+        assert!(RecordFilter::new(&LogQuery {
+            search: Some("(".to_string()),
+            regex: Some(true),
+            ..Default::default()
+        })
+        .is_err());
+    }
+
+    fn record_line(second: u32, message: &str) -> String {
+        format!("2026-02-10 12:00:{:02}.000 | INFO     | nanobot.agent.loop:run:1 - {}\n", second, message)
+    }
+
+    /// 三个文件：压缩的最早文件 m0-m2、轮转文件 m3-m5、当前文件 m6-m8，每秒一条记录
+    fn paged_dir(name: &str) -> (std::path::PathBuf, Vec<LogFile>) {
+        use std::io::Write;
+        use std::time::{Duration, SystemTime};
+
+        let dir = std::env::temp_dir().join(format!("nanoboard-query-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let lines = |range: std::ops::Range<u32>| -> String {
+            range.map(|i| record_line(i, &format!("m{}", i))).collect()
+        };
+
+        let gz = dir.join("nanobot.log.2026-02-10_12-00-02.gz");
+        let mut encoder = flate2::write::GzEncoder::new(File::create(&gz).unwrap(), flate2::Compression::default());
+        encoder.write_all(lines(0..3).as_bytes()).unwrap();
+        encoder.finish().unwrap();
+        let rotated = dir.join("nanobot.log.2026-02-10_12-00-05");
+        std::fs::write(&rotated, lines(3..6)).unwrap();
+        let current = dir.join("nanobot.log");
+        std::fs::write(&current, lines(6..9)).unwrap();
+
+        let now = SystemTime::now();
+        for (path, age) in [(&gz, 20), (&rotated, 10)] {
+            File::options()
+                .write(true)
+                .open(path)
+                .unwrap()
+                .set_modified(now - Duration::from_secs(age))
+                .unwrap();
+        }
+        let files = discover_log_files(&current).unwrap();
+        assert_eq!(files.len(), 3);
+        (dir, files)
+    }
+
+    fn messages(page: &LogPage) -> Vec<&str> {
+        page.records.iter().map(|(_, r)| r.message.as_str()).collect()
+    }
+
+    fn cursor_at(files: &[LogFile], pos: &RecordPos) -> (usize, u64) {
+        (files.iter().position(|f| f.id == pos.file_id).unwrap(), pos.offset)
+    }
+
+    #[test]
+    fn pages_across_rotated_and_compressed_files() {
+        let (dir, files) = paged_dir("pages");
+        let filter = RecordFilter::new(&LogQuery::default()).unwrap();
+
+        // 最新一页跨越当前文件与轮转文件
+        let page = query_older(&files, &filter, None, 4).unwrap();
+        assert_eq!(messages(&page), ["m5", "m6", "m7", "m8"]);
+        assert_eq!(page.sources[0], files[1].name);
+        assert_eq!(page.sources[3], files[2].name);
+        assert!(page.has_more_older && !page.has_more_newer);
+
+        // 继续向更早翻页，进入压缩文件
+        let cursor = cursor_at(&files, &page.records[0].0);
+        let page = query_older(&files, &filter, Some(cursor), 4).unwrap();
+        assert_eq!(messages(&page), ["m1", "m2", "m3", "m4"]);
+        assert!(page.has_more_older && page.has_more_newer);
+        assert_eq!(page.sources[0], files[0].name);
+
+        let cursor = cursor_at(&files, &page.records[0].0);
+        let oldest = query_older(&files, &filter, Some(cursor), 4).unwrap();
+        assert_eq!(messages(&oldest), ["m0"]);
+        assert!(!oldest.has_more_older && oldest.has_more_newer);
+
+        // 从最早的记录向更新方向翻回来
+        let cursor = cursor_at(&files, &oldest.records[0].0);
+        let page = query_newer(&files, &filter, cursor, 4).unwrap();
+        assert_eq!(messages(&page), ["m1", "m2", "m3", "m4"]);
+        assert!(page.has_more_older && page.has_more_newer);
+
+        let cursor = cursor_at(&files, &page.records[3].0);
+        let page = query_newer(&files, &filter, cursor, 4).unwrap();
+        assert_eq!(messages(&page), ["m5", "m6", "m7", "m8"]);
+        assert!(!page.has_more_newer);
+
+        // 已到最新记录时返回空页
+        let cursor = cursor_at(&files, &page.records[3].0);
+        let page = query_newer(&files, &filter, cursor, 4).unwrap();
+        assert!(page.records.is_empty() && !page.has_more_newer);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn stops_reading_older_files_before_since() {
+        let (dir, files) = paged_dir("since");
+        let filter = RecordFilter::new(&LogQuery {
+            since: Some("2026-02-10 12:00:04".to_string()),
+            ..Default::default()
+        })
+        .unwrap();
+
+        let page = query_older(&files, &filter, None, 10).unwrap();
+        assert_eq!(messages(&page), ["m4", "m5", "m6", "m7", "m8"]);
+        assert!(!page.has_more_older);
+        // 读到 m3 后停止，不再解压最早的文件
+        assert_eq!(page.scanned, 6);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn rejects_stale_cursor_after_copy_truncate() {
+        let (dir, files) = paged_dir("stale");
+        let filter = RecordFilter::new(&LogQuery::default()).unwrap();
+        let page = query_older(&files, &filter, None, 2).unwrap();
+        let response = page_to_json(page, None, &files);
+        let cursor = response["newer_cursor"].as_str().unwrap().to_string();
+
+        let check = |files: &[LogFile]| {
+            let (file_id, offset, head) = decode_cursor(&cursor).unwrap();
+            let index = resolve_cursor(files, file_id.as_deref())?;
+            check_cursor(&files[index], offset, head.as_deref())
+        };
+        assert!(check(&files).is_ok());
+
+        // 复制后截断并写入更多内容：文件标识不变，大小也超过了游标位置
+        let current = dir.join("nanobot.log");
+        let settings = crate::log_rotation::LogRotationSettings { compress: false, ..Default::default() };
+        crate::log_rotation::rotate_log_file(&current, &settings).unwrap().unwrap();
+        std::fs::write(&current, record_line(30, "a much longer message after rotation").repeat(4)).unwrap();
+        let files = discover_log_files(&current).unwrap();
+        assert!(check(&files).unwrap_err().contains("已失效"));
+
+        // 截断后偏移超出文件大小
+        std::fs::write(&current, "").unwrap();
+        let files = discover_log_files(&current).unwrap();
+        let current = files.iter().find(|f| f.current).unwrap();
+        assert!(check_cursor(current, 10, None).is_err());
+        assert!(check_cursor(current, 0, None).is_ok());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,367 @@
+// 日志轮转模块
+// nanobot 网关以追加模式持续持有 nanobot.log，重命名文件后它仍会写入旧文件，
+// 因此采用"复制后截断"的方式轮转：将内容复制到 nanobot.log.{时间}[.gz]，再把原文件截断为空。
+// 复制与截断之间写入的少量内容会丢失（与 logrotate 的 copytruncate 相同）。
+// 轮转默认关闭，开启后由后台任务按大小/时间定期检查。
+
+use anyhow::{Context, Result};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value as JsonValue};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::log_files::{discover_log_files, LOG_FILE_NAME};
+use crate::log_parser::parse_header;
+
+/// 后台检查间隔
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// 日志轮转设置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LogRotationSettings {
+    pub enabled: bool,
+    /// 文件达到该大小（MB）时轮转，0 表示不按大小轮转
+    pub max_size_mb: u64,
+    /// 最早一条记录超过该天数时轮转，0 表示不按时间轮转
+    pub max_age_days: u64,
+    /// 保留的轮转文件数量（只清理 nanoboard 生成的文件）
+    pub keep_files: usize,
+    /// 是否使用 gzip 压缩轮转文件
+    pub compress: bool,
+}
+
+impl Default for LogRotationSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_size_mb: 50,
+            max_age_days: 7,
+            keep_files: 5,
+            compress: true,
+        }
+    }
+}
+
+/// 获取轮转设置文件路径
+fn get_settings_path() -> PathBuf {
+    let home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
+    home.join(".nanobot").join("log_rotation.json")
+}
+
+/// 加载轮转设置，文件不存在或无法解析时使用默认值
+pub fn load_settings() -> LogRotationSettings {
+    fs::read_to_string(get_settings_path())
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// 保存轮转设置
+fn save_settings(settings: &LogRotationSettings) -> Result<()> {
+    let path = get_settings_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).context("创建配置目录失败")?;
+    }
+    let content = serde_json::to_string_pretty(settings).context("序列化轮转设置失败")?;
+    fs::write(&path, content).context("保存轮转设置失败")?;
+    Ok(())
+}
+
+/// 读取文件中第一条记录的时间
+fn first_record_time(path: &Path) -> Option<chrono::NaiveDateTime> {
+    let reader = BufReader::new(File::open(path).ok()?);
+    let record = reader
+        .lines()
+        .take(100)
+        .map_while(|line| line.ok())
+        .find_map(|line| parse_header(&line))?;
+    let timestamp = record.timestamp?;
+    chrono::NaiveDateTime::parse_from_str(timestamp.get(..19)?, "%Y-%m-%d %H:%M:%S").ok()
+}
+
+/// 判断是否需要轮转，返回轮转原因
+fn rotation_reason(path: &Path, settings: &LogRotationSettings) -> Option<String> {
+    let size = fs::metadata(path).ok()?.len();
+    if size == 0 {
+        return None;
+    }
+
+    if settings.max_size_mb > 0 && size >= settings.max_size_mb * 1024 * 1024 {
+        return Some(format!("日志文件超过 {} MB", settings.max_size_mb));
+    }
+
+    if settings.max_age_days > 0 {
+        let first = first_record_time(path)?;
+        let age = chrono::Local::now().naive_local() - first;
+        if age.num_days() >= settings.max_age_days as i64 {
+            return Some(format!("日志记录超过 {} 天", settings.max_age_days));
+        }
+    }
+    None
+}
+
+/// 复制后截断当前日志文件，返回轮转生成的文件路径；文件为空时不轮转
+pub fn rotate_log_file(path: &Path, settings: &LogRotationSettings) -> Result<Option<PathBuf>> {
+    let mut source = File::open(path).context("打开日志文件失败")?;
+    let size = source.metadata().context("读取日志文件信息失败")?.len();
+    if size == 0 {
+        return Ok(None);
+    }
+
+    let dir = path.parent().context("无效的日志路径")?;
+    let stamp = chrono::Local::now().format("%Y-%m-%d_%H-%M-%S");
+    let extension = if settings.compress { ".gz" } else { "" };
+    let mut target = dir.join(format!("{}.{}{}", LOG_FILE_NAME, stamp, extension));
+    let mut suffix = 1;
+    while target.exists() {
+        target = dir.join(format!("{}.{}_{}{}", LOG_FILE_NAME, stamp, suffix, extension));
+        suffix += 1;
+    }
+
+    // 只复制检查时的大小，之后追加的内容由截断前的短暂窗口决定
+    let mut content = (&mut source).take(size);
+    let output = File::create(&target).context("创建轮转文件失败")?;
+    if settings.compress {
+        let mut encoder = GzEncoder::new(output, Compression::default());
+        std::io::copy(&mut content, &mut encoder).context("压缩日志文件失败")?;
+        encoder.finish().context("压缩日志文件失败")?;
+    } else {
+        let mut output = output;
+        std::io::copy(&mut content, &mut output).context("复制日志文件失败")?;
+        output.flush().context("复制日志文件失败")?;
+    }
+
+    OpenOptions::new()
+        .write(true)
+        .open(path)
+        .and_then(|file| file.set_len(0))
+        .context("截断日志文件失败")?;
+
+    prune_rotated(path, settings.keep_files)?;
+    Ok(Some(target))
+}
+
+/// 判断文件名是否为 rotate_log_file 生成的 nanobot.log.%Y-%m-%d_%H-%M-%S[_N][.gz]
+fn is_rotated_name(name: &str) -> bool {
+    let Some(rest) = name.strip_prefix(LOG_FILE_NAME).and_then(|r| r.strip_prefix('.')) else {
+        return false;
+    };
+    let rest = rest.strip_suffix(".gz").unwrap_or(rest);
+    let Some((stamp, suffix)) = rest.split_at_checked(19) else {
+        return false;
+    };
+    if chrono::NaiveDateTime::parse_from_str(stamp, "%Y-%m-%d_%H-%M-%S").is_err() {
+        return false;
+    }
+    suffix.is_empty()
+        || suffix
+            .strip_prefix('_')
+            .is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// 删除超出保留数量的轮转文件
+/// 只处理 nanoboard 生成的文件，logrotate（nanobot.log.1）与 nanobot 自身（loguru）生成的文件不受影响
+fn prune_rotated(path: &Path, keep: usize) -> Result<()> {
+    let rotated: Vec<_> = discover_log_files(path)
+        .context("读取日志目录失败")?
+        .into_iter()
+        .filter(|f| !f.current && is_rotated_name(&f.name))
+        .collect();
+
+    // 文件按从旧到新排列
+    let excess = rotated.len().saturating_sub(keep.max(1));
+    for file in &rotated[..excess] {
+        if let Err(e) = fs::remove_file(&file.path) {
+            log::warn!("删除旧日志文件失败 {}: {}", file.name, e);
+        }
+    }
+    Ok(())
+}
+
+/// 按设置检查并在需要时轮转，返回轮转生成的文件与原因
+fn check_and_rotate(settings: &LogRotationSettings) -> Result<Option<(PathBuf, String)>> {
+    let path = crate::logger::get_log_path()?;
+    let Some(reason) = rotation_reason(&path, settings) else {
+        return Ok(None);
+    };
+    Ok(rotate_log_file(&path, settings)?.map(|target| (target, reason)))
+}
+
+/// 后台轮转任务，设置每次检查时重新读取，修改后无需重启
+pub async fn run_rotation_loop() {
+    loop {
+        tokio::time::sleep(CHECK_INTERVAL).await;
+
+        let settings = load_settings();
+        if !settings.enabled {
+            continue;
+        }
+        match tokio::task::spawn_blocking(move || check_and_rotate(&settings)).await {
+            Ok(Ok(Some((target, reason)))) => {
+                log::info!("{}，已轮转到 {}", reason, target.display());
+            }
+            Ok(Ok(None)) => {}
+            Ok(Err(e)) => log::warn!("日志轮转失败: {:#}", e),
+            Err(e) => log::warn!("日志轮转任务失败: {}", e),
+        }
+    }
+}
+
+/// 获取日志轮转设置
+#[tauri::command]
+pub async fn get_log_rotation_settings() -> Result<LogRotationSettings, String> {
+    Ok(load_settings())
+}
+
+/// 保存日志轮转设置
+#[tauri::command]
+pub async fn set_log_rotation_settings(settings: LogRotationSettings) -> Result<JsonValue, String> {
+    if settings.keep_files == 0 {
+        return Err("保留文件数量至少为 1".to_string());
+    }
+    save_settings(&settings).map_err(|e| format!("{:#}", e))?;
+
+    Ok(json!({
+        "success": true,
+        "message": "日志轮转设置已保存"
+    }))
+}
+
+/// 立即轮转日志
+#[tauri::command]
+pub async fn rotate_logs_now() -> Result<JsonValue, String> {
+    let log_path = crate::logger::get_log_path().map_err(|e| e.to_string())?;
+    if !log_path.exists() {
+        return Err("日志文件不存在".to_string());
+    }
+    let settings = load_settings();
+
+    let rotated = tokio::task::spawn_blocking(move || rotate_log_file(&log_path, &settings))
+        .await
+        .map_err(|e| format!("日志轮转任务失败: {}", e))?
+        .map_err(|e| format!("日志轮转失败: {:#}", e))?;
+
+    let file = rotated
+        .as_ref()
+        .and_then(|p| p.file_name())
+        .map(|n| n.to_string_lossy().to_string());
+    Ok(json!({
+        "success": true,
+        "message": if file.is_some() { "日志已轮转" } else { "日志文件为空，无需轮转" },
+        "file": file
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::log_files::file_identity;
+    use std::time::SystemTime;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("nanoboard-rotation-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn record(time: chrono::NaiveDateTime) -> String {
+        format!("{}.000 | INFO     | nanobot.agent.loop:run:42 - started\n", time.format("%Y-%m-%d %H:%M:%S"))
+    }
+
+    #[test]
+    fn rotates_by_size_and_age() {
+        let dir = temp_dir("reason");
+        let path = dir.join(LOG_FILE_NAME);
+        let now = chrono::Local::now().naive_local();
+        let settings = LogRotationSettings { max_size_mb: 1, max_age_days: 7, ..Default::default() };
+
+        fs::write(&path, "").unwrap();
+        assert!(rotation_reason(&path, &settings).is_none());
+
+        fs::write(&path, record(now - chrono::Duration::days(1))).unwrap();
+        assert!(rotation_reason(&path, &settings).is_none());
+
+        fs::write(&path, record(now - chrono::Duration::days(8))).unwrap();
+        assert!(rotation_reason(&path, &settings).unwrap().contains("7 天"));
+        let no_age = LogRotationSettings { max_age_days: 0, ..settings.clone() };
+        assert!(rotation_reason(&path, &no_age).is_none());
+
+        fs::write(&path, "x".repeat(1024 * 1024)).unwrap();
+        assert!(rotation_reason(&path, &settings).unwrap().contains("1 MB"));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn copy_truncate_keeps_identity_and_prunes_old_files() {
+        let dir = temp_dir("rotate");
+        let path = dir.join(LOG_FILE_NAME);
+        let settings = LogRotationSettings { keep_files: 2, compress: true, ..Default::default() };
+
+        // 已有的旧轮转文件与 loguru 生成的文件
+        let old = SystemTime::now() - Duration::from_secs(3600);
+        for name in ["nanobot.log.2026-01-01_00-00-00.gz", "nanobot.log.2026-01-02_00-00-00"] {
+            fs::write(dir.join(name), "old").unwrap();
+            File::options().write(true).open(dir.join(name)).unwrap().set_modified(old).unwrap();
+        }
+        let loguru = dir.join("nanobot.2026-01-01_00-00-00_000000.log");
+        fs::write(&loguru, "loguru").unwrap();
+        // logrotate 生成的文件
+        let logrotate = ["nanobot.log.1", "nanobot.log.2.gz"].map(|name| dir.join(name));
+        for file in &logrotate {
+            fs::write(file, "logrotate").unwrap();
+            File::options().write(true).open(file).unwrap().set_modified(old).unwrap();
+        }
+
+        let content = record(chrono::Local::now().naive_local()).repeat(3);
+        fs::write(&path, &content).unwrap();
+        let identity = file_identity(&fs::metadata(&path).unwrap());
+
+        let target = rotate_log_file(&path, &settings).unwrap().unwrap();
+        let meta = fs::metadata(&path).unwrap();
+        assert_eq!(meta.len(), 0);
+        assert_eq!(file_identity(&meta), identity);
+
+        let mut rotated = String::new();
+        flate2::read::GzDecoder::new(File::open(&target).unwrap())
+            .read_to_string(&mut rotated)
+            .unwrap();
+        assert_eq!(rotated, content);
+
+        // 空文件不轮转
+        assert!(rotate_log_file(&path, &settings).unwrap().is_none());
+
+        // 同一秒内再次轮转使用新的文件名，并且只保留最新的 2 个
+        fs::write(&path, &content).unwrap();
+        let second = rotate_log_file(&path, &settings).unwrap().unwrap();
+        assert_ne!(second, target);
+        assert!(target.exists() && second.exists());
+        assert!(!dir.join("nanobot.log.2026-01-01_00-00-00.gz").exists());
+        assert!(!dir.join("nanobot.log.2026-01-02_00-00-00").exists());
+        assert!(loguru.exists());
+        assert!(logrotate.iter().all(|file| file.exists()));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn recognizes_only_own_rotated_names() {
+        assert!(is_rotated_name("nanobot.log.2026-01-01_00-00-00"));
+        assert!(is_rotated_name("nanobot.log.2026-01-01_00-00-00.gz"));
+        assert!(is_rotated_name("nanobot.log.2026-01-01_00-00-00_3.gz"));
+
+        assert!(!is_rotated_name("nanobot.log"));
+        assert!(!is_rotated_name("nanobot.log.1"));
+        assert!(!is_rotated_name("nanobot.log.1.gz"));
+        assert!(!is_rotated_name("nanobot.log.2026-01-01"));
+        assert!(!is_rotated_name("nanobot.log.2026-01-01_00-00-00_"));
+        assert!(!is_rotated_name("nanobot.log.2026-01-01_00-00-00.bak"));
+        assert!(!is_rotated_name("nanobot.2026-01-01_00-00-00_000000.log"));
+    }
+}
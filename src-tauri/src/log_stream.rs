@@ -0,0 +1,359 @@
+// 日志流广播
+// 由唯一的后台任务读取日志新增内容，再分发给所有订阅者（窗口），每个订阅者可以有自己的过滤条件：
+// - 文件监控回调只负责唤醒读取任务，不读取文件，因此不会因为锁竞争丢弃更新；
+// - 只有读取任务推进读取位置，每条记录只会读取并分发一次；
+// - 第一个订阅者加入时启动读取任务，最后一个订阅者离开（或窗口关闭）时停止任务并释放文件监控；
+// - 除窗口外，应用内部的后台任务（例如告警）也可以通过通道订阅全部新记录。
+
+use serde_json::json;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+
+use crate::log_parser::LogRecord;
+use crate::log_query::{LogQuery, RecordFilter};
+use crate::logger::{FileTracker, RECORD_HOLD_TIMEOUT};
+
+/// 监控事件之外的兜底轮询间隔，防止 watcher 漏掉某些事件
+const POLL_INTERVAL: Duration = Duration::from_millis(2000);
+
+/// 日志流订阅者
+struct Subscriber {
+    /// 接收事件的窗口标签
+    target: String,
+    filter: Option<RecordFilter>,
+    /// 通过 start_log_stream 建立的订阅，使用旧版的 log-update / log-records 事件
+    legacy: bool,
+    /// 已发送给该订阅者的批次序号，前端可据此检查是否有遗漏或重复
+    seq: u64,
+}
+
+#[derive(Default)]
+struct HubInner {
+    subscribers: HashMap<u64, Subscriber>,
+    /// 内部订阅者，接收者被丢弃后自动移除
+    channels: HashMap<u64, tokio::sync::mpsc::UnboundedSender<Vec<LogRecord>>>,
+    next_id: u64,
+    task: Option<tokio::task::JoinHandle<()>>,
+}
+
+/// 日志流广播中心
+#[derive(Default)]
+pub struct LogHub {
+    inner: Mutex<HubInner>,
+}
+
+impl LogHub {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 是否在向窗口推送日志（内部订阅者不计入）
+    pub fn is_running(&self) -> bool {
+        let inner = self.inner.lock().unwrap();
+        inner.task.is_some() && !inner.subscribers.is_empty()
+    }
+
+    /// 读取任务未运行时启动
+    fn ensure_reader(self: &Arc<Self>, app: &tauri::AppHandle, inner: &mut HubInner) -> Result<(), String> {
+        if inner.task.is_none() {
+            let log_path = prepare_log_file()?;
+            // 在订阅时确定起始位置，任务启动前写入的日志也会推送
+            let start = std::fs::metadata(&log_path).map(|m| m.len()).unwrap_or(0);
+            inner.task = Some(tokio::spawn(run_reader(app.clone(), self.clone(), log_path, start)));
+        }
+        Ok(())
+    }
+
+    /// 添加订阅者，必要时启动读取任务，返回订阅 ID
+    fn subscribe(
+        self: &Arc<Self>,
+        app: &tauri::AppHandle,
+        target: &str,
+        filter: Option<RecordFilter>,
+        legacy: bool,
+    ) -> Result<u64, String> {
+        let mut inner = self.inner.lock().unwrap();
+        self.ensure_reader(app, &mut inner)?;
+
+        inner.next_id += 1;
+        let id = inner.next_id;
+        inner.subscribers.insert(id, Subscriber {
+            target: target.to_string(),
+            filter,
+            legacy,
+            seq: 0,
+        });
+        Ok(id)
+    }
+
+    /// 添加内部订阅者，通过返回的通道接收订阅之后的全部新记录
+    pub fn subscribe_channel(
+        self: &Arc<Self>,
+        app: &tauri::AppHandle,
+    ) -> Result<tokio::sync::mpsc::UnboundedReceiver<Vec<LogRecord>>, String> {
+        let mut inner = self.inner.lock().unwrap();
+        self.ensure_reader(app, &mut inner)?;
+
+        inner.next_id += 1;
+        let id = inner.next_id;
+        let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+        inner.channels.insert(id, sender);
+        Ok(receiver)
+    }
+
+    /// 移除接收者已被丢弃的内部订阅者，没有订阅者时停止读取任务
+    pub fn release_closed_channels(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.channels.retain(|_, sender| !sender.is_closed());
+        Self::stop_if_idle(&mut inner);
+    }
+
+    /// 移除满足条件的订阅者，没有订阅者时停止读取任务，返回移除的数量
+    fn remove_where(&self, predicate: impl Fn(u64, &Subscriber) -> bool) -> usize {
+        let mut inner = self.inner.lock().unwrap();
+        let before = inner.subscribers.len();
+        inner.subscribers.retain(|id, sub| !predicate(*id, sub));
+        let removed = before - inner.subscribers.len();
+        Self::stop_if_idle(&mut inner);
+        removed
+    }
+
+    fn stop_if_idle(inner: &mut HubInner) {
+        if inner.subscribers.is_empty() && inner.channels.is_empty() {
+            if let Some(task) = inner.task.take() {
+                // 任务中持有的文件监控随任务一起释放
+                task.abort();
+            }
+        }
+    }
+
+    /// 取消订阅，只能取消属于指定窗口的订阅
+    pub fn unsubscribe(&self, id: u64, label: &str) -> bool {
+        self.remove_where(|sub_id, sub| sub_id == id && sub.target == label) > 0
+    }
+
+    /// 移除某个窗口的全部订阅（窗口关闭时调用）
+    pub fn unsubscribe_window(&self, label: &str) -> usize {
+        self.remove_where(|_, sub| sub.target == label)
+    }
+
+    fn has_legacy(&self, label: &str) -> bool {
+        self.inner
+            .lock()
+            .unwrap()
+            .subscribers
+            .values()
+            .any(|sub| sub.legacy && sub.target == label)
+    }
+
+    /// 将新记录分发给各订阅者
+    fn broadcast(&self, app: &tauri::AppHandle, records: &[LogRecord]) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.channels.retain(|_, sender| sender.send(records.to_vec()).is_ok());
+
+        for (id, sub) in inner.subscribers.iter_mut() {
+            let matched: Vec<&LogRecord> = match &sub.filter {
+                Some(filter) => records.iter().filter(|r| filter.matches(r)).collect(),
+                None => records.iter().collect(),
+            };
+            if matched.is_empty() {
+                continue;
+            }
+            let lines: Vec<String> = matched.iter().map(|r| r.to_text()).collect();
+            // 序号只在实际发送给该订阅者时递增，过滤掉的批次不会造成空缺
+            sub.seq += 1;
+
+            let result = if sub.legacy {
+                // log-update 为兼容旧版的文本行，log-records 为结构化记录
+                app.emit_to(sub.target.as_str(), "log-update", lines)
+                    .and_then(|_| app.emit_to(sub.target.as_str(), "log-records", matched))
+            } else {
+                app.emit_to(sub.target.as_str(), "log-stream", json!({
+                    "subscription_id": id,
+                    "seq": sub.seq,
+                    "records": matched,
+                    "lines": lines
+                }))
+            };
+            if let Err(e) = result {
+                log::warn!("发送日志更新到窗口 {} 失败: {:?}", sub.target, e);
+            }
+        }
+    }
+}
+
+/// 确保日志文件存在，返回日志路径
+fn prepare_log_file() -> Result<PathBuf, String> {
+    let log_path = crate::logger::get_log_path().map_err(|e| e.to_string())?;
+
+    // 如果日志文件不存在，创建它
+    if !log_path.exists() {
+        if let Some(parent) = log_path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("创建日志目录失败: {}", e))?;
+        }
+        std::fs::File::create(&log_path)
+            .map_err(|e| format!("创建日志文件失败: {}", e))?;
+    }
+    Ok(log_path)
+}
+
+/// 读取任务：被文件监控唤醒或定时轮询时读取新增内容并广播
+async fn run_reader(app: tauri::AppHandle, hub: Arc<LogHub>, log_path: PathBuf, start: u64) {
+    use notify::{recommended_watcher, EventKind, RecursiveMode, Watcher};
+
+    // 从订阅时的文件末尾开始，只推送订阅之后写入的日志
+    let mut tracker = FileTracker::new();
+    tracker.follow(log_path.clone(), start);
+
+    // 监控回调只负责唤醒读取任务；多次唤醒会合并为一次读取，不会丢失
+    let wake = Arc::new(tokio::sync::Notify::new());
+    let wake_for_watch = wake.clone();
+    let file_name = log_path.file_name().map(|n| n.to_os_string());
+    let watcher = recommended_watcher(move |res: notify::Result<notify::Event>| match res {
+        Ok(event) => {
+            // 监控的是日志目录，只处理日志文件本身的修改与（轮转后的）重新创建
+            let relevant = matches!(event.kind, EventKind::Modify(_) | EventKind::Create(_))
+                && event.paths.iter().any(|p| p.file_name() == file_name.as_deref());
+            if relevant {
+                wake_for_watch.notify_one();
+            }
+        }
+        Err(e) => log::warn!("日志监控错误: {:?}", e),
+    });
+
+    // 监控失败时仍可依靠轮询继续工作
+    let _watcher = match watcher {
+        Ok(mut watcher) => {
+            let watch_target = log_path.parent().unwrap_or(&log_path);
+            if let Err(e) = watcher.watch(watch_target, RecursiveMode::NonRecursive) {
+                log::warn!("监控日志目录失败，改为仅轮询: {}", e);
+            }
+            Some(watcher)
+        }
+        Err(e) => {
+            log::warn!("创建文件监控器失败，改为仅轮询: {}", e);
+            None
+        }
+    };
+
+    loop {
+        // 有暂缓输出的记录时缩短等待，确保它在没有新日志时也能及时推送
+        let interval = if tracker.has_pending() { RECORD_HOLD_TIMEOUT } else { POLL_INTERVAL };
+        tokio::select! {
+            _ = wake.notified() => {}
+            _ = tokio::time::sleep(interval) => {}
+        }
+
+        match tracker.read_new_records() {
+            Ok(records) if !records.is_empty() => hub.broadcast(&app, &records),
+            Ok(_) => {}
+            Err(e) => log::warn!("读取新日志失败: {}", e),
+        }
+    }
+}
+
+/// 订阅日志流，新记录通过 log-stream 事件发送到调用的窗口
+#[tauri::command]
+pub async fn subscribe_log_stream(
+    window: tauri::Window,
+    filter: Option<LogQuery>,
+    hub: tauri::State<'_, Arc<LogHub>>,
+) -> Result<serde_json::Value, String> {
+    let filter = filter.as_ref().map(RecordFilter::new).transpose()?;
+    let id = hub.subscribe(window.app_handle(), window.label(), filter, false)?;
+
+    Ok(json!({
+        "success": true,
+        "subscription_id": id
+    }))
+}
+
+/// 取消日志流订阅
+#[tauri::command]
+pub async fn unsubscribe_log_stream(
+    window: tauri::Window,
+    subscription_id: u64,
+    hub: tauri::State<'_, Arc<LogHub>>,
+) -> Result<serde_json::Value, String> {
+    if !hub.unsubscribe(subscription_id, window.label()) {
+        return Err("订阅不存在或已取消".to_string());
+    }
+    Ok(json!({
+        "success": true,
+        "running": hub.is_running()
+    }))
+}
+
+/// 为窗口建立旧版日志流订阅（start_log_stream 使用），已订阅时不重复订阅
+pub(crate) fn start_legacy(window: &tauri::Window, hub: &Arc<LogHub>) -> Result<(), String> {
+    if hub.has_legacy(window.label()) {
+        return Ok(());
+    }
+    hub.subscribe(window.app_handle(), window.label(), None, true)?;
+    Ok(())
+}
+
+/// 取消窗口的旧版日志流订阅，返回是否存在订阅
+pub(crate) fn stop_legacy(window: &tauri::Window, hub: &LogHub) -> bool {
+    let label = window.label().to_string();
+    hub.remove_where(|_, sub| sub.legacy && sub.target == label) > 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hub_with(targets: &[&str]) -> LogHub {
+        let hub = LogHub::new();
+        {
+            let mut inner = hub.inner.lock().unwrap();
+            for target in targets {
+                inner.next_id += 1;
+                let id = inner.next_id;
+                inner.subscribers.insert(id, Subscriber {
+                    target: target.to_string(),
+                    filter: None,
+                    legacy: false,
+                    seq: 0,
+                });
+            }
+        }
+        hub
+    }
+
+    #[test]
+    fn unsubscribe_only_removes_own_subscriptions() {
+        let hub = hub_with(&["main", "logs"]);
+
+        // 其他窗口不能取消不属于自己的订阅
+        assert!(!hub.unsubscribe(1, "logs"));
+        assert!(hub.unsubscribe(1, "main"));
+        assert!(!hub.unsubscribe(1, "main"));
+
+        assert_eq!(hub.unsubscribe_window("logs"), 1);
+        assert!(hub.inner.lock().unwrap().subscribers.is_empty());
+    }
+
+    #[tokio::test]
+    async fn stops_reader_when_last_channel_is_dropped() {
+        let hub = LogHub::new();
+        let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+        {
+            let mut inner = hub.inner.lock().unwrap();
+            inner.channels.insert(1, sender);
+            inner.task = Some(tokio::spawn(std::future::pending()));
+        }
+
+        hub.release_closed_channels();
+        assert!(hub.inner.lock().unwrap().task.is_some());
+
+        drop(receiver);
+        hub.release_closed_channels();
+        let inner = hub.inner.lock().unwrap();
+        assert!(inner.channels.is_empty() && inner.task.is_none());
+    }
+}
@@ -0,0 +1,373 @@
+// 大日志文件的高效读取
+// - 反向读取：从文件末尾按块向前读取，获取最后 N 条记录只需读取文件尾部
+// - 增量统计：缓存上次扫描到的位置与各级别计数，之后只处理新增内容
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::log_files::file_identity;
+use crate::log_parser::{parse_header, LevelGroup, LogRecord};
+use crate::logger::ends_line_at;
+
+/// 反向读取的块大小
+const REVERSE_BLOCK_SIZE: usize = 64 * 1024;
+
+/// 从文件末尾向前逐行读取
+pub struct ReverseLineReader {
+    file: File,
+    /// 尚未读取部分的结束位置
+    position: u64,
+    /// 已读取但尚未输出的数据（位于 position 之后）
+    buffer: Vec<u8>,
+}
+
+impl ReverseLineReader {
+    /// 从指定位置（通常为文件大小）开始向前读取
+    pub fn new(path: &Path, end: u64) -> std::io::Result<Self> {
+        let file = File::open(path)?;
+        let len = file.metadata()?.len();
+        Ok(Self {
+            file,
+            position: end.min(len),
+            buffer: Vec::new(),
+        })
+    }
+
+    /// 读取前一块数据，已到达文件开头时返回 false
+    fn fill(&mut self) -> std::io::Result<bool> {
+        if self.position == 0 {
+            return Ok(false);
+        }
+        let size = (REVERSE_BLOCK_SIZE as u64).min(self.position) as usize;
+        self.position -= size as u64;
+        self.file.seek(SeekFrom::Start(self.position))?;
+
+        let mut block = vec![0u8; size];
+        self.file.read_exact(&mut block)?;
+        block.extend_from_slice(&self.buffer);
+        self.buffer = block;
+        Ok(true)
+    }
+
+    /// 返回前一行及其起始偏移，到达文件开头后返回 None
+    pub fn prev_line(&mut self) -> std::io::Result<Option<(u64, String)>> {
+        // 首次读取时先载入末尾的块，确保文件末尾的换行符不会被当作一个空行
+        if self.buffer.is_empty() && !self.fill()? {
+            return Ok(None);
+        }
+
+        // 去掉末尾的换行符，它属于当前行
+        if self.buffer.last() == Some(&b'\n') {
+            self.buffer.pop();
+            if self.buffer.last() == Some(&b'\r') {
+                self.buffer.pop();
+            }
+        }
+
+        loop {
+            if let Some(pos) = self.buffer.iter().rposition(|b| *b == b'\n') {
+                let line = String::from_utf8_lossy(&self.buffer[pos + 1..]).to_string();
+                let offset = self.position + pos as u64 + 1;
+                self.buffer.truncate(pos + 1);
+                return Ok(Some((offset, line)));
+            }
+            if !self.fill()? {
+                if self.buffer.is_empty() {
+                    return Ok(None);
+                }
+                let line = String::from_utf8_lossy(&self.buffer).to_string();
+                self.buffer.clear();
+                return Ok(Some((0, line)));
+            }
+        }
+    }
+}
+
+/// 从 end 开始反向逐条读取记录（由新到旧），回调返回 false 时停止
+pub fn for_each_record_rev(
+    path: &Path,
+    end: u64,
+    mut f: impl FnMut(u64, LogRecord) -> bool,
+) -> std::io::Result<()> {
+    let mut reader = ReverseLineReader::new(path, end)?;
+    // 反向读取时先遇到续行，暂存到遇到所属记录的头部为止
+    let mut continuation: Vec<String> = Vec::new();
+
+    while let Some((offset, line)) = reader.prev_line()? {
+        if let Some(mut record) = parse_header(&line) {
+            continuation.reverse();
+            record.continuation = std::mem::take(&mut continuation);
+            if !f(offset, record) {
+                return Ok(());
+            }
+        } else {
+            continuation.push(line);
+        }
+    }
+
+    // 文件开头没有头部的行：第一行作为孤立记录，其余作为它的续行
+    if !continuation.is_empty() {
+        continuation.reverse();
+        let first = continuation.remove(0);
+        let mut record = LogRecord::orphan(&first);
+        record.continuation = continuation;
+        f(0, record);
+    }
+    Ok(())
+}
+
+/// 读取 end 之前最后 limit 条记录（按时间正序），返回记录及其字节偏移
+pub fn tail_records(path: &Path, end: u64, limit: usize) -> std::io::Result<Vec<(u64, LogRecord)>> {
+    let mut records = Vec::with_capacity(limit);
+    if limit > 0 {
+        for_each_record_rev(path, end, |offset, record| {
+            records.push((offset, record));
+            records.len() < limit
+        })?;
+    }
+    records.reverse();
+    Ok(records)
+}
+
+/// 日志级别计数
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LogCounts {
+    pub total: usize,
+    pub debug: usize,
+    pub info: usize,
+    pub warn: usize,
+    pub error: usize,
+}
+
+impl LogCounts {
+    fn add(&mut self, group: LevelGroup) {
+        self.total += 1;
+        match group {
+            LevelGroup::Debug => self.debug += 1,
+            LevelGroup::Info => self.info += 1,
+            LevelGroup::Warn => self.warn += 1,
+            LevelGroup::Error => self.error += 1,
+            LevelGroup::Other => {}
+        }
+    }
+}
+
+/// 增量统计缓存
+#[derive(Debug)]
+struct LogStatsCache {
+    path: PathBuf,
+    /// 文件标识，日志被轮转为新文件时重新统计
+    identity: String,
+    /// 已统计到的位置（总在完整行之后）
+    offset: u64,
+    counts: LogCounts,
+    /// 是否已出现过记录；文件开头的非头部行计为一条孤立记录
+    seen_record: bool,
+}
+
+static LOG_STATS_CACHE: Mutex<Option<LogStatsCache>> = Mutex::new(None);
+
+/// 统计上次位置之后新增的完整行
+fn scan_counts(path: &Path, cache: &mut LogStatsCache, size: u64) -> std::io::Result<()> {
+    let mut file = File::open(path)?;
+    file.seek(SeekFrom::Start(cache.offset))?;
+    let mut reader = BufReader::new(file.take(size - cache.offset));
+    let mut buffer = Vec::new();
+
+    loop {
+        buffer.clear();
+        let read = reader.read_until(b'\n', &mut buffer)?;
+        // 行尾没有换行符说明这一行还没写完，留到下次统计
+        if read == 0 || buffer.last() != Some(&b'\n') {
+            break;
+        }
+
+        let line = String::from_utf8_lossy(&buffer);
+        match parse_header(line.trim_end_matches(['\n', '\r'])) {
+            Some(record) => {
+                cache.counts.add(record.level_group());
+                cache.seen_record = true;
+            }
+            None if !cache.seen_record => {
+                cache.counts.add(LevelGroup::Other);
+                cache.seen_record = true;
+            }
+            None => {}
+        }
+        cache.offset += read as u64;
+    }
+    Ok(())
+}
+
+/// 获取日志级别计数，只扫描上次统计之后新增的内容
+/// 文件被截断、轮转或更换路径时从头重新统计
+pub fn log_counts(path: &Path) -> std::io::Result<LogCounts> {
+    update_counts(&mut LOG_STATS_CACHE.lock().unwrap(), path)
+}
+
+fn update_counts(slot: &mut Option<LogStatsCache>, path: &Path) -> std::io::Result<LogCounts> {
+    let meta = std::fs::metadata(path)?;
+    let size = meta.len();
+    let identity = file_identity(&meta);
+
+    // 复制后截断的轮转保留文件标识；截断后又写入了更多内容时文件不会变小，
+    // 但上次位置之前的字节不再是换行符
+    let reset = match slot.as_ref() {
+        Some(cache) => {
+            cache.path != path
+                || cache.identity != identity
+                || size < cache.offset
+                || !ends_line_at(&mut File::open(path)?, cache.offset)
+        }
+        None => true,
+    };
+    if reset {
+        *slot = Some(LogStatsCache {
+            path: path.to_path_buf(),
+            identity,
+            offset: 0,
+            counts: LogCounts::default(),
+            seen_record: false,
+        });
+    }
+
+    let cache = slot.as_mut().unwrap();
+    if size > cache.offset {
+        scan_counts(path, cache, size)?;
+    }
+    Ok(cache.counts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    const INFO: &str = "2026-02-10 12:34:56.789 | INFO     | nanobot.agent.loop:run:42 - started";
+    const ERROR: &str = "2026-02-10 12:35:00.000 | ERROR    | nanobot.channels.telegram:poll:7 - failed";
+
+    fn temp_file(name: &str, content: &[u8]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("nanoboard-tail-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("nanobot.log");
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    fn remove(path: &Path) {
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn reads_lines_backwards_across_blocks() {
+        // 行长不一，含多字节字符，CRLF 换行，最后一行没有换行符，总长度跨越多个块
+        let lines: Vec<String> = (0..3000)
+            .map(|i| format!("第 {} 行 {}", i, "x".repeat(i % 97)))
+            .collect();
+        let content = lines.join("\r\n");
+        assert!(content.len() > 2 * REVERSE_BLOCK_SIZE);
+        let path = temp_file("reverse", content.as_bytes());
+
+        let mut expected = Vec::new();
+        let mut offset = 0u64;
+        for line in &lines {
+            expected.push((offset, line.clone()));
+            offset += line.len() as u64 + 2;
+        }
+
+        let mut reader = ReverseLineReader::new(&path, content.len() as u64).unwrap();
+        let mut actual = Vec::new();
+        while let Some(entry) = reader.prev_line().unwrap() {
+            actual.push(entry);
+        }
+        actual.reverse();
+        assert_eq!(actual, expected);
+
+        // 从中间某行结尾开始读取
+        let (end_offset, end_line) = &expected[1500];
+        let end = end_offset + end_line.len() as u64 + 2;
+        let mut reader = ReverseLineReader::new(&path, end).unwrap();
+        assert_eq!(reader.prev_line().unwrap().as_ref(), Some(&expected[1500]));
+
+        remove(&path);
+    }
+
+    #[test]
+    fn keeps_continuations_with_their_records() {
+        let trace: Vec<String> = (0..2000).map(|i| format!("  File \"x.py\", line {:>40}", i)).collect();
+        let content = format!(
+            "orphan\nmore\n{}\n{}\n{}\n{}\n",
+            INFO,
+            ERROR,
+            trace.join("\n"),
+            INFO
+        );
+        assert!(content.len() > REVERSE_BLOCK_SIZE);
+        let path = temp_file("records", content.as_bytes());
+
+        let mut records = Vec::new();
+        for_each_record_rev(&path, content.len() as u64, |offset, record| {
+            records.push((offset, record));
+            true
+        })
+        .unwrap();
+
+        assert_eq!(records.len(), 4);
+        assert!(records[0].1.continuation.is_empty());
+        assert_eq!(records[1].1.level, "ERROR");
+        assert_eq!(records[1].1.continuation, trace);
+        assert_eq!(records[1].0, content.find(ERROR).unwrap() as u64);
+        assert!(records[2].1.continuation.is_empty());
+        assert_eq!(records[3], (0, {
+            let mut orphan = LogRecord::orphan("orphan");
+            orphan.continuation = vec!["more".to_string()];
+            orphan
+        }));
+
+        // 只取最后两条，按时间正序
+        let tail = tail_records(&path, content.len() as u64, 2).unwrap();
+        assert_eq!(tail.len(), 2);
+        assert_eq!(tail[0].1.level, "ERROR");
+
+        remove(&path);
+    }
+
+    #[test]
+    fn counts_incrementally_and_resets_after_truncation() {
+        let path = temp_file("counts", format!("{}\n{}\ntrace\n", INFO, ERROR).as_bytes());
+        let mut cache = None;
+
+        let counts = update_counts(&mut cache, &path).unwrap();
+        assert_eq!((counts.total, counts.info, counts.error), (2, 1, 1));
+
+        // 未写完的行留到下次统计
+        let mut file = std::fs::OpenOptions::new().append(true).open(&path).unwrap();
+        write!(file, "{}", ERROR).unwrap();
+        let counts = update_counts(&mut cache, &path).unwrap();
+        assert_eq!(counts.total, 2);
+        let offset = cache.as_ref().unwrap().offset;
+        writeln!(file).unwrap();
+        let counts = update_counts(&mut cache, &path).unwrap();
+        assert_eq!((counts.total, counts.error), (3, 2));
+        assert!(cache.as_ref().unwrap().offset > offset);
+        drop(file);
+
+        // 复制后截断并写入更多内容：文件标识不变、文件也没有变小
+        let identity = cache.as_ref().unwrap().identity.clone();
+        let file = std::fs::OpenOptions::new().write(true).open(&path).unwrap();
+        file.set_len(0).unwrap();
+        drop(file);
+        let rewritten = format!("{}x\n{}\n{}\n{}\n", INFO, INFO, INFO, INFO);
+        let old_offset = cache.as_ref().unwrap().offset;
+        assert!(rewritten.len() as u64 > old_offset);
+        std::fs::OpenOptions::new().append(true).open(&path).unwrap().write_all(rewritten.as_bytes()).unwrap();
+        let counts = update_counts(&mut cache, &path).unwrap();
+        assert_eq!(cache.as_ref().unwrap().identity, identity);
+        assert_eq!((counts.total, counts.info, counts.error), (4, 4, 0));
+
+        remove(&path);
+    }
+}
@@ -2,15 +2,57 @@ use anyhow::{Context, Result};
 use dirs::home_dir;
 use serde_json::json;
 use std::fs::File;
-use std::io::{BufRead, BufReader, Seek, SeekFrom};
+use std::io::{Read, Seek, SeekFrom};
 use std::path::PathBuf;
 use std::sync::Arc;
-use tauri::Emitter;
+use std::time::{Duration, Instant};
+
+use crate::log_files::file_identity;
+use crate::log_parser::{for_each_line, LogRecord, RecordParser};
+use crate::log_stream::LogHub;
+use crate::log_tail::{log_counts, tail_records};
+
+/// 判断 position 是否位于一行的结尾之后（文件开头也视为是）
+pub(crate) fn ends_line_at(file: &mut File, position: u64) -> bool {
+    if position == 0 {
+        return true;
+    }
+    let mut byte = [0u8; 1];
+    file.seek(SeekFrom::Start(position - 1)).is_ok()
+        && file.read_exact(&mut byte).is_ok()
+        && byte[0] == b'\n'
+}
+
+/// 查找 position 之前最后一个换行符之后的位置，最多向前查找 64KB
+fn line_start_before(file: &mut File, position: u64) -> u64 {
+    let start = position.saturating_sub(64 * 1024);
+    let mut buffer = Vec::new();
+    let read = file.seek(SeekFrom::Start(start)).is_ok()
+        && file.by_ref().take(position - start).read_to_end(&mut buffer).is_ok();
+    if !read {
+        return position;
+    }
+    match buffer.iter().rposition(|b| *b == b'\n') {
+        Some(pos) => start + pos as u64 + 1,
+        None if start == 0 => 0,
+        None => position,
+    }
+}
+
+/// 最后一条记录在没有新行写入多久之后视为完整并输出
+pub(crate) const RECORD_HOLD_TIMEOUT: Duration = Duration::from_millis(500);
 
 /// 文件位置跟踪器
+/// 持有打开的文件句柄并记录文件标识，日志被重命名轮转后仍能读完旧文件中剩余的内容；
+/// 解析状态跨读取保留，异常堆栈分多次写入时仍归入同一条记录
 pub struct FileTracker {
     log_path: PathBuf,
     position: u64,
+    file: Option<File>,
+    identity: Option<String>,
+    parser: RecordParser,
+    /// 最近一次读到新行的时间
+    last_line_at: Option<Instant>,
 }
 
 impl FileTracker {
@@ -18,55 +60,138 @@ impl FileTracker {
         Self {
             log_path: PathBuf::new(),
             position: 0,
+            file: None,
+            identity: None,
+            parser: RecordParser::new(),
+            last_line_at: None,
         }
     }
-}
 
-/// Watcher 任务句柄，用于取消监控任务
-#[derive(Clone)]
-pub struct WatcherHandle {
-    running: Arc<std::sync::atomic::AtomicBool>,
-    task_handle: Arc<tokio::sync::Mutex<Option<tokio::task::JoinHandle<()>>>>,
-}
+    /// 是否有暂缓输出的记录，调用方应在 RECORD_HOLD_TIMEOUT 后再次读取
+    pub(crate) fn has_pending(&self) -> bool {
+        self.parser.has_pending()
+    }
 
-impl WatcherHandle {
-    pub fn new() -> Self {
-        Self {
-            running: Arc::new(std::sync::atomic::AtomicBool::new(false)),
-            task_handle: Arc::new(tokio::sync::Mutex::new(None)),
+    /// 开始跟踪指定文件，从 position 所在行的开头开始读取
+    /// 起始位置可能落在正在写入的行中间，退回到该行开头以免丢失或截断这一行
+    pub(crate) fn follow(&mut self, log_path: PathBuf, position: u64) {
+        self.log_path = log_path;
+        self.position = 0;
+        self.file = None;
+        self.identity = None;
+        self.parser = RecordParser::new();
+        self.last_line_at = None;
+        if self.open_current() {
+            if let Some(file) = self.file.as_mut() {
+                self.position = line_start_before(file, position);
+            }
         }
     }
 
-    pub fn is_running(&self) -> bool {
-        self.running.load(std::sync::atomic::Ordering::Relaxed)
+    /// 打开路径当前指向的文件
+    fn open_current(&mut self) -> bool {
+        match File::open(&self.log_path) {
+            Ok(file) => {
+                self.identity = file.metadata().ok().map(|m| file_identity(&m));
+                self.file = Some(file);
+                true
+            }
+            Err(_) => false,
+        }
     }
 
-    pub fn set_running(&self, running: bool) {
-        self.running.store(running, std::sync::atomic::Ordering::Relaxed);
-    }
+    /// 从已打开的文件中读取 position 之后的内容
+    /// 最后一条记录后面可能还有续行，暂不输出，直到出现下一条记录头、
+    /// 超过 RECORD_HOLD_TIMEOUT 没有新行，或 final_read 为 true（文件不会再有新内容，末尾未换行的部分也一并读取）
+    fn read_from_handle(&mut self, final_read: bool) -> Result<Vec<LogRecord>, String> {
+        let mut records = Vec::new();
+        let Some(file) = self.file.as_mut() else {
+            return Ok(records);
+        };
+
+        let current_size = file.metadata()
+            .map(|m| m.len())
+            .unwrap_or(0);
+
+        // 同一文件变小说明被截断（例如复制后截断的轮转），从头读取；
+        // 截断后又写入了更多内容时文件不会变小，但上次位置之前的字节不再是换行符
+        if current_size < self.position || !ends_line_at(file, self.position) {
+            self.position = 0;
+            records.extend(self.parser.finish().map(|(_, record)| record));
+        }
+
+        if current_size > self.position {
+            // 定位到上次读取的位置
+            file.seek(SeekFrom::Start(self.position))
+                .map_err(|e| format!("定位文件位置失败: {}", e))?;
+
+            let mut buffer = Vec::new();
+            file.take(current_size - self.position)
+                .read_to_end(&mut buffer)
+                .map_err(|e| format!("读取日志文件失败: {}", e))?;
+
+            let complete_len = if final_read {
+                buffer.len()
+            } else {
+                buffer.iter().rposition(|b| *b == b'\n').map_or(0, |pos| pos + 1)
+            };
+            if complete_len > 0 {
+                let parser = &mut self.parser;
+                for_each_line(&buffer[..complete_len], self.position, |offset, line| {
+                    records.extend(parser.push_line(offset, line).map(|(_, record)| record));
+                    true
+                });
+                // 更新位置为最后一个完整行之后
+                self.position += complete_len as u64;
+                self.last_line_at = Some(Instant::now());
+            }
+        }
 
-    pub async fn set_task_handle(&self, handle: tokio::task::JoinHandle<()>) {
-        let mut task = self.task_handle.lock().await;
-        *task = Some(handle);
+        let idle = self
+            .last_line_at
+            .is_some_and(|at| at.elapsed() >= RECORD_HOLD_TIMEOUT);
+        if final_read || idle {
+            records.extend(self.parser.finish().map(|(_, record)| record));
+        }
+        Ok(records)
     }
 
-    pub async fn abort(&self) {
-        let mut task = self.task_handle.lock().await;
-        if let Some(handle) = task.take() {
-            handle.abort();
+    /// 读取新增的日志记录（从上次位置开始）
+    /// 只读取到最后一个完整行，未写完的行留到下次读取；
+    /// 路径指向的文件标识变化时（日志被重命名轮转），先读完旧文件，再从头读取新文件
+    pub(crate) fn read_new_records(&mut self) -> Result<Vec<LogRecord>, String> {
+        if self.file.is_none() {
+            // 启动时文件不存在，或上次轮转后新文件尚未创建
+            if !self.open_current() {
+                return Ok(Vec::new());
+            }
+            self.position = 0;
+        }
+
+        let path_identity = std::fs::metadata(&self.log_path)
+            .ok()
+            .map(|m| file_identity(&m));
+        let rotated = path_identity.is_some() && path_identity != self.identity;
+
+        let mut records = self.read_from_handle(rotated)?;
+        if rotated {
+            self.position = 0;
+            if self.open_current() {
+                records.extend(self.read_from_handle(false)?);
+            }
         }
-        self.set_running(false);
+        Ok(records)
     }
 }
 
 /// 获取日志文件路径
-fn get_log_path() -> Result<PathBuf> {
+pub(crate) fn get_log_path() -> Result<PathBuf> {
     let home = home_dir().context("无法找到用户主目录")?;
     let log_path = home.join(".nanobot").join("logs").join("nanobot.log");
     Ok(log_path)
 }
 
-/// 获取最近的日志（优化版：只保留最后 N 行，避免大文件内存问题）
+/// 获取最近的日志（按记录计数，异常堆栈等续行归入所属记录）
 #[tauri::command]
 pub async fn get_logs(lines: Option<usize>) -> Result<serde_json::Value, String> {
     let log_path = get_log_path().map_err(|e| e.to_string())?;
@@ -79,228 +204,47 @@ pub async fn get_logs(lines: Option<usize>) -> Result<serde_json::Value, String>
         }));
     }
 
-    let file = File::open(&log_path)
-        .map_err(|e| format!("打开日志文件失败: {}", e))?;
-
-    let reader = BufReader::new(file);
+    // 从文件末尾反向读取，只需读取最后几块数据
+    let size = std::fs::metadata(&log_path)
+        .map(|m| m.len())
+        .map_err(|e| format!("读取日志文件信息失败: {}", e))?;
+    let records: Vec<LogRecord> = tail_records(&log_path, size, line_count)
+        .map_err(|e| format!("读取日志文件失败: {}", e))?
+        .into_iter()
+        .map(|(_, record)| record)
+        .collect();
+    let logs: Vec<String> = records.iter().map(|r| r.to_text()).collect();
 
-    // 使用环形缓冲区，只保留最后 N 行，避免将整个文件读入内存
-    let mut total_lines = 0usize;
-    let mut ring_buffer: Vec<String> = Vec::with_capacity(line_count);
-
-    for line in reader.lines().map_while(Result::ok) {
-        total_lines += 1;
-        if ring_buffer.len() < line_count {
-            ring_buffer.push(line);
-        } else {
-            // 环形缓冲区已满，移除最旧的行
-            ring_buffer.remove(0);
-            ring_buffer.push(line);
-        }
-    }
+    // 总记录数来自增量统计缓存
+    let total_records = log_counts(&log_path)
+        .map(|counts| counts.total)
+        .unwrap_or(records.len());
 
     Ok(json!({
-        "logs": ring_buffer,
-        "total": total_lines,
-        "showing": ring_buffer.len()
+        "logs": logs,
+        "records": records,
+        "total": total_records,
+        "showing": records.len()
     }))
 }
 
-/// 读取新增的日志行（从上次位置开始）
-fn read_new_lines(log_path: &PathBuf, last_pos: &mut u64) -> Result<Vec<String>, String> {
-    let mut file = File::open(log_path)
-        .map_err(|e| format!("打开日志文件失败: {}", e))?;
-
-    // 获取当前文件大小
-    let current_size = file.metadata()
-        .map(|m| m.len())
-        .unwrap_or(0);
-
-    // 如果文件被截断（例如日志轮转），重置位置
-    if current_size < *last_pos {
-        *last_pos = 0;
-    }
-
-    // 如果没有新内容，返回空
-    if current_size == *last_pos {
-        return Ok(Vec::new());
-    }
-
-    // 定位到上次读取的位置
-    file.seek(SeekFrom::Start(*last_pos))
-        .map_err(|e| format!("定位文件位置失败: {}", e))?;
-
-    let reader = BufReader::new(file);
-    let mut new_lines = Vec::new();
-
-    for log_line in reader.lines().map_while(Result::ok) {
-        new_lines.push(log_line);
-    }
-
-    // 更新位置为当前文件大小
-    *last_pos = current_size;
-
-    Ok(new_lines)
-}
-
-/// 启动日志流
+/// 启动日志流（旧版接口）
+/// 为调用的窗口订阅日志广播，新日志通过 log-update / log-records 事件发送
 #[tauri::command]
 pub async fn start_log_stream(
     window: tauri::Window,
-    file_tracker: tauri::State<'_, Arc<tokio::sync::Mutex<FileTracker>>>,
-    watcher_handle: tauri::State<'_, Arc<WatcherHandle>>,
+    hub: tauri::State<'_, Arc<LogHub>>,
 ) -> Result<(), String> {
-    // 检查是否已经在运行
-    if watcher_handle.is_running() {
-        return Ok(()); // 已经在运行，直接返回
-    }
-
-    watcher_handle.set_running(true);
-    let log_path = get_log_path().map_err(|e| e.to_string())?;
-
-    // 如果日志文件不存在，创建它
-    if !log_path.exists() {
-        // 确保目录存在
-        if let Some(parent) = log_path.parent() {
-            std::fs::create_dir_all(parent)
-                .map_err(|e| format!("创建日志目录失败: {}", e))?;
-        }
-        // 创建空日志文件
-        std::fs::File::create(&log_path)
-            .map_err(|e| format!("创建日志文件失败: {}", e))?;
-    }
-
-    // 获取当前文件大小作为起始位置
-    let file_size = std::fs::metadata(&log_path)
-        .map(|m| m.len())
-        .unwrap_or(0);
-
-    // 初始化文件位置跟踪
-    {
-        let mut tracker = file_tracker.lock().await;
-        tracker.log_path = log_path.clone();
-        tracker.position = file_size;
-    }
-
-    // 使用notify监控日志文件变化
-    let log_path_clone = log_path.clone();
-    let window_clone = window.clone();
-
-    // 克隆 file_tracker 的 Arc，这样可以在闭包中使用
-    let file_tracker_arc = file_tracker.inner().clone();
-
-    let task = tokio::spawn(async move {
-        use notify::{Watcher, RecursiveMode, EventKind, Result as NotifyResult, recommended_watcher};
-        use std::time::Duration;
-
-        let log_path_for_watch = log_path_clone.clone();
-        // 为轮询再克隆一次变量
-        let log_path_for_poll = log_path_clone.clone();
-        let file_tracker_for_poll = file_tracker_arc.clone();
-        let window_for_poll = window_clone.clone();
-
-        // 创建 watcher，同时使用轮询作为备选方案
-        let watcher_result = recommended_watcher(move |res: NotifyResult<notify::Event>| {
-            match res {
-                Ok(event) => {
-                    if matches!(event.kind, EventKind::Modify(_)) {
-                        // 使用 try_lock
-                        let mut tracker = match file_tracker_arc.try_lock() {
-                            Ok(guard) => guard,
-                            Err(_) => {
-                                eprintln!("无法获取锁，跳过此次更新");
-                                return;
-                            }
-                        };
-
-                        let last_pos = &mut tracker.position;
-                        match read_new_lines(&log_path_clone, last_pos) {
-                            Ok(new_lines) => {
-                                if !new_lines.is_empty() {
-                                    eprintln!("读取到 {} 行新日志", new_lines.len());
-                                    match window_clone.emit("log-update", new_lines) {
-                                        Ok(_) => {
-                                            eprintln!("成功发送日志更新事件");
-                                        }
-                                        Err(e) => {
-                                            eprintln!("发送事件失败: {:?}", e);
-                                        }
-                                    }
-                                }
-                            }
-                            Err(e) => {
-                                eprintln!("读取新行失败: {}", e);
-                            }
-                        }
-                    }
-                }
-                Err(e) => {
-                    eprintln!("监控错误: {:?}", e);
-                }
-            }
-        }).map_err(|e| format!("创建文件监控器失败: {}", e));
-
-        if let Err(ref e) = watcher_result {
-            eprintln!("Watcher 创建失败: {}", e);
-            return;
-        }
-
-        let mut watcher = watcher_result.unwrap();
-
-        // 开始监控
-        if let Err(e) = watcher.watch(&log_path_for_watch, RecursiveMode::NonRecursive) {
-            eprintln!("监控日志文件失败: {}", e);
-            return;
-        }
-
-        // 同时使用轮询作为备选方案，防止 watcher 漏掉某些事件
-        // 减少轮询频率以降低 CPU 使用率（从 500ms 增加到 2000ms）
-        loop {
-            tokio::time::sleep(Duration::from_millis(2000)).await;
-
-            // 定期检查文件大小是否有变化
-            if let Ok(metadata) = std::fs::metadata(&log_path_for_poll) {
-                let current_size = metadata.len();
-
-                // 尝试获取 tracker 检查是否有新内容
-                if let Ok(mut tracker) = file_tracker_for_poll.try_lock() {
-                    if current_size > tracker.position {
-                        // 有新内容，读取新行
-                        let last_pos = &mut tracker.position;
-                        match read_new_lines(&log_path_for_poll, last_pos) {
-                            Ok(new_lines) => {
-                                if !new_lines.is_empty() {
-                                    match window_for_poll.emit("log-update", new_lines) {
-                                        Ok(_) => {}
-                                        Err(e) => {
-                                            eprintln!("轮询发送事件失败: {:?}", e);
-                                        }
-                                    }
-                                }
-                            }
-                            Err(e) => {
-                                eprintln!("轮询读取新行失败: {}", e);
-                            }
-                        }
-                    }
-                }
-            }
-        }
-    });
-
-    // 保存任务句柄
-    watcher_handle.set_task_handle(task).await;
-
-    Ok(())
+    crate::log_stream::start_legacy(&window, hub.inner())
 }
 
-/// 停止日志流
+/// 停止日志流（旧版接口），只取消调用窗口的订阅
 #[tauri::command]
 pub async fn stop_log_stream(
-    watcher_handle: tauri::State<'_, Arc<WatcherHandle>>,
+    window: tauri::Window,
+    hub: tauri::State<'_, Arc<LogHub>>,
 ) -> Result<(), String> {
-    if watcher_handle.is_running() {
-        watcher_handle.abort().await;
+    if crate::log_stream::stop_legacy(&window, hub.inner()) {
         Ok(())
     } else {
         Err("没有正在运行的日志监控".to_string())
@@ -321,41 +265,17 @@ pub fn get_log_statistics_internal() -> Result<serde_json::Value, String> {
         }));
     }
 
-    let file = File::open(&log_path)
-        .map_err(|e| format!("打开日志文件失败: {}", e))?;
-
-    let reader = BufReader::new(file);
-    let logs: Vec<String> = reader.lines()
-        .map_while(Result::ok)
-        .collect();
-
-    let mut debug = 0usize;
-    let mut info = 0usize;
-    let mut warn = 0usize;
-    let mut error = 0usize;
-
-    for log in &logs {
-        let log_upper = log.to_uppercase();
-        // 匹配 "| DEBUG |" 格式（前后可能有空格）
-        if log_upper.contains("DEBUG") {
-            debug += 1;
-        } else if log_upper.contains("INFO") {
-            info += 1;
-        } else if log_upper.contains("WARNING") || log_upper.contains("WARN") {
-            warn += 1;
-        } else if log_upper.contains("ERROR") {
-            error += 1;
-        }
-    }
-
-    let total = logs.len();
+    // 按解析出的级别字段计数，消息内容中出现的级别关键字不影响统计
+    // 只扫描上次统计之后新增的内容，仪表盘刷新的开销与文件大小无关
+    let counts = log_counts(&log_path)
+        .map_err(|e| format!("读取日志文件失败: {}", e))?;
 
     Ok(json!({
-        "total": total,
-        "debug": debug,
-        "info": info,
-        "warn": warn,
-        "error": error,
+        "total": counts.total,
+        "debug": counts.debug,
+        "info": counts.info,
+        "warn": counts.warn,
+        "error": counts.error,
     }))
 }
 
@@ -368,7 +288,63 @@ pub async fn get_log_statistics() -> Result<serde_json::Value, String> {
 /// 检查日志流是否正在运行
 #[tauri::command]
 pub async fn is_log_stream_running(
-    watcher_handle: tauri::State<'_, Arc<WatcherHandle>>,
+    hub: tauri::State<'_, Arc<LogHub>>,
 ) -> Result<bool, String> {
-    Ok(watcher_handle.is_running())
+    Ok(hub.is_running())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    const ERROR: &str = "2026-02-10 12:35:00.000 | ERROR    | nanobot.channels.telegram:poll:7 - failed";
+    const INFO: &str = "2026-02-10 12:35:01.000 | INFO     | nanobot.agent.loop:run:42 - next";
+
+    fn append(path: &std::path::Path, text: &str) {
+        let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path).unwrap();
+        file.write_all(text.as_bytes()).unwrap();
+    }
+
+    #[test]
+    fn keeps_traceback_split_across_reads() {
+        let dir = std::env::temp_dir().join(format!("nanoboard-tracker-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("nanobot.log");
+        append(&path, "");
+
+        let mut tracker = FileTracker::new();
+        tracker.follow(path.clone(), 0);
+
+        // 记录头先写入，后面可能还有堆栈，暂不输出
+        append(&path, &format!("{}\nTraceback (most recent call last):\n", ERROR));
+        assert!(tracker.read_new_records().unwrap().is_empty());
+        assert!(tracker.has_pending());
+
+        // 堆栈的剩余部分与下一条记录头在第二次读取时到达
+        append(&path, "  File \"x.py\", line 1\nValueError: bad\n");
+        assert!(tracker.read_new_records().unwrap().is_empty());
+        append(&path, &format!("{}\n", INFO));
+        let records = tracker.read_new_records().unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].level, "ERROR");
+        assert_eq!(records[0].continuation.len(), 3);
+        assert_eq!(records[0].continuation[2], "ValueError: bad");
+
+        // 没有新行超过暂缓时间后输出最后一条记录
+        std::thread::sleep(RECORD_HOLD_TIMEOUT);
+        let records = tracker.read_new_records().unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].message, "next");
+        assert!(!tracker.has_pending());
+
+        // 最后一次读取时未换行的内容与暂缓的记录一并输出
+        append(&path, &format!("{}\ntail without newline", ERROR));
+        let records = tracker.read_from_handle(true).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].continuation, ["tail without newline"]);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
 }
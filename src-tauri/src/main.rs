@@ -12,6 +12,35 @@ mod theme;
 mod menu;
 mod cron;
 mod clawhub;
+mod interpolation;
+mod migration;
+mod config_model;
+mod providers;
+mod channels;
+mod mcp;
+mod log_parser;
+mod log_query;
+mod log_tail;
+mod log_files;
+mod log_rotation;
+mod alerts;
+mod log_analysis;
+mod log_stream;
+mod redaction;
+mod time_range;
+mod log_export;
+mod correlation;
+mod chat_session;
+mod session_index;
+mod search;
+mod session_export;
+mod session_archive;
+mod session_analytics;
+mod session_watch;
+mod chat_console;
+mod memory_history;
+#[cfg(test)]
+mod test_support;
 
 use std::sync::Mutex;
 use std::sync::Arc;
@@ -27,14 +56,17 @@ async fn main() {
 
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_notification::init())
         .manage(AppState {
             config_path: Mutex::new(None),
             nanobot_process: Mutex::new(None),
         })
-        .manage(Arc::new(tokio::sync::Mutex::new(logger::FileTracker::new())))
-        .manage(Arc::new(logger::WatcherHandle::new()))
+        .manage(Arc::new(log_stream::LogHub::new()))
         .manage(std::sync::Mutex::new(network::NetworkMonitor::new()))
         .manage(theme::ThemeState::new())
+        .manage(Arc::new(alerts::AlertState::new()))
+        .manage(Arc::new(session_watch::SessionWatchHub::new()))
+        .manage(Arc::new(chat_console::ChatConsoleState::new()))
         .setup(|app| {
             // 复用 Tauri 已加载的默认窗口图标，避免依赖 image-ico/image-png 可选特性。
             if let Some(window) = app.get_webview_window("main") {
@@ -62,8 +94,30 @@ async fn main() {
                 menu::handle_menu_event(&app_handle_for_menu, event.id.0.as_ref());
             });
 
+            // 按设置定期轮转日志（默认关闭）
+            tokio::spawn(log_rotation::run_rotation_loop());
+
+            // 按告警规则持续检查新日志
+            tokio::spawn(alerts::run_alert_loop(app_handle.clone()));
+
+            // 记录记忆文件的外部修改（agent 整理记忆等）
+            tokio::spawn(memory_history::run_history_loop());
+
+            // 清除回收站中过期的会话
+            tokio::spawn(session_archive::purge_expired_trash());
+
+            // 首次运行时记录配置对应的 nanobot 版本，用于判断之后是否需要迁移
+            tokio::spawn(migration::record_initial_config_version());
+
             Ok(())
         })
+        .on_window_event(|window, event| {
+            // 窗口关闭时移除它的日志流和会话跟随订阅，最后一个订阅者离开后停止日志监控
+            if let tauri::WindowEvent::Destroyed = event {
+                window.state::<Arc<log_stream::LogHub>>().unsubscribe_window(window.label());
+                window.state::<Arc<session_watch::SessionWatchHub>>().unsubscribe_window(window.label());
+            }
+        })
         .invoke_handler(tauri::generate_handler![
             // Config commands
             config::load_config,
@@ -73,6 +127,17 @@ async fn main() {
             config::get_config_history,
             config::restore_config_version,
             config::delete_config_version,
+            migration::check_config_migration,
+            migration::apply_config_migration,
+            config_model::get_typed_config,
+            config_model::get_channel_config,
+            config_model::update_channel_config,
+            config_model::update_provider_config,
+            config_model::update_agent_defaults,
+            providers::test_providers,
+            providers::list_provider_models,
+            channels::check_channels,
+            mcp::test_mcp_servers,
             // Process commands
             process::start_nanobot,
             process::stop_nanobot,
@@ -93,9 +158,26 @@ async fn main() {
             // Logger commands
             logger::get_logs,
             logger::get_log_statistics,
+            log_query::query_logs,
+            log_analysis::analyze_errors,
+            log_export::export_logs,
+            correlation::get_session_logs,
+            correlation::get_cron_run_logs,
+            log_files::list_log_files,
+            log_rotation::get_log_rotation_settings,
+            log_rotation::set_log_rotation_settings,
+            log_rotation::rotate_logs_now,
             logger::start_log_stream,
             logger::stop_log_stream,
             logger::is_log_stream_running,
+            log_stream::subscribe_log_stream,
+            log_stream::unsubscribe_log_stream,
+            // Alert commands
+            alerts::get_alert_rules,
+            alerts::save_alert_rules,
+            alerts::get_alert_history,
+            alerts::acknowledge_alerts,
+            alerts::clear_alert_history,
             // Network commands
             network::init_network_monitor,
             network::get_network_stats,
@@ -123,6 +205,27 @@ async fn main() {
             // Chat session commands
             session::list_chat_sessions,
             session::get_chat_session_content,
+            search::search_workspace,
+            session_export::export_chat_sessions,
+            session_archive::archive_chat_sessions,
+            session_archive::unarchive_chat_sessions,
+            session_archive::list_archived_chat_sessions,
+            session_archive::trash_chat_sessions,
+            session_archive::list_trashed_chat_sessions,
+            session_archive::restore_chat_sessions,
+            session_archive::purge_trashed_chat_sessions,
+            session_archive::bulk_manage_chat_sessions,
+            session_analytics::get_session_analytics,
+            session_watch::watch_chat_session,
+            session_watch::unwatch_chat_session,
+            session_watch::list_chat_session_watches,
+            chat_console::send_chat_message,
+            chat_console::cancel_chat_message,
+            memory_history::list_memory_history_files,
+            memory_history::list_memory_versions,
+            memory_history::get_memory_version,
+            memory_history::diff_memory_versions,
+            memory_history::restore_memory_version,
             // Theme commands
             theme::get_theme,
             theme::set_theme,
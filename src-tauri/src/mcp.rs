@@ -0,0 +1,751 @@
+// MCP 服务器检测模块
+// 启动 tools.mcpServers 中配置的 stdio 服务器（或连接 HTTP 服务器），
+// 完成 MCP initialize 握手并列出 tools / resources / prompts，随后干净地关闭，
+// 用于在重启 gateway 之前排查 MCP 配置问题。
+
+use serde::Serialize;
+use serde_json::{json, Value as JsonValue};
+use std::collections::VecDeque;
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+
+use crate::config_model::{McpServerConfig, NanobotConfig};
+
+/// 客户端声明的 MCP 协议版本
+const MCP_PROTOCOL_VERSION: &str = "2025-06-18";
+
+/// 单个请求的超时时间（首次启动可能需要下载依赖，如 npx）
+const MCP_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 关闭 stdio 服务器时等待其自行退出的时间
+const MCP_SHUTDOWN_GRACE: Duration = Duration::from_secs(3);
+
+/// 列表接口的最大翻页次数
+const MCP_MAX_PAGES: usize = 20;
+
+/// 保留的 stderr 行数
+const STDERR_TAIL_LINES: usize = 50;
+
+/// MCP 服务器检测结果
+#[derive(Debug, Clone, Serialize)]
+pub struct McpTestResult {
+    pub name: String,
+    /// stdio / http
+    pub transport: String,
+    pub success: bool,
+    pub message: String,
+    /// 从启动到握手完成的耗时
+    pub startup_ms: Option<u64>,
+    pub protocol_version: Option<String>,
+    pub server_info: Option<JsonValue>,
+    pub capabilities: Option<JsonValue>,
+    pub tools: Vec<JsonValue>,
+    pub resources: Vec<JsonValue>,
+    pub prompts: Vec<JsonValue>,
+    /// 列表接口的错误（握手成功但部分接口失败）
+    pub errors: Vec<String>,
+    /// stdio 服务器的 stderr 输出（末尾若干行）
+    pub stderr: Vec<String>,
+}
+
+impl McpTestResult {
+    fn new(name: &str, transport: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            transport: transport.to_string(),
+            success: false,
+            message: String::new(),
+            startup_ms: None,
+            protocol_version: None,
+            server_info: None,
+            capabilities: None,
+            tools: Vec::new(),
+            resources: Vec::new(),
+            prompts: Vec::new(),
+            errors: Vec::new(),
+            stderr: Vec::new(),
+        }
+    }
+}
+
+/// MCP 传输层
+trait McpTransport {
+    async fn request(&mut self, method: &str, params: JsonValue) -> Result<JsonValue, String>;
+    async fn notify(&mut self, method: &str, params: JsonValue) -> Result<(), String>;
+    async fn shutdown(self);
+}
+
+fn request_message(id: u64, method: &str, params: JsonValue) -> JsonValue {
+    json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params })
+}
+
+/// 从 JSON-RPC 响应中取出 result，error 转换为错误信息
+fn take_result(message: JsonValue) -> Result<JsonValue, String> {
+    if let Some(error) = message.get("error") {
+        let code = error.get("code").and_then(|c| c.as_i64()).unwrap_or(0);
+        let text = error.get("message").and_then(|m| m.as_str()).unwrap_or("未知错误");
+        return Err(format!("{} ({})", text, code));
+    }
+    Ok(message.get("result").cloned().unwrap_or(JsonValue::Null))
+}
+
+// ---------------------------------------------------------------------------
+// stdio 传输
+// ---------------------------------------------------------------------------
+
+struct StdioTransport {
+    child: Child,
+    stdin: Option<ChildStdin>,
+    stdout: BufReader<ChildStdout>,
+    stderr_task: Option<tokio::task::JoinHandle<()>>,
+    next_id: u64,
+}
+
+impl StdioTransport {
+    fn spawn(server: &McpServerConfig, stderr_tail: Arc<Mutex<VecDeque<String>>>) -> Result<Self, String> {
+        let command = server
+            .command
+            .as_deref()
+            .filter(|c| !c.is_empty())
+            .ok_or_else(|| "未配置 command".to_string())?;
+        // 桌面应用的 PATH 通常不完整，优先解析出完整路径
+        let program = crate::process::find_command(command).unwrap_or_else(|| command.to_string());
+
+        let mut cmd = Command::new(&program);
+        cmd.args(server.args.iter().flatten())
+            .envs(server.env.iter().flatten())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+
+        #[cfg(target_os = "windows")]
+        {
+            const CREATE_NO_WINDOW: u32 = 0x08000000;
+            cmd.creation_flags(CREATE_NO_WINDOW);
+        }
+
+        let mut child = cmd
+            .spawn()
+            .map_err(|e| format!("启动 MCP 服务器失败 ({}): {}", program, e))?;
+
+        let stdin = child.stdin.take();
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| "无法获取 MCP 服务器的标准输出".to_string())?;
+
+        let stderr_task = child.stderr.take().map(|stderr| {
+            tokio::spawn(async move {
+                let mut lines = BufReader::new(stderr).lines();
+                while let Ok(Some(line)) = lines.next_line().await {
+                    let mut tail = stderr_tail.lock().unwrap();
+                    if tail.len() >= STDERR_TAIL_LINES {
+                        tail.pop_front();
+                    }
+                    tail.push_back(line);
+                }
+            })
+        });
+
+        Ok(Self {
+            child,
+            stdin,
+            stdout: BufReader::new(stdout),
+            stderr_task,
+            next_id: 1,
+        })
+    }
+
+    async fn send(&mut self, message: &JsonValue) -> Result<(), String> {
+        let stdin = self
+            .stdin
+            .as_mut()
+            .ok_or_else(|| "MCP 服务器输入已关闭".to_string())?;
+        let mut line = message.to_string();
+        line.push('\n');
+        stdin
+            .write_all(line.as_bytes())
+            .await
+            .map_err(|e| format!("写入 MCP 服务器失败: {}", e))?;
+        stdin.flush().await.map_err(|e| format!("写入 MCP 服务器失败: {}", e))
+    }
+
+    /// 读取指定 id 的响应，忽略通知和服务器发起的请求
+    async fn read_response(&mut self, id: u64) -> Result<JsonValue, String> {
+        let mut line = String::new();
+        loop {
+            line.clear();
+            let read = self
+                .stdout
+                .read_line(&mut line)
+                .await
+                .map_err(|e| format!("读取 MCP 服务器输出失败: {}", e))?;
+            if read == 0 {
+                let status = self.child.try_wait().ok().flatten();
+                return Err(match status {
+                    Some(status) => format!("MCP 服务器已退出 ({})", status),
+                    None => "MCP 服务器关闭了输出".to_string(),
+                });
+            }
+
+            let trimmed = line.trim();
+            if trimmed.is_empty() {
+                continue;
+            }
+            let Ok(message) = serde_json::from_str::<JsonValue>(trimmed) else {
+                // 部分服务器会把日志打印到 stdout，跳过非 JSON 行
+                log::debug!("MCP 服务器输出非 JSON 行: {}", trimmed);
+                continue;
+            };
+
+            if message.get("id").and_then(|v| v.as_u64()) == Some(id) && message.get("method").is_none() {
+                return Ok(message);
+            }
+        }
+    }
+}
+
+impl McpTransport for StdioTransport {
+    async fn request(&mut self, method: &str, params: JsonValue) -> Result<JsonValue, String> {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.send(&request_message(id, method, params)).await?;
+
+        let response = tokio::time::timeout(MCP_REQUEST_TIMEOUT, self.read_response(id))
+            .await
+            .map_err(|_| format!("{} 请求超时", method))??;
+        take_result(response)
+    }
+
+    async fn notify(&mut self, method: &str, params: JsonValue) -> Result<(), String> {
+        self.send(&json!({ "jsonrpc": "2.0", "method": method, "params": params })).await
+    }
+
+    /// 按规范关闭：先关闭 stdin，等待退出，超时后强制结束
+    async fn shutdown(mut self) {
+        drop(self.stdin.take());
+        match tokio::time::timeout(MCP_SHUTDOWN_GRACE, self.child.wait()).await {
+            Ok(_) => {}
+            Err(_) => {
+                log::debug!("MCP 服务器未在规定时间内退出，强制结束");
+                let _ = self.child.kill().await;
+            }
+        }
+        // 等待 stderr 读完，避免进程刚退出时丢失最后几行输出
+        if let Some(task) = self.stderr_task.take() {
+            let _ = tokio::time::timeout(Duration::from_millis(500), task).await;
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Streamable HTTP 传输
+// ---------------------------------------------------------------------------
+
+struct HttpTransport {
+    client: reqwest::Client,
+    url: String,
+    headers: Vec<(String, String)>,
+    session_id: Option<String>,
+    protocol_version: Option<String>,
+    next_id: u64,
+}
+
+impl HttpTransport {
+    fn new(server: &McpServerConfig) -> Result<Self, String> {
+        let url = server
+            .url
+            .as_deref()
+            .filter(|u| !u.is_empty())
+            .ok_or_else(|| "未配置 url".to_string())?;
+        let client = reqwest::Client::builder()
+            .timeout(MCP_REQUEST_TIMEOUT)
+            .user_agent("nanoboard/1.0")
+            .build()
+            .map_err(|e| format!("创建 HTTP 客户端失败: {}", e))?;
+
+        Ok(Self {
+            client,
+            url: url.to_string(),
+            headers: server
+                .headers
+                .iter()
+                .flatten()
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect(),
+            session_id: None,
+            protocol_version: None,
+            next_id: 1,
+        })
+    }
+
+    fn post(&self, body: &JsonValue) -> reqwest::RequestBuilder {
+        let mut request = self
+            .client
+            .post(&self.url)
+            .header("Accept", "application/json, text/event-stream")
+            .json(body);
+        for (key, value) in &self.headers {
+            request = request.header(key.as_str(), value.as_str());
+        }
+        if let Some(session_id) = &self.session_id {
+            request = request.header("Mcp-Session-Id", session_id.as_str());
+        }
+        if let Some(version) = &self.protocol_version {
+            request = request.header("MCP-Protocol-Version", version.as_str());
+        }
+        request
+    }
+}
+
+/// 从 SSE 响应体中找出指定 id 的 JSON-RPC 消息
+fn find_sse_response(body: &str, id: u64) -> Option<JsonValue> {
+    let mut data = String::new();
+    for line in body.lines().chain(std::iter::once("")) {
+        if let Some(chunk) = line.strip_prefix("data:") {
+            data.push_str(chunk.trim_start());
+        } else if line.is_empty() && !data.is_empty() {
+            if let Ok(message) = serde_json::from_str::<JsonValue>(&data) {
+                if message.get("id").and_then(|v| v.as_u64()) == Some(id) {
+                    return Some(message);
+                }
+            }
+            data.clear();
+        }
+    }
+    None
+}
+
+impl McpTransport for HttpTransport {
+    async fn request(&mut self, method: &str, params: JsonValue) -> Result<JsonValue, String> {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let response = self
+            .post(&request_message(id, method, params))
+            .send()
+            .await
+            .map_err(|e| format!("{} 请求失败: {}", method, e))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(format!("{} 返回错误状态: {}", method, status));
+        }
+        if let Some(session_id) = response.headers().get("mcp-session-id").and_then(|v| v.to_str().ok()) {
+            self.session_id = Some(session_id.to_string());
+        }
+        let is_sse = response
+            .headers()
+            .get("content-type")
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.starts_with("text/event-stream"));
+
+        let body = response
+            .text()
+            .await
+            .map_err(|e| format!("读取 {} 响应失败: {}", method, e))?;
+        let message = if is_sse {
+            find_sse_response(&body, id).ok_or_else(|| format!("{} 响应中没有结果", method))?
+        } else {
+            serde_json::from_str(&body).map_err(|e| format!("解析 {} 响应失败: {}", method, e))?
+        };
+        let result = take_result(message)?;
+        // 握手后的请求需携带协商得到的协议版本
+        if method == "initialize" {
+            self.protocol_version = result
+                .get("protocolVersion")
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string());
+        }
+        Ok(result)
+    }
+
+    async fn notify(&mut self, method: &str, params: JsonValue) -> Result<(), String> {
+        self.post(&json!({ "jsonrpc": "2.0", "method": method, "params": params }))
+            .send()
+            .await
+            .map_err(|e| format!("{} 发送失败: {}", method, e))?;
+        Ok(())
+    }
+
+    /// 有会话时发送 DELETE 结束会话
+    async fn shutdown(self) {
+        if let Some(session_id) = &self.session_id {
+            let mut request = self.client.delete(&self.url).header("Mcp-Session-Id", session_id.as_str());
+            for (key, value) in &self.headers {
+                request = request.header(key.as_str(), value.as_str());
+            }
+            let _ = request.send().await;
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// 握手与列表
+// ---------------------------------------------------------------------------
+
+/// 调用分页的 list 接口并合并结果
+async fn list_all(transport: &mut impl McpTransport, method: &str, key: &str) -> Result<Vec<JsonValue>, String> {
+    let mut items = Vec::new();
+    let mut cursor: Option<String> = None;
+
+    for _ in 0..MCP_MAX_PAGES {
+        let params = match &cursor {
+            Some(c) => json!({ "cursor": c }),
+            None => json!({}),
+        };
+        let result = transport.request(method, params).await?;
+        if let Some(page) = result.get(key).and_then(|v| v.as_array()) {
+            items.extend(page.iter().cloned());
+        }
+        cursor = result
+            .get("nextCursor")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string());
+        if cursor.is_none() {
+            break;
+        }
+    }
+    Ok(items)
+}
+
+/// 在已建立的传输上执行握手并列出能力
+async fn run_session(
+    transport: &mut impl McpTransport,
+    result: &mut McpTestResult,
+    started: Instant,
+) -> Result<(), String> {
+    let init = transport
+        .request(
+            "initialize",
+            json!({
+                "protocolVersion": MCP_PROTOCOL_VERSION,
+                "capabilities": {},
+                "clientInfo": { "name": "nanoboard", "version": env!("CARGO_PKG_VERSION") }
+            }),
+        )
+        .await
+        .map_err(|e| format!("initialize 失败: {}", e))?;
+
+    result.startup_ms = Some(started.elapsed().as_millis() as u64);
+    result.protocol_version = init
+        .get("protocolVersion")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string());
+    result.server_info = init.get("serverInfo").cloned();
+    let capabilities = init.get("capabilities").cloned().unwrap_or_else(|| json!({}));
+    result.capabilities = Some(capabilities.clone());
+
+    transport.notify("notifications/initialized", json!({})).await?;
+
+    // 只调用服务器声明支持的列表接口
+    let lists = [
+        ("tools", "tools/list"),
+        ("resources", "resources/list"),
+        ("prompts", "prompts/list"),
+    ];
+    for (key, method) in lists {
+        if capabilities.get(key).is_none() {
+            continue;
+        }
+        match list_all(transport, method, key).await {
+            Ok(items) => match key {
+                "tools" => result.tools = items,
+                "resources" => result.resources = items,
+                _ => result.prompts = items,
+            },
+            Err(e) => result.errors.push(format!("{}: {}", method, e)),
+        }
+    }
+    Ok(())
+}
+
+/// 检测单个 MCP 服务器
+pub async fn test_mcp_server(name: &str, server: &McpServerConfig) -> McpTestResult {
+    let is_http = server.url.as_deref().is_some_and(|u| !u.is_empty());
+    let mut result = McpTestResult::new(name, if is_http { "http" } else { "stdio" });
+    let started = Instant::now();
+
+    let outcome = if is_http {
+        match HttpTransport::new(server) {
+            Ok(mut transport) => {
+                let outcome = run_session(&mut transport, &mut result, started).await;
+                transport.shutdown().await;
+                outcome
+            }
+            Err(e) => Err(e),
+        }
+    } else {
+        let stderr_tail = Arc::new(Mutex::new(VecDeque::new()));
+        let outcome = match StdioTransport::spawn(server, stderr_tail.clone()) {
+            Ok(mut transport) => {
+                let outcome = run_session(&mut transport, &mut result, started).await;
+                transport.shutdown().await;
+                outcome
+            }
+            Err(e) => Err(e),
+        };
+        result.stderr = stderr_tail.lock().unwrap().iter().cloned().collect();
+        outcome
+    };
+
+    match outcome {
+        Ok(()) => {
+            result.success = true;
+            result.message = format!(
+                "握手成功：{} 个工具，{} 个资源，{} 个提示词",
+                result.tools.len(),
+                result.resources.len(),
+                result.prompts.len()
+            );
+        }
+        Err(e) => result.message = e,
+    }
+    result
+}
+
+/// 检测 tools.mcpServers 中配置的 MCP 服务器
+/// 指定 name 时只检测该服务器
+#[tauri::command]
+pub async fn test_mcp_servers(name: Option<String>) -> Result<JsonValue, String> {
+    let config = crate::config::load_config_internal()?;
+    if config.get("error").and_then(|e| e.as_str()) == Some("config_not_found") {
+        return Ok(json!({
+            "success": false,
+            "message": "配置文件不存在",
+            "results": []
+        }));
+    }
+
+    // 使用展开环境变量后的配置，仅在内存中使用
+    let resolved = crate::interpolation::interpolate_config(&config).config;
+    let servers = NanobotConfig::from_json(&resolved)?
+        .tools
+        .and_then(|t| t.mcp_servers)
+        .unwrap_or_default();
+
+    let mut handles = Vec::new();
+    for (server_name, server) in servers {
+        if name.as_ref().is_some_and(|n| *n != server_name) {
+            continue;
+        }
+        handles.push(tokio::spawn(async move {
+            test_mcp_server(&server_name, &server).await
+        }));
+    }
+
+    if handles.is_empty() {
+        return Ok(json!({
+            "success": false,
+            "message": match &name {
+                Some(n) => format!("MCP 服务器 {} 未配置", n),
+                None => "未配置任何 MCP 服务器".to_string(),
+            },
+            "results": []
+        }));
+    }
+
+    let mut results = Vec::new();
+    for handle in handles {
+        match handle.await {
+            Ok(result) => results.push(result),
+            Err(e) => log::warn!("MCP 检测任务失败: {}", e),
+        }
+    }
+
+    let ok_count = results.iter().filter(|r| r.success).count();
+    Ok(json!({
+        "success": true,
+        "message": format!("{}/{} 个 MCP 服务器握手成功", ok_count, results.len()),
+        "results": results
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncReadExt;
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn finds_sse_response_by_id() {
+        let body = "event: message\ndata: {\"jsonrpc\":\"2.0\",\"method\":\"notifications/progress\"}\n\n\
+                    event: message\ndata: {\"jsonrpc\":\"2.0\",\"id\":1,\ndata: \"result\":{\"a\":1}}\n\n\
+                    data: {\"jsonrpc\":\"2.0\",\"id\":2,\"result\":{\"b\":2}}";
+
+        assert_eq!(find_sse_response(body, 1).unwrap()["result"]["a"], 1);
+        // 最后一个事件没有结尾空行
+        assert_eq!(find_sse_response(body, 2).unwrap()["result"]["b"], 2);
+        assert!(find_sse_response(body, 3).is_none());
+        assert!(find_sse_response("data: not json\n\n", 1).is_none());
+    }
+
+    /// 记录收到的请求：(请求行, 小写的请求头, 请求体)
+    type Requests = Arc<Mutex<Vec<(String, Vec<(String, String)>, String)>>>;
+
+    /// Streamable HTTP MCP 服务：initialize 以 SSE 返回并分配会话，tools/list 分两页
+    async fn http_server(requests: Requests) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let requests = requests.clone();
+                tokio::spawn(async move {
+                    let mut data = Vec::new();
+                    let mut buf = [0u8; 1024];
+                    let header_end = loop {
+                        if let Some(pos) = data.windows(4).position(|w| w == b"\r\n\r\n") {
+                            break pos + 4;
+                        }
+                        match socket.read(&mut buf).await {
+                            Ok(0) | Err(_) => return,
+                            Ok(n) => data.extend_from_slice(&buf[..n]),
+                        }
+                    };
+                    let head = String::from_utf8_lossy(&data[..header_end]).to_string();
+                    let mut lines = head.lines();
+                    let request_line = lines.next().unwrap_or_default().to_string();
+                    let headers: Vec<(String, String)> = lines
+                        .filter_map(|l| l.split_once(':'))
+                        .map(|(k, v)| (k.trim().to_ascii_lowercase(), v.trim().to_string()))
+                        .collect();
+                    let length: usize = headers
+                        .iter()
+                        .find(|(k, _)| k == "content-length")
+                        .and_then(|(_, v)| v.parse().ok())
+                        .unwrap_or(0);
+                    while data.len() < header_end + length {
+                        match socket.read(&mut buf).await {
+                            Ok(0) | Err(_) => return,
+                            Ok(n) => data.extend_from_slice(&buf[..n]),
+                        }
+                    }
+                    let body = String::from_utf8_lossy(&data[header_end..header_end + length]).to_string();
+                    requests.lock().unwrap().push((request_line.clone(), headers, body.clone()));
+
+                    let message: JsonValue = serde_json::from_str(&body).unwrap_or(JsonValue::Null);
+                    let id = message.get("id").cloned().unwrap_or(JsonValue::Null);
+                    let (status, content_type, extra, payload) = match message.get("method").and_then(|m| m.as_str()) {
+                        _ if request_line.starts_with("DELETE") => (200, "application/json", "", String::new()),
+                        Some("initialize") => (
+                            200,
+                            "text/event-stream",
+                            "Mcp-Session-Id: s-1\r\n",
+                            format!(
+                                "event: message\ndata: {}\n\n",
+                                json!({ "jsonrpc": "2.0", "id": id, "result": {
+                                    "protocolVersion": "2025-03-26",
+                                    "serverInfo": { "name": "stub" },
+                                    "capabilities": { "tools": {} }
+                                } })
+                            ),
+                        ),
+                        Some("tools/list") => {
+                            let result = if message["params"].get("cursor").is_some() {
+                                json!({ "tools": [{ "name": "b" }] })
+                            } else {
+                                json!({ "tools": [{ "name": "a" }], "nextCursor": "page-2" })
+                            };
+                            (200, "application/json", "", json!({ "jsonrpc": "2.0", "id": id, "result": result }).to_string())
+                        }
+                        _ => (202, "application/json", "", String::new()),
+                    };
+                    let response = format!(
+                        "HTTP/1.1 {} Stub\r\nContent-Type: {}\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        status,
+                        content_type,
+                        extra,
+                        payload.len(),
+                        payload
+                    );
+                    let _ = socket.write_all(response.as_bytes()).await;
+                });
+            }
+        });
+        format!("http://{}/mcp", addr)
+    }
+
+    #[tokio::test]
+    async fn tests_streamable_http_server() {
+        let requests: Requests = Arc::new(Mutex::new(Vec::new()));
+        let server = McpServerConfig {
+            url: Some(http_server(requests.clone()).await),
+            headers: Some([("Authorization".to_string(), "Bearer t".to_string())].into_iter().collect()),
+            ..Default::default()
+        };
+
+        let result = test_mcp_server("remote", &server).await;
+        assert!(result.success, "{}", result.message);
+        assert_eq!(result.transport, "http");
+        assert_eq!(result.protocol_version.as_deref(), Some("2025-03-26"));
+        let names: Vec<&str> = result.tools.iter().filter_map(|t| t["name"].as_str()).collect();
+        assert_eq!(names, ["a", "b"]);
+
+        let requests = requests.lock().unwrap();
+        // initialize、initialized 通知、两页 tools/list、结束会话
+        assert_eq!(requests.len(), 5);
+        let header = |i: usize, name: &str| {
+            requests[i].1.iter().find(|(k, _)| k == name).map(|(_, v)| v.clone())
+        };
+        assert_eq!(header(0, "authorization").as_deref(), Some("Bearer t"));
+        assert!(header(0, "mcp-session-id").is_none());
+        for i in 1..5 {
+            assert_eq!(header(i, "mcp-session-id").as_deref(), Some("s-1"));
+        }
+        assert_eq!(header(2, "mcp-protocol-version").as_deref(), Some("2025-03-26"));
+        assert!(requests[4].0.starts_with("DELETE"));
+    }
+
+    #[cfg(unix)]
+    fn stdio_server(script: &str) -> McpServerConfig {
+        McpServerConfig {
+            command: Some("sh".to_string()),
+            args: Some(vec!["-c".to_string(), script.to_string()]),
+            ..Default::default()
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn tests_stdio_server() {
+        // 在 stdout 上夹杂日志行与通知，tools/list 分两页，stdin 关闭后退出
+        let script = r#"
+while IFS= read -r line; do
+  case "$line" in
+    *'"initialize"'*)
+      echo 'starting stub'
+      echo '{"jsonrpc":"2.0","id":1,"result":{"protocolVersion":"2025-06-18","serverInfo":{"name":"stub"},"capabilities":{"tools":{}}}}' ;;
+    *'"tools/list"'*'"cursor"'*)
+      echo '{"jsonrpc":"2.0","id":3,"result":{"tools":[{"name":"b"}]}}' ;;
+    *'"tools/list"'*)
+      echo '{"jsonrpc":"2.0","method":"notifications/message","params":{}}'
+      echo '{"jsonrpc":"2.0","id":2,"result":{"tools":[{"name":"a"}],"nextCursor":"p2"}}' ;;
+  esac
+done
+echo 'bye' >&2
+"#;
+        let started = Instant::now();
+        let result = test_mcp_server("local", &stdio_server(script)).await;
+        assert!(result.success, "{}", result.message);
+        assert_eq!(result.transport, "stdio");
+        assert_eq!(result.server_info.as_ref().unwrap()["name"], "stub");
+        let names: Vec<&str> = result.tools.iter().filter_map(|t| t["name"].as_str()).collect();
+        assert_eq!(names, ["a", "b"]);
+        // 关闭 stdin 后服务器自行退出，不需要等待强制结束
+        assert!(started.elapsed() < MCP_SHUTDOWN_GRACE);
+        assert_eq!(result.stderr, ["bye"]);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn reports_stdio_failure_with_stderr_tail() {
+        let result = test_mcp_server("broken", &stdio_server("echo 'missing API token' >&2; exit 3")).await;
+        assert!(!result.success);
+        assert!(result.message.starts_with("initialize 失败"), "{}", result.message);
+        assert_eq!(result.stderr, ["missing API token"]);
+    }
+}
@@ -0,0 +1,675 @@
+// 记忆文件版本历史
+// 为 memory/*.md 以及 AGENTS.md / SOUL.md / USER.md 保存历史版本：
+// - nanoboard 保存前后各记录一次：保存前磁盘内容与最新版本不同说明被外部修改过，记为 agent；保存后的内容记为 user；
+//   文件还没有任何版本时，已有内容记为 initial（开始记录前的内容，来源未知）；
+// - 后台定期检查这些文件（按修改时间与大小跳过未变化的），agent 整理记忆时的改写记为 agent 版本；
+// - 版本保存在 ~/.nanobot/memory_history/<文件>/ 下，versions.json 记录时间戳与来源，内容与最新版本相同时不重复记录；
+// - 提供版本列表、按行比较与恢复。
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use dirs::home_dir;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+/// 每个文件保留的版本数
+const MAX_VERSIONS: usize = 50;
+
+/// 外部修改的检查间隔
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// 工作区根目录下需要记录历史的文件
+const WORKSPACE_FILES: &[&str] = &["AGENTS.md", "SOUL.md", "USER.md"];
+
+/// 行级比较的最大规模（旧行数 × 新行数），超过时只比较首尾相同部分之外的整块
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+/// 版本来源
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum VersionSource {
+    /// 在 nanoboard 中保存
+    User,
+    /// nanoboard 之外的修改（通常是 agent 整理记忆）
+    Agent,
+    /// 开始记录历史时文件已有的内容
+    Initial,
+}
+
+/// 历史版本信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryVersion {
+    pub id: String,
+    /// 毫秒时间戳
+    pub timestamp: i64,
+    pub source: VersionSource,
+    pub size: u64,
+    /// 由恢复操作产生时，记录恢复自哪个版本
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub restored_from: Option<String>,
+}
+
+/// 保存、检查与恢复互斥，避免后台检查把 nanoboard 的保存误记为外部修改
+static HISTORY_LOCK: Mutex<()> = Mutex::new(());
+
+/// 后台检查记录的文件状态（修改时间、大小）
+static FILE_STATES: Mutex<Option<HashMap<String, (SystemTime, u64)>>> = Mutex::new(None);
+
+/// 工作区与记忆历史目录
+struct HistoryRoots {
+    workspace: PathBuf,
+    history: PathBuf,
+}
+
+impl HistoryRoots {
+    /// ~/.nanobot/workspace 与 ~/.nanobot/memory_history
+    fn current() -> Result<Self> {
+        let home = home_dir().context("无法找到用户主目录")?;
+        Ok(Self {
+            workspace: home.join(".nanobot").join("workspace"),
+            history: home.join(".nanobot").join("memory_history"),
+        })
+    }
+
+    /// 文件在工作区中的路径
+    fn file_path(&self, file: &str) -> PathBuf {
+        self.workspace.join(file)
+    }
+
+    /// 文件的版本目录，memory/MEMORY.md 对应 memory__MEMORY.md
+    fn versions_dir(&self, file: &str) -> PathBuf {
+        self.history.join(file.replace('/', "__"))
+    }
+}
+
+/// 校验文件标识：memory/<名称>.md 或工作区根目录下的 AGENTS.md / SOUL.md / USER.md
+fn validate_file(file: &str) -> Result<(), String> {
+    if WORKSPACE_FILES.contains(&file) {
+        return Ok(());
+    }
+    let valid = file.strip_prefix("memory/").is_some_and(|name| {
+        name.ends_with(".md")
+            && !name.starts_with('.')
+            && name
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '-' || c == '_' || c == '.')
+    });
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("{} 不是可记录历史的记忆文件", file))
+    }
+}
+
+fn load_versions(dir: &Path) -> Vec<MemoryVersion> {
+    fs::read_to_string(dir.join("versions.json"))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_versions(dir: &Path, versions: &[MemoryVersion]) -> Result<()> {
+    let content = serde_json::to_string_pretty(versions).context("序列化版本列表失败")?;
+    fs::write(dir.join("versions.json"), content).context("写入版本列表失败")
+}
+
+fn read_version(dir: &Path, id: &str) -> Result<String> {
+    fs::read_to_string(dir.join(format!("{}.md", id)))
+        .with_context(|| format!("读取版本 {} 失败", id))
+}
+
+/// 在版本目录中记录一个版本，内容与最新版本相同时跳过，返回新版本
+fn snapshot(
+    dir: &Path,
+    content: &str,
+    source: VersionSource,
+    restored_from: Option<String>,
+) -> Result<Option<MemoryVersion>> {
+    fs::create_dir_all(dir).context("创建记忆历史目录失败")?;
+
+    let mut versions = load_versions(dir);
+    if let Some(latest) = versions.last() {
+        if restored_from.is_none()
+            && read_version(dir, &latest.id).ok().as_deref() == Some(content)
+        {
+            return Ok(None);
+        }
+    }
+
+    let mut timestamp = Utc::now().timestamp_millis();
+    if let Some(latest) = versions.last() {
+        timestamp = timestamp.max(latest.timestamp + 1);
+    }
+    let version = MemoryVersion {
+        id: timestamp.to_string(),
+        timestamp,
+        source,
+        size: content.len() as u64,
+        restored_from,
+    };
+    fs::write(dir.join(format!("{}.md", version.id)), content).context("写入历史版本失败")?;
+    versions.push(version.clone());
+
+    // 清理超出保留数量的旧版本
+    if versions.len() > MAX_VERSIONS {
+        let removed: Vec<MemoryVersion> = versions.drain(..versions.len() - MAX_VERSIONS).collect();
+        for old in removed {
+            let _ = fs::remove_file(dir.join(format!("{}.md", old.id)));
+        }
+    }
+    save_versions(dir, &versions)?;
+    Ok(Some(version))
+}
+
+/// 文件当前内容与最新版本不同时记为外部修改；还没有任何版本时记为初始内容
+fn record_external_change(roots: &HistoryRoots, file: &str) -> Result<Option<MemoryVersion>> {
+    let path = roots.file_path(file);
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let content = fs::read_to_string(&path).context("读取记忆文件失败")?;
+    let dir = roots.versions_dir(file);
+    let source = if load_versions(&dir).is_empty() {
+        VersionSource::Initial
+    } else {
+        VersionSource::Agent
+    };
+    snapshot(&dir, &content, source, None)
+}
+
+/// 在 nanoboard 中写入记忆文件并记录版本；不在记录范围内的文件直接写入
+/// 历史记录失败只记日志，不影响保存
+pub(crate) fn write_with_history(path: &Path, file: &str, content: &str) -> std::io::Result<()> {
+    if validate_file(file).is_err() {
+        return fs::write(path, content);
+    }
+    match HistoryRoots::current() {
+        Ok(roots) => save_with_history(&roots, path, file, content),
+        Err(e) => {
+            log::warn!("记录 {} 的历史版本失败: {:#}", file, e);
+            fs::write(path, content)
+        }
+    }
+}
+
+fn save_with_history(roots: &HistoryRoots, path: &Path, file: &str, content: &str) -> std::io::Result<()> {
+    let _guard = HISTORY_LOCK.lock().unwrap();
+    if let Err(e) = record_external_change(roots, file) {
+        log::warn!("记录 {} 的外部修改失败: {:#}", file, e);
+    }
+    fs::write(path, content)?;
+    if let Err(e) = snapshot(&roots.versions_dir(file), content, VersionSource::User, None) {
+        log::warn!("记录 {} 的历史版本失败: {:#}", file, e);
+    }
+    remember_state(file, path);
+    Ok(())
+}
+
+/// 记录文件当前的修改时间与大小，后台检查时据此跳过
+fn remember_state(file: &str, path: &Path) {
+    if let Ok(metadata) = fs::metadata(path) {
+        if let Ok(modified) = metadata.modified() {
+            FILE_STATES
+                .lock()
+                .unwrap()
+                .get_or_insert_with(HashMap::new)
+                .insert(file.to_string(), (modified, metadata.len()));
+        }
+    }
+}
+
+/// 当前需要记录历史的文件
+fn tracked_files(roots: &HistoryRoots) -> Vec<String> {
+    let workspace = &roots.workspace;
+    let mut files: Vec<String> = WORKSPACE_FILES
+        .iter()
+        .filter(|name| workspace.join(name).is_file())
+        .map(|name| name.to_string())
+        .collect();
+
+    if let Ok(entries) = fs::read_dir(workspace.join("memory")) {
+        for entry in entries.flatten() {
+            if let Some(name) = entry.file_name().to_str() {
+                let file = format!("memory/{}", name);
+                if entry.path().is_file() && validate_file(&file).is_ok() {
+                    files.push(file);
+                }
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+/// 检查全部记忆文件，返回新记录的外部修改数
+fn check_external_changes(roots: &HistoryRoots) -> usize {
+    let _guard = HISTORY_LOCK.lock().unwrap();
+    let mut recorded = 0;
+
+    for file in tracked_files(roots) {
+        let path = roots.file_path(&file);
+        let Ok(metadata) = fs::metadata(&path) else {
+            continue;
+        };
+        let Ok(modified) = metadata.modified() else {
+            continue;
+        };
+        let state = (modified, metadata.len());
+        let unchanged = FILE_STATES
+            .lock()
+            .unwrap()
+            .as_ref()
+            .and_then(|states| states.get(&file))
+            == Some(&state);
+        if unchanged {
+            continue;
+        }
+
+        match record_external_change(roots, &file) {
+            Ok(Some(_)) => recorded += 1,
+            Ok(None) => {}
+            Err(e) => log::warn!("记录 {} 的历史版本失败: {:#}", file, e),
+        }
+        FILE_STATES
+            .lock()
+            .unwrap()
+            .get_or_insert_with(HashMap::new)
+            .insert(file, state);
+    }
+    recorded
+}
+
+/// 定期检查记忆文件的外部修改（在应用启动时调用）
+pub async fn run_history_loop() {
+    loop {
+        let check = || HistoryRoots::current().map(|roots| check_external_changes(&roots));
+        match tokio::task::spawn_blocking(check).await {
+            Ok(Ok(recorded)) if recorded > 0 => {
+                log::info!("记录了 {} 个记忆文件的外部修改", recorded)
+            }
+            Ok(Ok(_)) => {}
+            Ok(Err(e)) => log::warn!("检查记忆文件修改失败: {:#}", e),
+            Err(e) => log::warn!("记忆历史任务失败: {}", e),
+        }
+        tokio::time::sleep(CHECK_INTERVAL).await;
+    }
+}
+
+/// 行级差异
+#[derive(Debug, Clone, Serialize)]
+pub struct DiffLine {
+    /// equal / insert / delete
+    pub op: &'static str,
+    pub text: String,
+    /// 在旧内容中的行号（从 1 开始）
+    pub old_line: Option<usize>,
+    /// 在新内容中的行号（从 1 开始）
+    pub new_line: Option<usize>,
+}
+
+/// 按行比较两段文本（最长公共子序列）
+fn diff_lines(old: &str, new: &str) -> Vec<DiffLine> {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+
+    // 先去掉首尾相同的行，缩小比较范围
+    let prefix = old_lines
+        .iter()
+        .zip(&new_lines)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let suffix = old_lines[prefix..]
+        .iter()
+        .rev()
+        .zip(new_lines[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let a = &old_lines[prefix..old_lines.len() - suffix];
+    let b = &new_lines[prefix..new_lines.len() - suffix];
+
+    let mut result = Vec::new();
+    let equal = |result: &mut Vec<DiffLine>, text: &str, i: usize, j: usize| {
+        result.push(DiffLine {
+            op: "equal",
+            text: text.to_string(),
+            old_line: Some(i + 1),
+            new_line: Some(j + 1),
+        });
+    };
+    let delete = |result: &mut Vec<DiffLine>, text: &str, i: usize| {
+        result.push(DiffLine {
+            op: "delete",
+            text: text.to_string(),
+            old_line: Some(i + 1),
+            new_line: None,
+        });
+    };
+    let insert = |result: &mut Vec<DiffLine>, text: &str, j: usize| {
+        result.push(DiffLine {
+            op: "insert",
+            text: text.to_string(),
+            old_line: None,
+            new_line: Some(j + 1),
+        });
+    };
+
+    for (i, line) in old_lines[..prefix].iter().enumerate() {
+        equal(&mut result, line, i, i);
+    }
+
+    if a.len().saturating_mul(b.len()) > MAX_DIFF_CELLS {
+        // 规模过大时整块替换
+        for (i, line) in a.iter().enumerate() {
+            delete(&mut result, line, prefix + i);
+        }
+        for (j, line) in b.iter().enumerate() {
+            insert(&mut result, line, prefix + j);
+        }
+    } else {
+        // lcs[i][j] 为 a[i..] 与 b[j..] 的最长公共子序列长度
+        let width = b.len() + 1;
+        let mut lcs = vec![0u32; (a.len() + 1) * width];
+        for i in (0..a.len()).rev() {
+            for j in (0..b.len()).rev() {
+                lcs[i * width + j] = if a[i] == b[j] {
+                    lcs[(i + 1) * width + j + 1] + 1
+                } else {
+                    lcs[(i + 1) * width + j].max(lcs[i * width + j + 1])
+                };
+            }
+        }
+
+        let (mut i, mut j) = (0, 0);
+        while i < a.len() || j < b.len() {
+            if i < a.len() && j < b.len() && a[i] == b[j] {
+                equal(&mut result, a[i], prefix + i, prefix + j);
+                i += 1;
+                j += 1;
+            } else if i < a.len()
+                && (j == b.len() || lcs[(i + 1) * width + j] >= lcs[i * width + j + 1])
+            {
+                delete(&mut result, a[i], prefix + i);
+                i += 1;
+            } else {
+                insert(&mut result, b[j], prefix + j);
+                j += 1;
+            }
+        }
+    }
+
+    let old_offset = old_lines.len() - suffix;
+    let new_offset = new_lines.len() - suffix;
+    for k in 0..suffix {
+        equal(
+            &mut result,
+            old_lines[old_offset + k],
+            old_offset + k,
+            new_offset + k,
+        );
+    }
+    result
+}
+
+/// 列出记录了历史的记忆文件
+#[tauri::command]
+pub async fn list_memory_history_files() -> Result<serde_json::Value, String> {
+    let roots = HistoryRoots::current().map_err(|e| e.to_string())?;
+    let mut files = tracked_files(&roots);
+    // 已删除但仍有历史的文件
+    if let Ok(entries) = fs::read_dir(&roots.history) {
+        for entry in entries.flatten() {
+            if let Some(name) = entry.file_name().to_str() {
+                let file = name.replacen("__", "/", 1);
+                if validate_file(&file).is_ok() && !files.contains(&file) {
+                    files.push(file);
+                }
+            }
+        }
+    }
+    files.sort();
+
+    let mut result = Vec::new();
+    for file in files {
+        let versions = load_versions(&roots.versions_dir(&file));
+        result.push(json!({
+            "file": file,
+            "exists": roots.file_path(&file).is_file(),
+            "versions": versions.len(),
+            "latest": versions.last()
+        }));
+    }
+    Ok(json!({ "files": result }))
+}
+
+/// 获取记忆文件的版本列表（最新的在前）
+/// 列出前先检查文件是否被外部修改，保证列表包含当前内容
+#[tauri::command]
+pub async fn list_memory_versions(file: String) -> Result<serde_json::Value, String> {
+    validate_file(&file)?;
+    let roots = HistoryRoots::current().map_err(|e| e.to_string())?;
+    {
+        let _guard = HISTORY_LOCK.lock().unwrap();
+        record_external_change(&roots, &file).map_err(|e| format!("记录外部修改失败: {:#}", e))?;
+    }
+    let mut versions = load_versions(&roots.versions_dir(&file));
+    versions.reverse();
+    Ok(json!({ "file": file, "versions": versions }))
+}
+
+/// 获取某个版本的内容
+#[tauri::command]
+pub async fn get_memory_version(
+    file: String,
+    version_id: String,
+) -> Result<serde_json::Value, String> {
+    validate_file(&file)?;
+    let dir = HistoryRoots::current().map_err(|e| e.to_string())?.versions_dir(&file);
+    let version = load_versions(&dir)
+        .into_iter()
+        .find(|v| v.id == version_id)
+        .ok_or_else(|| format!("版本 {} 不存在", version_id))?;
+    let content = read_version(&dir, &version.id).map_err(|e| format!("{:#}", e))?;
+    Ok(json!({ "file": file, "version": version, "content": content }))
+}
+
+/// 比较两个版本，to 未指定时与文件当前内容比较
+#[tauri::command]
+pub async fn diff_memory_versions(
+    file: String,
+    from: String,
+    to: Option<String>,
+) -> Result<serde_json::Value, String> {
+    validate_file(&file)?;
+    let roots = HistoryRoots::current().map_err(|e| e.to_string())?;
+    let dir = roots.versions_dir(&file);
+    let versions = load_versions(&dir);
+    let read = |id: &str| -> Result<String, String> {
+        if !versions.iter().any(|v| v.id == id) {
+            return Err(format!("版本 {} 不存在", id));
+        }
+        read_version(&dir, id).map_err(|e| format!("{:#}", e))
+    };
+
+    let old = read(&from)?;
+    let new = match to.as_deref() {
+        Some(id) => read(id)?,
+        None => {
+            let path = roots.file_path(&file);
+            if path.is_file() {
+                fs::read_to_string(&path).map_err(|e| format!("读取记忆文件失败: {}", e))?
+            } else {
+                String::new()
+            }
+        }
+    };
+
+    let lines = tokio::task::spawn_blocking(move || diff_lines(&old, &new))
+        .await
+        .map_err(|e| format!("比较版本失败: {}", e))?;
+    let added = lines.iter().filter(|l| l.op == "insert").count();
+    let removed = lines.iter().filter(|l| l.op == "delete").count();
+
+    Ok(json!({
+        "file": file,
+        "from": from,
+        "to": to,
+        "added": added,
+        "removed": removed,
+        "lines": lines
+    }))
+}
+
+/// 恢复到指定版本，恢复前的内容会先记录为一个版本，返回恢复后记录的版本
+fn restore_version(roots: &HistoryRoots, file: &str, version_id: &str) -> Result<Option<MemoryVersion>, String> {
+    let _guard = HISTORY_LOCK.lock().unwrap();
+
+    let dir = roots.versions_dir(file);
+    if !load_versions(&dir).iter().any(|v| v.id == version_id) {
+        return Err(format!("版本 {} 不存在", version_id));
+    }
+    let content = read_version(&dir, version_id).map_err(|e| format!("{:#}", e))?;
+
+    record_external_change(roots, file).map_err(|e| format!("备份当前内容失败: {:#}", e))?;
+
+    let path = roots.file_path(file);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("创建目录失败: {}", e))?;
+    }
+    fs::write(&path, &content).map_err(|e| format!("恢复记忆文件失败: {}", e))?;
+
+    let version = snapshot(&dir, &content, VersionSource::User, Some(version_id.to_string()))
+        .map_err(|e| format!("记录历史版本失败: {:#}", e))?;
+    remember_state(file, &path);
+    Ok(version)
+}
+
+/// 恢复到指定版本
+#[tauri::command]
+pub async fn restore_memory_version(
+    file: String,
+    version_id: String,
+) -> Result<serde_json::Value, String> {
+    validate_file(&file)?;
+    let roots = HistoryRoots::current().map_err(|e| e.to_string())?;
+    let version = restore_version(&roots, &file, &version_id)?;
+
+    Ok(json!({
+        "success": true,
+        "message": format!("{} 已恢复到版本 {}", file, version_id),
+        "version": version
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ops(diff: &[DiffLine]) -> Vec<(&'static str, &str, Option<usize>, Option<usize>)> {
+        diff.iter()
+            .map(|d| (d.op, d.text.as_str(), d.old_line, d.new_line))
+            .collect()
+    }
+
+    #[test]
+    fn diffs_lines_with_line_numbers() {
+        let diff = diff_lines("a\nb\nc\nd\n", "a\nx\nc\nd\ne");
+        assert_eq!(ops(&diff), vec![
+            ("equal", "a", Some(1), Some(1)),
+            ("delete", "b", Some(2), None),
+            ("insert", "x", None, Some(2)),
+            ("equal", "c", Some(3), Some(3)),
+            ("equal", "d", Some(4), Some(4)),
+            ("insert", "e", None, Some(5)),
+        ]);
+
+        assert!(diff_lines("same\n", "same\n").iter().all(|d| d.op == "equal"));
+        assert_eq!(ops(&diff_lines("", "new")), vec![("insert", "new", None, Some(1))]);
+        assert_eq!(ops(&diff_lines("old", "")), vec![("delete", "old", Some(1), None)]);
+    }
+
+    #[test]
+    fn validates_tracked_files() {
+        assert!(validate_file("SOUL.md").is_ok());
+        assert!(validate_file("memory/MEMORY.md").is_ok());
+        assert!(validate_file("memory/2026-02-10.md").is_ok());
+        assert!(validate_file("memory/../config.md").is_err());
+        assert!(validate_file("memory/sub/a.md").is_err());
+        assert!(validate_file("memory/.hidden.md").is_err());
+        assert!(validate_file("memory/notes.txt").is_err());
+        assert!(validate_file("README.md").is_err());
+    }
+
+    fn roots(name: &str) -> HistoryRoots {
+        let base = std::env::temp_dir().join(format!("nanoboard-memory-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&base);
+        fs::create_dir_all(base.join("workspace").join("memory")).unwrap();
+        HistoryRoots {
+            workspace: base.join("workspace"),
+            history: base.join("memory_history"),
+        }
+    }
+
+    fn sources(roots: &HistoryRoots, file: &str) -> Vec<VersionSource> {
+        load_versions(&roots.versions_dir(file)).iter().map(|v| v.source).collect()
+    }
+
+    #[test]
+    fn records_saves_external_edits_and_restores() {
+        let roots = roots("flow");
+        let file = "memory/MEMORY.md";
+        let path = roots.file_path(file);
+        fs::write(&path, "original\n").unwrap();
+
+        // 第一次保存：已有内容记为 initial，而不是 agent
+        save_with_history(&roots, &path, file, "saved\n").unwrap();
+        assert_eq!(sources(&roots, file), [VersionSource::Initial, VersionSource::User]);
+
+        // 内容未变时不重复记录
+        save_with_history(&roots, &path, file, "saved\n").unwrap();
+        assert_eq!(load_versions(&roots.versions_dir(file)).len(), 2);
+
+        // nanoboard 之外的修改在下次保存前记为 agent
+        fs::write(&path, "consolidated by agent\n").unwrap();
+        save_with_history(&roots, &path, file, "edited again\n").unwrap();
+        assert_eq!(
+            sources(&roots, file),
+            [VersionSource::Initial, VersionSource::User, VersionSource::Agent, VersionSource::User]
+        );
+
+        // 恢复：当前内容已是最新版本，不重复备份；恢复结果记录来源版本
+        let versions = load_versions(&roots.versions_dir(file));
+        let agent_version = versions[2].id.clone();
+        let restored = restore_version(&roots, file, &agent_version).unwrap().unwrap();
+        assert_eq!(restored.restored_from.as_deref(), Some(agent_version.as_str()));
+        assert_eq!(restored.source, VersionSource::User);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "consolidated by agent\n");
+        assert_eq!(load_versions(&roots.versions_dir(file)).len(), 5);
+
+        assert!(restore_version(&roots, file, "missing").is_err());
+
+        let _ = fs::remove_dir_all(roots.workspace.parent().unwrap());
+    }
+
+    #[test]
+    fn keeps_only_the_latest_versions() {
+        let roots = roots("prune");
+        let dir = roots.versions_dir("SOUL.md");
+        for i in 0..MAX_VERSIONS + 3 {
+            snapshot(&dir, &format!("v{}", i), VersionSource::User, None).unwrap();
+        }
+
+        let versions = load_versions(&dir);
+        assert_eq!(versions.len(), MAX_VERSIONS);
+        assert_eq!(read_version(&dir, &versions[0].id).unwrap(), "v3");
+        // 被清理的版本文件一并删除
+        let files = fs::read_dir(&dir).unwrap().count();
+        assert_eq!(files, MAX_VERSIONS + 1);
+
+        let _ = fs::remove_dir_all(roots.workspace.parent().unwrap());
+    }
+}
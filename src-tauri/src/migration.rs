@@ -0,0 +1,680 @@
+// 配置迁移模块
+// nanobot 在不同版本之间会重命名或移动配置键，这里按版本维护有序的迁移步骤，
+// 支持 dry-run 预览差异，应用前自动创建历史备份。
+
+use anyhow::{Context, Result};
+use dirs::home_dir;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value as JsonValue};
+use std::cmp::Ordering;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use crate::config::{get_config_path_internal, write_config_internal};
+
+/// 单个迁移步骤
+struct MigrationStep {
+    /// 引入该变化的 nanobot 版本
+    version: &'static str,
+    id: &'static str,
+    description: &'static str,
+    /// 对配置执行迁移，返回变更说明；未发生变化时返回空列表
+    /// 每个步骤都必须是幂等的
+    apply: fn(&mut JsonValue) -> Vec<String>,
+}
+
+/// 迁移步骤列表，按版本升序排列
+const MIGRATIONS: &[MigrationStep] = &[
+    MigrationStep {
+        version: "0.1.3.post4",
+        id: "snake_case_keys",
+        description: "配置键名由 snake_case 改为 camelCase",
+        apply: migrate_snake_case_keys,
+    },
+    MigrationStep {
+        version: "0.1.3.post7",
+        id: "restrict_to_workspace",
+        description: "tools.exec.restrictToWorkspace 移动到 tools.restrictToWorkspace",
+        apply: migrate_restrict_to_workspace,
+    },
+    MigrationStep {
+        version: "0.1.4",
+        id: "mcp_servers_under_tools",
+        description: "顶层 mcpServers 移动到 tools.mcpServers",
+        apply: migrate_mcp_servers,
+    },
+];
+
+/// is_migration_pending 的缓存：(当前版本, 记录版本, 配置修改时间, 配置大小) -> 是否需要迁移
+type PendingCacheKey = (String, Option<String>, SystemTime, u64);
+static PENDING_CACHE: Mutex<Option<(PendingCacheKey, bool)>> = Mutex::new(None);
+
+/// 迁移状态记录文件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct MigrationState {
+    #[serde(rename = "configVersion")]
+    config_version: String,
+    #[serde(rename = "migratedAt")]
+    migrated_at: i64,
+}
+
+/// 配置差异条目
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigDiffEntry {
+    pub path: String,
+    /// added / removed / changed
+    pub kind: String,
+    pub before: Option<JsonValue>,
+    pub after: Option<JsonValue>,
+}
+
+/// 迁移步骤的执行结果
+#[derive(Debug, Clone, Serialize)]
+pub struct MigrationStepResult {
+    pub id: String,
+    pub version: String,
+    pub description: String,
+    pub changes: Vec<String>,
+}
+
+/// 获取迁移状态文件路径
+fn get_migration_state_path() -> Result<PathBuf> {
+    let home = home_dir().context("无法找到用户主目录")?;
+    Ok(home.join(".nanobot").join("config_version.json"))
+}
+
+/// 读取配置文件对应的 nanobot 版本
+pub fn get_recorded_config_version() -> Option<String> {
+    let path = get_migration_state_path().ok()?;
+    let content = fs::read_to_string(path).ok()?;
+    let state: MigrationState = serde_json::from_str(&content).ok()?;
+    Some(state.config_version)
+}
+
+/// 记录配置文件对应的 nanobot 版本
+fn record_config_version(version: &str) -> Result<(), String> {
+    let path = get_migration_state_path().map_err(|e| e.to_string())?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("创建配置目录失败: {}", e))?;
+    }
+
+    let state = MigrationState {
+        config_version: version.to_string(),
+        migrated_at: chrono::Utc::now().timestamp(),
+    };
+    let content = serde_json::to_string_pretty(&state)
+        .map_err(|e| format!("序列化迁移状态失败: {}", e))?;
+    fs::write(&path, content).map_err(|e| format!("写入迁移状态失败: {}", e))?;
+    Ok(())
+}
+
+/// 解析后的版本号，例如 "0.1.4.post2" -> release [0, 1, 4], post 2
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct NanobotVersion {
+    release: Vec<u64>,
+    post: u64,
+}
+
+impl NanobotVersion {
+    /// 从 `nanobot -v` 的输出中解析版本号
+    /// 兼容 "nanobot 0.1.4"、"🐈 nanobot v0.1.4.post2"、"0.1.3.post7" 等格式
+    fn parse(input: &str) -> Option<Self> {
+        let token = input
+            .split_whitespace()
+            .map(|t| t.trim_start_matches('v'))
+            .find(|t| t.chars().next().is_some_and(|c| c.is_ascii_digit()))?;
+
+        let mut release = Vec::new();
+        let mut post = 0;
+        for part in token.split('.') {
+            if let Some(n) = part.strip_prefix("post") {
+                post = n.parse().unwrap_or(0);
+                break;
+            }
+            // 忽略预发布后缀（如 0.1.4rc1 中的 rc1）
+            let digits: String = part.chars().take_while(|c| c.is_ascii_digit()).collect();
+            match digits.parse() {
+                Ok(n) => release.push(n),
+                Err(_) => break,
+            }
+        }
+
+        if release.is_empty() {
+            return None;
+        }
+
+        // 去掉末尾的 0，使 0.1.4 与 0.1.4.0 相等
+        while release.len() > 1 && release.last() == Some(&0) {
+            release.pop();
+        }
+
+        Some(Self { release, post })
+    }
+}
+
+impl Ord for NanobotVersion {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.release.cmp(&other.release).then(self.post.cmp(&other.post))
+    }
+}
+
+impl PartialOrd for NanobotVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// 选出需要执行的迁移步骤：版本介于配置记录版本（不含）与当前版本（含）之间
+/// 没有记录版本时，执行所有不晚于当前版本的步骤
+fn pending_steps(current: &str, recorded: Option<&str>) -> Vec<&'static MigrationStep> {
+    let Some(current) = NanobotVersion::parse(current) else {
+        return Vec::new();
+    };
+    let recorded = recorded.and_then(NanobotVersion::parse);
+
+    MIGRATIONS
+        .iter()
+        .filter(|step| {
+            let Some(step_version) = NanobotVersion::parse(step.version) else {
+                return false;
+            };
+            step_version <= current && recorded.as_ref().is_none_or(|r| step_version > *r)
+        })
+        .collect()
+}
+
+/// 对配置执行迁移步骤，返回迁移后的配置以及每个步骤的结果
+fn run_migrations(config: &JsonValue, steps: &[&MigrationStep]) -> (JsonValue, Vec<MigrationStepResult>) {
+    let mut migrated = config.clone();
+    let results = steps
+        .iter()
+        .map(|step| MigrationStepResult {
+            id: step.id.to_string(),
+            version: step.version.to_string(),
+            description: step.description.to_string(),
+            changes: (step.apply)(&mut migrated),
+        })
+        .collect();
+    (migrated, results)
+}
+
+/// 计算两个配置之间的差异
+pub fn diff_config(before: &JsonValue, after: &JsonValue) -> Vec<ConfigDiffEntry> {
+    let mut entries = Vec::new();
+    diff_value("", before, after, &mut entries);
+    entries
+}
+
+fn diff_value(path: &str, before: &JsonValue, after: &JsonValue, entries: &mut Vec<ConfigDiffEntry>) {
+    match (before, after) {
+        (JsonValue::Object(a), JsonValue::Object(b)) => {
+            for (key, a_val) in a {
+                let child = if path.is_empty() { key.clone() } else { format!("{}.{}", path, key) };
+                match b.get(key) {
+                    Some(b_val) => diff_value(&child, a_val, b_val, entries),
+                    None => entries.push(ConfigDiffEntry {
+                        path: child,
+                        kind: "removed".to_string(),
+                        before: Some(a_val.clone()),
+                        after: None,
+                    }),
+                }
+            }
+            for (key, b_val) in b {
+                if !a.contains_key(key) {
+                    let child = if path.is_empty() { key.clone() } else { format!("{}.{}", path, key) };
+                    entries.push(ConfigDiffEntry {
+                        path: child,
+                        kind: "added".to_string(),
+                        before: None,
+                        after: Some(b_val.clone()),
+                    });
+                }
+            }
+        }
+        _ if before != after => entries.push(ConfigDiffEntry {
+            path: path.to_string(),
+            kind: "changed".to_string(),
+            before: Some(before.clone()),
+            after: Some(after.clone()),
+        }),
+        _ => {}
+    }
+}
+
+/// 判断当前 nanobot 版本是否需要迁移配置（只读，不写入任何文件）
+/// 结果按版本与配置文件的修改时间、大小缓存
+pub fn is_migration_pending(current_version: &str, recorded_version: Option<&str>) -> bool {
+    let steps = pending_steps(current_version, recorded_version);
+    if steps.is_empty() {
+        return false;
+    }
+
+    let Ok(config_path) = get_config_path_internal() else {
+        return false;
+    };
+    let Some((modified, size)) = fs::metadata(&config_path)
+        .ok()
+        .and_then(|m| Some((m.modified().ok()?, m.len())))
+    else {
+        return false;
+    };
+    let key = (
+        current_version.to_string(),
+        recorded_version.map(|v| v.to_string()),
+        modified,
+        size,
+    );
+    if let Some((cached_key, pending)) = PENDING_CACHE.lock().unwrap().as_ref() {
+        if *cached_key == key {
+            return *pending;
+        }
+    }
+
+    let pending = match read_config_file() {
+        Ok(Some(config)) => {
+            let (migrated, _) = run_migrations(&config, &steps);
+            migrated != config
+        }
+        _ => false,
+    };
+
+    *PENDING_CACHE.lock().unwrap() = Some((key, pending));
+    pending
+}
+
+/// 启动时调用：没有记录版本且当前配置无需迁移时记录当前 nanobot 版本，
+/// 之后只检查更新版本引入的步骤
+pub async fn record_initial_config_version() {
+    if get_recorded_config_version().is_some() {
+        return;
+    }
+    let result = tokio::task::spawn_blocking(|| -> Result<(), String> {
+        let Some(version) = crate::process::get_nanobot_version_internal()? else {
+            return Ok(());
+        };
+        let Some(config) = read_config_file()? else {
+            return Ok(());
+        };
+        let (migrated, _) = run_migrations(&config, &pending_steps(&version, None));
+        if migrated == config {
+            record_config_version(&version)?;
+        }
+        Ok(())
+    })
+    .await
+    .unwrap_or_else(|e| Err(e.to_string()));
+    if let Err(e) = result {
+        log::warn!("记录配置版本失败: {}", e);
+    }
+}
+
+/// 读取原始配置文件（不做环境变量展开，迁移结果需要写回磁盘）
+fn read_config_file() -> Result<Option<JsonValue>, String> {
+    let config_path = get_config_path_internal().map_err(|e| e.to_string())?;
+    if !config_path.exists() {
+        return Ok(None);
+    }
+
+    let content = fs::read_to_string(&config_path)
+        .map_err(|e| format!("读取配置文件失败: {}", e))?;
+    let config = serde_json::from_str(&content)
+        .map_err(|e| format!("解析配置文件失败: {}", e))?;
+    Ok(Some(config))
+}
+
+/// 检查配置是否需要迁移（dry-run，不修改任何文件）
+#[tauri::command]
+pub async fn check_config_migration() -> Result<JsonValue, String> {
+    let Some(current_version) = crate::process::get_nanobot_version_internal()? else {
+        return Ok(json!({
+            "needed": false,
+            "message": "未找到 nanobot"
+        }));
+    };
+    let recorded_version = get_recorded_config_version();
+
+    let Some(config) = read_config_file()? else {
+        return Ok(json!({
+            "needed": false,
+            "current_version": current_version,
+            "config_version": recorded_version,
+            "message": "配置文件不存在"
+        }));
+    };
+
+    let steps = pending_steps(&current_version, recorded_version.as_deref());
+    let (migrated, results) = run_migrations(&config, &steps);
+    let diff = diff_config(&config, &migrated);
+
+    Ok(json!({
+        "needed": !diff.is_empty(),
+        "current_version": current_version,
+        "config_version": recorded_version,
+        "steps": results,
+        "diff": diff
+    }))
+}
+
+/// 应用配置迁移：先备份当前配置，再写入迁移后的配置并记录版本
+#[tauri::command]
+pub async fn apply_config_migration() -> Result<JsonValue, String> {
+    let current_version = crate::process::get_nanobot_version_internal()?
+        .ok_or_else(|| "未找到 nanobot，无法确定目标版本".to_string())?;
+    let recorded_version = get_recorded_config_version();
+
+    let Some(config) = read_config_file()? else {
+        return Ok(json!({
+            "success": false,
+            "message": "配置文件不存在"
+        }));
+    };
+
+    let steps = pending_steps(&current_version, recorded_version.as_deref());
+    let (migrated, results) = run_migrations(&config, &steps);
+    let diff = diff_config(&config, &migrated);
+
+    // 备份失败时不写入，保护用户配置
+    let backup = if diff.is_empty() {
+        None
+    } else {
+        write_config_internal(&migrated)?
+    };
+
+    record_config_version(&current_version)?;
+
+    Ok(json!({
+        "success": true,
+        "message": if diff.is_empty() { "配置无需迁移" } else { "配置迁移完成" },
+        "current_version": current_version,
+        "steps": results,
+        "diff": diff,
+        "backup": backup
+    }))
+}
+
+// ---------------------------------------------------------------------------
+// 迁移步骤实现
+// ---------------------------------------------------------------------------
+
+/// 键名属于用户自定义映射的对象，这些对象的子键不做转换
+const USER_KEYED_MAPS: &[&str] = &["mcpServers", "mcp_servers", "env", "headers", "extraHeaders", "extra_headers"];
+
+fn snake_to_camel(key: &str) -> String {
+    let mut out = String::with_capacity(key.len());
+    let mut upper_next = false;
+    for c in key.chars() {
+        if c == '_' {
+            upper_next = true;
+        } else if upper_next {
+            out.push(c.to_ascii_uppercase());
+            upper_next = false;
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+fn camelize_keys(value: &mut JsonValue, path: &str, changes: &mut Vec<String>) {
+    let JsonValue::Object(map) = value else {
+        return;
+    };
+
+    let keys: Vec<String> = map.keys().cloned().collect();
+    for key in keys {
+        let camel = snake_to_camel(&key);
+        let target = if camel != key && !key.starts_with('_') && !map.contains_key(&camel) {
+            if let Some(v) = map.remove(&key) {
+                map.insert(camel.clone(), v);
+                changes.push(format!("{}{} -> {}{}", path, key, path, camel));
+            }
+            camel
+        } else {
+            key
+        };
+
+        // 用户自定义映射的子键保持原样，只处理其值
+        let is_user_map = USER_KEYED_MAPS.contains(&target.as_str());
+        if let Some(child) = map.get_mut(&target) {
+            let child_path = format!("{}{}.", path, target);
+            if is_user_map {
+                if let JsonValue::Object(entries) = child {
+                    if target == "mcpServers" {
+                        for (name, server) in entries.iter_mut() {
+                            camelize_keys(server, &format!("{}{}.", child_path, name), changes);
+                        }
+                    }
+                }
+            } else {
+                camelize_keys(child, &child_path, changes);
+            }
+        }
+    }
+}
+
+fn migrate_snake_case_keys(config: &mut JsonValue) -> Vec<String> {
+    let mut changes = Vec::new();
+    camelize_keys(config, "", &mut changes);
+    changes
+}
+
+fn migrate_restrict_to_workspace(config: &mut JsonValue) -> Vec<String> {
+    let Some(tools) = config.get_mut("tools").and_then(|t| t.as_object_mut()) else {
+        return Vec::new();
+    };
+
+    let value = tools
+        .get_mut("exec")
+        .and_then(|e| e.as_object_mut())
+        .and_then(|exec| exec.remove("restrictToWorkspace"));
+
+    match value {
+        Some(v) => {
+            if tools.contains_key("restrictToWorkspace") {
+                vec!["删除 tools.exec.restrictToWorkspace（已存在 tools.restrictToWorkspace）".to_string()]
+            } else {
+                tools.insert("restrictToWorkspace".to_string(), v);
+                vec!["tools.exec.restrictToWorkspace -> tools.restrictToWorkspace".to_string()]
+            }
+        }
+        None => Vec::new(),
+    }
+}
+
+fn migrate_mcp_servers(config: &mut JsonValue) -> Vec<String> {
+    let Some(root) = config.as_object_mut() else {
+        return Vec::new();
+    };
+    if !matches!(root.get("mcpServers"), Some(JsonValue::Object(_))) {
+        return Vec::new();
+    }
+    // 先确认目标位置可写，再移走旧键，避免 tools 不是对象时丢失配置
+    let target_ok = match root.get("tools") {
+        None => true,
+        Some(JsonValue::Object(tools)) => matches!(tools.get("mcpServers"), None | Some(JsonValue::Object(_))),
+        Some(_) => false,
+    };
+    if !target_ok {
+        return vec!["mcpServers 未迁移：tools 或 tools.mcpServers 不是对象，保留原配置".to_string()];
+    }
+    let Some(JsonValue::Object(servers)) = root.remove("mcpServers") else {
+        return Vec::new();
+    };
+
+    let Some(target) = root
+        .entry("tools")
+        .or_insert_with(|| JsonValue::Object(serde_json::Map::new()))
+        .as_object_mut()
+        .and_then(|tools| {
+            tools
+                .entry("mcpServers")
+                .or_insert_with(|| JsonValue::Object(serde_json::Map::new()))
+                .as_object_mut()
+        })
+    else {
+        return Vec::new();
+    };
+
+    let mut changes = Vec::new();
+    for (name, server) in servers {
+        if target.contains_key(&name) {
+            changes.push(format!("mcpServers.{} 已存在于 tools.mcpServers，保留后者", name));
+        } else {
+            changes.push(format!("mcpServers.{} -> tools.mcpServers.{}", name, name));
+            target.insert(name, server);
+        }
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(input: &str) -> NanobotVersion {
+        NanobotVersion::parse(input).unwrap()
+    }
+
+    /// 执行步骤并确认再次执行不再产生变化
+    fn apply_twice(apply: fn(&mut JsonValue) -> Vec<String>, mut config: JsonValue) -> (JsonValue, Vec<String>) {
+        let changes = apply(&mut config);
+        let once = config.clone();
+        assert!(apply(&mut config).is_empty(), "步骤不是幂等的");
+        assert_eq!(config, once);
+        (config, changes)
+    }
+
+    #[test]
+    fn parses_versions() {
+        assert_eq!(version("nanobot 0.1.4").release, vec![0, 1, 4]);
+        assert_eq!(version("🐈 nanobot v0.1.4.post2").post, 2);
+        assert_eq!(version("0.1.4rc1").release, vec![0, 1, 4]);
+        assert_eq!(version("0.1.4.0"), version("0.1.4"));
+        assert!(NanobotVersion::parse("nanobot").is_none());
+    }
+
+    #[test]
+    fn orders_post_releases() {
+        assert!(version("0.1.3") < version("0.1.3.post4"));
+        assert!(version("0.1.3.post4") < version("0.1.3.post7"));
+        assert!(version("0.1.3.post10") > version("0.1.3.post7"));
+        assert!(version("0.1.3.post7") < version("0.1.4"));
+        assert!(version("0.1.10") > version("0.1.4.post9"));
+    }
+
+    #[test]
+    fn migration_steps_are_sorted() {
+        let versions: Vec<NanobotVersion> = MIGRATIONS.iter().map(|s| version(s.version)).collect();
+        assert!(versions.windows(2).all(|w| w[0] <= w[1]));
+    }
+
+    #[test]
+    fn selects_pending_steps() {
+        let ids = |current: &str, recorded: Option<&str>| -> Vec<&str> {
+            pending_steps(current, recorded).iter().map(|s| s.id).collect()
+        };
+
+        assert_eq!(
+            ids("0.1.4", None),
+            vec!["snake_case_keys", "restrict_to_workspace", "mcp_servers_under_tools"]
+        );
+        assert_eq!(ids("0.1.3.post5", None), vec!["snake_case_keys"]);
+        assert_eq!(
+            ids("0.1.4.post1", Some("0.1.3.post4")),
+            vec!["restrict_to_workspace", "mcp_servers_under_tools"]
+        );
+        assert!(ids("0.1.4", Some("0.1.4")).is_empty());
+        assert!(ids("0.1.2", None).is_empty());
+        assert!(ids("unknown", None).is_empty());
+    }
+
+    #[test]
+    fn camelizes_keys_except_user_maps() {
+        let (config, changes) = apply_twice(
+            migrate_snake_case_keys,
+            json!({
+                "agents": { "defaults": { "max_tokens": 100 } },
+                "providers": { "openai": { "api_key": "k", "extra_headers": { "X_Org": "1" } } },
+                "mcpServers": { "my_server": { "tool_timeout": 5, "env": { "API_KEY": "x" } } }
+            }),
+        );
+
+        assert_eq!(config["agents"]["defaults"]["maxTokens"], 100);
+        assert_eq!(config["providers"]["openai"]["apiKey"], "k");
+        assert_eq!(config["providers"]["openai"]["extraHeaders"]["X_Org"], "1");
+        assert_eq!(config["mcpServers"]["my_server"]["toolTimeout"], 5);
+        assert_eq!(config["mcpServers"]["my_server"]["env"]["API_KEY"], "x");
+        assert_eq!(changes.len(), 4);
+    }
+
+    #[test]
+    fn camelize_keeps_existing_camel_key() {
+        let (config, changes) = apply_twice(
+            migrate_snake_case_keys,
+            json!({ "agents": { "maxTokens": 1, "max_tokens": 2 } }),
+        );
+        assert_eq!(config["agents"]["maxTokens"], 1);
+        assert!(changes.is_empty());
+    }
+
+    #[test]
+    fn moves_restrict_to_workspace() {
+        let (config, changes) = apply_twice(
+            migrate_restrict_to_workspace,
+            json!({ "tools": { "exec": { "timeout": 60, "restrictToWorkspace": true } } }),
+        );
+        assert_eq!(config, json!({ "tools": { "exec": { "timeout": 60 }, "restrictToWorkspace": true } }));
+        assert_eq!(changes.len(), 1);
+
+        let (config, _) = apply_twice(
+            migrate_restrict_to_workspace,
+            json!({ "tools": { "exec": { "restrictToWorkspace": true }, "restrictToWorkspace": false } }),
+        );
+        assert_eq!(config["tools"]["restrictToWorkspace"], false);
+        assert!(config["tools"]["exec"].get("restrictToWorkspace").is_none());
+    }
+
+    #[test]
+    fn moves_mcp_servers_under_tools() {
+        let (config, changes) = apply_twice(
+            migrate_mcp_servers,
+            json!({
+                "mcpServers": { "a": { "command": "x" }, "b": { "url": "u" } },
+                "tools": { "mcpServers": { "b": { "url": "kept" } } }
+            }),
+        );
+        assert!(config.get("mcpServers").is_none());
+        assert_eq!(config["tools"]["mcpServers"]["a"]["command"], "x");
+        assert_eq!(config["tools"]["mcpServers"]["b"]["url"], "kept");
+        assert_eq!(changes.len(), 2);
+
+        let (config, changes) = apply_twice(migrate_mcp_servers, json!({ "agents": {} }));
+        assert_eq!(config, json!({ "agents": {} }));
+        assert!(changes.is_empty());
+
+        for tools in [json!(null), json!({ "mcpServers": [] })] {
+            let input = json!({ "mcpServers": { "a": { "command": "x" } }, "tools": tools });
+            let mut config = input.clone();
+            let changes = migrate_mcp_servers(&mut config);
+            assert_eq!(config, input);
+            assert_eq!(changes.len(), 1);
+        }
+    }
+
+    #[test]
+    fn diffs_configs() {
+        let before = json!({ "a": 1, "b": { "c": true, "d": [1] }, "gone": "x" });
+        let after = json!({ "a": 2, "b": { "c": true, "d": [1, 2] }, "new": null });
+        let diff = diff_config(&before, &after);
+        let summary: Vec<(&str, &str)> = diff.iter().map(|e| (e.path.as_str(), e.kind.as_str())).collect();
+
+        assert_eq!(
+            summary,
+            vec![("a", "changed"), ("b.d", "changed"), ("gone", "removed"), ("new", "added")]
+        );
+        assert!(diff_config(&before, &before).is_empty());
+    }
+}
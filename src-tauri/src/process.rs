@@ -453,7 +453,7 @@ fn check_nanobot_running_impl() -> bool {
 }
 
 /// 检查nanobot进程是否正在运行（带缓存）
-fn check_nanobot_running() -> bool {
+pub(crate) fn check_nanobot_running() -> bool {
     get_cached_nanobot_status()
 }
 
@@ -533,6 +533,17 @@ pub async fn start_nanobot(port: Option<u16>, state: State<'_, AppState>) -> Res
         }
     }
 
+    // 展开配置中的环境变量引用，含有引用时通过本次启动专用的运行时配置传递给 gateway（不写回 config.json）
+    let launch = match crate::interpolation::launch_config(&nanobot_cmd, &module_args, "gateway").await {
+        Ok(launch) => launch,
+        Err(e) => {
+            return Ok(json!({
+                "status": "failed",
+                "message": format!("无法展开配置中的环境变量: {}", e)
+            }));
+        }
+    };
+
     // 打开日志文件用于追加（如果不存在则创建）
     let log_file = OpenOptions::new()
         .create(true)
@@ -551,14 +562,18 @@ pub async fn start_nanobot(port: Option<u16>, state: State<'_, AppState>) -> Res
         args.push("gateway".to_string());
         args.push("--port".to_string());
         args.push(port.to_string());
+        args.extend(launch.args);
         args
     } else {
-        vec!["gateway".to_string(), "--port".to_string(), port.to_string()]
+        let mut args = vec!["gateway".to_string(), "--port".to_string(), port.to_string()];
+        args.extend(launch.args);
+        args
     };
 
     // 启动 nanobot gateway，直接将 stdout 和 stderr 都重定向到日志文件
     let mut child = match apply_hidden_window(Command::new(&nanobot_cmd))
         .args(&start_args)
+        .envs(launch.envs)
         .env("PYTHONUTF8", "1")
         .env("PYTHONIOENCODING", "utf-8")
         .stdout(Stdio::from(log_file.try_clone().map_err(|e| format!("复制文件句柄失败: {}", e))?))
@@ -576,6 +591,10 @@ pub async fn start_nanobot(port: Option<u16>, state: State<'_, AppState>) -> Res
 
     // 获取进程ID
     let id = child.id();
+    // 运行时配置保留到 gateway 退出后再清理
+    if let Some(resolved) = launch.resolved {
+        resolved.release_on_exit(id);
+    }
     log::info!("Nanobot进程已启动 (PID: {})，等待初始化...", id);
 
     // 等待进程初始化（从 2 秒减少到 1.5 秒，因为不需要重启了）
@@ -963,23 +982,36 @@ pub async fn check_nanobot_config() -> Result<serde_json::Value, String> {
         .context("解析配置文件失败")
         .map_err(|e| e.to_string())?;
 
+    // 展开环境变量引用
+    let interpolated = crate::interpolation::interpolate_config(&config);
+    if !interpolated.unresolved.is_empty() {
+        return Ok(json!({
+            "valid": false,
+            "issue": "env_unresolved",
+            "message": crate::interpolation::describe_unresolved(&interpolated.unresolved).join("; ")
+        }));
+    }
+    let config = interpolated.config;
+
+    let config = match crate::config_model::NanobotConfig::from_json(&config) {
+        Ok(typed) => typed,
+        Err(e) => {
+            return Ok(json!({
+                "valid": false,
+                "issue": "config_invalid",
+                "message": e
+            }));
+        }
+    };
+
     // 检查是否有至少一个 API key
-    if let Some(providers) = config.get("providers") {
-        if let Some(obj) = providers.as_object() {
-            let has_api_key = obj.values().any(|provider| {
-                provider.get("apiKey")
-                    .and_then(|k| k.as_str())
-                    .map(|k| !k.is_empty())
-                    .unwrap_or(false)
-            });
-
-            if !has_api_key {
-                return Ok(json!({
-                    "valid": false,
-                    "issue": "api_key_missing",
-                    "message": "未配置 API key，请在配置编辑器中添加至少一个 provider 的 API key"
-                }));
-            }
+    if let Some(providers) = &config.providers {
+        if !providers.values().any(|p| p.has_api_key()) {
+            return Ok(json!({
+                "valid": false,
+                "issue": "api_key_missing",
+                "message": "未配置 API key，请在配置编辑器中添加至少一个 provider 的 API key"
+            }));
         }
     }
 
@@ -989,19 +1021,12 @@ pub async fn check_nanobot_config() -> Result<serde_json::Value, String> {
     }))
 }
 
-/// 获取nanobot版本信息
-#[tauri::command]
-pub async fn get_nanobot_version() -> Result<serde_json::Value, String> {
+/// 获取 nanobot 版本号（内部函数），未安装或执行失败时返回 None
+pub(crate) fn get_nanobot_version_internal() -> Result<Option<String>, String> {
     // 统一通过 nanobot -v 获取版本
     let nanobot_cmd = match find_command("nanobot") {
         Some(cmd) => cmd,
-        None => {
-            return Ok(json!({
-                "installed": false,
-                "version": null,
-                "message": "未找到 nanobot"
-            }))
-        }
+        None => return Ok(None),
     };
 
     let output = apply_hidden_window(Command::new(&nanobot_cmd))
@@ -1013,18 +1038,34 @@ pub async fn get_nanobot_version() -> Result<serde_json::Value, String> {
         .map_err(|e| e.to_string())?;
 
     if output.status.success() {
-        let version = String::from_utf8_lossy(&output.stdout).trim().to_string();
-        Ok(json!({
-            "installed": true,
-            "version": version,
-            "message": format!("nanobot {}", version)
-        }))
+        Ok(Some(String::from_utf8_lossy(&output.stdout).trim().to_string()))
     } else {
-        Ok(json!({
+        Ok(None)
+    }
+}
+
+/// 获取nanobot版本信息
+#[tauri::command]
+pub async fn get_nanobot_version() -> Result<serde_json::Value, String> {
+    match get_nanobot_version_internal()? {
+        Some(version) => {
+            // 比较配置文件记录的版本，判断是否需要迁移配置
+            let config_version = crate::migration::get_recorded_config_version();
+            let migration_pending = crate::migration::is_migration_pending(&version, config_version.as_deref());
+
+            Ok(json!({
+                "installed": true,
+                "version": version,
+                "message": format!("nanobot {}", version),
+                "config_version": config_version,
+                "migration_pending": migration_pending
+            }))
+        }
+        None => Ok(json!({
             "installed": false,
             "version": null,
             "message": "未找到 nanobot"
-        }))
+        })),
     }
 }
 
@@ -1752,7 +1793,14 @@ fn check_config_file() -> DiagnosticCheck {
         }
     };
 
-    match serde_json::from_str::<serde_json::Value>(&config_content) {
+    let parsed = serde_json::from_str::<serde_json::Value>(&config_content)
+        .map_err(|e| e.to_string())
+        .and_then(|value| {
+            let resolved = crate::interpolation::interpolate_config(&value).config;
+            crate::config_model::NanobotConfig::from_json(&resolved)
+        });
+
+    match parsed {
         Ok(_) => DiagnosticCheck {
             key: "configFile".to_string(),
             name: "配置文件".to_string(),
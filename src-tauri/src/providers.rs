@@ -0,0 +1,826 @@
+// LLM 提供商连通性检测与模型目录模块
+// 对每个已配置的 provider 发送一次最小的鉴权请求（GET {apiBase}/models），
+// 根据响应对结果分类并记录延迟；同一接口也用于获取并缓存可用模型列表。
+
+use anyhow::{Context, Result};
+use dirs::home_dir;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value as JsonValue};
+use std::collections::BTreeMap;
+use std::error::Error as StdError;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use crate::config_model::{NanobotConfig, ProviderConfig};
+
+/// 单次检测的超时时间
+const PROVIDER_TEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// 各提供商默认的 OpenAI 兼容 API 地址
+const DEFAULT_API_BASES: &[(&str, &str)] = &[
+    ("openrouter", "https://openrouter.ai/api/v1"),
+    ("anthropic", "https://api.anthropic.com/v1"),
+    ("openai", "https://api.openai.com/v1"),
+    ("deepseek", "https://api.deepseek.com/v1"),
+    ("groq", "https://api.groq.com/openai/v1"),
+    ("gemini", "https://generativelanguage.googleapis.com/v1beta/openai"),
+    ("minimax", "https://api.minimax.chat/v1"),
+    ("aihubmix", "https://aihubmix.com/v1"),
+    ("dashscope", "https://dashscope.aliyuncs.com/compatible-mode/v1"),
+    ("moonshot", "https://api.moonshot.cn/v1"),
+    ("zhipu", "https://open.bigmodel.cn/api/paas/v4"),
+    ("vllm", "http://localhost:8000/v1"),
+    ("siliconflow", "https://api.siliconflow.cn/v1"),
+    ("volcengine", "https://ark.cn-beijing.volces.com/api/v3"),
+];
+
+/// 使用 OAuth 登录的提供商，无法用 API key 检测
+const OAUTH_PROVIDERS: &[&str] = &["github_copilot", "openai_codex"];
+
+/// 提供商检测结果
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderTestResult {
+    pub provider: String,
+    /// ok / bad_key / quota / unreachable / tls_error / http_error / skipped
+    pub status: String,
+    pub message: String,
+    pub api_base: Option<String>,
+    pub http_status: Option<u16>,
+    pub latency_ms: Option<u64>,
+}
+
+impl ProviderTestResult {
+    fn skipped(provider: &str, api_base: Option<String>, message: &str) -> Self {
+        Self {
+            provider: provider.to_string(),
+            status: "skipped".to_string(),
+            message: message.to_string(),
+            api_base,
+            http_status: None,
+            latency_ms: None,
+        }
+    }
+}
+
+fn default_api_base(provider: &str) -> Option<&'static str> {
+    DEFAULT_API_BASES
+        .iter()
+        .find(|(name, _)| *name == provider)
+        .map(|(_, base)| *base)
+}
+
+/// 构建 HTTP 客户端
+/// 未在 provider 中配置 proxy 时，reqwest 会自动使用 HTTP(S)_PROXY 等环境变量
+fn build_client(provider: &ProviderConfig) -> Result<reqwest::Client, String> {
+    let mut builder = reqwest::Client::builder()
+        .timeout(PROVIDER_TEST_TIMEOUT)
+        .user_agent("nanoboard/1.0");
+
+    if let Some(proxy) = provider
+        .extra
+        .get("proxy")
+        .and_then(|p| p.as_str())
+        .filter(|p| !p.is_empty())
+    {
+        let proxy = reqwest::Proxy::all(proxy).map_err(|e| format!("代理地址无效: {}", e))?;
+        builder = builder.proxy(proxy);
+    }
+
+    builder.build().map_err(|e| format!("创建 HTTP 客户端失败: {}", e))
+}
+
+/// 判断请求错误是否由 TLS 握手或证书问题引起
+fn is_tls_error(error: &reqwest::Error) -> bool {
+    let mut source: Option<&dyn StdError> = Some(error);
+    while let Some(err) = source {
+        let text = err.to_string().to_lowercase();
+        if ["certificate", "tls", "ssl", "handshake"].iter().any(|k| text.contains(k)) {
+            return true;
+        }
+        source = err.source();
+    }
+    false
+}
+
+/// 根据 HTTP 状态码分类
+fn classify_status(status: u16) -> (&'static str, &'static str) {
+    match status {
+        200..=299 => ("ok", "连接正常，API key 有效"),
+        401 | 403 => ("bad_key", "API key 无效或无权限"),
+        402 | 429 => ("quota", "额度不足或请求频率受限"),
+        _ => ("http_error", "API 返回错误状态"),
+    }
+}
+
+/// 解析提供商的 API 地址：优先使用自定义 apiBase，否则使用默认地址
+fn resolve_api_base(name: &str, provider: &ProviderConfig) -> Option<String> {
+    provider
+        .api_base
+        .as_deref()
+        .filter(|b| !b.is_empty())
+        .or_else(|| default_api_base(name))
+        .map(|b| b.trim_end_matches('/').to_string())
+}
+
+/// 模型列表接口不校验 API key 的提供商，连通性检测改用校验 key 的接口
+const KEY_CHECK_ENDPOINTS: &[(&str, &str)] = &[("openrouter", "key")];
+
+/// 连通性检测请求的接口：默认为 models
+fn check_endpoint(name: &str) -> &'static str {
+    KEY_CHECK_ENDPOINTS
+        .iter()
+        .find(|(provider, _)| *provider == name)
+        .map(|(_, endpoint)| *endpoint)
+        .unwrap_or("models")
+}
+
+/// 构建带鉴权信息的 GET {apiBase}/{endpoint} 请求
+/// 无法检测时返回跳过原因
+fn api_request(
+    name: &str,
+    provider: &ProviderConfig,
+    endpoint: &str,
+) -> Result<(String, Result<reqwest::RequestBuilder, String>), ProviderTestResult> {
+    if OAUTH_PROVIDERS.contains(&name) {
+        return Err(ProviderTestResult::skipped(name, None, "OAuth 提供商请使用登录状态检查"));
+    }
+
+    let Some(api_base) = resolve_api_base(name, provider) else {
+        return Err(ProviderTestResult::skipped(name, None, "未配置 API 地址"));
+    };
+
+    let api_key = provider.api_key.as_deref().unwrap_or("");
+    if crate::interpolation::contains_reference(api_key) {
+        return Err(ProviderTestResult::skipped(name, Some(api_base), "API key 引用的环境变量未定义"));
+    }
+    // 本地部署（如 vLLM）可以不需要 API key，只要配置了地址就检测
+    if api_key.is_empty() && provider.api_base.as_deref().is_none_or(|b| b.is_empty()) {
+        return Err(ProviderTestResult::skipped(name, Some(api_base), "未配置 API key"));
+    }
+
+    let request = build_client(provider).map(|client| {
+        let mut request = client
+            .get(format!("{}/{}", api_base, endpoint))
+            .header("Accept", "application/json");
+
+        if name == "anthropic" {
+            request = request
+                .header("x-api-key", api_key)
+                .header("anthropic-version", "2023-06-01");
+        } else if !api_key.is_empty() {
+            request = request.bearer_auth(api_key);
+        }
+
+        if let Some(headers) = &provider.extra_headers {
+            for (key, value) in headers {
+                request = request.header(key.as_str(), value.as_str());
+            }
+        }
+        request
+    });
+
+    Ok((api_base, request))
+}
+
+/// 检测单个提供商
+pub async fn test_provider(name: &str, provider: &ProviderConfig) -> ProviderTestResult {
+    let (api_base, request) = match api_request(name, provider, check_endpoint(name)) {
+        Ok(prepared) => prepared,
+        Err(skipped) => return skipped,
+    };
+
+    let request = match request {
+        Ok(request) => request,
+        Err(e) => {
+            return ProviderTestResult {
+                provider: name.to_string(),
+                status: "unreachable".to_string(),
+                message: e,
+                api_base: Some(api_base),
+                http_status: None,
+                latency_ms: None,
+            };
+        }
+    };
+
+    let started = Instant::now();
+    let response = request.send().await;
+    let latency_ms = started.elapsed().as_millis() as u64;
+
+    match response {
+        Ok(response) => {
+            let status = response.status().as_u16();
+            let (kind, message) = classify_status(status);
+            ProviderTestResult {
+                provider: name.to_string(),
+                status: kind.to_string(),
+                message: if kind == "http_error" {
+                    format!("{}: {}", message, status)
+                } else {
+                    message.to_string()
+                },
+                api_base: Some(api_base),
+                http_status: Some(status),
+                latency_ms: Some(latency_ms),
+            }
+        }
+        Err(e) => {
+            let (kind, message) = if is_tls_error(&e) {
+                ("tls_error", format!("TLS 连接失败: {}", e))
+            } else if e.is_timeout() {
+                ("unreachable", "连接超时".to_string())
+            } else {
+                ("unreachable", format!("无法连接: {}", e))
+            };
+            ProviderTestResult {
+                provider: name.to_string(),
+                status: kind.to_string(),
+                message,
+                api_base: Some(api_base),
+                http_status: None,
+                latency_ms: None,
+            }
+        }
+    }
+}
+
+/// 读取展开环境变量后的 providers 配置（仅在内存中使用），配置文件不存在时返回 None
+fn load_resolved_providers() -> Result<Option<BTreeMap<String, ProviderConfig>>, String> {
+    let config = crate::config::load_config_internal()?;
+    if config.get("error").and_then(|e| e.as_str()) == Some("config_not_found") {
+        return Ok(None);
+    }
+
+    let resolved = crate::interpolation::interpolate_config(&config).config;
+    let config = NanobotConfig::from_json(&resolved)?;
+    Ok(Some(config.providers.unwrap_or_default()))
+}
+
+/// 检测所有已配置的提供商
+/// 指定 name 时只检测该提供商
+#[tauri::command]
+pub async fn test_providers(name: Option<String>) -> Result<JsonValue, String> {
+    let Some(providers) = load_resolved_providers()? else {
+        return Ok(json!({
+            "success": false,
+            "message": "配置文件不存在",
+            "results": []
+        }));
+    };
+
+    let mut handles = Vec::new();
+    for (provider_name, provider) in providers {
+        if name.as_ref().is_some_and(|n| *n != provider_name) {
+            continue;
+        }
+        handles.push(tokio::spawn(async move {
+            test_provider(&provider_name, &provider).await
+        }));
+    }
+
+    if handles.is_empty() {
+        return Ok(json!({
+            "success": false,
+            "message": match &name {
+                Some(n) => format!("提供商 {} 未配置", n),
+                None => "未配置任何提供商".to_string(),
+            },
+            "results": []
+        }));
+    }
+
+    let mut results = Vec::new();
+    for handle in handles {
+        match handle.await {
+            Ok(result) => results.push(result),
+            Err(e) => log::warn!("提供商检测任务失败: {}", e),
+        }
+    }
+
+    let ok_count = results.iter().filter(|r| r.status == "ok").count();
+    Ok(json!({
+        "success": true,
+        "message": format!("{}/{} 个提供商连接正常", ok_count, results.len()),
+        "results": results
+    }))
+}
+
+// ---------------------------------------------------------------------------
+// 模型目录
+// ---------------------------------------------------------------------------
+
+/// 模型目录缓存有效期
+const MODEL_CACHE_TTL_SECS: i64 = 24 * 60 * 60;
+
+/// 离线时使用的内置模型列表
+const STATIC_MODELS: &[(&str, &[&str])] = &[
+    ("anthropic", &["claude-opus-4-5", "claude-sonnet-4-5", "claude-haiku-4-5", "claude-opus-4-1", "claude-sonnet-4-0"]),
+    ("openai", &["gpt-5", "gpt-5-mini", "gpt-5-nano", "gpt-4.1", "gpt-4.1-mini", "gpt-4o", "gpt-4o-mini", "o3", "o4-mini"]),
+    ("openrouter", &["anthropic/claude-opus-4-5", "anthropic/claude-sonnet-4-5", "openai/gpt-5", "google/gemini-2.5-pro", "google/gemini-2.5-flash", "deepseek/deepseek-chat", "moonshotai/kimi-k2"]),
+    ("deepseek", &["deepseek-chat", "deepseek-reasoner"]),
+    ("groq", &["llama-3.3-70b-versatile", "llama-3.1-8b-instant", "openai/gpt-oss-120b"]),
+    ("gemini", &["gemini-2.5-pro", "gemini-2.5-flash", "gemini-2.5-flash-lite"]),
+    ("minimax", &["MiniMax-M2", "MiniMax-M1"]),
+    ("dashscope", &["qwen-max", "qwen-plus", "qwen-turbo", "qwen3-coder-plus"]),
+    ("moonshot", &["kimi-k2-0905-preview", "kimi-k2-turbo-preview", "moonshot-v1-128k"]),
+    ("zhipu", &["glm-4.6", "glm-4.5", "glm-4.5-air"]),
+    ("siliconflow", &["deepseek-ai/DeepSeek-V3", "Qwen/Qwen3-235B-A22B", "moonshotai/Kimi-K2-Instruct"]),
+];
+
+/// 单个提供商的模型目录缓存
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CachedModels {
+    models: Vec<String>,
+    fetched_at: i64,
+}
+
+/// 单个提供商的模型目录
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderModels {
+    pub provider: String,
+    pub models: Vec<String>,
+    /// remote / cache / static / none
+    pub source: String,
+    pub fetched_at: Option<i64>,
+    pub error: Option<String>,
+}
+
+fn get_model_cache_path() -> Result<PathBuf> {
+    let home = home_dir().context("无法找到用户主目录")?;
+    Ok(home.join(".nanobot").join("model_cache.json"))
+}
+
+fn read_model_cache() -> BTreeMap<String, CachedModels> {
+    get_model_cache_path()
+        .ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn write_model_cache(cache: &BTreeMap<String, CachedModels>) -> Result<(), String> {
+    let path = get_model_cache_path().map_err(|e| e.to_string())?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("创建缓存目录失败: {}", e))?;
+    }
+    let content = serde_json::to_string_pretty(cache)
+        .map_err(|e| format!("序列化模型缓存失败: {}", e))?;
+    fs::write(&path, content).map_err(|e| format!("写入模型缓存失败: {}", e))
+}
+
+fn static_models(provider: &str) -> Option<Vec<String>> {
+    STATIC_MODELS
+        .iter()
+        .find(|(name, _)| *name == provider)
+        .map(|(_, models)| models.iter().map(|m| m.to_string()).collect())
+}
+
+/// 解析 models 接口响应
+/// 兼容 OpenAI 格式 `{"data": [{"id": ...}]}` 与 Ollama 格式 `{"models": [{"name": ...}]}`
+fn parse_model_ids(body: &JsonValue) -> Vec<String> {
+    let items = body
+        .get("data")
+        .or_else(|| body.get("models"))
+        .and_then(|v| v.as_array());
+
+    let mut ids: Vec<String> = items
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|item| {
+                    item.get("id")
+                        .or_else(|| item.get("name"))
+                        .and_then(|v| v.as_str())
+                        .map(|s| s.to_string())
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    ids.sort();
+    ids.dedup();
+    ids
+}
+
+/// 从提供商的 models 接口获取模型列表
+pub async fn fetch_provider_models(name: &str, provider: &ProviderConfig) -> Result<Vec<String>, String> {
+    let (_, request) = api_request(name, provider, "models").map_err(|skipped| skipped.message)?;
+    let response = request?
+        .send()
+        .await
+        .map_err(|e| format!("请求失败: {}", e))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("{}: {}", classify_status(status.as_u16()).1, status.as_u16()));
+    }
+
+    let body: JsonValue = response
+        .json()
+        .await
+        .map_err(|e| format!("解析响应失败: {}", e))?;
+    let ids = parse_model_ids(&body);
+    if ids.is_empty() {
+        return Err("响应中没有模型列表".to_string());
+    }
+    Ok(ids)
+}
+
+/// 缓存或内置列表中的模型目录（不发起网络请求）
+fn offline_models(name: &str, cache: &BTreeMap<String, CachedModels>) -> ProviderModels {
+    if let Some(cached) = cache.get(name) {
+        return ProviderModels {
+            provider: name.to_string(),
+            models: cached.models.clone(),
+            source: "cache".to_string(),
+            fetched_at: Some(cached.fetched_at),
+            error: None,
+        };
+    }
+
+    let (models, source) = match static_models(name) {
+        Some(models) => (models, "static"),
+        None => (Vec::new(), "none"),
+    };
+    ProviderModels {
+        provider: name.to_string(),
+        models,
+        source: source.to_string(),
+        fetched_at: None,
+        error: None,
+    }
+}
+
+/// 目录中的 ID 是否为该模型名或其带日期的版本
+/// Anthropic 的 /v1/models 只返回 claude-sonnet-4-5-20250929 这类带日期的 ID，不含别名
+fn matches_model_id(id: &str, model: &str) -> bool {
+    match id.strip_prefix(model) {
+        Some("") => true,
+        Some(rest) => rest
+            .strip_prefix('-')
+            .is_some_and(|date| date.len() == 8 && date.bytes().all(|b| b.is_ascii_digit())),
+        None => false,
+    }
+}
+
+/// 检查模型是否出现在目录中
+/// 同时接受带提供商前缀（anthropic/claude-opus-4-5）与不带前缀的写法，以及省略日期后缀的别名
+pub fn model_in_catalogue<'a>(model: &str, mut catalogues: impl Iterator<Item = &'a ProviderModels>) -> bool {
+    let bare = model.split_once('/').map(|(_, rest)| rest);
+    catalogues.any(|c| {
+        c.models
+            .iter()
+            .any(|id| matches_model_id(id, model) || bare.is_some_and(|bare| matches_model_id(id, bare)))
+    })
+}
+
+/// 通过网关转发请求的提供商，模型名前缀是上游厂商（anthropic/claude-...）而不是提供商本身
+const GATEWAY_PROVIDERS: &[&str] = &["openrouter", "aihubmix"];
+
+/// 内置网关，或配置了自定义 apiBase 的提供商（如 vLLM）
+fn is_gateway(name: &str, provider: &ProviderConfig) -> bool {
+    GATEWAY_PROVIDERS.contains(&name) || provider.api_base.as_deref().is_some_and(|b| !b.is_empty())
+}
+
+/// 确定默认模型所属的提供商：优先使用 agents.defaults.provider，其次是模型名前缀（anthropic/claude-...）；
+/// 前缀不是已配置的提供商时（例如只配置了 OpenRouter），由各网关提供商承接
+/// 无法确定时返回空列表
+fn model_owners<'a>(
+    model: &str,
+    explicit: Option<&str>,
+    providers: &'a BTreeMap<String, ProviderConfig>,
+) -> Vec<&'a str> {
+    if let Some(explicit) = explicit.filter(|p| !p.is_empty() && *p != "auto") {
+        return providers.get_key_value(explicit).map(|(name, _)| vec![name.as_str()]).unwrap_or_default();
+    }
+    let Some((prefix, _)) = model.split_once('/') else {
+        return Vec::new();
+    };
+    if let Some((name, _)) = providers.get_key_value(prefix) {
+        return vec![name.as_str()];
+    }
+    providers
+        .iter()
+        .filter(|(name, provider)| is_gateway(name, provider))
+        .map(|(name, _)| name.as_str())
+        .collect()
+}
+
+/// 用模型目录检查默认模型
+/// 优先使用所属提供商缓存的在线目录；尚未获取过在线目录时回退到内置列表，
+/// 内置列表并不完整，此时的提示会注明依据的是内置列表。
+/// 由多个网关承接时，任一网关的目录包含该模型即视为有效，其中有网关没有可用目录时不做判断
+fn check_model_with(
+    model: &str,
+    explicit: Option<&str>,
+    providers: &BTreeMap<String, ProviderConfig>,
+    cache: &BTreeMap<String, CachedModels>,
+) -> Option<String> {
+    let owners = model_owners(model, explicit, providers);
+    if owners.is_empty() {
+        return None;
+    }
+
+    let mut catalogues = Vec::new();
+    for owner in &owners {
+        let (models, source) = match cache.get(*owner).filter(|c| !c.models.is_empty()) {
+            Some(cached) => (cached.models.clone(), "cache"),
+            None => (static_models(owner)?, "static"),
+        };
+        catalogues.push(ProviderModels {
+            provider: owner.to_string(),
+            models,
+            source: source.to_string(),
+            fetched_at: None,
+            error: None,
+        });
+    }
+    if model_in_catalogue(model, catalogues.iter()) {
+        return None;
+    }
+
+    let owners = owners.join("、");
+    if catalogues.iter().any(|c| c.source == "static") {
+        return Some(format!(
+            "默认模型 {} 不在提供商 {} 的内置模型列表中（尚未获取在线模型列表，内置列表可能不完整），请检查是否拼写错误或刷新模型列表",
+            model, owners
+        ));
+    }
+    Some(format!(
+        "默认模型 {} 不在提供商 {} 的模型列表中，请检查是否拼写错误",
+        model, owners
+    ))
+}
+
+/// 默认模型的目录检查，用于配置校验
+/// providers 只应包含已配置 API key 或 apiBase 的提供商；
+/// 无法确定所属提供商，或所属提供商既没有缓存目录也没有内置列表时不做判断，返回 None
+pub fn check_default_model(
+    model: &str,
+    explicit_provider: Option<&str>,
+    providers: &BTreeMap<String, ProviderConfig>,
+) -> Option<String> {
+    check_model_with(model, explicit_provider, providers, &read_model_cache())
+}
+
+/// 列出已配置提供商的可用模型
+/// 缓存未过期时直接使用缓存，refresh 为 true 时强制重新获取；
+/// 获取失败时回退到缓存或内置列表
+#[tauri::command]
+pub async fn list_provider_models(provider: Option<String>, refresh: Option<bool>) -> Result<JsonValue, String> {
+    let Some(providers) = load_resolved_providers()? else {
+        return Ok(json!({
+            "success": false,
+            "message": "配置文件不存在",
+            "providers": []
+        }));
+    };
+
+    let refresh = refresh.unwrap_or(false);
+    let now = chrono::Utc::now().timestamp();
+    let mut cache = read_model_cache();
+
+    let mut handles = Vec::new();
+    for (name, config) in providers {
+        if provider.as_ref().is_some_and(|p| *p != name) {
+            continue;
+        }
+        let fresh = cache
+            .get(&name)
+            .is_some_and(|c| now - c.fetched_at < MODEL_CACHE_TTL_SECS);
+        if fresh && !refresh {
+            handles.push((name, None));
+        } else {
+            let task_name = name.clone();
+            handles.push((
+                name,
+                Some(tokio::spawn(async move {
+                    fetch_provider_models(&task_name, &config).await
+                })),
+            ));
+        }
+    }
+
+    let mut results = Vec::new();
+    let mut cache_changed = false;
+    for (name, handle) in handles {
+        let fetched = match handle {
+            Some(handle) => Some(handle.await.map_err(|e| e.to_string()).and_then(|r| r)),
+            None => None,
+        };
+
+        match fetched {
+            Some(Ok(models)) => {
+                cache.insert(name.clone(), CachedModels { models: models.clone(), fetched_at: now });
+                cache_changed = true;
+                results.push(ProviderModels {
+                    provider: name,
+                    models,
+                    source: "remote".to_string(),
+                    fetched_at: Some(now),
+                    error: None,
+                });
+            }
+            Some(Err(e)) => {
+                let mut fallback = offline_models(&name, &cache);
+                fallback.error = Some(e);
+                results.push(fallback);
+            }
+            None => results.push(offline_models(&name, &cache)),
+        }
+    }
+
+    if cache_changed {
+        if let Err(e) = write_model_cache(&cache) {
+            log::warn!("{}", e);
+        }
+    }
+
+    Ok(json!({
+        "success": true,
+        "providers": results
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::stub_server;
+    use tokio::io::AsyncWriteExt;
+    use tokio::net::TcpListener;
+
+    /// 接受连接后立即返回明文响应的服务，用于模拟 TLS 握手失败
+    async fn plain_text_server() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let _ = socket.write_all(b"HTTP/1.1 400 Bad Request\r\nConnection: close\r\n\r\n").await;
+            }
+        });
+        addr.to_string()
+    }
+
+    fn provider(api_base: String) -> ProviderConfig {
+        ProviderConfig {
+            api_key: Some("sk-test".to_string()),
+            api_base: Some(api_base),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn classifies_http_statuses() {
+        for (status, expected) in [(200, "ok"), (401, "bad_key"), (403, "bad_key"), (429, "quota"), (500, "http_error")] {
+            let base = stub_server(vec![("/v1/models", status, r#"{"data":[]}"#)]).await;
+            let result = test_provider("openai", &provider(format!("{}/v1/", base))).await;
+
+            assert_eq!(result.status, expected, "HTTP {}", status);
+            assert_eq!(result.http_status, Some(status));
+            assert!(result.latency_ms.is_some());
+            assert_eq!(result.api_base.as_deref(), Some(format!("{}/v1", base).as_str()));
+        }
+    }
+
+    #[tokio::test]
+    async fn checks_openrouter_key_instead_of_public_model_list() {
+        let base = stub_server(vec![
+            ("/api/v1/models", 200, r#"{"data":[]}"#),
+            ("/api/v1/key", 401, r#"{"error":{"message":"No auth credentials found","code":401}}"#),
+        ])
+        .await;
+        let result = test_provider("openrouter", &provider(format!("{}/api/v1", base))).await;
+        assert_eq!(result.status, "bad_key");
+        assert_eq!(result.http_status, Some(401));
+
+        let base = stub_server(vec![("/api/v1/key", 200, r#"{"data":{"label":"sk-or-v1-abc"}}"#)]).await;
+        let result = test_provider("openrouter", &provider(format!("{}/api/v1", base))).await;
+        assert_eq!(result.status, "ok");
+    }
+
+    #[tokio::test]
+    async fn reports_unreachable_and_tls_errors() {
+        // 绑定后立即释放端口，连接会被拒绝
+        let closed = TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap();
+        let result = test_provider("openai", &provider(format!("http://{}/v1", closed))).await;
+        assert_eq!(result.status, "unreachable");
+        assert_eq!(result.latency_ms, None);
+
+        // 对明文 HTTP 服务发起 HTTPS 请求，握手失败
+        let addr = plain_text_server().await;
+        let result = test_provider("openai", &provider(format!("https://{}/v1", addr))).await;
+        assert_eq!(result.status, "tls_error", "{}", result.message);
+        assert_eq!(result.http_status, None);
+    }
+
+    #[tokio::test]
+    async fn skips_unresolved_and_missing_keys() {
+        let mut config = provider("http://127.0.0.1:1/v1".to_string());
+        config.api_key = Some("${OPENAI_KEY}".to_string());
+        assert_eq!(test_provider("openai", &config).await.status, "skipped");
+
+        let config = ProviderConfig::default();
+        assert_eq!(test_provider("openai", &config).await.status, "skipped");
+        assert_eq!(test_provider("github_copilot", &config).await.status, "skipped");
+    }
+
+    fn configured(entries: &[(&str, Option<&str>)]) -> BTreeMap<String, ProviderConfig> {
+        entries
+            .iter()
+            .map(|(name, api_base)| {
+                let config = ProviderConfig {
+                    api_key: Some("sk-test".to_string()),
+                    api_base: api_base.map(|b| b.to_string()),
+                    ..Default::default()
+                };
+                (name.to_string(), config)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn checks_default_model_against_owner_catalogue() {
+        let providers = configured(&[("anthropic", None), ("openai", None), ("vllm", Some("http://localhost:8000/v1"))]);
+        let mut cache = BTreeMap::new();
+        cache.insert(
+            "anthropic".to_string(),
+            CachedModels { models: vec!["claude-sonnet-4-5".to_string()], fetched_at: 0 },
+        );
+
+        // 前缀对应的提供商有缓存目录
+        assert!(check_model_with("anthropic/claude-sonnet-4-5", None, &providers, &cache).is_none());
+        assert!(check_model_with("anthropic/claude-sonet-4-5", None, &providers, &cache).is_some());
+        // 显式指定提供商
+        assert!(check_model_with("claude-sonet-4-5", Some("anthropic"), &providers, &cache).is_some());
+        assert!(check_model_with("claude-sonet-4-5", Some("auto"), &providers, &cache).is_none());
+        // 没有缓存时回退到内置列表，并注明依据的是内置列表
+        assert!(check_model_with("openai/gpt-5", None, &providers, &cache).is_none());
+        let warning = check_model_with("openai/gpt-5-mnii", None, &providers, &cache).unwrap();
+        assert!(warning.contains("内置模型列表"), "{}", warning);
+        // 无法确定所属提供商（vllm 自定义模型、由没有目录的网关承接的前缀）时不判断
+        assert!(check_model_with("my-local-model", None, &providers, &cache).is_none());
+        assert!(check_model_with("meta/llama", None, &providers, &cache).is_none());
+    }
+
+    #[test]
+    fn checks_prefixed_model_against_gateway_catalogue() {
+        let providers = configured(&[("openrouter", None)]);
+        let mut cache = BTreeMap::new();
+
+        // 只配置了 OpenRouter，anthropic/ 前缀由网关承接，使用内置列表
+        assert!(check_model_with("anthropic/claude-opus-4-5", None, &providers, &cache).is_none());
+        let warning = check_model_with("anthropic/claude-opus-4-6-typo", None, &providers, &cache).unwrap();
+        assert!(warning.contains("openrouter"), "{}", warning);
+        assert!(warning.contains("内置模型列表"), "{}", warning);
+
+        // 有缓存的在线目录时以缓存为准
+        cache.insert(
+            "openrouter".to_string(),
+            CachedModels { models: vec!["anthropic/claude-sonnet-4-5".to_string()], fetched_at: 0 },
+        );
+        assert!(check_model_with("anthropic/claude-sonnet-4-5", None, &providers, &cache).is_none());
+        let warning = check_model_with("anthropic/claude-sonet-4-5", None, &providers, &cache).unwrap();
+        assert!(!warning.contains("内置模型列表"), "{}", warning);
+
+        // 还有一个没有目录的自定义网关时无法判断
+        let mut providers = providers;
+        providers.extend(configured(&[("vllm", Some("http://localhost:8000/v1"))]));
+        assert!(check_model_with("anthropic/claude-sonet-4-5", None, &providers, &cache).is_none());
+    }
+
+    #[test]
+    fn accepts_aliases_of_dated_anthropic_models() {
+        let providers = configured(&[("anthropic", None)]);
+        let mut cache = BTreeMap::new();
+        cache.insert(
+            "anthropic".to_string(),
+            CachedModels {
+                models: vec![
+                    "claude-opus-4-5-20251101".to_string(),
+                    "claude-sonnet-4-5-20250929".to_string(),
+                ],
+                fetched_at: 0,
+            },
+        );
+
+        assert!(check_model_with("anthropic/claude-opus-4-5", None, &providers, &cache).is_none());
+        assert!(check_model_with("claude-sonnet-4-5", Some("anthropic"), &providers, &cache).is_none());
+        assert!(check_model_with("anthropic/claude-sonnet-4-5-20250929", None, &providers, &cache).is_none());
+        // 只接受日期后缀，不接受其他版本或截断的名称
+        assert!(check_model_with("anthropic/claude-opus-4", None, &providers, &cache).is_some());
+        assert!(check_model_with("anthropic/claude-sonnet-4-5-2025", None, &providers, &cache).is_some());
+    }
+
+    #[tokio::test]
+    async fn fetches_model_ids() {
+        let base = stub_server(vec![("/v1/models", 200, r#"{"data":[{"id":"b"},{"id":"a"},{"id":"a"}]}"#)]).await;
+        let models = fetch_provider_models("openai", &provider(format!("{}/v1", base))).await;
+        assert_eq!(models.unwrap(), vec!["a".to_string(), "b".to_string()]);
+
+        assert_eq!(
+            parse_model_ids(&json!({ "models": [{ "name": "llama3" }] })),
+            vec!["llama3".to_string()]
+        );
+    }
+}
@@ -0,0 +1,241 @@
+// 敏感信息脱敏
+// 以配置中实际使用的密钥（提供商 apiKey、渠道 token/secret/password、MCP 的 env/headers 等，
+// 取插值后的值）作为脱敏模式，并附加常见密钥格式（Bearer、sk-、Slack、Telegram bot token），
+// 用于导出日志、配置与诊断信息时替换为 [REDACTED]。
+
+use regex::Regex;
+use serde_json::Value as JsonValue;
+use std::borrow::Cow;
+
+use crate::log_parser::LogRecord;
+
+/// 脱敏后的占位文本
+pub const REDACTED: &str = "[REDACTED]";
+
+/// 短于该长度的值不作为脱敏模式，避免误替换普通文本
+const MIN_SECRET_LEN: usize = 6;
+
+/// 常见密钥格式
+const GENERIC_PATTERNS: &[&str] = &[
+    r"(?i)\bbearer\s+[A-Za-z0-9._~+/=-]{8,}",
+    r"\bsk-[A-Za-z0-9_-]{16,}",
+    r"\bxox[abprs]-[A-Za-z0-9-]{10,}",
+    r"\bxapp-[A-Za-z0-9-]{10,}",
+    r"\b\d{6,12}:[A-Za-z0-9_-]{30,}",
+];
+
+/// 个人信息格式（邮箱、电话号码、IPv4 地址），导出会话记录时可选脱敏
+const PII_PATTERNS: &[&str] = &[
+    r"\b[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}\b",
+    r"(?:\+\d{1,3}[\s-]?)?\(?\d{3}\)?[\s-]?\d{3,4}[\s-]?\d{4}\b",
+    r"\b(?:\d{1,3}\.){3}\d{1,3}\b",
+];
+
+/// 值整体视为敏感信息的字段名（小写子串匹配）
+const SENSITIVE_KEYS: &[&str] = &["key", "token", "secret", "password", "passwd", "authorization", "cookie"];
+
+/// 其下所有值都视为敏感信息的字段
+const SENSITIVE_MAPS: &[&str] = &["extraHeaders", "headers", "env"];
+
+/// 判断字段名是否表示敏感信息
+pub fn is_sensitive_key(key: &str) -> bool {
+    let key = key.to_lowercase();
+    SENSITIVE_KEYS.iter().any(|s| key.contains(s))
+}
+
+/// 从配置中收集敏感值
+fn collect_secrets(value: &JsonValue, sensitive: bool, secrets: &mut Vec<String>) {
+    match value {
+        // 未解析的 ${VAR} 引用不是密钥本身
+        JsonValue::String(s)
+            if sensitive && s.len() >= MIN_SECRET_LEN && !crate::interpolation::contains_reference(s) =>
+        {
+            secrets.push(s.clone())
+        }
+        JsonValue::Object(map) => {
+            for (key, child) in map {
+                let child_sensitive = sensitive || is_sensitive_key(key) || SENSITIVE_MAPS.contains(&key.as_str());
+                collect_secrets(child, child_sensitive, secrets);
+            }
+        }
+        JsonValue::Array(items) => {
+            for item in items {
+                collect_secrets(item, sensitive, secrets);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// 收集配置（插值后）中的密钥
+pub fn secrets_from_config(config: &JsonValue) -> Vec<String> {
+    let mut secrets = Vec::new();
+    collect_secrets(config, false, &mut secrets);
+    secrets
+}
+
+/// 当前 nanobot 配置（插值后）与 .env 中的密钥
+fn current_secrets() -> Vec<String> {
+    let mut secrets = Vec::new();
+    if let Ok(config) = crate::config::load_config_internal() {
+        let resolved = crate::interpolation::interpolate_config(&config).config;
+        secrets = secrets_from_config(&resolved);
+    }
+    for (key, value) in crate::interpolation::load_dotenv() {
+        if is_sensitive_key(&key) {
+            secrets.push(value);
+        }
+    }
+    secrets
+}
+
+/// 脱敏器
+pub struct Redactor {
+    pattern: Regex,
+}
+
+impl Redactor {
+    /// 使用给定的密钥与常见密钥格式构建
+    pub fn new(secrets: Vec<String>) -> Self {
+        Self::build(secrets, false)
+    }
+
+    fn build(mut secrets: Vec<String>, pii: bool) -> Self {
+        secrets.retain(|s| s.len() >= MIN_SECRET_LEN);
+        // 较长的值优先匹配，避免只替换掉包含关系中的一部分
+        secrets.sort_by_key(|s| std::cmp::Reverse(s.len()));
+        secrets.dedup();
+
+        let alternatives: Vec<String> = secrets
+            .iter()
+            .map(|s| regex::escape(s))
+            .chain(GENERIC_PATTERNS.iter().map(|p| format!("(?:{})", p)))
+            .chain(PII_PATTERNS.iter().filter(|_| pii).map(|p| format!("(?:{})", p)))
+            .collect();
+        Self {
+            pattern: Regex::new(&alternatives.join("|")).expect("脱敏模式无效"),
+        }
+    }
+
+    /// 以当前 nanobot 配置与 .env 中的密钥构建脱敏器，配置无法读取时只使用常见密钥格式
+    pub fn from_current_config() -> Self {
+        Self::new(current_secrets())
+    }
+
+    /// 在 from_current_config 的基础上同时脱敏个人信息
+    pub fn with_pii_from_current_config() -> Self {
+        Self::build(current_secrets(), true)
+    }
+
+    pub fn redact<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        self.pattern.replace_all(text, REDACTED)
+    }
+
+    pub fn redact_record(&self, record: &LogRecord) -> LogRecord {
+        LogRecord {
+            message: self.redact(&record.message).into_owned(),
+            continuation: record
+                .continuation
+                .iter()
+                .map(|line| self.redact(line).into_owned())
+                .collect(),
+            ..record.clone()
+        }
+    }
+
+    /// 脱敏 JSON：敏感字段的值整体替换（${VAR} 引用保留，便于排查），其余字符串按模式替换
+    pub fn redact_json(&self, value: &JsonValue) -> JsonValue {
+        self.redact_json_inner(value, false)
+    }
+
+    fn redact_json_inner(&self, value: &JsonValue, sensitive: bool) -> JsonValue {
+        match value {
+            JsonValue::String(s) if sensitive && !s.is_empty() && !crate::interpolation::contains_reference(s) => {
+                JsonValue::String(REDACTED.to_string())
+            }
+            JsonValue::String(s) => JsonValue::String(self.redact(s).into_owned()),
+            JsonValue::Object(map) => JsonValue::Object(
+                map.iter()
+                    .map(|(key, child)| {
+                        let child_sensitive =
+                            sensitive || is_sensitive_key(key) || SENSITIVE_MAPS.contains(&key.as_str());
+                        (key.clone(), self.redact_json_inner(child, child_sensitive))
+                    })
+                    .collect(),
+            ),
+            JsonValue::Array(items) => {
+                JsonValue::Array(items.iter().map(|item| self.redact_json_inner(item, sensitive)).collect())
+            }
+            other => other.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn config() -> JsonValue {
+        json!({
+            "providers": {
+                "openrouter": {
+                    "apiKey": "sk-or-v1-abcdefghijklmnop123456",
+                    "extraHeaders": { "X-Org": "org-998877" }
+                },
+                "openai": { "apiKey": "${OPENAI_API_KEY}" }
+            },
+            "channels": {
+                "telegram": { "enabled": true, "token": "123456789:AAbbCCddEEffGGhhIIjjKKllMMnnOOppQQ", "allowFrom": ["alice"] },
+                "email": { "imapPassword": "hunter22pw", "smtpPassword": "pw" }
+            },
+            "agents": { "defaults": { "maxTokens": 8192, "model": "anthropic/claude" } }
+        })
+    }
+
+    #[test]
+    fn collects_secrets_from_sensitive_fields() {
+        let mut secrets = secrets_from_config(&config());
+        secrets.sort();
+        // 过短的值、${VAR} 引用与普通字段不作为密钥
+        assert_eq!(secrets, vec![
+            "123456789:AAbbCCddEEffGGhhIIjjKKllMMnnOOppQQ",
+            "hunter22pw",
+            "org-998877",
+            "sk-or-v1-abcdefghijklmnop123456",
+        ]);
+        assert!(is_sensitive_key("apiKey") && is_sensitive_key("Authorization"));
+        assert!(!is_sensitive_key("model"));
+    }
+
+    #[test]
+    fn redacts_configured_and_generic_secrets() {
+        let redactor = Redactor::new(secrets_from_config(&config()));
+        let text = "key sk-or-v1-abcdefghijklmnop123456 pw hunter22pw org-998877 \
+                    Authorization: Bearer abcdefghijkl sk-proj-0123456789abcdefXYZ model anthropic/claude";
+        let redacted = redactor.redact(text);
+        for secret in ["abcdefghijklmnop", "hunter22pw", "998877", "abcdefghijkl", "sk-proj"] {
+            assert!(!redacted.contains(secret), "{}", redacted);
+        }
+        assert!(redacted.contains("model anthropic/claude"));
+        // 没有匹配时不复制
+        assert!(matches!(redactor.redact("nothing here"), Cow::Borrowed(_)));
+
+        // 默认不处理个人信息
+        let pii = "mail alice@example.com from 192.168.1.20";
+        assert_eq!(redactor.redact(pii), pii);
+        assert_eq!(Redactor::build(Vec::new(), true).redact(pii), "mail [REDACTED] from [REDACTED]");
+    }
+
+    #[test]
+    fn redacts_json_fields_but_keeps_references() {
+        let redactor = Redactor::new(Vec::new());
+        let redacted = redactor.redact_json(&config());
+        assert_eq!(redacted["providers"]["openrouter"]["apiKey"], REDACTED);
+        assert_eq!(redacted["providers"]["openrouter"]["extraHeaders"]["X-Org"], REDACTED);
+        assert_eq!(redacted["providers"]["openai"]["apiKey"], "${OPENAI_API_KEY}");
+        assert_eq!(redacted["channels"]["email"]["smtpPassword"], REDACTED);
+        assert_eq!(redacted["channels"]["telegram"]["allowFrom"][0], "alice");
+        assert_eq!(redacted["agents"]["defaults"]["maxTokens"], 8192);
+    }
+}
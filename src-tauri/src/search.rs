@@ -0,0 +1,693 @@
+// 全文搜索
+// 在聊天会话消息、memory/ 记忆文件、workspace 根目录的 Markdown（AGENTS.md 等）与 skills 中搜索。
+// - 会话按消息、Markdown 按段落切分为条目，每个文件单独维护倒排索引；
+// - 每次搜索前按修改时间与大小检查文件，只重新索引变化的文件，删除的文件移出索引；
+// - 英文按单词（前缀匹配）、中日韩文字按单字建立索引，候选条目再用不区分大小写的子串匹配确认；
+// - 结果带有片段（分段标记高亮）与跳转位置（会话 ID + 消息序号，或文件 + 行号）。
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value as JsonValue};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use crate::chat_session::{ParsedLine, SessionParser};
+use crate::time_range::{in_range, normalize_time};
+
+/// 默认返回的结果数
+const DEFAULT_LIMIT: usize = 50;
+
+/// 片段中匹配位置前后保留的字符数
+const SNIPPET_BEFORE: usize = 60;
+const SNIPPET_AFTER: usize = 120;
+
+/// 搜索来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchSource {
+    /// 聊天会话
+    Session,
+    /// memory/ 下的记忆文件
+    Memory,
+    /// workspace 根目录的 Markdown 文件
+    Workspace,
+    Skill,
+}
+
+/// 搜索条件
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchQuery {
+    pub query: String,
+    /// 限定来源，未指定时搜索全部
+    pub sources: Option<Vec<SearchSource>>,
+    /// 限定会话消息的角色（user / assistant / tool / system）
+    pub roles: Option<Vec<String>>,
+    /// 限定会话渠道（会话键中冒号前的部分，如 telegram）
+    pub channels: Option<Vec<String>>,
+    /// 时间范围（含），会话消息按消息时间，文件按修改时间
+    pub since: Option<String>,
+    pub until: Option<String>,
+    pub offset: Option<usize>,
+    pub limit: Option<usize>,
+}
+
+/// 可搜索的条目：一条会话消息或一个 Markdown 段落
+#[derive(Debug, Clone)]
+struct Entry {
+    text: String,
+    role: Option<String>,
+    timestamp: Option<String>,
+    /// 起始行号（从 1 开始）
+    line: usize,
+    message_index: Option<usize>,
+}
+
+/// 一个已索引的文件
+struct IndexedFile {
+    source: SearchSource,
+    /// 跳转用的 ID：会话文件名、记忆文件名、相对 workspace 的路径或 skill ID
+    id: String,
+    title: String,
+    channel: Option<String>,
+    modified: Option<SystemTime>,
+    size: u64,
+    entries: Vec<Entry>,
+    /// 词 -> 条目下标
+    postings: BTreeMap<String, Vec<u32>>,
+}
+
+/// 片段中的一段文本
+#[derive(Debug, Clone, Serialize)]
+pub struct SnippetPart {
+    pub text: String,
+    pub highlight: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchHit {
+    pub source: SearchSource,
+    pub id: String,
+    pub title: String,
+    pub channel: Option<String>,
+    pub role: Option<String>,
+    pub timestamp: Option<String>,
+    pub line: usize,
+    pub message_index: Option<usize>,
+    pub snippet: Vec<SnippetPart>,
+    /// 匹配次数
+    pub score: usize,
+}
+
+#[derive(Default)]
+struct SearchIndex {
+    files: HashMap<PathBuf, IndexedFile>,
+}
+
+static SEARCH_INDEX: Mutex<Option<SearchIndex>> = Mutex::new(None);
+
+fn is_cjk(c: char) -> bool {
+    matches!(c as u32,
+        0x3040..=0x30FF | 0x3400..=0x4DBF | 0x4E00..=0x9FFF | 0xAC00..=0xD7AF | 0xF900..=0xFAFF)
+}
+
+/// 分词：字母数字连续串作为一个词（小写），中日韩文字每个字一个词
+fn tokenize(text: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut word = String::new();
+    for c in text.chars() {
+        if is_cjk(c) {
+            if !word.is_empty() {
+                tokens.push(std::mem::take(&mut word));
+            }
+            tokens.push(c.to_string());
+        } else if c.is_alphanumeric() || c == '_' {
+            word.extend(c.to_lowercase());
+        } else if !word.is_empty() {
+            tokens.push(std::mem::take(&mut word));
+        }
+    }
+    if !word.is_empty() {
+        tokens.push(word);
+    }
+    tokens
+}
+
+fn build_postings(entries: &[Entry]) -> BTreeMap<String, Vec<u32>> {
+    let mut postings: BTreeMap<String, Vec<u32>> = BTreeMap::new();
+    for (i, entry) in entries.iter().enumerate() {
+        let unique: BTreeSet<String> = tokenize(&entry.text).into_iter().collect();
+        for token in unique {
+            postings.entry(token).or_default().push(i as u32);
+        }
+    }
+    postings
+}
+
+fn session_entries(path: &Path) -> std::io::Result<(Vec<Entry>, Option<String>)> {
+    let mut parser = SessionParser::new();
+    let mut entries = Vec::new();
+    let mut key = None;
+    for line in BufReader::new(File::open(path)?).lines() {
+        match parser.parse_line(&line?) {
+            ParsedLine::Header(header) => key = header.key,
+            ParsedLine::Message(message) => {
+                // 工具调用的名称与参数也可搜索
+                let mut text = message.content;
+                for call in &message.tool_calls {
+                    text.push('\n');
+                    text.push_str(&call.name);
+                    text.push(' ');
+                    text.push_str(&call.arguments.to_string());
+                }
+                if text.trim().is_empty() {
+                    continue;
+                }
+                entries.push(Entry {
+                    text,
+                    role: Some(message.role),
+                    timestamp: message.timestamp,
+                    line: message.line,
+                    message_index: Some(message.index),
+                });
+            }
+            _ => {}
+        }
+    }
+    Ok((entries, key))
+}
+
+/// Markdown 按空行分段
+fn markdown_entries(path: &Path, timestamp: Option<String>) -> std::io::Result<Vec<Entry>> {
+    let content = std::fs::read_to_string(path)?;
+    let mut entries = Vec::new();
+    let mut paragraph: Vec<&str> = Vec::new();
+    let mut start = 1;
+    for (i, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            if !paragraph.is_empty() {
+                entries.push(Entry {
+                    text: paragraph.join("\n"),
+                    role: None,
+                    timestamp: timestamp.clone(),
+                    line: start,
+                    message_index: None,
+                });
+                paragraph.clear();
+            }
+            continue;
+        }
+        if paragraph.is_empty() {
+            start = i + 1;
+        }
+        paragraph.push(line);
+    }
+    if !paragraph.is_empty() {
+        entries.push(Entry {
+            text: paragraph.join("\n"),
+            role: None,
+            timestamp,
+            line: start,
+            message_index: None,
+        });
+    }
+    Ok(entries)
+}
+
+fn format_time(time: Option<SystemTime>) -> Option<String> {
+    time.map(|t| chrono::DateTime::<chrono::Local>::from(t).format("%Y-%m-%dT%H:%M:%S").to_string())
+}
+
+/// 待索引的文件
+struct SourceFile {
+    path: PathBuf,
+    source: SearchSource,
+    id: String,
+    title: String,
+}
+
+/// 列出所有可搜索的文件
+fn discover_files() -> Vec<SourceFile> {
+    let mut files = Vec::new();
+    let list_dir = |dir: &Path| -> Vec<(PathBuf, String)> {
+        std::fs::read_dir(dir)
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .filter_map(|e| Some((e.path(), e.file_name().to_str()?.to_string())))
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    if let Ok(sessions) = crate::session::get_chat_sessions_path() {
+        for (path, name) in list_dir(&sessions) {
+            if name.ends_with(".jsonl") && path.is_file() {
+                let title = name.trim_end_matches(".jsonl").to_string();
+                files.push(SourceFile { path, source: SearchSource::Session, id: name, title });
+            }
+        }
+    }
+
+    let Some(workspace) = dirs::home_dir().map(|h| h.join(".nanobot").join("workspace")) else {
+        return files;
+    };
+    for (path, name) in list_dir(&workspace.join("memory")) {
+        if name.ends_with(".md") && path.is_file() {
+            files.push(SourceFile { path, source: SearchSource::Memory, id: name.clone(), title: name });
+        }
+    }
+    for (path, name) in list_dir(&workspace) {
+        if name.ends_with(".md") && path.is_file() {
+            files.push(SourceFile { path, source: SearchSource::Workspace, id: name.clone(), title: name });
+        }
+    }
+    for (path, name) in list_dir(&workspace.join("skills")) {
+        if path.is_dir() {
+            for file_name in ["SKILL.md", "SKILL.md.disabled"] {
+                let skill_file = path.join(file_name);
+                if skill_file.is_file() {
+                    files.push(SourceFile {
+                        path: skill_file,
+                        source: SearchSource::Skill,
+                        id: format!("{}/{}", name, file_name),
+                        title: name.clone(),
+                    });
+                }
+            }
+        } else if name.ends_with(".md") {
+            files.push(SourceFile { path, source: SearchSource::Skill, id: name.clone(), title: name });
+        }
+    }
+    files
+}
+
+/// 会话渠道：取会话键（或文件名）中分隔符前的部分
+pub(crate) fn session_channel(key: Option<&str>, file_name: &str) -> Option<String> {
+    match key {
+        Some(key) => key.split_once(':').map(|(c, _)| c.to_string()),
+        None => file_name.split_once('_').map(|(c, _)| c.to_string()),
+    }
+}
+
+impl SearchIndex {
+    /// 重新索引变化的文件，移除已删除的文件，返回重新索引的文件数
+    fn refresh(&mut self) -> usize {
+        let files = discover_files();
+        self.files.retain(|path, _| files.iter().any(|f| &f.path == path));
+
+        let mut reindexed = 0;
+        for file in files {
+            let Ok(metadata) = std::fs::metadata(&file.path) else { continue };
+            let modified = metadata.modified().ok();
+            if let Some(existing) = self.files.get(&file.path) {
+                if existing.modified == modified && existing.size == metadata.len() && existing.id == file.id {
+                    continue;
+                }
+            }
+
+            let (entries, channel) = if file.source == SearchSource::Session {
+                match session_entries(&file.path) {
+                    Ok((entries, key)) => {
+                        let channel = session_channel(key.as_deref(), &file.id);
+                        (entries, channel)
+                    }
+                    Err(e) => {
+                        log::warn!("索引会话 {} 失败: {}", file.id, e);
+                        continue;
+                    }
+                }
+            } else {
+                match markdown_entries(&file.path, format_time(modified)) {
+                    Ok(entries) => (entries, None),
+                    Err(e) => {
+                        log::warn!("索引文件 {} 失败: {}", file.path.display(), e);
+                        continue;
+                    }
+                }
+            };
+
+            reindexed += 1;
+            let postings = build_postings(&entries);
+            self.files.insert(file.path, IndexedFile {
+                source: file.source,
+                id: file.id,
+                title: file.title,
+                channel,
+                modified,
+                size: metadata.len(),
+                entries,
+                postings,
+            });
+        }
+        reindexed
+    }
+}
+
+impl IndexedFile {
+    /// 返回包含全部查询词的候选条目（英文词按前缀匹配）
+    /// 查询中没有可索引的词（如 `->`、`?`、表情符号）时全部条目都是候选，由子串匹配筛选
+    fn candidates(&self, tokens: &[String]) -> Vec<u32> {
+        if tokens.is_empty() {
+            return (0..self.entries.len() as u32).collect();
+        }
+        let mut result: Option<BTreeSet<u32>> = None;
+        for token in tokens {
+            let mut matched = BTreeSet::new();
+            for (key, ids) in self.postings.range(token.clone()..) {
+                if !key.starts_with(token.as_str()) {
+                    break;
+                }
+                matched.extend(ids.iter().copied());
+            }
+            result = Some(match result {
+                Some(previous) => previous.intersection(&matched).copied().collect(),
+                None => matched,
+            });
+            if result.as_ref().is_some_and(|r| r.is_empty()) {
+                break;
+            }
+        }
+        result.map(|r| r.into_iter().collect()).unwrap_or_default()
+    }
+}
+
+/// 生成带高亮的片段：以第一处匹配为中心截取
+fn build_snippet(text: &str, highlight: &Regex) -> (Vec<SnippetPart>, usize) {
+    let matches: Vec<(usize, usize)> = highlight.find_iter(text).map(|m| (m.start(), m.end())).collect();
+    let Some(&(first, _)) = matches.first() else {
+        return (Vec::new(), 0);
+    };
+
+    let start = text[..first]
+        .char_indices()
+        .rev()
+        .nth(SNIPPET_BEFORE - 1)
+        .map(|(i, _)| i)
+        .unwrap_or(0);
+    let end = text[first..]
+        .char_indices()
+        .nth(SNIPPET_AFTER)
+        .map(|(i, _)| first + i)
+        .unwrap_or(text.len());
+
+    let mut parts = Vec::new();
+    let mut push = |s: &str, highlight: bool| {
+        if !s.is_empty() {
+            let prefix = if parts.is_empty() && start > 0 { "…" } else { "" };
+            parts.push(SnippetPart { text: format!("{}{}", prefix, s), highlight });
+        }
+    };
+    let mut cursor = start;
+    for &(m_start, m_end) in matches.iter().filter(|(s, e)| *s >= start && *e <= end) {
+        push(&text[cursor..m_start], false);
+        push(&text[m_start..m_end], true);
+        cursor = m_end;
+    }
+    push(&text[cursor..end], false);
+    if end < text.len() {
+        parts.push(SnippetPart { text: "…".to_string(), highlight: false });
+    }
+    (parts, matches.len())
+}
+
+/// 编译后的搜索条件
+struct Matcher<'a> {
+    query: &'a SearchQuery,
+    /// 用于在倒排索引中查找候选条目的词
+    tokens: Vec<String>,
+    /// 每个搜索词都必须出现（不区分大小写的子串匹配）
+    term_patterns: Vec<Regex>,
+    highlight: Regex,
+    since: Option<String>,
+    until: Option<String>,
+    roles: Option<Vec<String>>,
+    channels: Option<Vec<String>>,
+}
+
+impl<'a> Matcher<'a> {
+    fn new(query: &'a SearchQuery) -> Result<Self, String> {
+        let terms: Vec<String> = query.query.split_whitespace().map(|t| t.to_string()).collect();
+        if terms.is_empty() {
+            return Err("搜索内容不能为空".to_string());
+        }
+        let term_patterns: Vec<Regex> = terms
+            .iter()
+            .map(|t| Regex::new(&format!("(?i){}", regex::escape(t))))
+            .collect::<Result<_, _>>()
+            .map_err(|e| format!("无效的搜索内容: {}", e))?;
+        let highlight = Regex::new(&format!(
+            "(?i){}",
+            terms.iter().map(|t| regex::escape(t)).collect::<Vec<_>>().join("|")
+        ))
+        .map_err(|e| format!("无效的搜索内容: {}", e))?;
+
+        Ok(Self {
+            query,
+            tokens: terms.iter().flat_map(|t| tokenize(t)).collect(),
+            term_patterns,
+            highlight,
+            since: normalize_time(query.since.as_deref()),
+            until: normalize_time(query.until.as_deref()),
+            roles: query.roles.as_ref().map(|r| r.iter().map(|s| s.to_lowercase()).collect()),
+            channels: query.channels.as_ref().map(|c| c.iter().map(|s| s.to_lowercase()).collect()),
+        })
+    }
+
+    /// 在单个文件中搜索，命中的条目追加到 hits
+    fn search_file(&self, file: &IndexedFile, hits: &mut Vec<SearchHit>) {
+        if self.query.sources.as_ref().is_some_and(|s| !s.contains(&file.source)) {
+            return;
+        }
+        if let Some(channels) = &self.channels {
+            if !file.channel.as_ref().is_some_and(|c| channels.contains(&c.to_lowercase())) {
+                return;
+            }
+        }
+
+        for i in file.candidates(&self.tokens) {
+            let entry = &file.entries[i as usize];
+            if let Some(roles) = &self.roles {
+                if !entry.role.as_ref().is_some_and(|r| roles.contains(r)) {
+                    continue;
+                }
+            }
+            if !in_range(entry.timestamp.as_deref(), self.since.as_deref(), self.until.as_deref()) {
+                continue;
+            }
+            if !self.term_patterns.iter().all(|p| p.is_match(&entry.text)) {
+                continue;
+            }
+            let (snippet, score) = build_snippet(&entry.text, &self.highlight);
+            hits.push(SearchHit {
+                source: file.source,
+                id: file.id.clone(),
+                title: file.title.clone(),
+                channel: file.channel.clone(),
+                role: entry.role.clone(),
+                timestamp: entry.timestamp.clone(),
+                line: entry.line,
+                message_index: entry.message_index,
+                snippet,
+                score,
+            });
+        }
+    }
+}
+
+/// 执行搜索
+fn run_search(query: &SearchQuery) -> Result<JsonValue, String> {
+    let matcher = Matcher::new(query)?;
+
+    let mut guard = SEARCH_INDEX.lock().unwrap();
+    let index = guard.get_or_insert_with(SearchIndex::default);
+    let reindexed = index.refresh();
+
+    let mut hits = Vec::new();
+    for file in index.files.values() {
+        matcher.search_file(file, &mut hits);
+    }
+    let indexed_files = index.files.len();
+    drop(guard);
+
+    // 匹配次数多的在前，其次是较新的
+    hits.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| b.timestamp.cmp(&a.timestamp)));
+    let total = hits.len();
+    let offset = query.offset.unwrap_or(0);
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT);
+    let results: Vec<SearchHit> = hits.into_iter().skip(offset).take(limit).collect();
+
+    Ok(json!({
+        "results": results,
+        "total": total,
+        "offset": offset,
+        "has_more": offset + results.len() < total,
+        "indexed_files": indexed_files,
+        "reindexed_files": reindexed
+    }))
+}
+
+/// 全文搜索会话、记忆、workspace 文件与 skills
+#[tauri::command]
+pub async fn search_workspace(query: SearchQuery) -> Result<JsonValue, String> {
+    tokio::task::spawn_blocking(move || run_search(&query))
+        .await
+        .map_err(|e| format!("搜索任务失败: {}", e))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(text: &str, role: Option<&str>, timestamp: Option<&str>, message_index: usize) -> Entry {
+        Entry {
+            text: text.to_string(),
+            role: role.map(|r| r.to_string()),
+            timestamp: timestamp.map(|t| t.to_string()),
+            line: message_index + 1,
+            message_index: Some(message_index),
+        }
+    }
+
+    fn session_file(channel: Option<&str>, entries: Vec<Entry>) -> IndexedFile {
+        IndexedFile {
+            source: SearchSource::Session,
+            id: "telegram_123.jsonl".to_string(),
+            title: "telegram:123".to_string(),
+            channel: channel.map(|c| c.to_string()),
+            modified: None,
+            size: 0,
+            postings: build_postings(&entries),
+            entries,
+        }
+    }
+
+    fn search(file: &IndexedFile, query: SearchQuery) -> Vec<SearchHit> {
+        let matcher = Matcher::new(&query).unwrap();
+        let mut hits = Vec::new();
+        matcher.search_file(file, &mut hits);
+        hits
+    }
+
+    fn text_of(parts: &[SnippetPart]) -> String {
+        parts.iter().map(|p| p.text.as_str()).collect()
+    }
+
+    #[test]
+    fn tokenizes_words_and_cjk_characters() {
+        assert_eq!(tokenize("Hello, World_2!"), vec!["hello", "world_2"]);
+        assert_eq!(tokenize("部署nanobot服务"), vec!["部", "署", "nanobot", "服", "务"]);
+        assert_eq!(tokenize("ÄPFEL über"), vec!["äpfel", "über"]);
+        assert!(tokenize(" ,.!").is_empty());
+    }
+
+    #[test]
+    fn candidates_match_prefixes_of_all_tokens() {
+        let file = session_file(None, vec![
+            entry("deploy the gateway", None, None, 0),
+            entry("deployment finished", None, None, 1),
+            entry("gateway 已启动", None, None, 2),
+        ]);
+        assert_eq!(file.candidates(&tokenize("dep")), vec![0, 1]);
+        assert_eq!(file.candidates(&tokenize("dep gate")), vec![0]);
+        assert_eq!(file.candidates(&tokenize("启动")), vec![2]);
+        assert!(file.candidates(&tokenize("ployment")).is_empty());
+        assert!(file.candidates(&tokenize("deploy missing")).is_empty());
+    }
+
+    #[test]
+    fn snippet_highlights_matches_in_multibyte_text() {
+        let highlight = Regex::new("(?i)错误|error").unwrap();
+        let (parts, score) = build_snippet("启动时出现错误：Error code 1", &highlight);
+        assert_eq!(score, 2);
+        let highlighted: Vec<&str> = parts.iter().filter(|p| p.highlight).map(|p| p.text.as_str()).collect();
+        assert_eq!(highlighted, vec!["错误", "Error"]);
+        assert_eq!(text_of(&parts), "启动时出现错误：Error code 1");
+
+        let (parts, score) = build_snippet("没有匹配", &highlight);
+        assert!(parts.is_empty());
+        assert_eq!(score, 0);
+    }
+
+    #[test]
+    fn snippet_adds_ellipsis_when_truncated() {
+        let highlight = Regex::new("(?i)目标").unwrap();
+        let text = format!("{}目标{}", "前".repeat(100), "后".repeat(200));
+        let (parts, score) = build_snippet(&text, &highlight);
+        assert_eq!(score, 1);
+        assert_eq!(parts.first().unwrap().text, format!("…{}", "前".repeat(SNIPPET_BEFORE)));
+        assert!(parts[1].highlight);
+        assert_eq!(parts[1].text, "目标");
+        assert_eq!(parts.last().unwrap().text, "…");
+        assert_eq!(text_of(&parts).chars().count(), 1 + SNIPPET_BEFORE + SNIPPET_AFTER + 1);
+    }
+
+    #[test]
+    fn filters_by_role_channel_and_date() {
+        let file = session_file(Some("telegram"), vec![
+            entry("restart the gateway", Some("user"), Some("2026-02-09T10:00:00"), 0),
+            entry("Gateway restarted", Some("assistant"), Some("2026-02-10T10:00:00"), 1),
+            entry("gateway is healthy", Some("assistant"), Some("2026-02-11T10:00:00"), 2),
+        ]);
+        let query = |roles: Option<&[&str]>, channels: Option<&[&str]>, since: Option<&str>, until: Option<&str>| {
+            SearchQuery {
+                query: "gateway".to_string(),
+                roles: roles.map(|r| r.iter().map(|s| s.to_string()).collect()),
+                channels: channels.map(|c| c.iter().map(|s| s.to_string()).collect()),
+                since: since.map(|s| s.to_string()),
+                until: until.map(|s| s.to_string()),
+                ..Default::default()
+            }
+        };
+        let indices = |hits: Vec<SearchHit>| hits.iter().map(|h| h.message_index.unwrap()).collect::<Vec<_>>();
+
+        assert_eq!(indices(search(&file, query(None, None, None, None))), vec![0, 1, 2]);
+        assert_eq!(indices(search(&file, query(Some(&["Assistant"]), None, None, None))), vec![1, 2]);
+        assert_eq!(indices(search(&file, query(None, Some(&["Telegram"]), None, None))), vec![0, 1, 2]);
+        assert!(search(&file, query(None, Some(&["discord"]), None, None)).is_empty());
+        assert_eq!(indices(search(&file, query(None, None, Some("2026-02-10"), Some("2026-02-10")))), vec![1]);
+        assert_eq!(indices(search(&file, query(Some(&["user"]), None, None, Some("2026-02-10T23:59:59Z")))), vec![0]);
+
+        let memory_only = SearchQuery { sources: Some(vec![SearchSource::Memory]), ..query(None, None, None, None) };
+        assert!(search(&file, memory_only).is_empty());
+    }
+
+    #[test]
+    fn requires_every_term_as_substring() {
+        let file = session_file(None, vec![
+            entry("cron job failed", None, None, 0),
+            entry("cron job succeeded", None, None, 1),
+        ]);
+        let hits = search(&file, SearchQuery { query: "CRON fail".to_string(), ..Default::default() });
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].message_index, Some(0));
+        assert_eq!(hits[0].score, 2);
+        assert!(Matcher::new(&SearchQuery { query: "   ".to_string(), ..Default::default() }).is_err());
+    }
+
+    #[test]
+    fn matches_queries_without_indexable_words() {
+        let file = session_file(None, vec![
+            entry("call a->b then c::d", None, None, 0),
+            entry("what now? 🚀", None, None, 1),
+            entry("plain text", None, None, 2),
+        ]);
+        let indices = |query: &str| {
+            search(&file, SearchQuery { query: query.to_string(), ..Default::default() })
+                .iter()
+                .map(|h| h.message_index.unwrap())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(indices("->"), vec![0]);
+        assert_eq!(indices("::"), vec![0]);
+        assert_eq!(indices("?"), vec![1]);
+        assert_eq!(indices("🚀"), vec![1]);
+        assert!(indices("=>").is_empty());
+        // 与普通词混合时仍按索引筛选候选
+        assert_eq!(indices("call ->"), vec![0]);
+    }
+}
@@ -338,8 +338,8 @@ pub async fn save_session_memory(session_id: String, content: String) -> Result<
 
     let file_path = memory_path.join(&session_id);
 
-    // 写入文件
-    fs::write(&file_path, content)
+    // 写入文件，并记录历史版本
+    crate::memory_history::write_with_history(&file_path, &format!("memory/{}", session_id), &content)
         .map_err(|e| format!("保存会话内容失败: {}", e))?;
 
     Ok(json!({
@@ -359,8 +359,8 @@ pub async fn save_workspace_file(file_name: String, content: String) -> Result<s
 
     let file_path = workspace_path.join(&file_name);
 
-    // 写入文件
-    fs::write(&file_path, content)
+    // 写入文件，AGENTS.md 等文件同时记录历史版本
+    crate::memory_history::write_with_history(&file_path, &file_name, &content)
         .map_err(|e| format!("保存工作区文件失败: {}", e))?;
 
     Ok(json!({
@@ -1136,7 +1136,7 @@ pub async fn delete_file(relative_path: String) -> Result<serde_json::Value, Str
 
 /// 获取 chat sessions 路径
 /// 动态搜索 sessions 文件夹，优先使用 workspace/sessions，其次使用 .nanobot/sessions
-fn get_chat_sessions_path() -> Result<PathBuf> {
+pub(crate) fn get_chat_sessions_path() -> Result<PathBuf> {
     let home = home_dir().context("无法找到用户主目录")?;
     let nanobot_dir = home.join(".nanobot");
 
@@ -1156,7 +1156,25 @@ fn get_chat_sessions_path() -> Result<PathBuf> {
     Ok(workspace_sessions)
 }
 
+/// 读取会话文件第一行元数据中的会话键（如 telegram:123）
+pub(crate) fn read_chat_session_key(session_id: &str) -> Option<String> {
+    use std::io::BufRead;
+
+    if session_id.contains('/') || session_id.contains('\\') {
+        return None;
+    }
+    let path = get_chat_sessions_path().ok()?.join(session_id);
+    let file = fs::File::open(path).ok()?;
+    let first_line = std::io::BufReader::new(file).lines().next()?.ok()?;
+    let json: serde_json::Value = serde_json::from_str(&first_line).ok()?;
+    if json.get("_type").and_then(|t| t.as_str()) != Some("metadata") {
+        return None;
+    }
+    json.get("key").and_then(|k| k.as_str()).map(|k| k.to_string())
+}
+
 /// 列出所有聊天会话
+/// 只读取每个会话文件的开头生成摘要，并按修改时间与大小缓存
 #[tauri::command]
 pub async fn list_chat_sessions() -> Result<serde_json::Value, String> {
     let sessions_path = get_chat_sessions_path().map_err(|e| e.to_string())?;
@@ -1168,65 +1186,10 @@ pub async fn list_chat_sessions() -> Result<serde_json::Value, String> {
         }));
     }
 
-    let mut sessions = Vec::new();
-
-    // 读取 sessions 目录中的所有 .jsonl 文件
-    if let Ok(entries) = fs::read_dir(&sessions_path) {
-        for entry in entries.flatten() {
-            let path = entry.path();
-            if path.is_file() {
-                if let Some(file_name) = path.file_name().and_then(|n| n.to_str()) {
-                    if file_name.ends_with(".jsonl") {
-                        // 读取文件元数据
-                        if let Ok(metadata) = fs::metadata(&path) {
-                            if let Ok(modified) = metadata.modified() {
-                                // 尝试读取第一行获取会话标题
-                                let title = if let Ok(content) = fs::read_to_string(&path) {
-                                    content
-                                        .lines()
-                                        .next()
-                                        .and_then(|line| {
-                                            if let Ok(json) = serde_json::from_str::<serde_json::Value>(line) {
-                                                json.get("content")
-                                                    .and_then(|c| c.as_str())
-                                                    .map(|s| {
-                                                        // 截取前50个字符作为预览
-                                                        let preview: String = s.chars().take(50).collect();
-                                                        if s.len() > 50 { format!("{}...", preview) } else { preview }
-                                                    })
-                                            } else {
-                                                None
-                                            }
-                                        })
-                                        .unwrap_or_else(|| file_name.to_string())
-                                } else {
-                                    file_name.to_string()
-                                };
-
-                                sessions.push(json!({
-                                    "id": file_name,
-                                    "name": file_name.trim_end_matches(".jsonl"),
-                                    "title": title,
-                                    "path": path.to_string_lossy(),
-                                    "modified": modified.duration_since(std::time::UNIX_EPOCH)
-                                        .unwrap_or_default()
-                                        .as_secs(),
-                                    "size": metadata.len()
-                                }));
-                            }
-                        }
-                    }
-                }
-            }
-        }
-    }
-
-    // 按修改时间倒序排列
-    sessions.sort_by(|a, b| {
-        let a_time = a["modified"].as_u64().unwrap_or(0);
-        let b_time = b["modified"].as_u64().unwrap_or(0);
-        b_time.cmp(&a_time)
-    });
+    let sessions = tokio::task::spawn_blocking(move || crate::session_index::list_summaries(&sessions_path))
+        .await
+        .map_err(|e| format!("读取会话列表失败: {}", e))?
+        .map_err(|e| format!("读取会话列表失败: {}", e))?;
 
     Ok(json!({
         "sessions": sessions,
@@ -1235,8 +1198,18 @@ pub async fn list_chat_sessions() -> Result<serde_json::Value, String> {
 }
 
 /// 获取聊天会话内容并返回结构化消息数据
+/// - offset / limit：分页读取，未指定时返回全部消息
+/// - since：只返回序号大于 since 的消息，用于增量获取新消息；
+///   返回的 generation 变化说明文件被改写，之前获取的消息已失效，需要重新读取
+/// - include_raw：是否同时返回文件原文
 #[tauri::command]
-pub async fn get_chat_session_content(session_id: String) -> Result<serde_json::Value, String> {
+pub async fn get_chat_session_content(
+    session_id: String,
+    offset: Option<usize>,
+    limit: Option<usize>,
+    since: Option<usize>,
+    include_raw: Option<bool>,
+) -> Result<serde_json::Value, String> {
     // 验证 session_id 格式（只允许 .jsonl 文件名）
     if session_id.contains('/') || session_id.contains('\\') || session_id == ".." {
         return Ok(json!({
@@ -1255,45 +1228,38 @@ pub async fn get_chat_session_content(session_id: String) -> Result<serde_json::
         }));
     }
 
-    let content = fs::read_to_string(&session_path)
+    let start = since.map(|n| n + 1).unwrap_or(0).max(offset.unwrap_or(0));
+    let read_path = session_path.clone();
+    let page = tokio::task::spawn_blocking(move || crate::session_index::read_messages(&read_path, start, limit))
+        .await
+        .map_err(|e| format!("读取会话失败: {}", e))?
         .map_err(|e| format!("读取会话失败: {}", e))?;
 
-    // 将 JSONL 转换为结构化消息数组
-    let mut messages = Vec::new();
-
-    for line in content.lines() {
-        if line.trim().is_empty() {
-            continue;
-        }
-
-        if let Ok(json) = serde_json::from_str::<serde_json::Value>(line) {
-            // 只处理有效的消息（有 role 字段且内容非空）
-            if let Some(role) = json.get("role").and_then(|r| r.as_str()) {
-                let msg_content = json.get("content").and_then(|c| c.as_str()).unwrap_or("");
-
-                // 跳过空内容的消息
-                if !msg_content.trim().is_empty() {
-                    messages.push(json!({
-                        "role": role,
-                        "content": msg_content
-                    }));
-                }
-            }
-        }
-    }
-
     let metadata = fs::metadata(&session_path)
         .map_err(|e| format!("读取会话元数据失败: {}", e))?;
 
-    Ok(json!({
+    let mut result = json!({
         "success": true,
         "id": session_id,
         "name": session_id.trim_end_matches(".jsonl"),
-        "messages": messages,
-        "raw_content": content,
+        "header": page.header,
+        "messages": page.messages,
+        "total": page.total,
+        "offset": page.offset,
+        "has_more": page.has_more,
+        "invalid_lines": page.invalid_lines,
+        "generation": page.generation,
         "size": metadata.len(),
         "modified": metadata.modified()
             .map(|t| t.duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs())
             .unwrap_or(0)
-    }))
+    });
+
+    if include_raw.unwrap_or(false) {
+        let content = fs::read_to_string(&session_path)
+            .map_err(|e| format!("读取会话失败: {}", e))?;
+        result["raw_content"] = json!(content);
+    }
+
+    Ok(result)
 }
@@ -0,0 +1,400 @@
+// 聊天会话统计
+// 汇总所有会话：每天各角色的消息数、各渠道的会话数、平均轮次、工具调用频率，
+// 以及消息带有 usage 元数据时的 token 用量与费用。
+// 每个会话文件的统计按天保存，与消息索引共用追加读取进度：文件只是追加时只统计新增的行，
+// 被改写时才重新统计；
+// 按日期范围查询时直接合并缓存中对应日期的数据。
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value as JsonValue};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::chat_session::{ParsedLine, SessionMessage};
+use crate::session_index::AppendTracker;
+
+/// 没有时间戳的消息归入的日期
+const UNKNOWN_DAY: &str = "unknown";
+
+/// 默认返回的会话排行数量
+const DEFAULT_TOP_SESSIONS: usize = 10;
+
+/// 统计条件
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnalyticsQuery {
+    /// 日期范围（含），格式 YYYY-MM-DD
+    pub since: Option<String>,
+    pub until: Option<String>,
+    /// 最近 N 天，未指定 since 时生效
+    pub days: Option<u32>,
+    /// 会话排行的数量
+    pub top: Option<usize>,
+}
+
+/// token 用量
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TokenUsage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub total_tokens: u64,
+    /// 费用（仅当元数据中带有费用时）
+    pub cost: f64,
+    /// 带有 usage 元数据的消息数
+    pub messages: usize,
+}
+
+impl TokenUsage {
+    fn merge(&mut self, other: &TokenUsage) {
+        self.prompt_tokens += other.prompt_tokens;
+        self.completion_tokens += other.completion_tokens;
+        self.total_tokens += other.total_tokens;
+        self.cost += other.cost;
+        self.messages += other.messages;
+    }
+}
+
+/// 某一天的统计
+#[derive(Debug, Clone, Default)]
+struct DayStats {
+    by_role: HashMap<String, usize>,
+    /// 工具名 -> (调用次数, 错误次数)
+    tools: HashMap<String, (usize, usize)>,
+    usage: TokenUsage,
+    usage_by_model: HashMap<String, TokenUsage>,
+}
+
+/// 单个会话文件的统计
+#[derive(Debug, Clone, Default)]
+struct FileStats {
+    id: String,
+    key: Option<String>,
+    channel: Option<String>,
+    days: BTreeMap<String, DayStats>,
+}
+
+/// 单个会话文件的统计与读取进度
+#[derive(Default)]
+struct FileEntry {
+    tracker: AppendTracker,
+    /// 工具调用 ID -> (调用时的日期, 工具名)，工具结果可能在之后追加的行中
+    calls: HashMap<String, (String, String)>,
+    stats: FileStats,
+}
+
+#[derive(Default)]
+struct AnalyticsCache {
+    files: HashMap<PathBuf, FileEntry>,
+}
+
+static ANALYTICS_CACHE: Mutex<Option<AnalyticsCache>> = Mutex::new(None);
+
+fn as_u64(map: &Map<String, JsonValue>, keys: &[&str]) -> u64 {
+    keys.iter().find_map(|k| map.get(*k).and_then(|v| v.as_u64())).unwrap_or(0)
+}
+
+/// 解析消息中的 usage 元数据，兼容 OpenAI（prompt/completion）与 Anthropic（input/output）字段名
+fn parse_usage(extra: &Map<String, JsonValue>) -> Option<TokenUsage> {
+    let usage = extra.get("usage")?.as_object()?;
+    let prompt = as_u64(usage, &["prompt_tokens", "input_tokens"]);
+    let completion = as_u64(usage, &["completion_tokens", "output_tokens"]);
+    let total = match as_u64(usage, &["total_tokens"]) {
+        0 => prompt + completion,
+        total => total,
+    };
+    let cost = ["cost", "total_cost"]
+        .iter()
+        .find_map(|k| usage.get(*k).or_else(|| extra.get(*k)).and_then(|v| v.as_f64()))
+        .unwrap_or(0.0);
+    Some(TokenUsage {
+        prompt_tokens: prompt,
+        completion_tokens: completion,
+        total_tokens: total,
+        cost,
+        messages: 1,
+    })
+}
+
+/// 将一条消息计入统计
+fn add_message(
+    days: &mut BTreeMap<String, DayStats>,
+    calls: &mut HashMap<String, (String, String)>,
+    message: &SessionMessage,
+) {
+    let day = message
+        .timestamp
+        .as_deref()
+        .and_then(|t| t.get(..10))
+        .unwrap_or(UNKNOWN_DAY)
+        .to_string();
+    let day_stats = days.entry(day.clone()).or_default();
+    *day_stats.by_role.entry(message.role.clone()).or_default() += 1;
+
+    for call in &message.tool_calls {
+        day_stats.tools.entry(call.name.clone()).or_default().0 += 1;
+        if let Some(id) = &call.id {
+            calls.insert(id.clone(), (day.clone(), call.name.clone()));
+        }
+    }
+    if let Some(usage) = parse_usage(&message.extra) {
+        let model = message
+            .extra
+            .get("model")
+            .and_then(|m| m.as_str())
+            .unwrap_or("unknown")
+            .to_string();
+        day_stats.usage.merge(&usage);
+        day_stats.usage_by_model.entry(model).or_default().merge(&usage);
+    }
+
+    // 工具结果通过调用 ID 关联回调用时的日期与工具名
+    if message.role == "tool" && crate::chat_session::looks_like_error(&message.content) {
+        if let Some((call_day, name)) = message.tool_call_id.as_ref().and_then(|id| calls.get(id)) {
+            if let Some(tool) = days.get_mut(call_day).and_then(|d| d.tools.get_mut(name)) {
+                tool.1 += 1;
+            }
+        }
+    }
+}
+
+/// 统计文件新增的内容，文件被改写时重新统计，返回文件是否有变化
+fn update_file_stats(entry: &mut FileEntry, path: &Path, id: &str) -> std::io::Result<bool> {
+    let Some(pending) = entry.tracker.open_changes(path)? else {
+        return Ok(false);
+    };
+    if pending.rewritten {
+        entry.stats.days.clear();
+        entry.calls.clear();
+    }
+
+    let (days, calls) = (&mut entry.stats.days, &mut entry.calls);
+    entry.tracker.read_lines(pending, |_, _, parsed| {
+        if let ParsedLine::Message(message) = parsed {
+            add_message(days, calls, &message);
+        }
+    })?;
+
+    entry.stats.id = id.to_string();
+    entry.stats.key = entry.tracker.header.as_ref().and_then(|h| h.key.clone());
+    entry.stats.channel = crate::search::session_channel(entry.stats.key.as_deref(), id);
+    Ok(true)
+}
+
+/// 更新缓存，返回有变化的文件数
+fn refresh(cache: &mut AnalyticsCache) -> Result<usize, String> {
+    let dir = crate::session::get_chat_sessions_path().map_err(|e| e.to_string())?;
+    let mut found = Vec::new();
+    if let Ok(entries) = std::fs::read_dir(&dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Some(name) = path.file_name().and_then(|n| n.to_str()).map(|s| s.to_string()) else {
+                continue;
+            };
+            if name.ends_with(".jsonl") && path.is_file() {
+                found.push((path, name));
+            }
+        }
+    }
+    cache.files.retain(|path, _| found.iter().any(|(p, _)| p == path));
+
+    let mut updated = 0;
+    for (path, name) in found {
+        let entry = cache.files.entry(path.clone()).or_default();
+        match update_file_stats(entry, &path, &name) {
+            Ok(true) => updated += 1,
+            Ok(false) => {}
+            Err(e) => log::warn!("统计会话 {} 失败: {}", name, e),
+        }
+    }
+    Ok(updated)
+}
+
+fn in_range(day: &str, since: Option<&str>, until: Option<&str>) -> bool {
+    if day == UNKNOWN_DAY {
+        return since.is_none() && until.is_none();
+    }
+    since.is_none_or(|s| day >= s) && until.is_none_or(|u| day <= u)
+}
+
+/// 计算统计结果
+fn compute_analytics(query: &AnalyticsQuery) -> Result<JsonValue, String> {
+    let since = query
+        .since
+        .clone()
+        .filter(|s| !s.is_empty())
+        .or_else(|| {
+            query.days.map(|days| {
+                (chrono::Local::now() - chrono::Duration::days(days.saturating_sub(1) as i64))
+                    .format("%Y-%m-%d")
+                    .to_string()
+            })
+        })
+        .map(|s| s.chars().take(10).collect::<String>());
+    let until = query
+        .until
+        .clone()
+        .filter(|s| !s.is_empty())
+        .map(|s| s.chars().take(10).collect::<String>());
+
+    let mut guard = ANALYTICS_CACHE.lock().unwrap();
+    let cache = guard.get_or_insert_with(AnalyticsCache::default);
+    let recomputed = refresh(cache)?;
+
+    let mut per_day: BTreeMap<String, HashMap<String, usize>> = BTreeMap::new();
+    let mut channels: BTreeMap<String, (usize, usize)> = BTreeMap::new();
+    let mut tools: HashMap<String, (usize, usize)> = HashMap::new();
+    let mut usage = TokenUsage::default();
+    let mut usage_by_model: HashMap<String, TokenUsage> = HashMap::new();
+    let mut sessions = Vec::new();
+    let mut total_messages = 0;
+    let mut total_turns = 0;
+
+    for file in cache.files.values().map(|entry| &entry.stats) {
+        let mut messages = 0;
+        let mut turns = 0;
+        for (day, stats) in file.days.iter().filter(|(day, _)| in_range(day, since.as_deref(), until.as_deref())) {
+            let counts = per_day.entry(day.clone()).or_default();
+            for (role, count) in &stats.by_role {
+                *counts.entry(role.clone()).or_default() += count;
+                messages += count;
+            }
+            turns += stats.by_role.get("user").copied().unwrap_or(0);
+            for (name, (calls, errors)) in &stats.tools {
+                let entry = tools.entry(name.clone()).or_default();
+                entry.0 += calls;
+                entry.1 += errors;
+            }
+            usage.merge(&stats.usage);
+            for (model, model_usage) in &stats.usage_by_model {
+                usage_by_model.entry(model.clone()).or_default().merge(model_usage);
+            }
+        }
+        if messages == 0 {
+            continue;
+        }
+
+        let channel = file.channel.clone().unwrap_or_else(|| "unknown".to_string());
+        let entry = channels.entry(channel.clone()).or_default();
+        entry.0 += 1;
+        entry.1 += messages;
+        total_messages += messages;
+        total_turns += turns;
+        sessions.push(json!({
+            "id": file.id,
+            "key": file.key,
+            "channel": channel,
+            "messages": messages,
+            "turns": turns
+        }));
+    }
+    drop(guard);
+
+    let session_count = sessions.len();
+    sessions.sort_by(|a, b| b["messages"].as_u64().cmp(&a["messages"].as_u64()));
+    sessions.truncate(query.top.unwrap_or(DEFAULT_TOP_SESSIONS));
+
+    let messages_per_day: Vec<JsonValue> = per_day
+        .into_iter()
+        .map(|(date, roles)| {
+            let total: usize = roles.values().sum();
+            json!({ "date": date, "roles": roles, "total": total })
+        })
+        .collect();
+    let mut channels: Vec<JsonValue> = channels
+        .into_iter()
+        .map(|(channel, (sessions, messages))| json!({ "channel": channel, "sessions": sessions, "messages": messages }))
+        .collect();
+    channels.sort_by(|a, b| b["messages"].as_u64().cmp(&a["messages"].as_u64()));
+    let mut tools: Vec<JsonValue> = tools
+        .into_iter()
+        .map(|(name, (calls, errors))| json!({ "name": name, "calls": calls, "errors": errors }))
+        .collect();
+    tools.sort_by(|a, b| b["calls"].as_u64().cmp(&a["calls"].as_u64()));
+    let mut models: Vec<JsonValue> = usage_by_model
+        .into_iter()
+        .map(|(model, usage)| json!({ "model": model, "usage": usage }))
+        .collect();
+    models.sort_by(|a, b| b["usage"]["total_tokens"].as_u64().cmp(&a["usage"]["total_tokens"].as_u64()));
+
+    Ok(json!({
+        "since": since,
+        "until": until,
+        "sessions": session_count,
+        "messages": total_messages,
+        "average_turns": if session_count > 0 { total_turns as f64 / session_count as f64 } else { 0.0 },
+        "messages_per_day": messages_per_day,
+        "channels": channels,
+        "top_sessions": sessions,
+        "tools": tools,
+        // 没有任何消息带 usage 元数据时为 null
+        "usage": (usage.messages > 0).then_some(usage),
+        "usage_by_model": models,
+        "recomputed_files": recomputed
+    }))
+}
+
+/// 会话统计
+#[tauri::command]
+pub async fn get_session_analytics(query: Option<AnalyticsQuery>) -> Result<JsonValue, String> {
+    let query = query.unwrap_or_default();
+    tokio::task::spawn_blocking(move || compute_analytics(&query))
+        .await
+        .map_err(|e| format!("会话统计任务失败: {}", e))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn append(path: &Path, lines: &[&str]) {
+        let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path).unwrap();
+        for line in lines {
+            writeln!(file, "{}", line).unwrap();
+        }
+    }
+
+    fn total(entry: &FileEntry, role: &str) -> usize {
+        entry.stats.days.values().filter_map(|d| d.by_role.get(role)).sum()
+    }
+
+    #[test]
+    fn counts_appended_lines_incrementally() {
+        let dir = std::env::temp_dir().join(format!("nanoboard-analytics-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("telegram_42.jsonl");
+        let _ = std::fs::remove_file(&path);
+        append(&path, &[
+            r#"{"_type":"metadata","key":"telegram:42"}"#,
+            r#"{"role":"user","content":"hi","timestamp":"2026-02-10T10:00:00"}"#,
+            r#"{"role":"assistant","content":"","timestamp":"2026-02-10T10:00:01","tool_calls":[{"id":"c1","function":{"name":"exec","arguments":"{}"}}]}"#,
+        ]);
+
+        let mut entry = FileEntry::default();
+        assert!(update_file_stats(&mut entry, &path, "telegram_42.jsonl").unwrap());
+        assert_eq!(entry.stats.key.as_deref(), Some("telegram:42"));
+        assert_eq!(total(&entry, "user"), 1);
+        assert!(!update_file_stats(&mut entry, &path, "telegram_42.jsonl").unwrap());
+
+        // 追加的工具结果关联到之前读取的调用，之前的消息不会重复计数
+        append(&path, &[
+            r#"{"role":"tool","tool_call_id":"c1","content":"Error: denied","timestamp":"2026-02-11T00:00:00"}"#,
+            r#"{"role":"user","content":"again","timestamp":"2026-02-11T00:00:05","usage":{"input_tokens":3,"output_tokens":4}}"#,
+        ]);
+        assert!(update_file_stats(&mut entry, &path, "telegram_42.jsonl").unwrap());
+        assert_eq!(total(&entry, "user"), 2);
+        assert_eq!(total(&entry, "assistant"), 1);
+        assert_eq!(entry.stats.days["2026-02-10"].tools["exec"], (1, 1));
+        assert_eq!(entry.stats.days["2026-02-11"].usage.total_tokens, 7);
+
+        // 文件被改写时重新统计
+        std::fs::write(&path, "{\"role\":\"user\",\"content\":\"new\",\"timestamp\":\"2026-03-01T00:00:00\"}\n").unwrap();
+        assert!(update_file_stats(&mut entry, &path, "telegram_42.jsonl").unwrap());
+        assert_eq!(total(&entry, "user"), 1);
+        assert_eq!(entry.stats.days.keys().collect::<Vec<_>>(), vec!["2026-03-01"]);
+        assert!(entry.calls.is_empty());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,536 @@
+// 聊天会话管理：归档、回收站与恢复
+// - 归档：移动到 sessions/.archive/，nanobot 与会话列表只读取 sessions/ 下的 .jsonl，因此归档后隐藏；
+// - 删除：移动到 sessions/.trash/ 并在 trash.json 中记录删除时间与过期时间，过期后自动清除；
+// - 恢复：从归档或回收站移回 sessions/，同名会话已存在时拒绝；
+// - 批量操作：按最后修改时间与渠道筛选会话。
+// nanobot 网关会在内存中保留会话，下次保存时重新创建被移走的文件，导致会话被拆成两份；
+// 因此网关运行时默认拒绝移动，传入 force 时仍会拒绝最近被写入（确定正在使用）的会话。
+// 回收站清单的读写由同一把锁保护，过期条目在应用启动、删除与列出回收站时清除。
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value as JsonValue};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+use crate::session_index::list_summaries;
+
+/// 归档目录名
+const ARCHIVE_DIR: &str = ".archive";
+
+/// 回收站目录名
+const TRASH_DIR: &str = ".trash";
+
+/// 回收站清单文件
+const TRASH_MANIFEST: &str = "trash.json";
+
+/// 回收站默认保留天数
+const DEFAULT_RETENTION_DAYS: u32 = 30;
+
+/// 网关运行时，最近多久内被写入的会话视为正在使用
+const ACTIVE_WINDOW: Duration = Duration::from_secs(5 * 60);
+
+/// 保护回收站清单的读取-修改-写入
+static TRASH_LOCK: Mutex<()> = Mutex::new(());
+
+/// 回收站条目
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrashEntry {
+    /// 回收站中的文件名
+    pub id: String,
+    /// 原会话文件名
+    pub session_id: String,
+    pub key: Option<String>,
+    pub title: String,
+    pub size: u64,
+    /// RFC 3339 时间
+    pub deleted_at: String,
+    pub expires_at: String,
+}
+
+/// 批量操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BulkAction {
+    Archive,
+    Trash,
+}
+
+/// 批量操作条件
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkSessionRequest {
+    pub action: BulkAction,
+    /// 最后修改时间早于 N 天前的会话
+    pub older_than_days: Option<u32>,
+    /// 限定渠道（如 telegram、cli）
+    pub channels: Option<Vec<String>>,
+    /// 只返回将被处理的会话，不实际移动
+    pub dry_run: Option<bool>,
+    /// 回收站保留天数（action 为 trash 时）
+    pub retention_days: Option<u32>,
+    /// 网关运行时仍然移动（最近被写入的会话除外）
+    pub force: Option<bool>,
+}
+
+fn sessions_dir() -> Result<PathBuf, String> {
+    crate::session::get_chat_sessions_path().map_err(|e| e.to_string())
+}
+
+fn validate_session_id(id: &str) -> Result<(), String> {
+    if id.contains('/') || id.contains('\\') || id.starts_with('.') || !id.ends_with(".jsonl") {
+        return Err(format!("无效的会话 ID 格式: {}", id));
+    }
+    Ok(())
+}
+
+/// 检查网关运行时能否移动该会话
+fn check_movable(path: &Path, id: &str, gateway_running: bool, force: bool) -> Result<(), String> {
+    if !gateway_running {
+        return Ok(());
+    }
+    if !force {
+        return Err(format!(
+            "nanobot 网关正在运行，移动会话 {} 后网关可能重新创建该文件；请先停止网关，或使用 force 强制执行",
+            id
+        ));
+    }
+    let recently_written = std::fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|m| SystemTime::now().duration_since(m).ok())
+        .is_some_and(|age| age < ACTIVE_WINDOW);
+    if recently_written {
+        return Err(format!("会话 {} 正在被 nanobot 使用，请稍后再试", id));
+    }
+    Ok(())
+}
+
+/// 网关运行时强制移动的提示
+fn force_warning(gateway_running: bool) -> Option<&'static str> {
+    gateway_running.then_some("nanobot 网关正在运行，若网关仍持有这些会话，下次保存时可能重新创建同名文件")
+}
+
+fn load_trash(trash_dir: &Path) -> Vec<TrashEntry> {
+    std::fs::read_to_string(trash_dir.join(TRASH_MANIFEST))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_trash(trash_dir: &Path, entries: &[TrashEntry]) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(entries).map_err(|e| format!("序列化回收站清单失败: {}", e))?;
+    std::fs::write(trash_dir.join(TRASH_MANIFEST), content).map_err(|e| format!("写入回收站清单失败: {}", e))
+}
+
+/// 在锁内读取回收站清单并修改，修改后（f 返回 true 时）写回
+fn update_trash<T>(trash_dir: &Path, f: impl FnOnce(&mut Vec<TrashEntry>) -> (T, bool)) -> Result<T, String> {
+    let _guard = TRASH_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut entries = load_trash(trash_dir);
+    let (result, changed) = f(&mut entries);
+    if changed {
+        save_trash(trash_dir, &entries)?;
+    }
+    Ok(result)
+}
+
+/// 清除过期的回收站条目，返回清除的数量
+fn purge_expired(trash_dir: &Path, entries: &mut Vec<TrashEntry>) -> usize {
+    let now = chrono::Local::now();
+    let before = entries.len();
+    entries.retain(|entry| {
+        let expired = chrono::DateTime::parse_from_rfc3339(&entry.expires_at).is_ok_and(|t| t < now);
+        if expired {
+            let _ = std::fs::remove_file(trash_dir.join(&entry.id));
+        }
+        !expired
+    });
+    // 清单中没有记录的文件（例如手动放入的）不处理；清单中文件已不存在的条目移除
+    entries.retain(|entry| trash_dir.join(&entry.id).exists());
+    before - entries.len()
+}
+
+/// 单个会话的处理结果
+fn outcome(session_id: &str, error: Option<String>) -> JsonValue {
+    json!({
+        "session_id": session_id,
+        "success": error.is_none(),
+        "message": error
+    })
+}
+
+/// 将会话移动到归档目录
+fn archive_one(dir: &Path, id: &str, gateway_running: bool, force: bool) -> Result<(), String> {
+    validate_session_id(id)?;
+    let source = dir.join(id);
+    if !source.exists() {
+        return Err(format!("会话 {} 不存在", id));
+    }
+    check_movable(&source, id, gateway_running, force)?;
+    let archive_dir = dir.join(ARCHIVE_DIR);
+    std::fs::create_dir_all(&archive_dir).map_err(|e| format!("创建归档目录失败: {}", e))?;
+    let target = archive_dir.join(id);
+    if target.exists() {
+        return Err(format!("归档中已存在同名会话 {}", id));
+    }
+    std::fs::rename(&source, &target).map_err(|e| format!("归档会话 {} 失败: {}", id, e))
+}
+
+/// 将会话移动到回收站
+fn trash_one(
+    dir: &Path,
+    id: &str,
+    gateway_running: bool,
+    force: bool,
+    retention_days: u32,
+    entries: &mut Vec<TrashEntry>,
+) -> Result<(), String> {
+    validate_session_id(id)?;
+    let source = dir.join(id);
+    if !source.exists() {
+        return Err(format!("会话 {} 不存在", id));
+    }
+    check_movable(&source, id, gateway_running, force)?;
+    let summary = list_summaries(dir)
+        .ok()
+        .and_then(|list| list.into_iter().find(|s| s.id == id));
+    let size = std::fs::metadata(&source).map(|m| m.len()).unwrap_or(0);
+
+    let trash_dir = dir.join(TRASH_DIR);
+    std::fs::create_dir_all(&trash_dir).map_err(|e| format!("创建回收站目录失败: {}", e))?;
+    let now = chrono::Local::now();
+    // 同一会话可能被多次删除（网关重新创建后再删除），用时间戳区分
+    let trash_id = format!("{}__{}", now.format("%Y%m%d%H%M%S%3f"), id);
+    std::fs::rename(&source, trash_dir.join(&trash_id)).map_err(|e| format!("删除会话 {} 失败: {}", id, e))?;
+
+    entries.push(TrashEntry {
+        id: trash_id,
+        session_id: id.to_string(),
+        key: summary.as_ref().and_then(|s| s.key.clone()),
+        title: summary.map(|s| s.title).unwrap_or_else(|| id.to_string()),
+        size,
+        deleted_at: now.to_rfc3339(),
+        expires_at: (now + chrono::Duration::days(retention_days as i64)).to_rfc3339(),
+    });
+    Ok(())
+}
+
+/// 归档会话，网关运行时需要 force
+#[tauri::command]
+pub async fn archive_chat_sessions(session_ids: Vec<String>, force: Option<bool>) -> Result<JsonValue, String> {
+    let dir = sessions_dir()?;
+    let gateway_running = crate::process::check_nanobot_running();
+    let force = force.unwrap_or(false);
+    let results: Vec<JsonValue> = session_ids
+        .iter()
+        .map(|id| outcome(id, archive_one(&dir, id, gateway_running, force).err()))
+        .collect();
+    let archived = results.iter().filter(|r| r["success"] == true).count();
+
+    Ok(json!({
+        "success": archived == session_ids.len(),
+        "message": format!("已归档 {} 个会话", archived),
+        "gateway_running": gateway_running,
+        "warning": force_warning(gateway_running && archived > 0),
+        "results": results
+    }))
+}
+
+/// 取消归档，将会话移回会话目录
+#[tauri::command]
+pub async fn unarchive_chat_sessions(session_ids: Vec<String>) -> Result<JsonValue, String> {
+    let dir = sessions_dir()?;
+    let archive_dir = dir.join(ARCHIVE_DIR);
+    let results: Vec<JsonValue> = session_ids
+        .iter()
+        .map(|id| {
+            let result = validate_session_id(id).and_then(|_| {
+                let source = archive_dir.join(id);
+                let target = dir.join(id);
+                if !source.exists() {
+                    return Err(format!("归档中不存在会话 {}", id));
+                }
+                if target.exists() {
+                    return Err(format!("会话 {} 已存在，无法恢复", id));
+                }
+                std::fs::rename(&source, &target).map_err(|e| format!("恢复会话 {} 失败: {}", id, e))
+            });
+            outcome(id, result.err())
+        })
+        .collect();
+    let restored = results.iter().filter(|r| r["success"] == true).count();
+
+    Ok(json!({
+        "success": restored == session_ids.len(),
+        "message": format!("已恢复 {} 个会话", restored),
+        "results": results
+    }))
+}
+
+/// 列出已归档的会话
+#[tauri::command]
+pub async fn list_archived_chat_sessions() -> Result<JsonValue, String> {
+    let archive_dir = sessions_dir()?.join(ARCHIVE_DIR);
+    if !archive_dir.exists() {
+        return Ok(json!({ "sessions": [], "total": 0 }));
+    }
+    let sessions = tokio::task::spawn_blocking(move || list_summaries(&archive_dir))
+        .await
+        .map_err(|e| format!("读取归档会话失败: {}", e))?
+        .map_err(|e| format!("读取归档会话失败: {}", e))?;
+
+    Ok(json!({
+        "sessions": sessions,
+        "total": sessions.len()
+    }))
+}
+
+/// 删除会话到回收站（同时清除过期条目），网关运行时需要 force
+#[tauri::command]
+pub async fn trash_chat_sessions(
+    session_ids: Vec<String>,
+    retention_days: Option<u32>,
+    force: Option<bool>,
+) -> Result<JsonValue, String> {
+    let dir = sessions_dir()?;
+    let gateway_running = crate::process::check_nanobot_running();
+    let force = force.unwrap_or(false);
+    let retention_days = retention_days.unwrap_or(DEFAULT_RETENTION_DAYS).max(1);
+    let trash_dir = dir.join(TRASH_DIR);
+
+    let (results, purged) = update_trash(&trash_dir, |entries| {
+        let purged = purge_expired(&trash_dir, entries);
+        let results: Vec<JsonValue> = session_ids
+            .iter()
+            .map(|id| outcome(id, trash_one(&dir, id, gateway_running, force, retention_days, entries).err()))
+            .collect();
+        let changed = purged > 0 || results.iter().any(|r| r["success"] == true);
+        ((results, purged), changed)
+    })?;
+    let trashed = results.iter().filter(|r| r["success"] == true).count();
+
+    Ok(json!({
+        "success": trashed == session_ids.len(),
+        "message": format!("已将 {} 个会话移到回收站，{} 天后自动清除", trashed, retention_days),
+        "gateway_running": gateway_running,
+        "warning": force_warning(gateway_running && trashed > 0),
+        "purged": purged,
+        "results": results
+    }))
+}
+
+/// 启动时清除回收站中的过期条目
+pub async fn purge_expired_trash() {
+    let result = tokio::task::spawn_blocking(|| {
+        let trash_dir = sessions_dir()?.join(TRASH_DIR);
+        if !trash_dir.exists() {
+            return Ok(0);
+        }
+        update_trash(&trash_dir, |entries| {
+            let purged = purge_expired(&trash_dir, entries);
+            (purged, purged > 0)
+        })
+    })
+    .await;
+    match result {
+        Ok(Ok(purged)) if purged > 0 => log::info!("已清除 {} 个过期的回收站会话", purged),
+        Ok(Ok(_)) => {}
+        Ok(Err(e)) => log::warn!("清除过期的回收站会话失败: {}", e),
+        Err(e) => log::warn!("清除过期的回收站会话失败: {}", e),
+    }
+}
+
+/// 列出回收站中的会话（同时清除过期条目）
+#[tauri::command]
+pub async fn list_trashed_chat_sessions() -> Result<JsonValue, String> {
+    let trash_dir = sessions_dir()?.join(TRASH_DIR);
+    let (mut entries, purged) = update_trash(&trash_dir, |entries| {
+        let purged = purge_expired(&trash_dir, entries);
+        ((entries.clone(), purged), purged > 0)
+    })?;
+    entries.sort_by(|a, b| b.deleted_at.cmp(&a.deleted_at));
+
+    Ok(json!({
+        "entries": entries,
+        "total": entries.len(),
+        "purged": purged
+    }))
+}
+
+/// 从回收站恢复会话
+#[tauri::command]
+pub async fn restore_chat_sessions(trash_ids: Vec<String>) -> Result<JsonValue, String> {
+    let dir = sessions_dir()?;
+    let trash_dir = dir.join(TRASH_DIR);
+    let results = update_trash(&trash_dir, |entries| {
+        let mut results = Vec::new();
+        for trash_id in &trash_ids {
+            let result = match entries.iter().position(|e| &e.id == trash_id) {
+                None => Err(format!("回收站中不存在 {}", trash_id)),
+                Some(index) => {
+                    let session_id = entries[index].session_id.clone();
+                    let target = dir.join(&session_id);
+                    if target.exists() {
+                        Err(format!("会话 {} 已存在，无法恢复", session_id))
+                    } else {
+                        std::fs::rename(trash_dir.join(trash_id), &target)
+                            .map_err(|e| format!("恢复会话 {} 失败: {}", session_id, e))
+                            .map(|_| {
+                                entries.remove(index);
+                            })
+                    }
+                }
+            };
+            results.push(json!({
+                "trash_id": trash_id,
+                "success": result.is_ok(),
+                "message": result.err()
+            }));
+        }
+        let changed = results.iter().any(|r| r["success"] == true);
+        (results, changed)
+    })?;
+    let restored = results.iter().filter(|r| r["success"] == true).count();
+
+    Ok(json!({
+        "success": restored == trash_ids.len(),
+        "message": format!("已恢复 {} 个会话", restored),
+        "results": results
+    }))
+}
+
+/// 永久删除回收站中的会话，未指定时清空回收站
+#[tauri::command]
+pub async fn purge_trashed_chat_sessions(trash_ids: Option<Vec<String>>) -> Result<JsonValue, String> {
+    let trash_dir = sessions_dir()?.join(TRASH_DIR);
+    let purged = update_trash(&trash_dir, |entries| {
+        let before = entries.len();
+        entries.retain(|entry| {
+            let selected = trash_ids.as_ref().is_none_or(|ids| ids.contains(&entry.id));
+            if selected {
+                let _ = std::fs::remove_file(trash_dir.join(&entry.id));
+            }
+            !selected
+        });
+        let purged = before - entries.len();
+        (purged, purged > 0)
+    })?;
+
+    Ok(json!({
+        "success": true,
+        "message": format!("已永久删除 {} 个会话", purged),
+        "purged": purged
+    }))
+}
+
+/// 按修改时间与渠道批量归档或删除会话
+#[tauri::command]
+pub async fn bulk_manage_chat_sessions(request: BulkSessionRequest) -> Result<JsonValue, String> {
+    if request.older_than_days.is_none() && request.channels.as_ref().is_none_or(|c| c.is_empty()) {
+        return Err("请至少指定时间或渠道条件".to_string());
+    }
+    let dir = sessions_dir()?;
+    let summaries = list_summaries(&dir).map_err(|e| format!("读取会话列表失败: {}", e))?;
+
+    let cutoff = request.older_than_days.map(|days| {
+        SystemTime::now()
+            .checked_sub(Duration::from_secs(days as u64 * 86400))
+            .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+            .map(|d| d.as_secs())
+            .unwrap_or(0)
+    });
+    let channels: Option<Vec<String>> = request
+        .channels
+        .as_ref()
+        .filter(|c| !c.is_empty())
+        .map(|c| c.iter().map(|s| s.to_lowercase()).collect());
+
+    let selected: Vec<String> = summaries
+        .iter()
+        .filter(|s| cutoff.is_none_or(|cutoff| s.modified < cutoff))
+        .filter(|s| {
+            channels.as_ref().is_none_or(|channels| {
+                crate::search::session_channel(s.key.as_deref(), &s.id).is_some_and(|c| channels.contains(&c.to_lowercase()))
+            })
+        })
+        .map(|s| s.id.clone())
+        .collect();
+
+    if request.dry_run.unwrap_or(false) {
+        return Ok(json!({
+            "success": true,
+            "dry_run": true,
+            "session_ids": selected,
+            "total": selected.len()
+        }));
+    }
+
+    let mut result = match request.action {
+        BulkAction::Archive => archive_chat_sessions(selected.clone(), request.force).await?,
+        BulkAction::Trash => trash_chat_sessions(selected.clone(), request.retention_days, request.force).await?,
+    };
+    result["session_ids"] = json!(selected);
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: &str, expires_at: chrono::DateTime<chrono::Local>) -> TrashEntry {
+        TrashEntry {
+            id: id.to_string(),
+            session_id: "cli_direct.jsonl".to_string(),
+            key: None,
+            title: String::new(),
+            size: 0,
+            deleted_at: chrono::Local::now().to_rfc3339(),
+            expires_at: expires_at.to_rfc3339(),
+        }
+    }
+
+    #[test]
+    fn refuses_moves_while_gateway_runs() {
+        let dir = std::env::temp_dir().join(format!("nanoboard-archive-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("cli_direct.jsonl"), "{}\n").unwrap();
+
+        assert!(archive_one(&dir, "cli_direct.jsonl", true, false).is_err());
+        // 强制执行时仍拒绝最近被写入的会话
+        assert!(archive_one(&dir, "cli_direct.jsonl", true, true).unwrap_err().contains("正在被 nanobot 使用"));
+        assert!(archive_one(&dir, "../x.jsonl", false, false).is_err());
+        archive_one(&dir, "cli_direct.jsonl", false, false).unwrap();
+        assert!(dir.join(ARCHIVE_DIR).join("cli_direct.jsonl").exists());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn purges_expired_trash_entries() {
+        let trash_dir = std::env::temp_dir().join(format!("nanoboard-trash-{}", std::process::id()));
+        std::fs::create_dir_all(&trash_dir).unwrap();
+        let now = chrono::Local::now();
+        for id in ["expired", "kept"] {
+            std::fs::write(trash_dir.join(id), "{}\n").unwrap();
+        }
+        let entries = vec![
+            entry("expired", now - chrono::Duration::days(1)),
+            entry("kept", now + chrono::Duration::days(1)),
+            entry("missing", now + chrono::Duration::days(1)),
+        ];
+        save_trash(&trash_dir, &entries).unwrap();
+
+        let purged = update_trash(&trash_dir, |entries| {
+            let purged = purge_expired(&trash_dir, entries);
+            (purged, purged > 0)
+        })
+        .unwrap();
+        assert_eq!(purged, 2);
+        assert!(!trash_dir.join("expired").exists());
+        let ids: Vec<String> = load_trash(&trash_dir).into_iter().map(|e| e.id).collect();
+        assert_eq!(ids, vec!["kept".to_string()]);
+
+        let _ = std::fs::remove_dir_all(&trash_dir);
+    }
+}
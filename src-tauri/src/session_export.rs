@@ -0,0 +1,580 @@
+// 聊天会话导出
+// 将一个或多个会话导出为单个文件，便于交给相关人员查看：
+// - markdown：每个会话一节，工具调用使用 <details> 折叠；
+// - html：独立的 HTML 页面（内联样式，无外部依赖），工具调用可折叠；
+// - json：规范化的消息结构（与 get_chat_session_content 返回的消息相同）。
+// 可按消息时间过滤，并可选对密钥与个人信息（邮箱、电话、IP）脱敏。
+
+use serde::Deserialize;
+use serde_json::{json, Value as JsonValue};
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+use crate::chat_session::{ContentPart, SessionHeader, SessionMessage};
+use crate::log_export::default_export_path;
+use crate::redaction::{Redactor, REDACTED};
+use crate::time_range::{in_range, normalize_time};
+
+/// 导出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SessionExportFormat {
+    Markdown,
+    Html,
+    Json,
+}
+
+impl SessionExportFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            SessionExportFormat::Markdown => "md",
+            SessionExportFormat::Html => "html",
+            SessionExportFormat::Json => "json",
+        }
+    }
+}
+
+/// 会话导出请求
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionExportRequest {
+    /// 会话文件名（如 telegram_123.jsonl）
+    pub session_ids: Vec<String>,
+    pub format: SessionExportFormat,
+    /// 只导出该时间范围内的消息（含），格式同消息时间戳，可只给日期
+    pub since: Option<String>,
+    pub until: Option<String>,
+    /// 是否脱敏密钥与个人信息，默认关闭
+    pub redact: Option<bool>,
+    /// 是否包含工具调用与工具结果，默认包含
+    pub include_tools: Option<bool>,
+    /// 导出文件路径，未指定时保存到下载目录
+    pub path: Option<String>,
+}
+
+/// 待导出的会话
+struct ExportSession {
+    id: String,
+    header: Option<SessionHeader>,
+    messages: Vec<SessionMessage>,
+}
+
+impl ExportSession {
+    fn title(&self) -> String {
+        self.header
+            .as_ref()
+            .and_then(|h| h.key.clone())
+            .unwrap_or_else(|| self.id.trim_end_matches(".jsonl").to_string())
+    }
+}
+
+fn role_label(role: &str) -> &str {
+    match role {
+        "user" => "用户",
+        "assistant" => "助手",
+        "system" => "系统",
+        "tool" => "工具",
+        other => other,
+    }
+}
+
+fn redact_option(redactor: &Redactor, value: &mut Option<String>) {
+    if let Some(text) = value {
+        *text = redactor.redact(text).into_owned();
+    }
+}
+
+/// 对消息中的文本、附件、工具参数与结果脱敏
+/// 本地路径与外部链接可能包含用户名等信息，整体替换；内嵌的 data: 图片保留
+fn redact_message(redactor: &Redactor, message: &mut SessionMessage) {
+    message.content = redactor.redact(&message.content).into_owned();
+    for part in &mut message.parts {
+        match part {
+            ContentPart::Text { text } => *text = redactor.redact(text).into_owned(),
+            ContentPart::Image { url, .. } if !url.starts_with("data:") => *url = REDACTED.to_string(),
+            ContentPart::Image { .. } => {}
+            ContentPart::File { name, path, .. } => {
+                redact_option(redactor, name);
+                if path.is_some() {
+                    *path = Some(REDACTED.to_string());
+                }
+            }
+            ContentPart::Other { raw } => *raw = redactor.redact_json(raw),
+        }
+    }
+    for call in &mut message.tool_calls {
+        call.arguments = redactor.redact_json(&call.arguments);
+        redact_option(redactor, &mut call.result);
+    }
+    redact_option(redactor, &mut message.name);
+    for value in message.extra.values_mut() {
+        *value = redactor.redact_json(value);
+    }
+}
+
+/// 对会话标识、元数据与全部消息脱敏（会话键可能包含邮箱或电话号码）
+fn redact_session(redactor: &Redactor, session: &mut ExportSession) {
+    session.id = redactor.redact(&session.id).into_owned();
+    if let Some(header) = &mut session.header {
+        redact_option(redactor, &mut header.key);
+        if let Some(metadata) = &mut header.metadata {
+            *metadata = redactor.redact_json(metadata);
+        }
+        for value in header.extra.values_mut() {
+            *value = redactor.redact_json(value);
+        }
+    }
+    for message in &mut session.messages {
+        redact_message(redactor, message);
+    }
+}
+
+/// 读取并过滤会话
+fn load_session(id: &str, request: &SessionExportRequest, redactor: Option<&Redactor>) -> Result<ExportSession, String> {
+    if id.contains('/') || id.contains('\\') || id == ".." || !id.ends_with(".jsonl") {
+        return Err(format!("无效的会话 ID 格式: {}", id));
+    }
+    let path = crate::session::get_chat_sessions_path()
+        .map_err(|e| e.to_string())?
+        .join(id);
+    if !path.exists() {
+        return Err(format!("会话 {} 不存在", id));
+    }
+    read_session(&path, id, request, redactor)
+}
+
+/// 读取会话文件，按请求过滤消息并脱敏
+fn read_session(
+    path: &Path,
+    id: &str,
+    request: &SessionExportRequest,
+    redactor: Option<&Redactor>,
+) -> Result<ExportSession, String> {
+    let page = crate::session_index::read_messages(path, 0, None)
+        .map_err(|e| format!("读取会话 {} 失败: {}", id, e))?;
+
+    let since = normalize_time(request.since.as_deref());
+    let until = normalize_time(request.until.as_deref());
+    let include_tools = request.include_tools.unwrap_or(true);
+
+    let messages = page
+        .messages
+        .into_iter()
+        .filter(|m| in_range(m.timestamp.as_deref(), since.as_deref(), until.as_deref()))
+        .filter(|m| include_tools || m.role != "tool")
+        .map(|mut m| {
+            if !include_tools {
+                m.tool_calls.clear();
+            }
+            m
+        })
+        // 没有文本也没有工具调用的消息在导出中没有意义
+        .filter(|m| !m.content.trim().is_empty() || !m.tool_calls.is_empty() || !m.parts.is_empty())
+        .collect();
+
+    let mut session = ExportSession {
+        id: id.to_string(),
+        header: page.header,
+        messages,
+    };
+    if let Some(redactor) = redactor {
+        redact_session(redactor, &mut session);
+    }
+    Ok(session)
+}
+
+/// 工具结果已关联到调用上时，不再单独输出 tool 消息
+fn is_attached_result(session: &ExportSession, message: &SessionMessage) -> bool {
+    message.role == "tool"
+        && message.tool_call_id.as_ref().is_some_and(|id| {
+            session
+                .messages
+                .iter()
+                .any(|m| m.tool_calls.iter().any(|c| c.id.as_ref() == Some(id) && c.result.is_some()))
+        })
+}
+
+fn format_arguments(arguments: &JsonValue) -> String {
+    match arguments {
+        JsonValue::String(s) => s.clone(),
+        other => serde_json::to_string_pretty(other).unwrap_or_default(),
+    }
+}
+
+/// 生成足够长的代码块围栏，避免与内容中的反引号冲突
+fn fence(content: &str) -> String {
+    let mut longest = 0;
+    let mut current = 0;
+    for c in content.chars() {
+        if c == '`' {
+            current += 1;
+            longest = longest.max(current);
+        } else {
+            current = 0;
+        }
+    }
+    "`".repeat(longest.max(2) + 1)
+}
+
+fn to_markdown(sessions: &[ExportSession]) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "# 会话记录\n\n导出时间：{}\n", chrono::Local::now().format("%Y-%m-%d %H:%M:%S"));
+
+    for session in sessions {
+        let _ = writeln!(out, "## {}\n", session.title());
+        if let Some(created) = session.header.as_ref().and_then(|h| h.created_at.as_deref()) {
+            let _ = writeln!(out, "创建时间：{}  ", created);
+        }
+        let _ = writeln!(out, "消息数：{}\n", session.messages.len());
+
+        for message in &session.messages {
+            if is_attached_result(session, message) {
+                continue;
+            }
+            let label = match (message.role.as_str(), &message.name) {
+                ("tool", Some(name)) => format!("{} `{}`", role_label("tool"), name),
+                (role, _) => role_label(role).to_string(),
+            };
+            match &message.timestamp {
+                Some(ts) => {
+                    let _ = writeln!(out, "### {} · {}\n", label, ts);
+                }
+                None => {
+                    let _ = writeln!(out, "### {}\n", label);
+                }
+            }
+            if !message.content.trim().is_empty() {
+                let _ = writeln!(out, "{}\n", message.content.trim_end());
+            }
+            for part in &message.parts {
+                match part {
+                    ContentPart::Image { url, .. } if !url.starts_with("data:") && url != REDACTED => {
+                        let _ = writeln!(out, "![image]({})\n", url);
+                    }
+                    ContentPart::Image { url, .. } if url == REDACTED => {
+                        let _ = writeln!(out, "*[图片]*\n");
+                    }
+                    ContentPart::Image { .. } => {
+                        let _ = writeln!(out, "*[内嵌图片]*\n");
+                    }
+                    ContentPart::File { name, path, .. } => {
+                        let _ = writeln!(out, "*[文件: {}]*\n", name.as_deref().or(path.as_deref()).unwrap_or("-"));
+                    }
+                    _ => {}
+                }
+            }
+            for call in &message.tool_calls {
+                let arguments = format_arguments(&call.arguments);
+                let mut summary = format!("工具调用 <code>{}</code>", html_escape(&call.name));
+                if let Some(ms) = call.duration_ms {
+                    let _ = write!(summary, " · {} ms", ms);
+                }
+                if call.is_error {
+                    summary.push_str(" · 错误");
+                }
+                let _ = writeln!(out, "<details>\n<summary>{}</summary>\n", summary);
+                let f = fence(&arguments);
+                let _ = writeln!(out, "参数：\n\n{}json\n{}\n{}\n", f, arguments, f);
+                if let Some(result) = &call.result {
+                    let f = fence(result);
+                    let _ = writeln!(out, "结果：\n\n{}\n{}\n{}\n", f, result.trim_end(), f);
+                }
+                let _ = writeln!(out, "</details>\n");
+            }
+        }
+    }
+    out
+}
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+const HTML_STYLE: &str = "body{font-family:-apple-system,BlinkMacSystemFont,'Segoe UI',sans-serif;max-width:900px;margin:2em auto;padding:0 1em;color:#1f2937;background:#f9fafb}\
+h1{font-size:1.6em}h2{margin-top:2em;border-bottom:1px solid #e5e7eb;padding-bottom:.3em}\
+.meta{color:#6b7280;font-size:.9em}\
+.msg{margin:1em 0;padding:.8em 1em;border-radius:12px;background:#fff;border:1px solid #e5e7eb}\
+.msg.user{background:#eff6ff;border-color:#bfdbfe}.msg.tool,.msg.system{background:#f3f4f6}\
+.role{font-weight:600;font-size:.9em}.time{color:#9ca3af;font-size:.8em;margin-left:.5em}\
+.content{white-space:pre-wrap;word-break:break-word;margin-top:.4em}\
+details{margin-top:.6em;border:1px solid #e5e7eb;border-radius:8px;padding:.4em .8em;background:#f9fafb}\
+summary{cursor:pointer;font-size:.9em}.error{color:#dc2626}\
+pre{white-space:pre-wrap;word-break:break-word;background:#111827;color:#e5e7eb;padding:.6em;border-radius:6px;font-size:.85em}\
+img{max-width:100%;border-radius:8px;margin-top:.4em}";
+
+fn to_html(sessions: &[ExportSession]) -> String {
+    let mut out = String::new();
+    let _ = write!(
+        out,
+        "<!DOCTYPE html>\n<html lang=\"zh-CN\">\n<head>\n<meta charset=\"utf-8\">\n<title>会话记录</title>\n<style>{}</style>\n</head>\n<body>\n<h1>会话记录</h1>\n<p class=\"meta\">导出时间：{}</p>\n",
+        HTML_STYLE,
+        chrono::Local::now().format("%Y-%m-%d %H:%M:%S")
+    );
+
+    for session in sessions {
+        let _ = writeln!(out, "<section>\n<h2>{}</h2>", html_escape(&session.title()));
+        let created = session
+            .header
+            .as_ref()
+            .and_then(|h| h.created_at.as_deref())
+            .map(|c| format!("创建时间：{} · ", html_escape(c)))
+            .unwrap_or_default();
+        let _ = writeln!(out, "<p class=\"meta\">{}消息数：{}</p>", created, session.messages.len());
+
+        for message in &session.messages {
+            if is_attached_result(session, message) {
+                continue;
+            }
+            let label = match (message.role.as_str(), &message.name) {
+                ("tool", Some(name)) => format!("{} {}", role_label("tool"), name),
+                (role, _) => role_label(role).to_string(),
+            };
+            let _ = write!(
+                out,
+                "<div class=\"msg {}\">\n<span class=\"role\">{}</span>",
+                html_escape(&message.role),
+                html_escape(&label)
+            );
+            if let Some(ts) = &message.timestamp {
+                let _ = write!(out, "<span class=\"time\">{}</span>", html_escape(ts));
+            }
+            if !message.content.trim().is_empty() {
+                let _ = write!(out, "\n<div class=\"content\">{}</div>", html_escape(message.content.trim_end()));
+            }
+            for part in &message.parts {
+                match part {
+                    ContentPart::Image { url, .. } if url == REDACTED => {
+                        out.push_str("\n<p class=\"meta\">[图片]</p>");
+                    }
+                    ContentPart::Image { url, .. } => {
+                        let _ = write!(out, "\n<img src=\"{}\" alt=\"image\">", html_escape(url));
+                    }
+                    ContentPart::File { name, path, .. } => {
+                        let _ = write!(
+                            out,
+                            "\n<p class=\"meta\">文件：{}</p>",
+                            html_escape(name.as_deref().or(path.as_deref()).unwrap_or("-"))
+                        );
+                    }
+                    _ => {}
+                }
+            }
+            for call in &message.tool_calls {
+                let _ = write!(out, "\n<details>\n<summary>工具调用 <code>{}</code>", html_escape(&call.name));
+                if let Some(ms) = call.duration_ms {
+                    let _ = write!(out, " · {} ms", ms);
+                }
+                if call.is_error {
+                    out.push_str(" · <span class=\"error\">错误</span>");
+                }
+                let _ = write!(
+                    out,
+                    "</summary>\n<div class=\"meta\">参数</div><pre>{}</pre>",
+                    html_escape(&format_arguments(&call.arguments))
+                );
+                if let Some(result) = &call.result {
+                    let _ = write!(out, "\n<div class=\"meta\">结果</div><pre>{}</pre>", html_escape(result.trim_end()));
+                }
+                out.push_str("\n</details>");
+            }
+            out.push_str("\n</div>\n");
+        }
+        out.push_str("</section>\n");
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+fn to_json(sessions: &[ExportSession]) -> String {
+    let sessions: Vec<JsonValue> = sessions
+        .iter()
+        .map(|s| {
+            json!({
+                "id": s.id,
+                "key": s.header.as_ref().and_then(|h| h.key.clone()),
+                "header": s.header,
+                "messages": s.messages
+            })
+        })
+        .collect();
+    serde_json::to_string_pretty(&json!({
+        "exported_at": chrono::Local::now().to_rfc3339(),
+        "sessions": sessions
+    }))
+    .unwrap_or_default()
+}
+
+/// 导出聊天会话
+#[tauri::command]
+pub async fn export_chat_sessions(request: SessionExportRequest) -> Result<JsonValue, String> {
+    if request.session_ids.is_empty() {
+        return Err("请选择要导出的会话".to_string());
+    }
+    let target = request
+        .path
+        .as_deref()
+        .filter(|p| !p.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| default_export_path("nanobot-sessions", request.format.extension()));
+    if let Some(parent) = target.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent).map_err(|e| format!("创建导出目录失败: {}", e))?;
+    }
+
+    let export_target = target.clone();
+    let (session_count, message_count) = tokio::task::spawn_blocking(move || {
+        let redactor = request
+            .redact
+            .unwrap_or(false)
+            .then(Redactor::with_pii_from_current_config);
+        let sessions = request
+            .session_ids
+            .iter()
+            .map(|id| load_session(id, &request, redactor.as_ref()))
+            .collect::<Result<Vec<_>, String>>()?;
+
+        let content = match request.format {
+            SessionExportFormat::Markdown => to_markdown(&sessions),
+            SessionExportFormat::Html => to_html(&sessions),
+            SessionExportFormat::Json => to_json(&sessions),
+        };
+        std::fs::write(&export_target, content).map_err(|e| format!("写入导出文件失败: {}", e))?;
+        Ok::<_, String>((sessions.len(), sessions.iter().map(|s| s.messages.len()).sum::<usize>()))
+    })
+    .await
+    .map_err(|e| format!("会话导出任务失败: {}", e))??;
+
+    Ok(json!({
+        "success": true,
+        "message": format!("已导出 {} 个会话，共 {} 条消息", session_count, message_count),
+        "path": target.to_string_lossy(),
+        "sessions": session_count,
+        "messages": message_count
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chat_session::{ParsedLine, SessionParser};
+
+    fn parse(lines: &[&str]) -> ExportSession {
+        let mut parser = SessionParser::new();
+        let mut session = ExportSession {
+            id: "email_alice@example.com.jsonl".to_string(),
+            header: None,
+            messages: Vec::new(),
+        };
+        for line in lines {
+            match parser.parse_line(line) {
+                ParsedLine::Header(header) => session.header = Some(header),
+                ParsedLine::Message(message) => session.messages.push(message),
+                _ => {}
+            }
+        }
+        session
+    }
+
+    const SESSION: &[&str] = &[
+        r#"{"_type":"metadata","key":"cli:direct","created_at":"2026-02-10T09:00:00"}"#,
+        r#"{"role":"user","content":"list <files> & dirs","timestamp":"2026-02-10T10:00:00"}"#,
+        r#"{"role":"assistant","content":"","tool_calls":[{"id":"c1","type":"function","function":{"name":"exec","arguments":"{\"command\":\"ls\"}"}}],"timestamp":"2026-02-10T10:00:01"}"#,
+        r#"{"role":"tool","tool_call_id":"c1","name":"exec","content":"<script>alert(1)</script>","timestamp":"2026-02-10T10:00:02"}"#,
+        r#"{"role":"assistant","content":"done \"quoted\"","timestamp":"2026-02-10T10:00:03"}"#,
+        r#"{"role":"user","content":"next day","timestamp":"2026-02-11T08:00:00"}"#,
+    ];
+
+    fn request(since: Option<&str>, until: Option<&str>, include_tools: bool) -> SessionExportRequest {
+        SessionExportRequest {
+            session_ids: vec!["cli_direct.jsonl".to_string()],
+            format: SessionExportFormat::Html,
+            since: since.map(str::to_string),
+            until: until.map(str::to_string),
+            redact: None,
+            include_tools: Some(include_tools),
+            path: None,
+        }
+    }
+
+    /// 写入会话文件并按请求读取，返回导出的会话
+    fn read(name: &str, request: &SessionExportRequest) -> ExportSession {
+        let dir = std::env::temp_dir().join(format!("nanoboard-session-export-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("cli_direct.jsonl");
+        std::fs::write(&path, SESSION.join("\n") + "\n").unwrap();
+        let session = read_session(&path, "cli_direct.jsonl", request, None).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+        session
+    }
+
+    fn contents(session: &ExportSession) -> Vec<(&str, &str)> {
+        session.messages.iter().map(|m| (m.role.as_str(), m.content.as_str())).collect()
+    }
+
+    #[test]
+    fn filters_messages_by_time_range() {
+        let session = read("range", &request(Some("2026-02-10T10:00:02"), Some("2026-02-10"), true));
+        assert_eq!(contents(&session), [("tool", "<script>alert(1)</script>"), ("assistant", "done \"quoted\"")]);
+
+        // 只给日期的 since 包含当天全部消息
+        let session = read("since", &request(Some("2026-02-11"), None, true));
+        assert_eq!(contents(&session), [("user", "next day")]);
+    }
+
+    #[test]
+    fn omits_tool_calls_and_results_when_excluded() {
+        let session = read("tools", &request(None, None, true));
+        assert_eq!(session.messages.len(), 5);
+        assert_eq!(session.messages[1].tool_calls[0].result.as_deref(), Some("<script>alert(1)</script>"));
+
+        // 只有工具调用的助手消息与工具结果一起去掉
+        let session = read("no-tools", &request(None, None, false));
+        assert_eq!(
+            contents(&session),
+            [("user", "list <files> & dirs"), ("assistant", "done \"quoted\""), ("user", "next day")]
+        );
+        assert!(session.messages.iter().all(|m| m.tool_calls.is_empty()));
+        let html = to_html(std::slice::from_ref(&session));
+        assert!(!html.contains("工具调用") && !html.contains("alert(1)"));
+    }
+
+    #[test]
+    fn escapes_html_in_content_and_tool_results() {
+        let session = read("html", &request(None, None, true));
+        let html = to_html(std::slice::from_ref(&session));
+
+        assert!(html.contains("list &lt;files&gt; &amp; dirs"));
+        assert!(html.contains("done &quot;quoted&quot;"));
+        assert!(html.contains("&lt;script&gt;alert(1)&lt;/script&gt;"));
+        assert!(!html.contains("<script>") && !html.contains("<files>"));
+        // 已关联到调用的工具结果只输出一次
+        assert_eq!(html.matches("alert(1)").count(), 1);
+    }
+
+    #[test]
+    fn redacts_titles_metadata_and_attachments() {
+        let mut session = parse(&[
+            r#"{"_type":"metadata","key":"email:alice@example.com","metadata":{"from":"alice@example.com","phone":"+1 415 555 0100"}}"#,
+            r#"{"role":"user","content":[{"type":"text","text":"mail bob@example.com"},{"type":"image_url","image_url":{"url":"/home/alice/Pictures/a.png"}},{"type":"image_url","image_url":"data:image/png;base64,AAAA"},{"type":"file","file":{"filename":"alice@example.com.pdf","path":"/home/alice/a.pdf"}}]}"#,
+        ]);
+        let redactor = Redactor::new(vec!["alice@example.com".to_string()]);
+        redact_session(&redactor, &mut session);
+
+        assert!(!session.title().contains("alice"));
+        assert!(!session.id.contains("alice"));
+        let header = session.header.as_ref().unwrap();
+        assert_eq!(header.metadata.as_ref().unwrap()["from"], REDACTED);
+
+        let parts = &session.messages[0].parts;
+        assert!(matches!(&parts[1], ContentPart::Image { url, .. } if url == REDACTED));
+        assert!(matches!(&parts[2], ContentPart::Image { url, .. } if url.starts_with("data:")));
+        assert!(matches!(&parts[3], ContentPart::File { name: Some(n), path: Some(p), .. } if !n.contains("alice") && p == REDACTED));
+
+        let sessions = std::slice::from_ref(&session);
+        for output in [to_markdown(sessions), to_html(sessions), to_json(sessions)] {
+            assert!(!output.contains("alice"), "{}", output);
+        }
+    }
+}
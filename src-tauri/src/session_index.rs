@@ -0,0 +1,568 @@
+// 聊天会话索引缓存
+// 会话文件可能很长，列表与分页读取都不应每次完整读取文件：
+// - 列表摘要只读取文件开头几行（元数据与第一条用户消息），按修改时间与大小缓存；
+// - 消息索引记录每条消息所在行的字节偏移，分页读取时直接定位到目标消息；
+//   文件只是追加时增量更新，否则（nanobot 保存时可能重写整个文件）重新建立。
+
+use serde::Serialize;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fs::File;
+use std::hash::{Hash, Hasher};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use crate::chat_session::{ParsedLine, SessionHeader, SessionMessage, SessionParser};
+
+/// 读取摘要时最多读取的行数
+const SUMMARY_HEAD_LINES: usize = 20;
+
+/// 标题预览的字符数
+const TITLE_PREVIEW_CHARS: usize = 50;
+
+/// 分页末尾的工具调用最多向后查找多少条消息来补全结果
+const TOOL_RESULT_LOOKAHEAD: usize = 50;
+
+/// 会话列表摘要
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionSummary {
+    pub id: String,
+    pub name: String,
+    /// 元数据中的会话键
+    pub key: Option<String>,
+    pub title: String,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+    pub path: String,
+    pub modified: u64,
+    pub size: u64,
+}
+
+/// 消息位置
+#[derive(Debug, Clone, Copy)]
+struct MessagePos {
+    offset: u64,
+    /// 所在行号（从 1 开始）
+    line: usize,
+}
+
+/// 会话文件的追加读取进度，消息索引与会话统计共用
+/// 文件只在末尾追加时从上次的位置继续读取，否则从头读取
+#[derive(Debug, Clone, Default)]
+pub(crate) struct AppendTracker {
+    modified: Option<SystemTime>,
+    size: u64,
+    pub header: Option<SessionHeader>,
+    /// 元数据行的长度，变化时之后所有偏移都会变化
+    header_len: Option<u64>,
+    /// 已读取到的位置（总在完整行之后）
+    read_to: u64,
+    lines: usize,
+    messages: usize,
+    /// 最后一行的位置与哈希，用于判断文件是否只是追加
+    last_line: Option<(u64, u64)>,
+}
+
+/// 待读取的文件变化
+pub(crate) struct PendingRead {
+    file: File,
+    /// 文件被改写，读取进度已重置，调用方应丢弃之前的结果
+    pub rewritten: bool,
+    modified: Option<SystemTime>,
+    size: u64,
+}
+
+/// 单个会话文件的消息索引
+struct MessageIndex {
+    tracker: AppendTracker,
+    messages: Vec<MessagePos>,
+    invalid_lines: Vec<usize>,
+    /// 文件每被改写一次加一
+    generation: u64,
+}
+
+#[derive(Default)]
+struct IndexCache {
+    summaries: HashMap<PathBuf, (Option<SystemTime>, u64, SessionSummary)>,
+    messages: HashMap<PathBuf, MessageIndex>,
+}
+
+static SESSION_INDEX: Mutex<Option<IndexCache>> = Mutex::new(None);
+
+fn with_cache<T>(f: impl FnOnce(&mut IndexCache) -> T) -> T {
+    let mut guard = SESSION_INDEX.lock().unwrap();
+    f(guard.get_or_insert_with(IndexCache::default))
+}
+
+fn hash_bytes(bytes: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    bytes.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn preview(text: &str) -> String {
+    let text = text.trim();
+    let short: String = text.chars().take(TITLE_PREVIEW_CHARS).collect();
+    if text.chars().count() > TITLE_PREVIEW_CHARS {
+        format!("{}...", short)
+    } else {
+        short
+    }
+}
+
+/// 读取会话文件开头，生成摘要
+fn read_summary(path: &Path, file_name: &str, modified: Option<SystemTime>, size: u64) -> SessionSummary {
+    let mut header = None;
+    let mut title = None;
+    let mut fallback = None;
+
+    if let Ok(file) = File::open(path) {
+        let mut parser = SessionParser::new();
+        for line in BufReader::new(file).lines().take(SUMMARY_HEAD_LINES) {
+            let Ok(line) = line else { break };
+            match parser.parse_line(&line) {
+                ParsedLine::Header(h) => header = Some(h),
+                ParsedLine::Message(message) if !message.content.trim().is_empty() => {
+                    if message.role == "user" {
+                        title = Some(preview(&message.content));
+                        break;
+                    }
+                    fallback.get_or_insert_with(|| preview(&message.content));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    SessionSummary {
+        id: file_name.to_string(),
+        name: file_name.trim_end_matches(".jsonl").to_string(),
+        key: header.as_ref().and_then(|h| h.key.clone()),
+        title: title.or(fallback).unwrap_or_else(|| file_name.to_string()),
+        created_at: header.as_ref().and_then(|h| h.created_at.clone()),
+        updated_at: header.as_ref().and_then(|h| h.updated_at.clone()),
+        path: path.to_string_lossy().to_string(),
+        modified: modified
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+        size,
+    }
+}
+
+/// 列出目录中所有会话的摘要，按修改时间倒序
+pub fn list_summaries(dir: &Path) -> std::io::Result<Vec<SessionSummary>> {
+    let mut found = Vec::new();
+    for entry in std::fs::read_dir(dir)?.flatten() {
+        let path = entry.path();
+        let Some(file_name) = path.file_name().and_then(|n| n.to_str()).map(|s| s.to_string()) else {
+            continue;
+        };
+        if !file_name.ends_with(".jsonl") {
+            continue;
+        }
+        let Ok(metadata) = entry.metadata() else { continue };
+        if metadata.is_file() {
+            found.push((path, file_name, metadata.modified().ok(), metadata.len()));
+        }
+    }
+
+    let mut summaries = with_cache(|cache| {
+        // 该目录中已删除、归档或移入回收站的会话不再保留缓存，其他目录的缓存不受影响
+        let exists = |path: &PathBuf| path.parent() != Some(dir) || found.iter().any(|(p, ..)| p == path);
+        cache.summaries.retain(|path, _| exists(path));
+        cache.messages.retain(|path, _| exists(path));
+        found
+            .into_iter()
+            .map(|(path, file_name, modified, size)| {
+                if let Some((cached_modified, cached_size, summary)) = cache.summaries.get(&path) {
+                    if *cached_modified == modified && *cached_size == size {
+                        return summary.clone();
+                    }
+                }
+                let summary = read_summary(&path, &file_name, modified, size);
+                cache.summaries.insert(path, (modified, size, summary.clone()));
+                summary
+            })
+            .collect::<Vec<_>>()
+    });
+
+    summaries.sort_by_key(|s| std::cmp::Reverse(s.modified));
+    Ok(summaries)
+}
+
+impl AppendTracker {
+    /// 判断文件是否只在末尾追加了内容（元数据行可能被原长度改写，此时刷新元数据）
+    fn is_append_only(&mut self, file: &mut File, size: u64) -> std::io::Result<bool> {
+        if size < self.read_to {
+            return Ok(false);
+        }
+        // 元数据行长度不变，后续偏移才有效
+        if let Some(header_len) = self.header_len {
+            let mut first = Vec::new();
+            file.seek(SeekFrom::Start(0))?;
+            BufReader::new(&mut *file).read_until(b'\n', &mut first)?;
+            if first.len() as u64 != header_len {
+                return Ok(false);
+            }
+            if let ParsedLine::Header(header) = SessionParser::new().parse_line(&String::from_utf8_lossy(&first)) {
+                self.header = Some(header);
+            }
+        }
+        if let Some((offset, hash)) = self.last_line {
+            let mut last = vec![0u8; (self.read_to - offset) as usize];
+            file.seek(SeekFrom::Start(offset))?;
+            file.read_exact(&mut last)?;
+            if hash_bytes(&last) != hash {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// 检查文件是否有变化，没有变化时返回 None
+    pub(crate) fn open_changes(&mut self, path: &Path) -> std::io::Result<Option<PendingRead>> {
+        let metadata = std::fs::metadata(path)?;
+        let modified = metadata.modified().ok();
+        if modified == self.modified && metadata.len() == self.size {
+            return Ok(None);
+        }
+
+        let mut file = File::open(path)?;
+        let rewritten = !self.is_append_only(&mut file, metadata.len())?;
+        if rewritten {
+            *self = Self::default();
+        }
+        Ok(Some(PendingRead {
+            file,
+            rewritten,
+            modified,
+            size: metadata.len(),
+        }))
+    }
+
+    /// 从上次的位置读取新增的完整行，回调收到行起始偏移、行号与解析结果
+    pub(crate) fn read_lines(
+        &mut self,
+        pending: PendingRead,
+        mut f: impl FnMut(u64, usize, ParsedLine),
+    ) -> std::io::Result<()> {
+        let mut file = pending.file;
+        file.seek(SeekFrom::Start(self.read_to))?;
+        let mut reader = BufReader::new(file);
+        let mut parser = SessionParser::starting_at(self.messages, self.lines);
+        let mut buffer = Vec::new();
+        loop {
+            buffer.clear();
+            let read = reader.read_until(b'\n', &mut buffer)?;
+            // 没有换行符的最后一行可能还没写完，留到下次读取
+            if read == 0 || buffer.last() != Some(&b'\n') {
+                break;
+            }
+            let offset = self.read_to;
+            let line = String::from_utf8_lossy(&buffer);
+            let parsed = parser.parse_line(line.trim_end_matches(['\n', '\r']));
+            match &parsed {
+                ParsedLine::Header(header) => {
+                    if offset == 0 {
+                        self.header_len = Some(read as u64);
+                    }
+                    self.header = Some(header.clone());
+                }
+                ParsedLine::Message(_) => self.messages += 1,
+                ParsedLine::Empty | ParsedLine::Invalid => {}
+            }
+            self.read_to += read as u64;
+            self.lines = parser.current_line();
+            self.last_line = Some((offset, hash_bytes(&buffer)));
+            f(offset, self.lines, parsed);
+        }
+
+        self.modified = pending.modified;
+        self.size = pending.size;
+        Ok(())
+    }
+}
+
+impl MessageIndex {
+    fn new() -> Self {
+        Self {
+            tracker: AppendTracker::default(),
+            messages: Vec::new(),
+            invalid_lines: Vec::new(),
+            generation: 0,
+        }
+    }
+
+    /// 更新到文件当前内容
+    fn update(&mut self, path: &Path) -> std::io::Result<()> {
+        let Some(pending) = self.tracker.open_changes(path)? else {
+            return Ok(());
+        };
+        if pending.rewritten {
+            self.messages.clear();
+            self.invalid_lines.clear();
+            self.generation += 1;
+        }
+        let (messages, invalid_lines) = (&mut self.messages, &mut self.invalid_lines);
+        self.tracker.read_lines(pending, |offset, line, parsed| match parsed {
+            ParsedLine::Message(_) => messages.push(MessagePos { offset, line }),
+            ParsedLine::Invalid => invalid_lines.push(line),
+            ParsedLine::Header(_) | ParsedLine::Empty => {}
+        })
+    }
+}
+
+/// 分页读取的结果
+#[derive(Debug, Clone, Serialize)]
+pub struct MessagePage {
+    pub header: Option<SessionHeader>,
+    pub messages: Vec<SessionMessage>,
+    /// 会话中的消息总数
+    pub total: usize,
+    pub offset: usize,
+    pub has_more: bool,
+    pub invalid_lines: Vec<usize>,
+    /// 文件改写的次数，变化时之前读取的消息都已失效（消息数可能不变）
+    pub generation: u64,
+}
+
+/// 读取从第 offset 条开始的最多 limit 条消息（limit 为 None 时读取到末尾）
+pub fn read_messages(path: &Path, offset: usize, limit: Option<usize>) -> std::io::Result<MessagePage> {
+    let (header, total, start, invalid_lines, generation) = with_cache(|cache| {
+        let index = cache
+            .messages
+            .entry(path.to_path_buf())
+            .or_insert_with(MessageIndex::new);
+        if let Err(e) = index.update(path) {
+            cache.messages.remove(path);
+            return Err(e);
+        }
+        Ok::<_, std::io::Error>((
+            index.tracker.header.clone(),
+            index.messages.len(),
+            index.messages.get(offset).copied(),
+            index.invalid_lines.clone(),
+            index.generation,
+        ))
+    })?;
+
+    let end = limit.map_or(total, |limit| offset.saturating_add(limit).min(total));
+    let mut messages = Vec::new();
+
+    if let Some(start) = start {
+        let mut file = File::open(path)?;
+        file.seek(SeekFrom::Start(start.offset))?;
+        let reader = BufReader::new(file);
+        let mut parser = SessionParser::starting_at(offset, start.line - 1);
+        let mut lookahead = 0;
+
+        for line in reader.lines() {
+            let line = line?;
+            let ParsedLine::Message(message) = parser.parse_line(&line) else {
+                continue;
+            };
+            // 工具结果补到本页中对应的调用上
+            if let Some((index, call_index, result)) = parser.resolve_tool_result(&message) {
+                if let Some(call) = messages
+                    .get_mut(index - offset)
+                    .and_then(|m: &mut SessionMessage| m.tool_calls.get_mut(call_index))
+                {
+                    call.apply_result(result);
+                }
+            }
+            if message.index < end {
+                messages.push(message);
+                continue;
+            }
+            // 本页已读完，只为补全工具结果继续向后查找
+            lookahead += 1;
+            if !parser.has_pending_calls() || lookahead > TOOL_RESULT_LOOKAHEAD {
+                break;
+            }
+        }
+    }
+
+    Ok(MessagePage {
+        header,
+        has_more: end < total,
+        offset,
+        total,
+        messages,
+        invalid_lines,
+        generation,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    const HEADER: &str = r#"{"_type":"metadata","key":"cli:direct","updated_at":"2026-02-10T10:00:00"}"#;
+
+    fn message(content: &str) -> String {
+        format!(r#"{{"role":"user","content":"{}"}}"#, content)
+    }
+
+    fn append(path: &Path, text: &str) {
+        let mut file = std::fs::OpenOptions::new().append(true).open(path).unwrap();
+        file.write_all(text.as_bytes()).unwrap();
+    }
+
+    fn check(index: &mut MessageIndex, path: &Path) -> bool {
+        let size = std::fs::metadata(path).unwrap().len();
+        index.tracker.is_append_only(&mut File::open(path).unwrap(), size).unwrap()
+    }
+
+    #[test]
+    fn detects_append_only_changes() {
+        let dir = std::env::temp_dir().join(format!("nanoboard-index-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("cli_direct.jsonl");
+        std::fs::write(&path, format!("{}\n{}\n{}\n", HEADER, message("a"), message("b"))).unwrap();
+
+        let mut index = MessageIndex::new();
+        index.update(&path).unwrap();
+        assert_eq!(index.messages.len(), 2);
+        assert_eq!(index.messages[1].line, 3);
+
+        // 追加新消息，以及尚未写完的最后一行
+        append(&path, &format!("{}\n{{\"role\":", message("c")));
+        assert!(check(&mut index, &path));
+        index.update(&path).unwrap();
+        assert_eq!(index.messages.len(), 3);
+        append(&path, "\"user\",\"content\":\"d\"}\n");
+        index.update(&path).unwrap();
+        assert_eq!(index.messages.len(), 4);
+
+        // 元数据行被等长改写时只刷新元数据
+        let content = std::fs::read_to_string(&path).unwrap();
+        std::fs::write(&path, content.replacen("10:00:00", "11:11:11", 1)).unwrap();
+        assert!(check(&mut index, &path));
+        assert_eq!(index.tracker.header.as_ref().unwrap().updated_at.as_deref(), Some("2026-02-10T11:11:11"));
+
+        // 最后一行被改写、元数据行长度变化、文件变短都需要重新索引
+        std::fs::write(&path, content.replace("\"d\"", "\"e\"")).unwrap();
+        assert!(!check(&mut index, &path));
+        std::fs::write(&path, content.replacen("cli:direct", "cli:other", 1)).unwrap();
+        assert!(!check(&mut index, &path));
+        std::fs::write(&path, format!("{}\n", HEADER)).unwrap();
+        assert!(!check(&mut index, &path));
+        assert_eq!(index.generation, 0);
+        index.update(&path).unwrap();
+        assert!(index.messages.is_empty());
+        assert_eq!(index.generation, 1);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    fn temp_session(name: &str, lines: &[String]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("nanoboard-index-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("cli_direct.jsonl");
+        let mut content = format!("{}\n", HEADER);
+        for line in lines {
+            content.push_str(line);
+            content.push('\n');
+        }
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    fn contents(page: &MessagePage) -> Vec<&str> {
+        page.messages.iter().map(|m| m.content.as_str()).collect()
+    }
+
+    #[test]
+    fn pages_by_offset_and_limit() {
+        let lines: Vec<String> = (0..5).map(|i| message(&format!("m{}", i))).collect();
+        let path = temp_session("paging", &lines);
+
+        let page = read_messages(&path, 0, Some(2)).unwrap();
+        assert_eq!(contents(&page), ["m0", "m1"]);
+        assert_eq!((page.total, page.offset, page.has_more), (5, 0, true));
+        assert_eq!(page.header.as_ref().unwrap().key.as_deref(), Some("cli:direct"));
+        assert_eq!(page.messages[1].index, 1);
+
+        let page = read_messages(&path, 4, Some(2)).unwrap();
+        assert_eq!(contents(&page), ["m4"]);
+        assert!(!page.has_more);
+
+        // 增量获取：从 since + 1 读取到末尾
+        let page = read_messages(&path, 3, None).unwrap();
+        assert_eq!(contents(&page), ["m3", "m4"]);
+        assert!(!page.has_more);
+
+        // 超出范围与只取总数
+        let page = read_messages(&path, 10, Some(2)).unwrap();
+        assert!(page.messages.is_empty() && !page.has_more);
+        let page = read_messages(&path, usize::MAX, Some(0)).unwrap();
+        assert_eq!(page.total, 5);
+
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    fn tool_call(id: &str) -> String {
+        format!(
+            r#"{{"role":"assistant","content":"","tool_calls":[{{"id":"{}","function":{{"name":"exec","arguments":"{{}}"}}}}]}}"#,
+            id
+        )
+    }
+
+    fn tool_result(id: &str, content: &str) -> String {
+        format!(r#"{{"role":"tool","tool_call_id":"{}","name":"exec","content":"{}"}}"#, id, content)
+    }
+
+    #[test]
+    fn fills_tool_results_after_page_boundary() {
+        // 调用位于第一页末尾，结果在下一页
+        let mut lines = vec![message("run it"), tool_call("c1")];
+        lines.extend((0..10).map(|i| message(&format!("filler {}", i))));
+        lines.push(tool_result("c1", "done"));
+        let path = temp_session("lookahead", &lines);
+
+        let page = read_messages(&path, 0, Some(2)).unwrap();
+        assert_eq!(page.messages.len(), 2);
+        assert_eq!(page.messages[1].tool_calls[0].result.as_deref(), Some("done"));
+        assert!(page.has_more);
+
+        // 结果在本页之内时同样补全，结果消息本身也在页中
+        let page = read_messages(&path, 1, None).unwrap();
+        assert_eq!(page.messages[0].tool_calls[0].result.as_deref(), Some("done"));
+        assert_eq!(page.messages.last().unwrap().role, "tool");
+
+        // 超出向后查找范围的结果不再补全
+        let mut lines = vec![tool_call("c2")];
+        lines.extend((0..TOOL_RESULT_LOOKAHEAD + 10).map(|i| message(&format!("filler {}", i))));
+        lines.push(tool_result("c2", "late"));
+        let far = temp_session("lookahead-far", &lines);
+        let page = read_messages(&far, 0, Some(1)).unwrap();
+        assert_eq!(page.messages[0].tool_calls[0].result, None);
+
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+        let _ = std::fs::remove_dir_all(far.parent().unwrap());
+    }
+
+    #[test]
+    fn prunes_cached_index_of_removed_sessions() {
+        let path = temp_session("prune", &[message("hi")]);
+        let dir = path.parent().unwrap().to_path_buf();
+        read_messages(&path, 0, None).unwrap();
+        list_summaries(&dir).unwrap();
+        assert!(with_cache(|cache| cache.messages.contains_key(&path) && cache.summaries.contains_key(&path)));
+
+        std::fs::remove_file(&path).unwrap();
+        list_summaries(&dir).unwrap();
+        assert!(with_cache(|cache| !cache.messages.contains_key(&path) && !cache.summaries.contains_key(&path)));
+
+        // 读取已删除的会话失败时也不留下缓存
+        assert!(read_messages(&path, 0, None).is_err());
+        assert!(with_cache(|cache| !cache.messages.contains_key(&path)));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,450 @@
+// 聊天会话实时跟随
+// 与日志流类似，监控指定会话文件的变化，将新增的消息（含工具调用）解析后通过 session-update 事件推送：
+// - 每个订阅一个后台任务，文件监控回调只负责唤醒任务，另有定时轮询兜底；
+// - nanobot 保存会话时会重写整个文件，读取通过会话索引完成：只是追加时增量读取，否则重新索引；
+// - 文件被改写（例如会话被 /new 清空，消息数可能不变）时推送 reset，并从头重新推送全部消息；
+// - 窗口关闭时移除该窗口的全部订阅。
+
+use serde_json::json;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+
+use crate::session_index::MessagePage;
+
+/// 监控事件之外的兜底轮询间隔
+const POLL_INTERVAL: Duration = Duration::from_millis(1500);
+
+/// 单次推送的最大消息数，较长的积压分多批推送
+const MAX_BATCH: usize = 200;
+
+/// 跟随进度：根据每次读取的结果决定推送内容
+#[derive(Debug)]
+struct WatchState {
+    /// 已推送的消息数
+    known: usize,
+    /// 已推送消息所属的文件版本
+    generation: u64,
+    seq: u64,
+    /// 下一次推送是否需要前端丢弃已有消息
+    reset: bool,
+}
+
+/// 一次读取后的处理结果
+#[derive(Debug)]
+enum WatchStep {
+    /// 文件已被改写，需要从头重新读取
+    Restart,
+    /// 推送这一页，has_more 时立即继续读取
+    Emit { seq: u64, reset: bool },
+    /// 没有新消息
+    Idle,
+}
+
+impl WatchState {
+    fn new(known: usize, generation: u64) -> Self {
+        Self { known, generation, seq: 0, reset: false }
+    }
+
+    /// 处理从 known 开始读取的一页消息
+    fn apply(&mut self, page: &MessagePage) -> WatchStep {
+        if page.generation != self.generation || page.total < self.known {
+            self.generation = page.generation;
+            self.reset = true;
+            // 这一页不是从头读取的，之前的消息需要重新推送
+            if page.offset > 0 {
+                self.known = 0;
+                return WatchStep::Restart;
+            }
+        }
+        if page.messages.is_empty() && !self.reset {
+            return WatchStep::Idle;
+        }
+        self.known = page.offset + page.messages.len();
+        self.seq += 1;
+        WatchStep::Emit {
+            seq: self.seq,
+            reset: std::mem::take(&mut self.reset),
+        }
+    }
+}
+
+/// 会话订阅
+struct SessionWatch {
+    session_id: String,
+    /// 接收事件的窗口标签
+    target: String,
+    task: tokio::task::JoinHandle<()>,
+}
+
+/// 会话跟随管理
+#[derive(Default)]
+pub struct SessionWatchHub {
+    watches: Mutex<HashMap<u64, SessionWatch>>,
+    next_id: Mutex<u64>,
+}
+
+impl SessionWatchHub {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn add(
+        &self,
+        app: &tauri::AppHandle,
+        target: &str,
+        session_id: &str,
+        path: PathBuf,
+        state: WatchState,
+    ) -> u64 {
+        let id = {
+            let mut next_id = self.next_id.lock().unwrap();
+            *next_id += 1;
+            *next_id
+        };
+        let task = tokio::spawn(run_watch(
+            app.clone(),
+            id,
+            target.to_string(),
+            session_id.to_string(),
+            path,
+            state,
+        ));
+        self.watches.lock().unwrap().insert(
+            id,
+            SessionWatch {
+                session_id: session_id.to_string(),
+                target: target.to_string(),
+                task,
+            },
+        );
+        id
+    }
+
+    /// 取消订阅，只能取消属于指定窗口的订阅，返回是否存在
+    pub fn unsubscribe(&self, id: u64, label: &str) -> bool {
+        let mut watches = self.watches.lock().unwrap();
+        if watches.get(&id).is_none_or(|w| w.target != label) {
+            return false;
+        }
+        if let Some(watch) = watches.remove(&id) {
+            watch.task.abort();
+        }
+        true
+    }
+
+    /// 移除某个窗口的全部订阅（窗口关闭时调用）
+    pub fn unsubscribe_window(&self, label: &str) -> usize {
+        let mut watches = self.watches.lock().unwrap();
+        let ids: Vec<u64> = watches
+            .iter()
+            .filter(|(_, w)| w.target == label)
+            .map(|(id, _)| *id)
+            .collect();
+        for id in &ids {
+            if let Some(watch) = watches.remove(id) {
+                watch.task.abort();
+            }
+        }
+        ids.len()
+    }
+
+    fn active(&self) -> Vec<serde_json::Value> {
+        self.watches
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(id, w)| json!({ "subscription_id": id, "session_id": w.session_id, "window": w.target }))
+            .collect()
+    }
+}
+
+/// 跟随任务：文件变化时读取新消息并推送
+async fn run_watch(
+    app: tauri::AppHandle,
+    id: u64,
+    target: String,
+    session_id: String,
+    path: PathBuf,
+    mut state: WatchState,
+) {
+    use notify::{recommended_watcher, EventKind, RecursiveMode, Watcher};
+
+    let wake = Arc::new(tokio::sync::Notify::new());
+    let wake_for_watch = wake.clone();
+    let file_name = path.file_name().map(|n| n.to_os_string());
+    let watcher = recommended_watcher(move |res: notify::Result<notify::Event>| {
+        if let Ok(event) = res {
+            // 监控的是会话目录（文件会被整体重写或重新创建），只处理该会话文件
+            let relevant = matches!(event.kind, EventKind::Modify(_) | EventKind::Create(_))
+                && event
+                    .paths
+                    .iter()
+                    .any(|p| p.file_name() == file_name.as_deref());
+            if relevant {
+                wake_for_watch.notify_one();
+            }
+        }
+    });
+    let _watcher = match watcher {
+        Ok(mut watcher) => {
+            if let Some(dir) = path.parent() {
+                if let Err(e) = watcher.watch(dir, RecursiveMode::NonRecursive) {
+                    log::warn!("监控会话目录失败，改为仅轮询: {}", e);
+                }
+            }
+            Some(watcher)
+        }
+        Err(e) => {
+            log::warn!("创建会话监控器失败，改为仅轮询: {}", e);
+            None
+        }
+    };
+
+    loop {
+        let read_path = path.clone();
+        let offset = state.known;
+        let page = tokio::task::spawn_blocking(move || {
+            crate::session_index::read_messages(&read_path, offset, Some(MAX_BATCH))
+        })
+        .await;
+
+        match page {
+            Ok(Ok(page)) => match state.apply(&page) {
+                WatchStep::Restart => continue,
+                WatchStep::Emit { seq, reset } => {
+                    let payload = json!({
+                        "subscription_id": id,
+                        "session_id": session_id,
+                        "seq": seq,
+                        "messages": page.messages,
+                        "total": page.total,
+                        "header": page.header,
+                        "reset": reset
+                    });
+                    if let Err(e) = app.emit_to(target.as_str(), "session-update", payload) {
+                        log::warn!("发送会话更新到窗口 {} 失败: {:?}", target, e);
+                    }
+                    // 还有积压时立即继续读取
+                    if page.has_more {
+                        continue;
+                    }
+                }
+                WatchStep::Idle => {}
+            },
+            // 文件暂时不存在（例如正在被重写）时等待下一次检查
+            Ok(Err(e)) => log::debug!("读取会话 {} 失败: {}", session_id, e),
+            Err(e) => log::warn!("会话跟随任务失败: {}", e),
+        }
+
+        tokio::select! {
+            _ = wake.notified() => {}
+            _ = tokio::time::sleep(POLL_INTERVAL) => {}
+        }
+    }
+}
+
+/// 开始跟随会话，新消息通过 session-update 事件发送到调用的窗口
+/// since 为已有的最后一条消息序号，未指定时只推送之后新增的消息
+#[tauri::command]
+pub async fn watch_chat_session(
+    window: tauri::Window,
+    session_id: String,
+    since: Option<usize>,
+    hub: tauri::State<'_, Arc<SessionWatchHub>>,
+) -> Result<serde_json::Value, String> {
+    if session_id.contains('/') || session_id.contains('\\') || !session_id.ends_with(".jsonl") {
+        return Err("无效的会话 ID 格式".to_string());
+    }
+    let path = crate::session::get_chat_sessions_path()
+        .map_err(|e| e.to_string())?
+        .join(&session_id);
+    if !path.exists() {
+        return Err(format!("会话 {} 不存在", session_id));
+    }
+
+    let current = {
+        let read_path = path.clone();
+        tokio::task::spawn_blocking(move || {
+            crate::session_index::read_messages(&read_path, usize::MAX, Some(0))
+        })
+        .await
+        .map_err(|e| format!("读取会话失败: {}", e))?
+        .map_err(|e| format!("读取会话失败: {}", e))?
+    };
+    let total = current.total;
+    let start = since.map(|n| (n + 1).min(total)).unwrap_or(total);
+    let id = hub.add(
+        window.app_handle(),
+        window.label(),
+        &session_id,
+        path,
+        WatchState::new(start, current.generation),
+    );
+
+    Ok(json!({
+        "success": true,
+        "subscription_id": id,
+        "total": total
+    }))
+}
+
+/// 停止跟随会话
+#[tauri::command]
+pub async fn unwatch_chat_session(
+    window: tauri::Window,
+    subscription_id: u64,
+    hub: tauri::State<'_, Arc<SessionWatchHub>>,
+) -> Result<serde_json::Value, String> {
+    if !hub.unsubscribe(subscription_id, window.label()) {
+        return Err("订阅不存在或已取消".to_string());
+    }
+    Ok(json!({ "success": true }))
+}
+
+/// 列出正在跟随的会话
+#[tauri::command]
+pub async fn list_chat_session_watches(
+    hub: tauri::State<'_, Arc<SessionWatchHub>>,
+) -> Result<serde_json::Value, String> {
+    Ok(json!({ "watches": hub.active() }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::path::Path;
+
+    const HEADER: &str = r#"{"_type":"metadata","key":"cli:direct"}"#;
+
+    fn session(contents: &[&str]) -> String {
+        let mut text = format!("{}\n", HEADER);
+        for content in contents {
+            text.push_str(&format!("{{\"role\":\"user\",\"content\":\"{}\"}}\n", content));
+        }
+        text
+    }
+
+    /// 读取并处理，直到没有新消息为止，返回每次推送的 (reset, 消息内容)
+    fn drain(state: &mut WatchState, path: &Path, batch: usize) -> Vec<(bool, Vec<String>)> {
+        let mut updates = Vec::new();
+        loop {
+            let page = crate::session_index::read_messages(path, state.known, Some(batch)).unwrap();
+            match state.apply(&page) {
+                WatchStep::Restart => continue,
+                WatchStep::Emit { seq, reset } => {
+                    assert_eq!(seq, state.seq);
+                    let has_more = page.has_more;
+                    updates.push((reset, page.messages.into_iter().map(|m| m.content).collect()));
+                    if !has_more {
+                        break;
+                    }
+                }
+                WatchStep::Idle => break,
+            }
+        }
+        updates
+    }
+
+    fn start(path: &Path) -> WatchState {
+        let page = crate::session_index::read_messages(path, usize::MAX, Some(0)).unwrap();
+        WatchState::new(page.total, page.generation)
+    }
+
+    fn owned(items: &[&str]) -> Vec<String> {
+        items.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn pushes_appended_messages_in_batches() {
+        let dir = std::env::temp_dir().join(format!("nanoboard-watch-append-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("cli_direct.jsonl");
+        std::fs::write(&path, session(&["a"])).unwrap();
+
+        let mut state = start(&path);
+        assert!(drain(&mut state, &path, 2).is_empty());
+
+        let mut file = std::fs::OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(session(&["b", "c", "d"]).trim_start_matches(HEADER).trim_start().as_bytes()).unwrap();
+        let updates = drain(&mut state, &path, 2);
+        assert_eq!(updates, vec![(false, owned(&["b", "c"])), (false, owned(&["d"]))]);
+        assert_eq!(state.known, 4);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn resets_when_rewritten_with_same_count() {
+        let dir = std::env::temp_dir().join(format!("nanoboard-watch-rewrite-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("cli_direct.jsonl");
+        std::fs::write(&path, session(&["a", "b"])).unwrap();
+
+        let mut state = start(&path);
+        // 大小相同的重写只能通过修改时间识别，显式设置修改时间以免两次写入落在同一时间刻度内
+        std::fs::write(&path, session(&["x", "y"])).unwrap();
+        let modified = std::fs::metadata(&path).unwrap().modified().unwrap() + std::time::Duration::from_secs(1);
+        std::fs::File::options().write(true).open(&path).unwrap().set_modified(modified).unwrap();
+        let updates = drain(&mut state, &path, 10);
+        assert_eq!(updates, vec![(true, owned(&["x", "y"]))]);
+
+        // reset 只随第一批推送
+        std::fs::write(&path, session(&["p", "q", "r"])).unwrap();
+        let updates = drain(&mut state, &path, 2);
+        assert_eq!(updates, vec![(true, owned(&["p", "q"])), (false, owned(&["r"]))]);
+
+        // 清空后推送不含消息的 reset
+        std::fs::write(&path, session(&[])).unwrap();
+        assert_eq!(drain(&mut state, &path, 10), vec![(true, Vec::new())]);
+        assert_eq!(state.known, 0);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn unsubscribe_only_removes_own_watches() {
+        let hub = SessionWatchHub::new();
+        for (id, target) in [(1, "main"), (2, "chat")] {
+            hub.watches.lock().unwrap().insert(
+                id,
+                SessionWatch {
+                    session_id: "cli:direct".to_string(),
+                    target: target.to_string(),
+                    task: tokio::spawn(std::future::pending()),
+                },
+            );
+        }
+
+        // 其他窗口不能取消不属于自己的订阅
+        assert!(!hub.unsubscribe(1, "chat"));
+        assert!(hub.unsubscribe(1, "main"));
+        assert!(!hub.unsubscribe(1, "main"));
+
+        assert_eq!(hub.unsubscribe_window("chat"), 1);
+        assert!(hub.watches.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn restarts_when_page_was_read_before_rewrite() {
+        let mut state = WatchState::new(3, 1);
+        let page = |offset: usize, total: usize, generation: u64| MessagePage {
+            header: None,
+            messages: Vec::new(),
+            total,
+            offset,
+            has_more: false,
+            invalid_lines: Vec::new(),
+            generation,
+        };
+        assert!(matches!(state.apply(&page(3, 3, 1)), WatchStep::Idle));
+        // 版本变化但消息数更多：不能把新文件的后半段当作追加
+        assert!(matches!(state.apply(&page(3, 5, 2)), WatchStep::Restart));
+        assert_eq!((state.known, state.generation), (0, 2));
+        assert!(matches!(state.apply(&page(0, 5, 2)), WatchStep::Emit { reset: true, seq: 1, .. }));
+        assert!(matches!(state.apply(&page(0, 5, 2)), WatchStep::Idle));
+    }
+}
@@ -0,0 +1,64 @@
+// 时间范围过滤
+// 日志与会话的时间戳都是固定格式的字符串（2026-02-10 12:00:00.000 或 2026-02-10T12:00:00.123456），
+// 日期与时间之间的 'T' 统一视为空格后按字符串比较即可；
+// until 只比较给定的精度，只给出日期时包含当天全部时间。
+
+use std::borrow::Cow;
+
+/// 日期与时间之间的 'T' 换成空格
+fn unify(value: &str) -> Cow<'_, str> {
+    if value.contains('T') {
+        Cow::Owned(value.replacen('T', " ", 1))
+    } else {
+        Cow::Borrowed(value)
+    }
+}
+
+/// 规范化查询中的时间边界，空字符串视为未设置，去掉末尾的 Z
+pub fn normalize_time(value: Option<&str>) -> Option<String> {
+    value
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|s| unify(s).trim_end_matches('Z').to_string())
+}
+
+/// 时间戳是否在 [since, until] 内（边界需先经过 normalize_time）
+/// 设置了时间范围时，没有时间戳的条目视为不在范围内
+pub fn in_range(timestamp: Option<&str>, since: Option<&str>, until: Option<&str>) -> bool {
+    if since.is_none() && until.is_none() {
+        return true;
+    }
+    let Some(ts) = timestamp.map(unify) else {
+        return false;
+    };
+    let ts = ts.as_ref();
+    // until 比时间戳长或截断位置落在多字节字符中间时按整个时间戳比较
+    since.is_none_or(|s| ts >= s) && until.is_none_or(|u| ts.get(..u.len()).unwrap_or(ts) <= u)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filters_by_time_range() {
+        assert!(in_range(None, None, None));
+        assert!(!in_range(None, Some("2026-02-10"), None));
+        assert!(in_range(Some("2026-02-10 23:59:59"), Some("2026-02-10"), Some("2026-02-10")));
+        assert!(in_range(Some("2026-02-10T23:59:59.123456"), Some("2026-02-10 12:00"), Some("2026-02-10")));
+        assert!(!in_range(Some("2026-02-11T00:00:00"), None, Some("2026-02-10")));
+        assert!(!in_range(Some("2026-02-09 23:59:59.999"), Some("2026-02-10"), None));
+        // until 比时间戳长或包含多字节字符时不会越界
+        assert!(in_range(Some("2026-02-10"), None, Some("2026-02-10 12:00:00.000000")));
+        assert!(!in_range(Some("2026-02-1é"), None, Some("2026-02-10")));
+        assert!(!in_range(Some("时间2026-02-10"), None, Some("2026-02")));
+    }
+
+    #[test]
+    fn normalizes_bounds() {
+        assert_eq!(normalize_time(Some(" 2026-02-10T12:00:00Z ")).as_deref(), Some("2026-02-10 12:00:00"));
+        assert_eq!(normalize_time(Some("2026-02-10")).as_deref(), Some("2026-02-10"));
+        assert_eq!(normalize_time(Some("  ")), None);
+        assert_eq!(normalize_time(None), None);
+    }
+}
@@ -10,6 +10,16 @@ import type {
   DiagnosticResult,
   NanobotPath,
   LogResponse,
+  LogRecord,
+  LogRotationSettings,
+  LogStreamBatch,
+  AlertRule,
+  AlertEntry,
+  SearchQuery,
+  ChatSessionUpdate,
+  ChatConsoleEvent,
+  MemoryVersion,
+  MemoryDiffLine,
   NetworkStats,
   SessionListResult,
   SessionMemory,
@@ -35,6 +45,13 @@ export const configApi = {
   getHistory: () => invoke<ConfigHistoryVersion[]>("get_config_history"),
   restoreVersion: (filename: string) => invoke<void>("restore_config_version", { filename }),
   deleteVersion: (filename: string) => invoke<void>("delete_config_version", { filename }),
+  checkMigration: () => invoke<AnyResponse>("check_config_migration"),
+  applyMigration: () => invoke<AnyResponse>("apply_config_migration"),
+  getTyped: () => invoke<AnyResponse>("get_typed_config"),
+  getChannel: (name: string) => invoke<AnyResponse>("get_channel_config", { name }),
+  updateChannel: (name: string, fields: Record<string, unknown>) => invoke<AnyResponse>("update_channel_config", { name, fields }),
+  updateProvider: (name: string, fields: Record<string, unknown>) => invoke<AnyResponse>("update_provider_config", { name, fields }),
+  updateAgentDefaults: (fields: Record<string, unknown>) => invoke<AnyResponse>("update_agent_defaults", { fields }),
 };
 
 // Process API
@@ -49,6 +66,10 @@ export const processApi = {
   providerLogin: (provider: string) => invoke<OperationResult>("provider_login", { provider }),
   checkOAuthToken: (provider: string) => invoke<{ has_token: boolean; is_expired?: boolean; message: string }>("check_oauth_token", { provider }),
   checkConfig: () => invoke<ConfigCheckResult>("check_nanobot_config"),
+  testProviders: (name?: string) => invoke<AnyResponse>("test_providers", { name }),
+  listProviderModels: (provider?: string, refresh?: boolean) => invoke<AnyResponse>("list_provider_models", { provider, refresh }),
+  checkChannels: (name?: string) => invoke<AnyResponse>("check_channels", { name }),
+  testMcpServers: (name?: string) => invoke<AnyResponse>("test_mcp_servers", { name }),
   diagnose: () => invoke<DiagnosticResult>("diagnose_nanobot"),
   setCustomPaths: (pythonPath?: string, nanobotPath?: string, nodePath?: string, npmPath?: string) =>
     invoke<OperationResult>("set_custom_paths", { pythonPath, nanobotPath, nodePath, npmPath }),
@@ -62,11 +83,42 @@ export const processApi = {
 export const loggerApi = {
   getLogs: (lines?: number) => invoke<LogResponse>("get_logs", { lines }),
   getStatistics: () => invoke<AnyResponse>("get_log_statistics"),
+  query: (query: Record<string, unknown>) => invoke<AnyResponse>("query_logs", { query }),
+  listFiles: () => invoke<AnyResponse>("list_log_files"),
+  analyzeErrors: (query?: { since?: string; until?: string; days?: number; includeWarnings?: boolean; limit?: number }) =>
+    invoke<AnyResponse>("analyze_errors", { query }),
+  export: (request: {
+    format: "text" | "jsonl" | "bundle";
+    filter?: Record<string, unknown>;
+    path?: string;
+    includeRotated?: boolean;
+    redact?: boolean;
+  }) => invoke<AnyResponse>("export_logs", { request }),
+  getRotationSettings: () => invoke<LogRotationSettings>("get_log_rotation_settings"),
+  setRotationSettings: (settings: LogRotationSettings) =>
+    invoke<AnyResponse>("set_log_rotation_settings", { settings }),
+  rotateNow: () => invoke<AnyResponse>("rotate_logs_now"),
+  getSessionLogs: (session: string, since?: string, until?: string, limit?: number) =>
+    invoke<AnyResponse>("get_session_logs", { session, since, until, limit }),
+  getCronRunLogs: (jobId: string, since?: string, until?: string, limit?: number) =>
+    invoke<AnyResponse>("get_cron_run_logs", { jobId, since, until, limit }),
   startStream: () => invoke<void>("start_log_stream"),
+  subscribe: (filter?: Record<string, unknown>) => invoke<AnyResponse>("subscribe_log_stream", { filter }),
+  unsubscribe: (subscriptionId: number) => invoke<AnyResponse>("unsubscribe_log_stream", { subscriptionId }),
   stopStream: () => invoke<void>("stop_log_stream"),
   isStreamRunning: () => invoke<boolean>("is_log_stream_running"),
 };
 
+// Alert API
+export const alertApi = {
+  getRules: () => invoke<AnyResponse>("get_alert_rules"),
+  saveRules: (rules: AlertRule[]) => invoke<AnyResponse>("save_alert_rules", { rules }),
+  getHistory: (unacknowledgedOnly?: boolean, limit?: number) =>
+    invoke<AnyResponse>("get_alert_history", { unacknowledgedOnly, limit }),
+  acknowledge: (ids?: string[]) => invoke<AnyResponse>("acknowledge_alerts", { ids }),
+  clearHistory: () => invoke<AnyResponse>("clear_alert_history"),
+};
+
 // Network API
 export const networkApi = {
   initMonitor: () => invoke<void>("init_network_monitor"),
@@ -87,7 +139,67 @@ export const sessionApi = {
 // Chat Session API
 export const chatSessionApi = {
   list: () => invoke<AnyResponse>("list_chat_sessions"),
-  getContent: (sessionId: string) => invoke<AnyResponse>("get_chat_session_content", { sessionId }),
+  getContent: (sessionId: string, options?: { offset?: number; limit?: number; since?: number; includeRaw?: boolean }) =>
+    invoke<AnyResponse>("get_chat_session_content", { sessionId, ...options }),
+  export: (request: {
+    sessionIds: string[];
+    format: "markdown" | "html" | "json";
+    since?: string;
+    until?: string;
+    redact?: boolean;
+    includeTools?: boolean;
+    path?: string;
+  }) => invoke<AnyResponse>("export_chat_sessions", { request }),
+  archive: (sessionIds: string[], force?: boolean) =>
+    invoke<AnyResponse>("archive_chat_sessions", { sessionIds, force }),
+  unarchive: (sessionIds: string[]) => invoke<AnyResponse>("unarchive_chat_sessions", { sessionIds }),
+  listArchived: () => invoke<AnyResponse>("list_archived_chat_sessions"),
+  trash: (sessionIds: string[], retentionDays?: number, force?: boolean) =>
+    invoke<AnyResponse>("trash_chat_sessions", { sessionIds, retentionDays, force }),
+  listTrash: () => invoke<AnyResponse>("list_trashed_chat_sessions"),
+  restore: (trashIds: string[]) => invoke<AnyResponse>("restore_chat_sessions", { trashIds }),
+  purgeTrash: (trashIds?: string[]) => invoke<AnyResponse>("purge_trashed_chat_sessions", { trashIds }),
+  bulk: (request: {
+    action: "archive" | "trash";
+    olderThanDays?: number;
+    channels?: string[];
+    dryRun?: boolean;
+    retentionDays?: number;
+    force?: boolean;
+  }) => invoke<AnyResponse>("bulk_manage_chat_sessions", { request }),
+  getAnalytics: (query?: { since?: string; until?: string; days?: number; top?: number }) =>
+    invoke<AnyResponse>("get_session_analytics", { query }),
+  watch: (sessionId: string, since?: number) => invoke<AnyResponse>("watch_chat_session", { sessionId, since }),
+  unwatch: (subscriptionId: number) => invoke<AnyResponse>("unwatch_chat_session", { subscriptionId }),
+  listWatches: () => invoke<AnyResponse>("list_chat_session_watches"),
+};
+
+// Chat Console API
+export const chatConsoleApi = {
+  send: (request: { message: string; sessionKey?: string; sessionId?: string; timeoutSecs?: number }) =>
+    invoke<AnyResponse>("send_chat_message", { request }),
+  cancel: (turnId: number) => invoke<AnyResponse>("cancel_chat_message", { turnId }),
+};
+
+// Memory History API
+export const memoryHistoryApi = {
+  listFiles: () => invoke<AnyResponse>("list_memory_history_files"),
+  listVersions: (file: string) =>
+    invoke<{ file: string; versions: MemoryVersion[] }>("list_memory_versions", { file }),
+  getVersion: (file: string, versionId: string) =>
+    invoke<{ file: string; version: MemoryVersion; content: string }>("get_memory_version", { file, versionId }),
+  diff: (file: string, from: string, to?: string) =>
+    invoke<{ file: string; from: string; to: string | null; added: number; removed: number; lines: MemoryDiffLine[] }>(
+      "diff_memory_versions",
+      { file, from, to }
+    ),
+  restore: (file: string, versionId: string) =>
+    invoke<AnyResponse>("restore_memory_version", { file, versionId }),
+};
+
+// Search API
+export const searchApi = {
+  search: (query: SearchQuery) => invoke<AnyResponse>("search_workspace", { query }),
 };
 
 // Skill API
@@ -113,6 +225,16 @@ export const fsApi = {
 export const events = {
   onLogUpdate: (callback: (data: string[]) => void) =>
     listen<string[]>("log-update", (event) => callback(event.payload)),
+  onLogRecords: (callback: (data: LogRecord[]) => void) =>
+    listen<LogRecord[]>("log-records", (event) => callback(event.payload)),
+  onLogStream: (callback: (batch: LogStreamBatch) => void) =>
+    listen<LogStreamBatch>("log-stream", (event) => callback(event.payload)),
+  onAlertTriggered: (callback: (alert: AlertEntry) => void) =>
+    listen<AlertEntry>("alert-triggered", (event) => callback(event.payload)),
+  onSessionUpdate: (callback: (update: ChatSessionUpdate) => void) =>
+    listen<ChatSessionUpdate>("session-update", (event) => callback(event.payload)),
+  onChatConsole: (callback: (event: ChatConsoleEvent) => void) =>
+    listen<ChatConsoleEvent>("chat-console", (event) => callback(event.payload)),
 };
 
 // Theme API
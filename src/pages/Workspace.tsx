@@ -17,7 +17,7 @@ import {
   useCron, formatTimestamp, formatSize, describeSchedule, describeCron, describeIntervalMs, formatCronTimestamp,
   formatCronRelativeTime,
 } from "../components/workspace";
-import type { Skill, Memory as MemoryType, CronJob, CronSchedule, FsItem, Breadcrumb, FrontmatterData, ChatSession, ChatMessage, TabType } from "../types";
+import type { Skill, Memory as MemoryType, CronJob, CronSchedule, FsItem, Breadcrumb, FrontmatterData, ChatSession, ChatMessage, ChatToolCall, TabType } from "../types";
 
 export default function Workspace() {
   const { t, i18n } = useTranslation();
@@ -324,23 +324,65 @@ export default function Workspace() {
       case "user": return { container: "justify-end", bubble: bubbleStyle, icon: User, label: t("workspace.chatUser"), labelClass: "text-gray-600 dark:text-gray-400" };
       case "assistant": return { container: "justify-start", bubble: bubbleStyle, icon: Bot, label: t("workspace.chatAssistant"), labelClass: "text-gray-600 dark:text-gray-400" };
       case "system": return { container: "justify-center", bubble: bubbleStyle, icon: Settings, label: t("workspace.chatSystem"), labelClass: "text-gray-600 dark:text-gray-400" };
+      case "tool": return { container: "justify-start", bubble: bubbleStyle, icon: Wrench, label: t("workspace.chatTool"), labelClass: "text-gray-600 dark:text-gray-400" };
       default: return { container: "justify-start", bubble: bubbleStyle, icon: MessageSquare, label: role, labelClass: "text-gray-600 dark:text-gray-400" };
     }
   }
 
+  function formatToolArguments(args: unknown) {
+    return typeof args === "string" ? args : JSON.stringify(args, null, 2);
+  }
+
+  function renderToolCall(call: ChatToolCall, index: number) {
+    return (
+      <details key={call.id || index} className="mt-2 rounded-lg border border-gray-200/50 dark:border-gray-700/50 bg-gray-50/50 dark:bg-gray-900/30">
+        <summary className="flex items-center gap-2 px-3 py-2 cursor-pointer text-sm">
+          <Wrench className="w-3.5 h-3.5 flex-shrink-0" />
+          <span className="font-mono font-medium">{call.name}</span>
+          {call.duration_ms != null && <span className="text-xs text-gray-500 dark:text-gray-400">{call.duration_ms} ms</span>}
+          {call.is_error && <span className="text-xs text-red-500">{t("workspace.chatToolError")}</span>}
+        </summary>
+        <div className="px-3 pb-3 space-y-2 text-xs">
+          <div>
+            <div className="mb-1 text-gray-500 dark:text-gray-400">{t("workspace.chatToolArguments")}</div>
+            <pre className="p-2 rounded bg-gray-800/50 dark:bg-gray-900/50 overflow-x-auto whitespace-pre-wrap break-words">{formatToolArguments(call.arguments)}</pre>
+          </div>
+          <div>
+            <div className="mb-1 text-gray-500 dark:text-gray-400">{t("workspace.chatToolResult")}</div>
+            {call.result != null
+              ? <pre className="p-2 rounded bg-gray-800/50 dark:bg-gray-900/50 overflow-x-auto whitespace-pre-wrap break-words max-h-80">{call.result}</pre>
+              : <span className="text-gray-500 dark:text-gray-400">{t("workspace.chatToolPending")}</span>}
+          </div>
+        </div>
+      </details>
+    );
+  }
+
   function renderChatMessage(message: ChatMessage, index: number) {
+    // 已关联到工具调用上的结果不再单独显示
+    if (message.role === "tool" && message.tool_call_id && chatMessages.some((m) => m.tool_calls?.some((c) => c.id === message.tool_call_id && c.result != null))) {
+      return null;
+    }
     const style = getChatMessageStyle(message.role);
     const Icon = style.icon;
+    const attachments = (message.parts || []).filter((p) => p.type !== "text");
     return (
       <div key={index} className={`flex ${style.container} mb-4 min-w-0`}>
         <div className={`max-w-[85%] rounded-2xl px-4 py-3 ${style.bubble} min-w-0 overflow-hidden`}>
           <div className="flex items-center gap-2 mb-2">
             <Icon className="w-4 h-4 flex-shrink-0" />
-            <span className={`text-sm font-medium ${style.labelClass}`}>{style.label}</span>
-          </div>
-          <div className="prose prose-sm dark:prose-invert max-w-none prose-p:my-0 prose-p:leading-relaxed prose-pre:my-2 prose-pre:bg-gray-800/50 dark:prose-pre:bg-gray-900/50 prose-pre:overflow-x-auto prose-code:text-inherit prose-table:text-sm prose-table:block prose-table:overflow-x-auto prose-th:bg-gray-100/50 dark:prose-th:bg-gray-700/50 prose-th:p-2 prose-td:p-2 prose-thead:border-b prose-tbody:border-collapse break-words">
-            <ReactMarkdown remarkPlugins={[remarkGfm]}>{message.content}</ReactMarkdown>
+            <span className={`text-sm font-medium ${style.labelClass}`}>{message.role === "tool" && message.name ? message.name : style.label}</span>
+            {message.timestamp && <span className="text-xs text-gray-400 dark:text-gray-500">{message.timestamp.replace("T", " ").slice(0, 19)}</span>}
           </div>
+          {message.content && (
+            <div className="prose prose-sm dark:prose-invert max-w-none prose-p:my-0 prose-p:leading-relaxed prose-pre:my-2 prose-pre:bg-gray-800/50 dark:prose-pre:bg-gray-900/50 prose-pre:overflow-x-auto prose-code:text-inherit prose-table:text-sm prose-table:block prose-table:overflow-x-auto prose-th:bg-gray-100/50 dark:prose-th:bg-gray-700/50 prose-th:p-2 prose-td:p-2 prose-thead:border-b prose-tbody:border-collapse break-words">
+              <ReactMarkdown remarkPlugins={[remarkGfm]}>{message.content}</ReactMarkdown>
+            </div>
+          )}
+          {attachments.map((part, i) => part.type === "image"
+            ? <img key={i} src={part.url} alt="" className="mt-2 max-h-64 rounded-lg" />
+            : <div key={i} className="mt-2 flex items-center gap-2 text-sm"><File className="w-4 h-4" />{part.type === "file" ? (part.name || part.path) : JSON.stringify(part.raw)}</div>)}
+          {message.tool_calls?.map((call, i) => renderToolCall(call, i))}
         </div>
       </div>
     );
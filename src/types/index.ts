@@ -32,6 +32,17 @@ export type {
   FrontmatterData,
   ChatSession,
   ChatMessage,
+  ChatContentPart,
+  ChatToolCall,
+  ChatSessionHeader,
+  ChatSessionUpdate,
+  ChatConsoleEvent,
+  ChatConsoleEventKind,
+  MemoryVersion,
+  MemoryDiffLine,
+  SearchSource,
+  SearchQuery,
+  SearchHit,
 } from "./workspace";
 
 // Skills 类型
@@ -100,9 +111,67 @@ export interface NanobotPath {
   exists: boolean;
 }
 
+export interface LogRecord {
+  timestamp: string | null;
+  level: string;
+  module: string | null;
+  function: string | null;
+  line: number | null;
+  message: string;
+  continuation: string[];
+}
+
 export interface LogResponse {
   logs: string[];
+  records?: LogRecord[];
   total: number;
+  showing?: number;
+}
+
+export interface LogStreamBatch {
+  subscription_id: number;
+  seq: number;
+  records: LogRecord[];
+  lines: string[];
+}
+
+export interface LogRotationSettings {
+  enabled: boolean;
+  maxSizeMb: number;
+  maxAgeDays: number;
+  keepFiles: number;
+  compress: boolean;
+}
+
+export type AlertCondition =
+  | { type: "level"; minLevel: string }
+  | { type: "pattern"; pattern: string; caseSensitive?: boolean }
+  | { type: "error_rate"; perMinute: number }
+  | { type: "silence"; minutes: number };
+
+export interface AlertRule {
+  id: string;
+  name: string;
+  enabled: boolean;
+  condition: AlertCondition;
+  module?: string | null;
+  actions: {
+    event: boolean;
+    notification: boolean;
+    history: boolean;
+  };
+  cooldownSecs: number;
+}
+
+export interface AlertEntry {
+  id: string;
+  ruleId: string;
+  ruleName: string;
+  message: string;
+  sample: string | null;
+  triggeredAt: string;
+  acknowledged: boolean;
+  acknowledgedAt: string | null;
 }
 
 export interface NetworkStats {
@@ -30,14 +30,125 @@ export interface ChatSession {
   id: string;
   name: string;
   title: string;
+  key?: string | null;
+  created_at?: string | null;
+  updated_at?: string | null;
   modified: number;
   size: number;
 }
 
+// 聊天消息内容片段
+export type ChatContentPart =
+  | { type: "text"; text: string }
+  | { type: "image"; url: string; detail?: string | null }
+  | { type: "file"; name?: string | null; path?: string | null; mime_type?: string | null }
+  | { type: "other"; raw: unknown };
+
+// 工具调用
+export interface ChatToolCall {
+  id?: string | null;
+  name: string;
+  arguments: unknown;
+  result?: string | null;
+  result_timestamp?: string | null;
+  duration_ms?: number | null;
+  is_error: boolean;
+}
+
+// 会话元数据
+export interface ChatSessionHeader {
+  key?: string | null;
+  created_at?: string | null;
+  updated_at?: string | null;
+  last_consolidated?: number | null;
+  metadata?: unknown;
+  [key: string]: unknown;
+}
+
 // 聊天消息
 export interface ChatMessage {
+  index?: number;
+  line?: number;
   role: string;
   content: string;
+  parts?: ChatContentPart[];
+  timestamp?: string | null;
+  tool_calls?: ChatToolCall[];
+  tool_call_id?: string | null;
+  name?: string | null;
+  [key: string]: unknown;
+}
+
+// 会话跟随推送的新消息
+export interface ChatSessionUpdate {
+  subscription_id: number;
+  session_id: string;
+  seq: number;
+  messages: ChatMessage[];
+  total: number;
+  header?: ChatSessionHeader | null;
+  // 会话被重写变短时为 true，messages 从第一条重新开始
+  reset: boolean;
+}
+
+// 对话控制台事件
+export type ChatConsoleEventKind = "output" | "progress" | "tool" | "log" | "done" | "error";
+
+export interface ChatConsoleEvent {
+  turn_id: number;
+  kind: ChatConsoleEventKind;
+  text?: string;
+  record?: unknown;
+  session_key?: string;
+  session_id?: string | null;
+  exit_code?: number | null;
+  success?: boolean;
+  reply?: string | null;
+  messages?: ChatMessage[];
+  message?: string;
+}
+
+// 记忆文件历史版本
+export interface MemoryVersion {
+  id: string;
+  timestamp: number;
+  source: "user" | "agent" | "initial";
+  size: number;
+  restored_from?: string;
+}
+
+export interface MemoryDiffLine {
+  op: "equal" | "insert" | "delete";
+  text: string;
+  old_line: number | null;
+  new_line: number | null;
+}
+
+// 全文搜索
+export type SearchSource = "session" | "memory" | "workspace" | "skill";
+
+export interface SearchQuery {
+  query: string;
+  sources?: SearchSource[];
+  roles?: string[];
+  channels?: string[];
+  since?: string;
+  until?: string;
+  offset?: number;
+  limit?: number;
+}
+
+export interface SearchHit {
+  source: SearchSource;
+  id: string;
+  title: string;
+  channel?: string | null;
+  role?: string | null;
+  timestamp?: string | null;
+  line: number;
+  message_index?: number | null;
+  snippet: { text: string; highlight: boolean }[];
+  score: number;
 }
 
 // Tab 类型